@@ -1,5 +1,6 @@
 use crate::database::Database;
-use crate::model::{DatabaseError, IpBanCreate};
+use crate::extract::{scope, Auth};
+use crate::model::IpBanCreate;
 use databeam::prelude::DefaultReturn;
 
 use axum::response::IntoResponse;
@@ -7,23 +8,15 @@ use axum::{
     extract::{Path, State},
     Json,
 };
-use axum_extra::extract::cookie::CookieJar;
 
 /// Create a ipban
 pub async fn create_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
     State(database): State<Database>,
     Json(props): Json<IpBanCreate>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // return
     match database.create_ipban(props, auth_user).await {
         Ok(_) => Json(DefaultReturn {
@@ -37,19 +30,12 @@ pub async fn create_request(
 
 /// Delete an ipban
 pub async fn delete_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // return
     match database.delete_ipban(&id, auth_user).await {
         Ok(_) => Json(DefaultReturn {
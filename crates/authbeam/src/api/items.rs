@@ -1,16 +1,18 @@
 use crate::database::Database;
+use crate::extract::{scope, Auth};
 use crate::model::{
-    DatabaseError, ItemCreate, ItemEdit, ItemEditContent, ItemType, SetItemStatus, TokenPermission,
+    DatabaseError, FinePermission, ItemCreate, ItemEdit, ItemEditContent, ItemReviewCreate,
+    ItemReviewReply, ItemSearch, ItemStatus, ItemType, ItemVersionPin, SetItemStatus,
     TransactionCreate,
 };
 use databeam::prelude::DefaultReturn;
 
+use axum::http::HeaderMap;
 use axum::response::IntoResponse;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     Json,
 };
-use axum_extra::extract::cookie::CookieJar;
 
 /// Get an item
 pub async fn get_request(
@@ -36,35 +38,75 @@ pub async fn get_request(
     })
 }
 
-/// Create an item
-pub async fn create_request(
-    jar: CookieJar,
+/// Search items (mirrors the `/market` page)
+pub async fn search_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth,
     State(database): State<Database>,
-    Json(props): Json<ItemCreate>,
+    Query(props): Query<ItemSearch>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::ManageAssets)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
+    // check permission to see unapproved items
+    if (props.status != ItemStatus::Approved) && (props.status != ItemStatus::Featured) {
+        let group = match database.get_group_by_id(auth_user.group).await {
+            Ok(g) => g,
+            Err(e) => return Json(e.to_json()),
+        };
+
+        if !group.permissions.check(FinePermission::ECON_MASTER) {
+            return Json(DatabaseError::NotAllowed.to_json());
         }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
+    }
+
+    // get items
+    let mut items = match database.search_items(props).await {
+        Ok(i) => i,
+        Err(e) => return Json(e.to_json()),
     };
 
+    for (item, creator, _) in items.iter_mut() {
+        // the content of items has to be bought (modules are public through `get_request`)
+        if item.r#type != ItemType::Module {
+            item.content = String::new();
+        }
+
+        creator.clean();
+    }
+
+    // return
+    Json(DefaultReturn {
+        success: true,
+        message: items.len().to_string(),
+        payload: Some(items),
+    })
+}
+
+/// Get the tags of an item
+pub async fn tags_request(
+    State(database): State<Database>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    // make sure the item exists
+    if let Err(e) = database.get_item(&id).await {
+        return Json(e.to_json());
+    }
+
+    // return
+    Json(DefaultReturn {
+        success: true,
+        message: id.clone(),
+        payload: Some(database.get_item_tags(&id).await),
+    })
+}
+
+/// Create an item
+pub async fn create_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
+    State(database): State<Database>,
+    Json(props): Json<ItemCreate>,
+) -> impl IntoResponse {
     // return
     let item = match database.create_item(props, &auth_user.id).await {
         Ok(m) => m,
@@ -80,19 +122,12 @@ pub async fn create_request(
 
 /// Delete an item
 pub async fn delete_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // return
     if let Err(e) = database.delete_item(&id, auth_user).await {
         return Json(e.to_json());
@@ -107,20 +142,13 @@ pub async fn delete_request(
 
 /// Update item status
 pub async fn update_status_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<SetItemStatus>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // return
     if let Err(e) = database
         .update_item_status(&id, props.status, auth_user)
@@ -138,20 +166,13 @@ pub async fn update_status_request(
 
 /// Update item fields
 pub async fn update_item_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<ItemEdit>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // return
     if let Err(e) = database.update_item(&id, props, auth_user).await {
         return Json(e.to_json());
@@ -166,47 +187,121 @@ pub async fn update_item_request(
 
 /// Update item content
 pub async fn update_item_content_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<ItemEditContent>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
+    // return
+    match database.update_item_content(&id, props, auth_user).await {
+        Ok(v) => Json(DefaultReturn {
+            success: true,
+            message: "Version submitted".to_string(),
+            payload: Some(v),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
 
+/// Approve or reject an item version
+pub async fn update_version_status_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+    Json(props): Json<SetItemStatus>,
+) -> impl IntoResponse {
     // return
-    if let Err(e) = database.update_item_content(&id, props, auth_user).await {
+    if let Err(e) = database
+        .update_item_version_status(&id, props.status, auth_user)
+        .await
+    {
         return Json(e.to_json());
     }
 
     Json(DefaultReturn {
         success: true,
-        message: "Item updated".to_string(),
+        message: "Version updated".to_string(),
+        payload: (),
+    })
+}
+
+/// Pin the version of an item you use
+pub async fn pin_version_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+    Json(props): Json<ItemVersionPin>,
+) -> impl IntoResponse {
+    // return
+    if let Err(e) = database.pin_item_version(&id, props, auth_user).await {
+        return Json(e.to_json());
+    }
+
+    Json(DefaultReturn {
+        success: true,
+        message: "Version pinned".to_string(),
+        payload: (),
+    })
+}
+
+/// Apply a layout or theme item to your profile
+pub async fn apply_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    // return
+    match database.apply_item(&id, auth_user).await {
+        Ok(install) => Json(DefaultReturn {
+            success: true,
+            message: "Item applied".to_string(),
+            payload: Some(install),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
+
+/// Restore what your profile looked like before applying a layout or theme item
+pub async fn revert_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    // return
+    if let Err(e) = database.revert_item(&id, auth_user).await {
+        return Json(e.to_json());
+    }
+
+    Json(DefaultReturn {
+        success: true,
+        message: "Item reverted".to_string(),
         payload: (),
     })
 }
 
 /// Buy an item
+///
+/// Purchases are idempotent: the `Idempotency-Key` header (or the item ID when it isn't
+/// provided) is recorded in the ledger, and repeating a purchase with a used key succeeds
+/// without charging again.
 pub async fn buy_request(
-    jar: CookieJar,
+    headers: HeaderMap,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAccount>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // return
     let item = match database.get_item(&id).await {
         Ok(i) => i,
@@ -221,6 +316,24 @@ pub async fn buy_request(
         });
     }
 
+    // check idempotency key
+    let idempotency_key = match headers.get("Idempotency-Key") {
+        Some(k) => format!("purchase:{}", k.to_str().unwrap_or(&item.id)),
+        None => format!("purchase:{}", item.id),
+    };
+
+    if database
+        .get_ledger_transfer_by_key(&auth_user.id, &idempotency_key)
+        .await
+        .is_ok()
+    {
+        return Json(DefaultReturn {
+            success: true,
+            message: "Purchase successful".to_string(),
+            payload: (),
+        });
+    }
+
     // make sure we don't already have this item
     if let Ok(_) = database
         .get_transaction_by_customer_item(&auth_user.id, &item.id)
@@ -240,6 +353,7 @@ pub async fn buy_request(
                 merchant: item.creator.clone(),
                 item: item.id.clone(),
                 amount: -(item.cost),
+                idempotency_key,
             },
             &auth_user.id,
         )
@@ -254,3 +368,59 @@ pub async fn buy_request(
         payload: (),
     })
 }
+
+/// Review an item
+pub async fn create_review_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+    Json(props): Json<ItemReviewCreate>,
+) -> impl IntoResponse {
+    match database.create_review(&id, props, auth_user).await {
+        Ok(r) => Json(DefaultReturn {
+            success: true,
+            message: "Review created".to_string(),
+            payload: Some(r),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
+
+/// Reply to a review of an item you created
+pub async fn reply_review_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+    Json(props): Json<ItemReviewReply>,
+) -> impl IntoResponse {
+    match database.update_review_reply(&id, props, auth_user).await {
+        Ok(_) => Json(DefaultReturn {
+            success: true,
+            message: "Review updated".to_string(),
+            payload: (),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
+
+/// Delete a review
+pub async fn delete_review_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    match database.delete_review(&id, auth_user).await {
+        Ok(_) => Json(DefaultReturn {
+            success: true,
+            message: "Review deleted".to_string(),
+            payload: (),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
@@ -1,5 +1,6 @@
 use crate::database::Database;
-use crate::model::{DatabaseError, LabelCreate, TokenPermission};
+use crate::extract::{scope, Auth};
+use crate::model::LabelCreate;
 use databeam::prelude::DefaultReturn;
 
 use axum::response::IntoResponse;
@@ -7,7 +8,6 @@ use axum::{
     extract::{Path, State},
     Json,
 };
-use axum_extra::extract::cookie::CookieJar;
 
 /// Get a label
 pub async fn get_request(
@@ -30,34 +30,12 @@ pub async fn get_request(
 
 /// Create a label
 pub async fn create_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     State(database): State<Database>,
     Json(props): Json<LabelCreate>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::ManageAssets)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // return
     let label = match database
         .create_label(&props.name, props.id, &auth_user.id)
@@ -76,19 +54,12 @@ pub async fn create_request(
 
 /// Delete a label
 pub async fn delete_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<i64>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // return
     if let Err(e) = database.delete_label(id, auth_user).await {
         return Json(e.to_json());
@@ -0,0 +1,46 @@
+use crate::database::Database;
+use crate::extract::{scope, Auth};
+use databeam::prelude::DefaultReturn;
+
+use axum::response::IntoResponse;
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+/// Check every balance against the ledger
+pub async fn report_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    // return
+    match database.get_ledger_report(auth_user).await {
+        Ok(r) => Json(DefaultReturn {
+            success: true,
+            message: format!("{} accounts drifted", r.drift.len()),
+            payload: Some(r),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
+
+/// Reset a profile's coins to their balance in the ledger
+pub async fn reconcile_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    // return
+    match database.reconcile_profile_coins(&id, auth_user).await {
+        Ok(b) => Json(DefaultReturn {
+            success: true,
+            message: "Acceptable".to_string(),
+            payload: Some(b),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
@@ -1,27 +1,23 @@
 use crate::database::Database;
+use crate::extract::{scope, Auth};
 use crate::model::{DatabaseError, TokenContext, TokenPermission};
+use crate::settings::SettingUrl;
 use serde::{Deserialize, Serialize};
 use databeam::prelude::DefaultReturn;
 
 use axum::http::{HeaderMap, HeaderValue};
 use axum::response::{IntoResponse, Redirect};
 use axum::{extract::State, Json};
-use axum_extra::extract::cookie::CookieJar;
 use pathbufd::pathd;
 
 use crate::avif::{save_avif_buffer, Image};
 
 /// Returns the current user's username
-pub async fn get_request(jar: CookieJar, State(database): State<Database>) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
+pub async fn get_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Any>,
+) -> impl IntoResponse {
     // return
     Json(DefaultReturn {
         success: true,
@@ -39,19 +35,12 @@ pub struct DeleteProfile {
 
 /// Delete the current user's profile
 pub async fn delete_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAccount>,
     State(database): State<Database>,
     Json(props): Json<DeleteProfile>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // get profile
     let hashed = rainbeam_shared::hash::hash_salted(props.password, auth_user.salt.clone());
 
@@ -82,38 +71,16 @@ pub async fn delete_request(
 
 /// Generate a new token and session (like logging in while already logged in)
 pub async fn generate_token_request(
-    jar: CookieJar,
+    Auth {
+        user: mut auth_user,
+        token,
+        ..
+    }: Auth<scope::GenerateTokens>,
     headers: HeaderMap,
     State(database): State<Database>,
     Json(props): Json<TokenContext>,
 ) -> impl IntoResponse {
-    // get user from token
-    let mut existing_permissions: Option<Vec<TokenPermission>> = None;
-    let mut auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    let token = ua.token_context_from_token(&token);
-
-                    if let Some(ref permissions) = token.permissions {
-                        existing_permissions = Some(permissions.to_owned())
-                    }
-
-                    if !token.can_do(TokenPermission::GenerateTokens) {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
+    let existing_permissions: Option<Vec<TokenPermission>> = token.permissions;
 
     // for every token that doesn't have a context, insert the default context
     for (i, _) in auth_user.tokens.clone().iter().enumerate() {
@@ -195,34 +162,13 @@ pub struct UpdateTokens {
 
 /// Update the current user's session tokens
 pub async fn update_tokens_request(
-    jar: CookieJar,
+    Auth {
+        user: mut auth_user,
+        ..
+    }: Auth<scope::ManageAccount>,
     State(database): State<Database>,
     Json(req): Json<UpdateTokens>,
 ) -> impl IntoResponse {
-    // get user from token
-    let mut auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::ManageAccount)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // for every token that doesn't have a context, insert the default context
     for (i, _) in auth_user.tokens.clone().iter().enumerate() {
         if let None = auth_user.token_context.get(i) {
@@ -280,21 +226,12 @@ static MAXIUMUM_FILE_SIZE: usize = 8388608;
 
 /// Upload avatar
 pub async fn upload_avatar_request(
-    jar: CookieJar,
+    auth: Option<Auth<scope::ManageProfile>>,
     State(database): State<Database>,
     img: Image,
 ) -> impl IntoResponse {
-    // get user from token
-    let mut auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => {
-                return Redirect::to(&format!(
-                    "/settings/profile?ANNC={}&ANNC_TYPE=caution",
-                    e.to_string()
-                ));
-            }
-        },
+    let mut auth_user = match auth {
+        Some(a) => a.user,
         None => {
             return Redirect::to(&format!(
                 "/settings/profile?ANNC={}&ANNC_TYPE=caution",
@@ -327,10 +264,9 @@ pub async fn upload_avatar_request(
     match save_avif_buffer(&path, bytes) {
         Ok(_) => {
             // update profile config
-            auth_user
-                .metadata
-                .kv
-                .insert("sparkler:avatar_url".to_string(), "rb://".to_string());
+            let mut settings = auth_user.metadata.settings();
+            settings.avatar_url = SettingUrl("rb://".to_string());
+            auth_user.metadata.set_settings(&settings);
 
             match database
                 .update_profile_metadata(&auth_user.id, auth_user.metadata)
@@ -355,21 +291,12 @@ pub async fn upload_avatar_request(
 
 /// Upload banner
 pub async fn upload_banner_request(
-    jar: CookieJar,
+    auth: Option<Auth<scope::ManageProfile>>,
     State(database): State<Database>,
     img: Image,
 ) -> impl IntoResponse {
-    // get user from token
-    let mut auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => {
-                return Redirect::to(&format!(
-                    "/settings/profile?ANNC={}&ANNC_TYPE=caution",
-                    e.to_string()
-                ));
-            }
-        },
+    let mut auth_user = match auth {
+        Some(a) => a.user,
         None => {
             return Redirect::to(&format!(
                 "/settings/profile?ANNC={}&ANNC_TYPE=caution",
@@ -402,10 +329,9 @@ pub async fn upload_banner_request(
     match save_avif_buffer(&path, bytes) {
         Ok(_) => {
             // update profile config
-            auth_user
-                .metadata
-                .kv
-                .insert("sparkler:banner_url".to_string(), "rb://".to_string());
+            let mut settings = auth_user.metadata.settings();
+            settings.banner_url = SettingUrl("rb://".to_string());
+            auth_user.metadata.set_settings(&settings);
 
             match database
                 .update_profile_metadata(&auth_user.id, auth_user.metadata)
@@ -15,9 +15,11 @@ pub mod ipbans;
 pub mod ipblocks;
 pub mod items;
 pub mod labels;
+pub mod ledger;
 pub mod me;
 pub mod notifications;
 pub mod profile;
+pub mod push;
 pub mod relationships;
 pub mod warnings;
 
@@ -36,6 +38,18 @@ pub fn routes(database: Database) -> Router {
             "/relationships/follow/{id}",
             post(relationships::follow_request),
         )
+        .route(
+            "/relationships/follow_requests",
+            get(relationships::follow_requests_request),
+        )
+        .route(
+            "/relationships/follow_requests/{id}",
+            post(relationships::accept_follow_request),
+        )
+        .route(
+            "/relationships/follow_requests/{id}",
+            delete(relationships::deny_follow_request),
+        )
         .route(
             "/relationships/friend/{id}",
             post(relationships::friend_request),
@@ -48,6 +62,14 @@ pub fn routes(database: Database) -> Router {
             "/relationships/current/{id}",
             delete(relationships::delete_request),
         )
+        .route(
+            "/relationships/mute/{id}",
+            post(relationships::mute_request),
+        )
+        .route(
+            "/relationships/mute/{id}",
+            delete(relationships::unmute_request),
+        )
         // profiles
         .route(
             "/profile/{id}/tokens/generate",
@@ -57,6 +79,10 @@ pub fn routes(database: Database) -> Router {
         .route("/profile/{id}/tier", post(profile::update_tier_request))
         .route("/profile/{id}/group", post(profile::update_group_request))
         .route("/profile/{id}/coins", post(profile::update_coins_request))
+        .route(
+            "/profile/{id}/coins/reconcile",
+            post(ledger::reconcile_request),
+        )
         .route(
             "/profile/{id}/password",
             post(profile::update_password_request),
@@ -75,6 +101,14 @@ pub fn routes(database: Database) -> Router {
         )
         .route("/profile/{id}/badges", post(profile::update_badges_request))
         .route("/profile/{id}/labels", post(profile::update_labels_request))
+        .route(
+            "/profile/{id}/quarantine",
+            post(profile::quarantine_request),
+        )
+        .route(
+            "/profile/{id}/quarantine",
+            delete(profile::lift_quarantine_request),
+        )
         .route("/profile/{id}/links", post(profile::update_links_request))
         .route("/profile/{id}/layout", post(profile::update_layout_request))
         .route("/profile/{id}/totp", post(profile::enable_totp_request))
@@ -86,20 +120,35 @@ pub fn routes(database: Database) -> Router {
         .route("/profile/{id}/banner", get(profile::banner_request))
         .route("/profile/{id}/avatar", get(profile::avatar_request))
         .route("/profile/{id}/custom.css", get(profile::css_request))
+        .route("/profile/{id}/unlock", post(profile::unlock_request))
         .route("/profile/{id}", delete(profile::delete_request))
         .route("/profile/{id}", get(profile::get_request))
         .route("/token/{token}", get(profile::get_from_token_request))
         // items
+        .route("/items", get(items::search_request))
         .route("/items", post(items::create_request))
         .route("/item/{id}", get(items::get_request))
         .route("/item/{id}", post(items::update_item_request))
+        .route("/item/{id}/tags", get(items::tags_request))
         .route("/item/{id}/buy", post(items::buy_request))
+        .route("/item/{id}/pin", post(items::pin_version_request))
+        .route("/item/{id}/apply", post(items::apply_request))
+        .route("/item/{id}/revert", post(items::revert_request))
+        .route(
+            "/item/version/{id}/status",
+            post(items::update_version_status_request),
+        )
         .route("/item/{id}/status", post(items::update_status_request))
         .route(
             "/item/{id}/content",
             post(items::update_item_content_request),
         )
         .route("/item/{id}", delete(items::delete_request))
+        .route("/item/{id}/reviews", post(items::create_review_request))
+        .route("/review/{id}/reply", post(items::reply_review_request))
+        .route("/review/{id}", delete(items::delete_review_request))
+        // ledger
+        .route("/ledger/report", get(ledger::report_request))
         // labels
         .route("/labels", post(labels::create_request))
         .route("/label/{id}", get(labels::get_request))
@@ -110,9 +159,31 @@ pub fn routes(database: Database) -> Router {
             "/notifications/clear",
             delete(notifications::delete_all_request),
         )
+        .route("/notifications/read", post(notifications::read_all_request))
+        .route(
+            "/notifications/{id}/read",
+            post(notifications::read_request),
+        )
+        .route(
+            "/notifications/{id}/read",
+            delete(notifications::unread_request),
+        )
+        // push
+        .route("/push/key", get(push::key_request))
+        .route("/push/subscriptions", post(push::create_request))
+        .route("/push/subscriptions", delete(push::delete_request))
         // warnings
         .route("/warnings", post(warnings::create_request))
         .route("/warnings/{id}", delete(warnings::delete_request))
+        .route(
+            "/warnings/{id}/acknowledge",
+            post(warnings::acknowledge_request),
+        )
+        .route("/profile/{id}/suspension", post(warnings::suspend_request))
+        .route(
+            "/profile/{id}/suspension",
+            delete(warnings::lift_suspension_request),
+        )
         // ipbans
         .route("/ipbans", post(ipbans::create_request))
         .route("/ipbans/{id}", delete(ipbans::delete_request))
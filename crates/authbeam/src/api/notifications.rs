@@ -1,5 +1,5 @@
 use crate::database::Database;
-use crate::model::DatabaseError;
+use crate::extract::{scope, Auth};
 use databeam::prelude::DefaultReturn;
 
 use axum::response::IntoResponse;
@@ -7,23 +7,15 @@ use axum::{
     extract::{Path, State},
     Json,
 };
-use axum_extra::extract::cookie::CookieJar;
 
 /// Delete a notification
 pub async fn delete_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // return
     if let Err(e) = database.delete_notification(&id, auth_user).await {
         return Json(e.to_json());
@@ -38,18 +30,11 @@ pub async fn delete_request(
 
 /// Delete the current user's notifications
 pub async fn delete_all_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // return
     if let Err(e) = database
         .delete_notifications_by_recipient(&auth_user.id.clone(), auth_user)
@@ -64,3 +49,71 @@ pub async fn delete_all_request(
         payload: (),
     })
 }
+
+/// Mark a notification as read
+pub async fn read_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    // return
+    if let Err(e) = database
+        .update_notification_read(&id, true, auth_user)
+        .await
+    {
+        return Json(e.to_json());
+    }
+
+    Json(DefaultReturn {
+        success: true,
+        message: "Notification marked as read".to_string(),
+        payload: (),
+    })
+}
+
+/// Mark a notification as unread
+pub async fn unread_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    // return
+    if let Err(e) = database
+        .update_notification_read(&id, false, auth_user)
+        .await
+    {
+        return Json(e.to_json());
+    }
+
+    Json(DefaultReturn {
+        success: true,
+        message: "Notification marked as unread".to_string(),
+        payload: (),
+    })
+}
+
+/// Mark all of the current user's notifications as read
+pub async fn read_all_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    // return
+    if let Err(e) = database
+        .update_notifications_read_by_recipient(&auth_user.id.clone(), auth_user)
+        .await
+    {
+        return Json(e.to_json());
+    }
+
+    Json(DefaultReturn {
+        success: true,
+        message: "Notifications marked as read".to_string(),
+        payload: (),
+    })
+}
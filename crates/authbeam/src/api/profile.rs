@@ -1,11 +1,16 @@
 use crate::database::Database;
+use crate::extract::{scope, Auth};
+use crate::css::sanitize_css;
+use crate::layout::LayoutError;
+use crate::settings::ProfileSettings;
 use crate::model::{
-    DatabaseError, FinePermission, NotificationCreate, RenderLayout, SetProfileBadges,
-    SetProfileCoins, SetProfileGroup, SetProfileLabels, SetProfileLayout, SetProfileLinks,
-    SetProfileMetadata, SetProfilePassword, SetProfileTier, SetProfileUsername, TOTPDisable,
-    TokenContext, TokenPermission,
+    DatabaseError, FinePermission, NotificationCreate, NotificationKind, RenderLayout,
+    SetProfileBadges, SetProfileCoins, SetProfileGroup, SetProfileLabels, SetProfileLayout,
+    SetProfileLinks, SetProfileMetadata, SetProfilePassword, SetProfileTier, SetProfileUsername,
+    TOTPDisable, TokenContext, TokenPermission, UnlockProfile, RESERVED_LABEL_QUARANTINE,
 };
 use crate::simplify;
+use crate::unlock::{unlock_cookie, UNLOCK_TTL};
 use databeam::prelude::DefaultReturn;
 use pathbufd::pathd;
 
@@ -13,12 +18,12 @@ use axum::body::Body;
 use axum::http::{HeaderMap, HeaderValue};
 use axum::response::IntoResponse;
 use axum::{
-    extract::{Path, State},
+    extract::{ConnectInfo, Path, State},
     Json,
 };
-use axum_extra::extract::cookie::CookieJar;
 use serde::Serialize;
 
+use std::net::SocketAddr;
 use std::{fs::File, io::Read};
 
 pub fn read_image(static_dir: String, image: String) -> Vec<u8> {
@@ -56,10 +61,8 @@ pub async fn avatar_request(
     };
 
     // ...
-    let avatar_url = match auth_user.metadata.kv.get("sparkler:avatar_url") {
-        Some(r) => r,
-        None => "",
-    };
+    let settings = auth_user.metadata.settings();
+    let avatar_url = settings.avatar_url.as_str();
 
     if (avatar_url == "rb://") && !database.config.media_dir.to_string().is_empty() {
         return (
@@ -179,10 +182,8 @@ pub async fn banner_request(
     };
 
     // ...
-    let banner_url = match auth_user.metadata.kv.get("sparkler:banner_url") {
-        Some(r) => r,
-        None => "",
-    };
+    let settings = auth_user.metadata.settings();
+    let banner_url = settings.banner_url.as_str();
 
     if (banner_url == "rb://") && !database.config.media_dir.to_string().is_empty() {
         return (
@@ -304,6 +305,75 @@ pub async fn get_request(
     })
 }
 
+/// Unlock a profile which requires a view password
+///
+/// Sets a cookie scoped to the profile instead of passing the password around.
+pub async fn unlock_request(
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+    Json(props): Json<UnlockProfile>,
+) -> impl IntoResponse {
+    // get user
+    let other_user = match database.get_profile(&id).await {
+        Ok(ua) => ua,
+        Err(e) => return (HeaderMap::new(), Json(e.to_json())),
+    };
+
+    // get real ip
+    let real_ip = if let Some(ref real_ip_header) = database.config.real_ip_header {
+        headers
+            .get(real_ip_header.to_owned())
+            .unwrap_or(&HeaderValue::from_static(""))
+            .to_str()
+            .unwrap_or("")
+            .to_string()
+    } else {
+        String::new()
+    };
+
+    // attempts are limited per ip, so everybody can't share the same (empty) one
+    let real_ip = if real_ip.is_empty() {
+        addr.ip().to_string()
+    } else {
+        real_ip
+    };
+
+    // unlock
+    let unlock = match database
+        .unlock_profile(&other_user, &props.password, &real_ip)
+        .await
+    {
+        Ok(u) => u,
+        Err(e) => return (HeaderMap::new(), Json(e.to_json())),
+    };
+
+    // return
+    let mut headers = HeaderMap::new();
+
+    headers.insert(
+        "Set-Cookie",
+        format!(
+            "{}={}; SameSite=Lax; Secure; Path=/; HostOnly=true; HttpOnly=true; Max-Age={}",
+            unlock_cookie(&other_user.id),
+            unlock,
+            UNLOCK_TTL
+        )
+        .parse()
+        .unwrap(),
+    );
+
+    (
+        headers,
+        Json(DefaultReturn {
+            success: true,
+            message: "Profile unlocked".to_string(),
+            payload: (),
+        }),
+    )
+}
+
 /// View a profile's information from auth token (no cleaning)
 pub async fn get_from_token_request(
     Path(token): Path<String>,
@@ -325,35 +395,13 @@ pub async fn get_from_token_request(
 
 /// Change a profile's tier
 pub async fn update_tier_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<SetProfileTier>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::Moderator)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // check permission
     let group = match database.get_group_by_id(auth_user.group).await {
         Ok(g) => g,
@@ -405,20 +453,13 @@ pub async fn update_tier_request(
 
 /// Change a profile's group
 pub async fn update_group_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<SetProfileGroup>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // check permission
     let our_group = match database.get_group_by_id(auth_user.group).await {
         Ok(g) => g,
@@ -496,20 +537,13 @@ pub async fn update_group_request(
 
 /// Change a profile's coins
 pub async fn update_coins_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<SetProfileCoins>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // check permission
     let group = match database.get_group_by_id(auth_user.group).await {
         Ok(g) => g,
@@ -563,35 +597,13 @@ pub async fn update_coins_request(
 
 /// Update the given user's session tokens
 pub async fn update_tokens_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(req): Json<super::me::UpdateTokens>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::Moderator)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     let mut other = match database.get_profile(&id).await {
         Ok(o) => o,
         Err(e) => return Json(e.to_json()),
@@ -684,36 +696,14 @@ pub async fn update_tokens_request(
 
 /// Generate a new token and session (like logging in while already logged in)
 pub async fn generate_token_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
     headers: HeaderMap,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<TokenContext>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::Moderator)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     let mut other = match database.get_profile(&id).await {
         Ok(o) => o,
         Err(e) => return Json(e.to_json()),
@@ -807,35 +797,13 @@ pub async fn generate_token_request(
 
 /// Change a profile's password
 pub async fn update_password_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAccount>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<SetProfilePassword>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::ManageAccount)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // check permission
     let mut is_manager = false;
     if auth_user.id != id && auth_user.username != id {
@@ -889,35 +857,13 @@ pub async fn update_password_request(
 
 /// Change a profile's username
 pub async fn update_username_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAccount>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<SetProfileUsername>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::ManageAccount)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // check permission
     if auth_user.id != id && auth_user.username != id {
         let group = match database.get_group_by_id(auth_user.group).await {
@@ -967,36 +913,16 @@ pub async fn update_username_request(
 }
 
 /// Update a user's metadata
+///
+/// The payload is everything removed from custom CSS by [`crate::css::sanitize_css`].
 pub async fn update_metdata_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<SetProfileMetadata>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::ManageProfile)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // check permission
     if auth_user.id != id && auth_user.username != id {
         let group = match database.get_group_by_id(auth_user.group).await {
@@ -1005,7 +931,7 @@ pub async fn update_metdata_request(
                 return Json(DefaultReturn {
                     success: false,
                     message: e.to_string(),
-                    payload: (),
+                    payload: Vec::new(),
                 })
             }
         };
@@ -1027,52 +953,50 @@ pub async fn update_metdata_request(
         return Json(DefaultReturn {
             success: false,
             message: DatabaseError::NotAllowed.to_string(),
-            payload: (),
+            payload: Vec::new(),
+        });
+    }
+
+    // check settings
+    let other_user = match database.get_profile(&id).await {
+        Ok(ua) => ua,
+        Err(e) => return Json(e.to_json()),
+    };
+
+    if let Err(e) = ProfileSettings::parse_changes(&other_user.metadata.kv, &props.metadata.kv) {
+        return Json(DefaultReturn {
+            success: false,
+            message: e.message,
+            payload: Vec::new(),
         });
     }
 
+    // sanitize css
+    let mut metadata = props.metadata;
+    let removed = metadata.sanitize_css();
+
     // return
-    match database.update_profile_metadata(&id, props.metadata).await {
+    match database.update_profile_metadata(&id, metadata).await {
         Ok(_) => Json(DefaultReturn {
             success: true,
             message: "Acceptable".to_string(),
-            payload: (),
+            payload: removed,
         }),
         Err(e) => Json(e.to_json()),
     }
 }
 
 /// Patch a user's metadata
+///
+/// The payload is everything removed from custom CSS by [`crate::css::sanitize_css`].
 pub async fn patch_metdata_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<SetProfileMetadata>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::ManageProfile)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // get other user
     let other_user = match database.get_profile(&id).await {
         Ok(ua) => ua,
@@ -1087,7 +1011,7 @@ pub async fn patch_metdata_request(
                 return Json(DefaultReturn {
                     success: false,
                     message: e.to_string(),
-                    payload: (),
+                    payload: Vec::new(),
                 })
             }
         };
@@ -1109,7 +1033,7 @@ pub async fn patch_metdata_request(
         return Json(DefaultReturn {
             success: false,
             message: DatabaseError::NotAllowed.to_string(),
-            payload: (),
+            payload: Vec::new(),
         });
     }
 
@@ -1124,12 +1048,23 @@ pub async fn patch_metdata_request(
         metadata.policy_consent = props.metadata.policy_consent;
     }
 
+    // check settings
+    if let Err(e) = ProfileSettings::parse_changes(&other_user.metadata.kv, &metadata.kv) {
+        return Json(DefaultReturn {
+            success: false,
+            message: e.message,
+            payload: Vec::new(),
+        });
+    }
+
+    let removed = metadata.sanitize_css();
+
     // return
     match database.update_profile_metadata(&id, metadata).await {
         Ok(_) => Json(DefaultReturn {
             success: true,
             message: "Acceptable".to_string(),
-            payload: (),
+            payload: removed,
         }),
         Err(e) => Json(e.to_json()),
     }
@@ -1137,35 +1072,13 @@ pub async fn patch_metdata_request(
 
 /// Update a user's badges
 pub async fn update_badges_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<SetProfileBadges>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::Moderator)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // check permission
     let group = match database.get_group_by_id(auth_user.group).await {
         Ok(g) => g,
@@ -1197,35 +1110,13 @@ pub async fn update_badges_request(
 
 /// Update a user's labels
 pub async fn update_labels_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<SetProfileLabels>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::Moderator)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // check permission
     let group = match database.get_group_by_id(auth_user.group).await {
         Ok(g) => g,
@@ -1244,11 +1135,79 @@ pub async fn update_labels_request(
         });
     }
 
+    let other = match database.get_profile(&id).await {
+        Ok(ua) => ua,
+        Err(e) => return Json(e.to_json()),
+    };
+
+    let quarantined = props.labels.contains(&RESERVED_LABEL_QUARANTINE);
+
     // return
     match database.update_profile_labels(&id, props.labels).await {
+        Ok(_) => {
+            // quarantine changes are audited
+            if other.is_quarantined() != quarantined {
+                if let Err(e) = database
+                    .audit(
+                        &auth_user.id,
+                        &if quarantined {
+                            format!("Quarantined a user: [{}](/+u/{})", other.id, other.id)
+                        } else {
+                            format!("Lifted a quarantine: [{}](/+u/{})", other.id, other.id)
+                        },
+                    )
+                    .await
+                {
+                    return Json(e.to_json());
+                }
+            }
+
+            Json(DefaultReturn {
+                success: true,
+                message: "Acceptable".to_string(),
+                payload: (),
+            })
+        }
+        Err(e) => Json(e.to_json()),
+    }
+}
+
+/// Quarantine a user
+pub async fn quarantine_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    match database
+        .update_profile_quarantine(&id, true, auth_user)
+        .await
+    {
         Ok(_) => Json(DefaultReturn {
             success: true,
-            message: "Acceptable".to_string(),
+            message: "User quarantined".to_string(),
+            payload: (),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
+
+/// Lift a user's quarantine
+pub async fn lift_quarantine_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    match database
+        .update_profile_quarantine(&id, false, auth_user)
+        .await
+    {
+        Ok(_) => Json(DefaultReturn {
+            success: true,
+            message: "Quarantine lifted".to_string(),
             payload: (),
         }),
         Err(e) => Json(e.to_json()),
@@ -1257,35 +1216,13 @@ pub async fn update_labels_request(
 
 /// Update a user's links
 pub async fn update_links_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<SetProfileLinks>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::Moderator)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // check permission
     let group = match database.get_group_by_id(auth_user.group).await {
         Ok(g) => g,
@@ -1313,35 +1250,13 @@ pub async fn update_links_request(
 
 /// Update a user's layout
 pub async fn update_layout_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<SetProfileLayout>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::Moderator)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // check permission
     let group = match database.get_group_by_id(auth_user.group).await {
         Ok(g) => g,
@@ -1356,12 +1271,21 @@ pub async fn update_layout_request(
         return Json(DatabaseError::NotAllowed.to_json());
     }
 
+    // validate layout
+    if let Err(errors) = props.layout.validate() {
+        return Json(DefaultReturn {
+            success: false,
+            message: errors[0].message.clone(),
+            payload: Some(errors),
+        });
+    }
+
     // return
     match database.update_profile_layout(&id, props.layout).await {
         Ok(_) => Json(DefaultReturn {
             success: true,
             message: "Acceptable".to_string(),
-            payload: (),
+            payload: None,
         }),
         Err(e) => Json(e.to_json()),
     }
@@ -1371,33 +1295,43 @@ pub async fn update_layout_request(
 struct LayoutRenderResult {
     pub block: String,
     pub tree: String,
+    /// Why the layout wasn't rendered (empty if it's valid)
+    pub errors: Vec<LayoutError>,
 }
 
 /// Render a layout (in block form).
+///
+/// Invalid layouts aren't rendered, their validation errors are returned instead.
 pub async fn render_layout_request(Json(props): Json<RenderLayout>) -> impl IntoResponse {
+    if let Err(errors) = props.layout.validate() {
+        return Json(LayoutRenderResult {
+            block: String::new(),
+            tree: String::new(),
+            errors,
+        });
+    }
+
     Json(LayoutRenderResult {
         block: props.layout.render_block(),
         tree: props.layout.render_tree(),
+        errors: Vec::new(),
     })
 }
 
 /// Delete another user
 pub async fn delete_request(
-    jar: CookieJar,
+    auth: Auth<scope::ManageAccount>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
+    let auth_user = auth.user;
 
     // check permission
     if auth_user.username != id {
+        if !auth.token.can_do(TokenPermission::Moderator) {
+            return Json(DatabaseError::NotAllowed.to_json());
+        }
+
         let group = match database.get_group_by_id(auth_user.group).await {
             Ok(g) => g,
             Err(e) => {
@@ -1428,6 +1362,7 @@ pub async fn delete_request(
                         content: format!("Deleted a profile: @{}", other_user.username),
                         address: format!("/+u/{actor_id}"),
                         recipient: "*(audit)".to_string(), // all staff, audit
+                        kind: NotificationKind::System,
                     },
                     None,
                 )
@@ -1510,39 +1445,19 @@ pub async fn css_request(
     }
 
     out.push_str(&auth_user.metadata.soft_get("sparkler:custom_css"));
-    format!("{out}\n}}")
+
+    // metadata saved before css was sanitized on write is sanitized here
+    sanitize_css(&format!("{out}\n}}")).0
 }
 
 /// Enable TOTP for a user.
 pub async fn enable_totp_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAccount>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::ManageAccount)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // update
     match database.enable_totp(auth_user, &id).await {
         Ok(t) => {
@@ -1558,35 +1473,11 @@ pub async fn enable_totp_request(
 
 /// Disable TOTP for a user.
 pub async fn disable_totp_request(
-    jar: CookieJar,
+    _: Auth<scope::ManageAccount>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<TOTPDisable>,
 ) -> impl IntoResponse {
-    // get user from token
-    match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::ManageAccount)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // get profile
     let profile = match database.get_profile(&id).await {
         Ok(p) => p,
@@ -1616,35 +1507,11 @@ pub async fn disable_totp_request(
 
 /// Refresh TOTP recovery codes for a user.
 pub async fn refresh_totp_recovery_codes_request(
-    jar: CookieJar,
+    _: Auth<scope::ManageAccount>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<TOTPDisable>,
 ) -> impl IntoResponse {
-    // get user from token
-    match jar.get("__Secure-Token") {
-        Some(c) => {
-            let token = c.value_trimmed();
-
-            match database.get_profile_by_unhashed(token).await {
-                Ok(ua) => {
-                    // check token permission
-                    if !ua
-                        .token_context_from_token(&token)
-                        .can_do(TokenPermission::ManageAccount)
-                    {
-                        return Json(DatabaseError::NotAllowed.to_json());
-                    }
-
-                    // return
-                    ua
-                }
-                Err(e) => return Json(e.to_json()),
-            }
-        }
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // get profile
     let profile = match database.get_profile(&id).await {
         Ok(p) => p,
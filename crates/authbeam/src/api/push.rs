@@ -0,0 +1,68 @@
+use crate::database::Database;
+use crate::extract::{scope, Auth};
+use crate::model::{DatabaseError, PushSubscriptionCreate, PushSubscriptionDelete};
+use databeam::prelude::DefaultReturn;
+
+use axum::response::IntoResponse;
+use axum::{extract::State, Json};
+
+/// Get the VAPID public key browsers subscribe with
+pub async fn key_request(State(database): State<Database>) -> impl IntoResponse {
+    match database.push {
+        Some(ref push) => Json(DefaultReturn {
+            success: true,
+            message: String::new(),
+            payload: Some(push.public_key()),
+        }),
+        None => Json(DefaultReturn {
+            success: false,
+            message: DatabaseError::NotAllowed.to_string(),
+            payload: None,
+        }),
+    }
+}
+
+/// Register a push subscription for the current session
+pub async fn create_request(
+    Auth {
+        user: auth_user,
+        session,
+        ..
+    }: Auth<scope::ManageProfile>,
+    State(database): State<Database>,
+    Json(props): Json<PushSubscriptionCreate>,
+) -> impl IntoResponse {
+    match database
+        .create_push_subscription(props, auth_user, &session)
+        .await
+    {
+        Ok(s) => Json(DefaultReturn {
+            success: true,
+            message: "Push notifications enabled".to_string(),
+            payload: Some(s),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
+
+/// Remove a push subscription
+pub async fn delete_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    State(database): State<Database>,
+    Json(props): Json<PushSubscriptionDelete>,
+) -> impl IntoResponse {
+    if let Err(e) = database
+        .delete_push_subscription_by_endpoint(&props.endpoint, auth_user)
+        .await
+    {
+        return Json(e.to_json());
+    }
+
+    Json(DefaultReturn {
+        success: true,
+        message: "Push notifications disabled".to_string(),
+        payload: (),
+    })
+}
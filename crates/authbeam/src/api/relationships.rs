@@ -1,5 +1,8 @@
 use crate::database::Database;
-use crate::model::{DatabaseError, UserFollow, RelationshipStatus};
+use crate::extract::{scope, Auth};
+use crate::model::{
+    DatabaseError, FollowRequest, Profile, UserFollow, UserMuteCreate, RelationshipStatus,
+};
 use databeam::prelude::DefaultReturn;
 
 use axum::response::IntoResponse;
@@ -7,23 +10,15 @@ use axum::{
     extract::{Path, State},
     Json,
 };
-use axum_extra::extract::cookie::CookieJar;
 
 /// Toggle following on the given user
 pub async fn follow_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // check block status
     let attempting_to_follow = match database.get_profile(&id).await {
         Ok(ua) => ua,
@@ -50,6 +45,13 @@ pub async fn follow_request(
         });
     }
 
+    // follows of profiles which approve followers are sent as requests instead
+    let is_request = attempting_to_follow.metadata.settings().approve_followers
+        && database
+            .get_follow(&auth_user.id, &attempting_to_follow.id)
+            .await
+            .is_err();
+
     // return
     match database
         .toggle_user_follow(&mut UserFollow {
@@ -60,28 +62,127 @@ pub async fn follow_request(
     {
         Ok(_) => Json(DefaultReturn {
             success: true,
-            message: "Follow toggled".to_string(),
+            message: if is_request {
+                "Follow request toggled".to_string()
+            } else {
+                "Follow toggled".to_string()
+            },
             payload: (),
         }),
         Err(e) => Json(e.to_json()),
     }
 }
 
-/// Send/accept a friend request to/from another user
-pub async fn friend_request(
-    jar: CookieJar,
+/// Get all pending follow requests to the current user
+pub async fn follow_requests_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    match database.get_follow_requests(&auth_user.id).await {
+        Ok(mut requests) => {
+            for request in &mut requests {
+                request.1.clean();
+            }
+
+            Json(DefaultReturn {
+                success: true,
+                message: String::new(),
+                payload: Some(requests),
+            })
+        }
+        Err(e) => Json(DefaultReturn {
+            success: false,
+            message: e.to_string(),
+            payload: None::<Vec<(FollowRequest, Box<Profile>)>>,
+        }),
+    }
+}
+
+/// Accept a pending follow request from another user
+pub async fn accept_follow_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
+    // ...
+    let other_user = match database.get_profile(&id).await {
+        Ok(ua) => ua,
+        Err(_) => {
+            return Json(DefaultReturn {
+                success: false,
+                message: DatabaseError::NotFound.to_string(),
+                payload: (),
+            })
+        }
     };
 
+    // return
+    match database
+        .accept_follow_request(&other_user.id, &auth_user.id)
+        .await
+    {
+        Ok(_) => Json(DefaultReturn {
+            success: true,
+            message: "Follow request accepted!".to_string(),
+            payload: (),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
+
+/// Deny a pending follow request from another user
+pub async fn deny_follow_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    // ...
+    let other_user = match database.get_profile(&id).await {
+        Ok(ua) => ua,
+        Err(_) => {
+            return Json(DefaultReturn {
+                success: false,
+                message: DatabaseError::NotFound.to_string(),
+                payload: (),
+            })
+        }
+    };
+
+    if let Err(e) = database
+        .get_follow_request(&other_user.id, &auth_user.id)
+        .await
+    {
+        return Json(e.to_json());
+    }
+
+    // return
+    match database
+        .delete_follow_request(&other_user.id, &auth_user.id)
+        .await
+    {
+        Ok(_) => Json(DefaultReturn {
+            success: true,
+            message: "Follow request denied".to_string(),
+            payload: (),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
+
+/// Send/accept a friend request to/from another user
+pub async fn friend_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
     // ...
     let other_user = match database.get_profile(&id).await {
         Ok(ua) => ua,
@@ -194,19 +295,12 @@ pub async fn friend_request(
 
 /// Block another user
 pub async fn block_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // ...
     let other_user = match database.get_profile(&id).await {
         Ok(ua) => ua,
@@ -277,19 +371,12 @@ pub async fn block_request(
 
 /// Remove relationship with another user
 pub async fn delete_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // ...
     let other_user = match database.get_profile(&id).await {
         Ok(ua) => ua,
@@ -336,3 +423,69 @@ pub async fn delete_request(
         Err(e) => return Json(e.to_json()),
     }
 }
+
+/// Mute another user
+pub async fn mute_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+    Json(props): Json<UserMuteCreate>,
+) -> impl IntoResponse {
+    // ...
+    let other_user = match database.get_profile(&id).await {
+        Ok(ua) => ua,
+        Err(_) => {
+            return Json(DefaultReturn {
+                success: false,
+                message: DatabaseError::NotFound.to_string(),
+                payload: (),
+            })
+        }
+    };
+
+    // return
+    match database
+        .mute_user(&auth_user.id, &other_user.id, props.duration)
+        .await
+    {
+        Ok(_) => Json(DefaultReturn {
+            success: true,
+            message: "User muted!".to_string(),
+            payload: (),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
+
+/// Unmute another user
+pub async fn unmute_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    // ...
+    let other_user = match database.get_profile(&id).await {
+        Ok(ua) => ua,
+        Err(_) => {
+            return Json(DefaultReturn {
+                success: false,
+                message: DatabaseError::NotFound.to_string(),
+                payload: (),
+            })
+        }
+    };
+
+    // return
+    match database.unmute_user(&auth_user.id, &other_user.id).await {
+        Ok(_) => Json(DefaultReturn {
+            success: true,
+            message: "User unmuted!".to_string(),
+            payload: (),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
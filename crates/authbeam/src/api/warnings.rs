@@ -1,5 +1,6 @@
 use crate::database::Database;
-use crate::model::{DatabaseError, WarningCreate};
+use crate::extract::{scope, Auth};
+use crate::model::{SuspensionCreate, WarningCreate};
 use databeam::prelude::DefaultReturn;
 
 use axum::response::IntoResponse;
@@ -7,23 +8,15 @@ use axum::{
     extract::{Path, State},
     Json,
 };
-use axum_extra::extract::cookie::CookieJar;
 
 /// Create a warning
 pub async fn create_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
     State(database): State<Database>,
     Json(props): Json<WarningCreate>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // return
     match database.create_warning(props, auth_user).await {
         Ok(_) => Json(DefaultReturn {
@@ -37,19 +30,12 @@ pub async fn create_request(
 
 /// Delete a warning
 pub async fn delete_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database.get_profile_by_unhashed(c.value_trimmed()).await {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     // return
     match database.delete_warning(&id, auth_user).await {
         Ok(_) => Json(DefaultReturn {
@@ -60,3 +46,61 @@ pub async fn delete_request(
         Err(e) => Json(e.to_json()),
     }
 }
+
+/// Acknowledge a warning
+pub async fn acknowledge_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    // return
+    match database.acknowledge_warning(&id, auth_user).await {
+        Ok(_) => Json(DefaultReturn {
+            success: true,
+            message: "Warning acknowledged".to_string(),
+            payload: (),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
+
+/// Suspend a user
+pub async fn suspend_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+    Json(props): Json<SuspensionCreate>,
+) -> impl IntoResponse {
+    // return
+    match database.suspend_user(&id, props, auth_user).await {
+        Ok(_) => Json(DefaultReturn {
+            success: true,
+            message: "User suspended".to_string(),
+            payload: (),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
+
+/// Lift a user's suspension
+pub async fn lift_suspension_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    // return
+    match database.lift_suspension(&id, auth_user).await {
+        Ok(_) => Json(DefaultReturn {
+            success: true,
+            message: "Suspension lifted".to_string(),
+            payload: (),
+        }),
+        Err(e) => Json(e.to_json()),
+    }
+}
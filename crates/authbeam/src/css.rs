@@ -0,0 +1,440 @@
+//! Custom CSS sanitizer for profiles, circles and themes.
+//!
+//! The sanitizer never re-serializes CSS. It parses the input with [`cssparser`]
+//! and only cuts (or rewrites) the parts it doesn't allow, so everything else
+//! (including comments, which themes use as markers) is kept exactly as written.
+use cssparser::{
+    AtRuleParser, CowRcStr, DeclarationParser, ParseError, Parser, ParserInput, ParserState,
+    QualifiedRuleParser, RuleBodyItemParser, RuleBodyParser, SourceLocation, SourcePosition, Token,
+};
+use serde::{Deserialize, Serialize};
+
+/// Names (elements, ids and classes) of the site's navigation and auth UI which
+/// custom CSS isn't allowed to select.
+pub const PROTECTED_SELECTORS: &[&str] = &[
+    "nav",
+    "nav_side",
+    "title-img",
+    "title-content",
+    "dialog",
+    "tokens_dialog",
+    "toast_zone",
+    "toast",
+];
+
+/// The route external images are loaded through.
+pub const IMAGE_PROXY: &str = "/api/v0/util/ext/image?img=";
+
+/// The maximum length of [`CssRemoval::source`].
+const SOURCE_MAX_CHARS: usize = 128;
+
+/// The reason part of some CSS was removed by [`sanitize_css`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CssRemovalKind {
+    /// An `@import` rule.
+    Import,
+    /// A `url()` which doesn't point to an image or this site (`javascript:`, etc.).
+    Url,
+    /// A `position: fixed` declaration (or a `position` using `var()`).
+    FixedPosition,
+    /// A rule selecting something in [`PROTECTED_SELECTORS`].
+    ProtectedSelector,
+    /// Something which couldn't be parsed.
+    Invalid,
+}
+
+/// Part of some CSS removed by [`sanitize_css`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CssRemoval {
+    /// Why it was removed
+    pub kind: CssRemovalKind,
+    /// The line the removed CSS started on (starting at 1)
+    pub line: u32,
+    /// The removed CSS (shortened to 128 characters)
+    pub source: String,
+}
+
+/// Sanitize custom CSS.
+///
+/// * `@import` rules are removed
+/// * external `url()`s are routed through [`IMAGE_PROXY`], any other scheme removes the declaration
+/// * `position: fixed` declarations are removed
+/// * rules selecting anything in [`PROTECTED_SELECTORS`] are removed
+///
+/// The CSS can be a stylesheet or a list of declarations (or both, through nesting).
+///
+/// # Returns
+/// `(sanitized css, removals)`
+pub fn sanitize_css(css: &str) -> (String, Vec<CssRemoval>) {
+    let mut input = ParserInput::new(css);
+    let mut parser = Parser::new(&mut input);
+    let mut sanitizer = Sanitizer::default();
+    sanitize_body(&mut parser, &mut sanitizer);
+
+    // apply edits
+    let mut edits = sanitizer.edits;
+    edits.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+
+    let mut out = String::new();
+    let mut cursor = 0;
+
+    for edit in edits {
+        if edit.start < cursor {
+            // inside of something already removed
+            continue;
+        }
+
+        out.push_str(&css[cursor..edit.start]);
+        out.push_str(&edit.replacement);
+        cursor = edit.end;
+    }
+
+    out.push_str(&css[cursor..]);
+
+    // css is rendered inside of `<style>` elements, it must never be able to close them
+    (out.replace("</", "<\\/"), sanitizer.removals)
+}
+
+/// A replacement of `start..end` (byte indexes) in the original CSS.
+struct Edit {
+    start: usize,
+    end: usize,
+    replacement: String,
+}
+
+/// What [`Sanitizer`] decided to do with a declaration or rule.
+enum Item {
+    Keep,
+    Remove(CssRemovalKind),
+}
+
+#[derive(Default)]
+struct Sanitizer {
+    edits: Vec<Edit>,
+    removals: Vec<CssRemoval>,
+}
+
+impl Sanitizer {
+    /// Remove `start..end` and report it.
+    fn remove(
+        &mut self,
+        kind: CssRemovalKind,
+        location: SourceLocation,
+        (start, end): (SourcePosition, SourcePosition),
+        source: &str,
+    ) {
+        self.edits.push(Edit {
+            start: start.byte_index(),
+            end: end.byte_index(),
+            replacement: String::new(),
+        });
+
+        self.removals.push(CssRemoval {
+            kind,
+            line: location.line + 1,
+            source: source.trim().chars().take(SOURCE_MAX_CHARS).collect(),
+        });
+    }
+
+    /// Decide what to do with a prelude once it has been scanned.
+    fn prelude(&mut self, scan: Scan) -> Option<CssRemovalKind> {
+        if scan.bad_url {
+            return Some(CssRemovalKind::Url);
+        }
+
+        if scan
+            .names
+            .iter()
+            .any(|n| PROTECTED_SELECTORS.contains(&n.as_str()))
+        {
+            return Some(CssRemovalKind::ProtectedSelector);
+        }
+
+        self.edits.extend(scan.edits);
+        None
+    }
+
+    /// Sanitize the body of a block rule.
+    fn block<'i, 't>(
+        &mut self,
+        prelude: Option<CssRemovalKind>,
+        input: &mut Parser<'i, 't>,
+    ) -> Item {
+        match prelude {
+            Some(kind) => {
+                while input.next().is_ok() {}
+                Item::Remove(kind)
+            }
+            None => {
+                sanitize_body(input, self);
+                Item::Keep
+            }
+        }
+    }
+}
+
+/// Sanitize a list of declarations and rules.
+fn sanitize_body(input: &mut Parser, sanitizer: &mut Sanitizer) {
+    let mut body = RuleBodyParser::new(input, sanitizer);
+
+    loop {
+        body.input.skip_whitespace();
+        let start = body.input.position();
+        let location = body.input.current_source_location();
+
+        let kind = match body.next() {
+            Some(Ok(Item::Keep)) => continue,
+            Some(Ok(Item::Remove(kind))) => kind,
+            Some(Err(_)) => CssRemovalKind::Invalid,
+            None => break,
+        };
+
+        let end = body.input.position();
+        let source = body.input.slice(start..end);
+        body.parser.remove(kind, location, (start, end), source);
+    }
+}
+
+impl<'i> DeclarationParser<'i> for Sanitizer {
+    type Declaration = Item;
+    type Error = ();
+
+    fn parse_value<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+        _declaration_start: &ParserState,
+    ) -> Result<Item, ParseError<'i, ()>> {
+        let mut scan = Scan::default();
+        scan.tokens(input, false, false)?;
+
+        if scan.bad_url {
+            return Ok(Item::Remove(CssRemovalKind::Url));
+        }
+
+        if name.eq_ignore_ascii_case("position")
+            && (scan.var | scan.names.iter().any(|n| n == "fixed"))
+        {
+            return Ok(Item::Remove(CssRemovalKind::FixedPosition));
+        }
+
+        self.edits.extend(scan.edits);
+        Ok(Item::Keep)
+    }
+}
+
+impl<'i> AtRuleParser<'i> for Sanitizer {
+    type Prelude = Option<CssRemovalKind>;
+    type AtRule = Item;
+    type Error = ();
+
+    fn parse_prelude<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, ()>> {
+        if name.eq_ignore_ascii_case("import") {
+            while input.next().is_ok() {}
+            return Ok(Some(CssRemovalKind::Import));
+        }
+
+        let mut scan = Scan::default();
+        scan.tokens(input, true, false)?;
+        Ok(self.prelude(scan))
+    }
+
+    fn rule_without_block(
+        &mut self,
+        prelude: Self::Prelude,
+        _start: &ParserState,
+    ) -> Result<Item, ()> {
+        Ok(match prelude {
+            Some(kind) => Item::Remove(kind),
+            None => Item::Keep,
+        })
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        prelude: Self::Prelude,
+        _start: &ParserState,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Item, ParseError<'i, ()>> {
+        Ok(self.block(prelude, input))
+    }
+}
+
+impl<'i> QualifiedRuleParser<'i> for Sanitizer {
+    type Prelude = Option<CssRemovalKind>;
+    type QualifiedRule = Item;
+    type Error = ();
+
+    fn parse_prelude<'t>(
+        &mut self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, ()>> {
+        let mut scan = Scan::default();
+        scan.tokens(input, true, false)?;
+        Ok(self.prelude(scan))
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        prelude: Self::Prelude,
+        _start: &ParserState,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Item, ParseError<'i, ()>> {
+        Ok(self.block(prelude, input))
+    }
+}
+
+impl<'i> RuleBodyItemParser<'i, Item, ()> for Sanitizer {
+    fn parse_declarations(&self) -> bool {
+        true
+    }
+
+    fn parse_qualified(&self) -> bool {
+        true
+    }
+}
+
+/// Everything found while scanning the tokens of a value or prelude.
+///
+/// Edits are only committed to the [`Sanitizer`] once the value is kept, since
+/// declarations are retried as rules when they fail to parse.
+#[derive(Default)]
+struct Scan {
+    /// Lowercase identifiers, hashes and strings
+    names: Vec<String>,
+    edits: Vec<Edit>,
+    bad_url: bool,
+    var: bool,
+}
+
+impl Scan {
+    /// Scan all remaining tokens in `input`.
+    ///
+    /// `{}` blocks are only allowed when `blocks` is true, which keeps nested rules
+    /// (`a:hover { ... }`) from parsing as declarations. `strings_are_urls` is
+    /// true inside of `image-set()`.
+    fn tokens<'i, 't>(
+        &mut self,
+        input: &mut Parser<'i, 't>,
+        blocks: bool,
+        strings_are_urls: bool,
+    ) -> Result<(), ParseError<'i, ()>> {
+        loop {
+            let start = input.position();
+            let token = match input.next_including_whitespace_and_comments() {
+                Ok(token) => token.clone(),
+                Err(_) => return Ok(()),
+            };
+
+            match token {
+                Token::QuotedString(ref value) if strings_are_urls => {
+                    self.url(start, input.position(), value)
+                }
+                Token::Ident(ref name)
+                | Token::IDHash(ref name)
+                | Token::Hash(ref name)
+                | Token::QuotedString(ref name) => self.names.push(name.to_ascii_lowercase()),
+                Token::UnquotedUrl(ref url) => self.url(start, input.position(), url),
+                Token::Function(ref name) if name.eq_ignore_ascii_case("url") => {
+                    let url = input.parse_nested_block(|input| {
+                        input.expect_string_cloned().map_err(ParseError::from)
+                    })?;
+
+                    self.url(start, input.position(), &url)
+                }
+                Token::Function(ref name) => {
+                    let name = name.to_ascii_lowercase();
+                    self.var |= name == "var";
+
+                    input.parse_nested_block(|input| {
+                        self.tokens(input, true, name.ends_with("image-set"))
+                    })?
+                }
+                Token::ParenthesisBlock | Token::SquareBracketBlock => {
+                    input.parse_nested_block(|input| self.tokens(input, true, false))?
+                }
+                Token::CurlyBracketBlock if blocks => {
+                    input.parse_nested_block(|input| self.tokens(input, true, false))?
+                }
+                Token::CurlyBracketBlock | Token::BadUrl(_) | Token::BadString(_) => {
+                    return Err(input.new_unexpected_token_error(token))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Check a url found between `start` and `end`.
+    fn url(&mut self, start: SourcePosition, end: SourcePosition, url: &str) {
+        match proxied_url(url) {
+            Some(replacement) => {
+                if replacement == url {
+                    return;
+                }
+
+                let mut css = String::from("url(");
+                let _ = cssparser::serialize_string(&replacement, &mut css);
+                css.push(')');
+
+                self.edits.push(Edit {
+                    start: start.byte_index(),
+                    end: end.byte_index(),
+                    replacement: css,
+                })
+            }
+            None => self.bad_url = true,
+        }
+    }
+}
+
+/// Get the url CSS should use in place of `url`.
+///
+/// # Returns
+/// * `Some(url)`: the url (or the url through [`IMAGE_PROXY`] if it's external)
+/// * `None`: the url isn't allowed
+fn proxied_url(url: &str) -> Option<String> {
+    let url = url.trim();
+    // browsers treat "\" as "/" in urls
+    let normalized = url.replace('\\', "/").to_ascii_lowercase();
+
+    if normalized.starts_with("https://") | normalized.starts_with("http://") {
+        return Some(format!("{IMAGE_PROXY}{}", encode_query(url)));
+    }
+
+    if normalized.starts_with("//") {
+        return Some(format!(
+            "{IMAGE_PROXY}{}",
+            encode_query(&format!("https://{}", &url[2..]))
+        ));
+    }
+
+    if normalized.starts_with("data:image/") {
+        return Some(url.to_string());
+    }
+
+    // anything else with a scheme isn't allowed, relative urls stay on this site
+    match normalized.find(':') {
+        Some(i) if !normalized[..i].contains(['/', '?', '#']) => None,
+        _ => Some(url.to_string()),
+    }
+}
+
+/// Percent-encode everything but unreserved characters.
+fn encode_query(value: &str) -> String {
+    let mut out = String::new();
+
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() | [b'-', b'_', b'.', b'~'].contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    out
+}
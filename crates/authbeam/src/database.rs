@@ -1,13 +1,28 @@
-use std::collections::BTreeMap;
-
-use crate::layout::LayoutComponent;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+use crate::css::sanitize_css;
+use crate::settings::{ProfileSettings, COLOR_KEYS};
+use crate::layout::{LayoutComponent, MARKET_ID_OPTION};
 use crate::model::{
     DatabaseError, FinePermission, IpBan, IpBanCreate, IpBlock, IpBlockCreate, Item, ItemCreate,
     ItemEdit, ItemEditContent, ItemStatus, ItemType, Profile, ProfileCreate, ProfileMetadata,
-    RelationshipStatus, TokenContext, Transaction, TransactionCreate, UserLabel, Warning,
+    RelationshipStatus, TokenContext, Transaction, TransactionCreate, UserLabel, UserMute, Warning,
     WarningCreate,
 };
-use crate::model::{Group, Notification, NotificationCreate, UserFollow};
+use crate::model::{ItemRating, ItemReview, ItemReviewCreate, ItemReviewReply};
+use crate::model::{ItemInstall, ItemSearch, ItemSort, ItemVersion, ItemVersionPin};
+use crate::model::{LedgerDrift, LedgerEntry, LedgerReport};
+use crate::model::{Suspension, SuspensionCreate, WarningSeverity, RESERVED_LABEL_QUARANTINE};
+use crate::model::{
+    FollowRequest, Group, Notification, NotificationCreate, NotificationKind, UserFollow,
+};
+use crate::model::{PushMessage, PushSubscription, PushSubscriptionCreate};
+use crate::push::{PushConfig, PushResult, WebPush};
+use crate::unlock::{unlock_cookie, UnlockKey, UNLOCK_ATTEMPTS_MAX};
+use axum_extra::extract::cookie::CookieJar;
 use hcaptcha_no_wasm::Hcaptcha;
 use rainbeam_shared::snow::AlmostSnowflake;
 use reqwest::Client as HttpClient;
@@ -23,63 +38,13 @@ use std::sync::LazyLock;
 
 use crate::{cache_sync, from_row, update_profile_count, simplify};
 
-/// Custom keys allowed to be used as metadata options.
+/// Custom keys allowed to be used as metadata options (see [`ProfileSettings`]).
 pub static ALLOWED_CUSTOM_KEYS: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
-    vec![
-        "sparkler:display_name",
-        "sparkler:status_note",
-        "sparkler:status_emoji",
-        "sparkler:limited_friend_requests",
-        "sparkler:private_profile",
-        "sparkler:allow_drawings",
-        "sparkler:biography",
-        "sparkler:sidebar",
-        "sparkler:avatar_url",
-        "sparkler:banner_url",
-        "sparkler:banner_fit",
-        "sparkler:website_theme",
-        "sparkler:allow_profile_themes",
-        "sparkler:motivational_header",
-        "sparkler:warning",
-        "sparkler:anonymous_username",
-        "sparkler:anonymous_avatar",
-        "sparkler:pinned",
-        "sparkler:profile_theme",
-        "sparkler:desktop_tl_logo",
-        "sparkler:custom_css",
-        "sparkler:color_surface",
-        "sparkler:color_lowered",
-        "sparkler:color_super_lowered",
-        "sparkler:color_raised",
-        "sparkler:color_super_raised",
-        "sparkler:color_text",
-        "sparkler:color_text_raised",
-        "sparkler:color_text_lowered",
-        "sparkler:color_link",
-        "sparkler:color_primary",
-        "sparkler:color_primary_lowered",
-        "sparkler:color_primary_raised",
-        "sparkler:color_text_primary",
-        "sparkler:color_shadow",
-        "sparkler:lock_profile",
-        "sparkler:disallow_anonymous",
-        "sparkler:disallow_anonymous_comments",
-        "sparkler:require_account",
-        "sparkler:private_social",
-        "sparkler:filter",
-        "rainbeam:verify_url",
-        "rainbeam:verify_code",
-        "rainbeam:market_theme_template",
-        "rainbeam:nsfw_profile",
-        "rainbeam:share_hashtag",
-        "rainbeam:authenticated_only",
-        "rainbeam:force_default_layout",
-        "rainbeam:disallow_response_comments",
-        "rainbeam:view_password",
-        "rainbeam:do_not_send_global_questions_to_inbox",
-        "rainbeam:do_not_clear_inbox_count_on_view",
-        "rainbeam:do_not_send_global_questions_to_friends",
-    ]
+    ProfileSettings::KEYS
+        .iter()
+        .chain(COLOR_KEYS.iter())
+        .copied()
+        .collect()
 });
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -109,6 +74,9 @@ pub struct ServerOptions {
     /// A list of image hosts that are blocked
     #[serde(default)]
     pub blocked_hosts: Vec<String>,
+    /// The policy used to automatically suspend users after repeated warnings
+    #[serde(default)]
+    pub strike_policy: StrikePolicy,
 }
 
 impl Default for ServerOptions {
@@ -122,16 +90,83 @@ impl Default for ServerOptions {
             host: String::new(),
             snowflake_server_id: 1234567890,
             blocked_hosts: Vec::new(),
+            strike_policy: StrikePolicy::default(),
+        }
+    }
+}
+
+/// Automatic suspensions after repeated warnings
+///
+/// Users who collect `strikes` strikes (see [`WarningSeverity::strikes`]) within `window`
+/// are suspended for `duration`. Only strikes received after the user's last suspension
+/// are counted.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StrikePolicy {
+    /// If the strike policy is enforced
+    #[serde(default)]
+    pub enabled: bool,
+    /// The number of strikes which triggers a suspension
+    pub strikes: usize,
+    /// The time (in milliseconds) strikes are counted over
+    pub window: u128,
+    /// The length of the suspension (in milliseconds)
+    pub duration: u128,
+}
+
+impl Default for StrikePolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            strikes: 3,
+            window: 30 * 24 * 60 * 60 * 1000,
+            duration: 7 * 24 * 60 * 60 * 1000,
+        }
+    }
+}
+
+impl StrikePolicy {
+    /// Pull configuration file
+    pub fn get_config() -> Self {
+        let path = PathBufD::current().extend(&[".config", "strikes", "config.toml"]);
+
+        match rainbeam_shared::fs::read(path) {
+            Ok(c) => toml::from_str::<Self>(&c).unwrap(),
+            Err(_) => {
+                let config = Self::default();
+                Self::update_config(config.clone()).expect("failed to write default strike policy");
+                config
+            }
         }
     }
+
+    /// Update configuration file
+    pub fn update_config(contents: Self) -> std::io::Result<()> {
+        let dir = PathBufD::current().extend(&[".config", "strikes"]);
+        rainbeam_shared::fs::mkdir(&dir)?;
+
+        rainbeam_shared::fs::write(
+            dir.join("config.toml"),
+            toml::to_string_pretty::<Self>(&contents).unwrap(),
+        )
+    }
 }
 
+/// A hook run with the ID of a profile after it has been deleted
+pub type ProfileDeleteHook =
+    Arc<dyn Fn(String) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
 /// Database connector
 #[derive(Clone)]
 pub struct Database {
     pub base: StarterDatabase,
     pub config: ServerOptions,
     pub http: HttpClient,
+    /// The Web Push client, `None` if push notifications are disabled
+    pub push: Option<Arc<WebPush>>,
+    /// The key profile unlock cookies are signed with
+    pub unlock_key: UnlockKey,
+    /// Hooks run after a profile is deleted (see [`Database::on_profile_delete`])
+    pub profile_delete_hooks: Arc<RwLock<Vec<ProfileDeleteHook>>>,
 }
 
 impl Database {
@@ -139,6 +174,7 @@ impl Database {
     pub async fn new(
         database_options: databeam::DatabaseOpts,
         server_options: ServerOptions,
+        push_config: PushConfig,
     ) -> Self {
         let base = StarterDatabase::new(database_options).await;
 
@@ -146,6 +182,9 @@ impl Database {
             base: base.clone(),
             http: HttpClient::new(),
             config: server_options,
+            push: WebPush::new(push_config).map(Arc::new),
+            unlock_key: UnlockKey::generate(),
+            profile_delete_hooks: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
@@ -220,6 +259,16 @@ impl Database {
         .execute(c)
         .await;
 
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xfollow_requests\" (
+                user      TEXT,
+                following TEXT,
+                timestamp TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
         let _ = sqlquery(
             "CREATE TABLE IF NOT EXISTS \"xnotifications\" (
                 title     TEXT,
@@ -227,19 +276,38 @@ impl Database {
                 address   TEXT,
                 timestamp TEXT,
                 id        TEXT,
-                recipient TEXT
+                recipient TEXT,
+                kind      TEXT,
+                read      TEXT,
+                others    TEXT
             )",
         )
         .execute(c)
         .await;
 
         let _ = sqlquery(
-            "CREATE TABLE IF NOT EXISTS \"xwarnings\" (
-                id        TEXT,
-                content   TEXT,
+            "CREATE TABLE IF NOT EXISTS \"xsuspensions\" (
+                user      TEXT,
+                reason    TEXT,
+                moderator TEXT,
                 timestamp TEXT,
-                recipient TEXT,
-                moderator TEXT
+                expires   TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xwarnings\" (
+                id           TEXT,
+                content      TEXT,
+                timestamp    TEXT,
+                recipient    TEXT,
+                moderator    TEXT,
+                severity     TEXT,
+                rule         TEXT,
+                strikes      TEXT,
+                acknowledged TEXT
             )",
         )
         .execute(c)
@@ -268,6 +336,17 @@ impl Database {
         .execute(c)
         .await;
 
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xmutes\" (
+                user      TEXT,
+                muted     TEXT,
+                timestamp TEXT,
+                expires   TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
         let _ = sqlquery(
             "CREATE TABLE IF NOT EXISTS \"xipblocks\" (
                 id        TEXT,
@@ -305,6 +384,36 @@ impl Database {
         .execute(c)
         .await;
 
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xugc_reviews\" (
+                id        TEXT,
+                item      TEXT,
+                author    TEXT,
+                purchase  TEXT,
+                rating    TEXT,
+                content   TEXT,
+                reply     TEXT,
+                timestamp TEXT,
+                replied   TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xugc_ledger\" (
+                id          TEXT,
+                transfer    TEXT,
+                account     TEXT,
+                amount      TEXT,
+                reference   TEXT,
+                idempotency TEXT,
+                timestamp   TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
         let _ = sqlquery(
             "CREATE TABLE IF NOT EXISTS \"xugc_items\" (
                 id          TEXT,
@@ -320,6 +429,137 @@ impl Database {
         )
         .execute(c)
         .await;
+
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xugc_item_tags\" (
+                item TEXT,
+                tag  TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xugc_item_versions\" (
+                id        TEXT,
+                item      TEXT,
+                number    TEXT,
+                content   TEXT,
+                changelog TEXT,
+                status    TEXT,
+                timestamp TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xugc_item_pins\" (
+                item    TEXT,
+                user    TEXT,
+                version TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xugc_item_installs\" (
+                user      TEXT,
+                type      TEXT,
+                item      TEXT,
+                previous  TEXT,
+                timestamp TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xpush_subscriptions\" (
+                id        TEXT,
+                user      TEXT,
+                session   TEXT,
+                endpoint  TEXT,
+                p256dh    TEXT,
+                auth      TEXT,
+                timestamp TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        // view passwords saved before they were hashed
+        self.hash_view_passwords().await;
+    }
+
+    /// Hash every [`ProfileSettings::view_password`] which is still stored in plaintext
+    /// (see [`ProfileSettings::hash_view_password`])
+    pub async fn hash_view_passwords(&self) {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xprofiles\" WHERE \"metadata\" LIKE ?"
+        } else {
+            "SELECT * FROM \"xprofiles\" WHERE \"metadata\" LIKE $1"
+        };
+
+        let c = &self.base.db.client;
+        let rows = match sqlquery(query)
+            .bind::<&str>("%\"rainbeam:view_password\"%")
+            .fetch_all(c)
+            .await
+        {
+            Ok(r) => r,
+            Err(_) => return,
+        };
+
+        for row in rows {
+            let row = self.base.textify_row(row).0;
+
+            let id = from_row!(row->id());
+            let username = from_row!(row->username());
+            let mut metadata: ProfileMetadata =
+                match serde_json::from_str(&from_row!(row->metadata())) {
+                    Ok(m) => m,
+                    Err(_) => continue,
+                };
+
+            let mut settings = metadata.settings();
+
+            if !settings.has_view_password() | settings.is_view_password_hashed() {
+                continue;
+            }
+
+            settings.hash_view_password();
+            metadata.set_settings(&settings);
+
+            // update user
+            let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                "UPDATE \"xprofiles\" SET \"metadata\" = ? WHERE \"id\" = ?"
+            } else {
+                "UPDATE \"xprofiles\" SET (\"metadata\") = ($1) WHERE \"id\" = $2"
+            };
+
+            let meta = &serde_json::to_string(&metadata).unwrap();
+            if sqlquery(query)
+                .bind::<&str>(meta)
+                .bind::<&str>(&id)
+                .execute(c)
+                .await
+                .is_err()
+            {
+                continue;
+            }
+
+            self.base
+                .cache
+                .remove(format!("rbeam.auth.profile:{}", username))
+                .await;
+
+            self.base
+                .cache
+                .remove(format!("rbeam.auth.profile:{}", id))
+                .await;
+        }
     }
 
     // util
@@ -333,6 +573,7 @@ impl Database {
                     content: content.to_string(),
                     address: format!("/+u/{actor_id}"),
                     recipient: "*(audit)".to_string(), // all staff, audit registry
+                    kind: NotificationKind::System,
                 },
                 None,
             )
@@ -350,8 +591,7 @@ impl Database {
         let id = from_row!(row->id());
 
         let metadata: ProfileMetadata = from_row!(row->metadata(json); DatabaseError::ValueError);
-        let do_not_clear_inbox_count_on_view =
-            metadata.is_true("rainbeam:do_not_clear_inbox_count_on_view");
+        let do_not_clear_inbox_count_on_view = metadata.settings().do_not_clear_inbox_count_on_view;
 
         Ok(Box::new(Profile {
             id: id.clone(),
@@ -787,16 +1027,32 @@ impl Database {
         };
 
         // check metadata kv
-        for kv in metadata.kv.clone() {
-            if !ALLOWED_CUSTOM_KEYS.contains(&kv.0.as_str()) {
-                metadata.kv.remove(&kv.0);
-            }
+        let mut settings = match ProfileSettings::parse_changes(&profile.metadata.kv, &metadata.kv)
+        {
+            Ok(s) => s,
+            Err(_) => return Err(DatabaseError::ValueError),
+        };
+
+        // view passwords are only ever stored hashed
+        if (settings.view_password != profile.metadata.settings().view_password)
+            | !settings.is_view_password_hashed()
+        {
+            settings.hash_view_password();
         }
 
+        settings.write_kv(&mut metadata.kv);
+
         if !metadata.check() {
             return Err(DatabaseError::TooLong);
         }
 
+        // the email is used as a mail header, so it has to be a single plain address
+        if (metadata.email != profile.metadata.email) && !metadata.check_email() {
+            return Err(DatabaseError::ValueError);
+        }
+
+        metadata.sanitize_css();
+
         // update user
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
             "UPDATE \"xprofiles\" SET \"metadata\" = ? WHERE \"id\" = ?"
@@ -967,6 +1223,61 @@ impl Database {
         }
     }
 
+    /// Quarantine a [`Profile`] or lift its quarantine ([`RESERVED_LABEL_QUARANTINE`])
+    ///
+    /// # Arguments
+    /// * `id`
+    /// * `quarantined`
+    /// * `moderator` - the user doing this
+    pub async fn update_profile_quarantine(
+        &self,
+        id: &str,
+        quarantined: bool,
+        moderator: Box<Profile>,
+    ) -> Result<()> {
+        // check permission
+        let group = match self.get_group_by_id(moderator.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        if !group
+            .permissions
+            .check(FinePermission::MANAGE_PROFILE_SETTINGS)
+        {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // make sure user exists
+        let ua = self.get_profile(id).await?;
+
+        if ua.is_quarantined() == quarantined {
+            return Ok(());
+        }
+
+        // update labels
+        let mut labels = ua.labels.clone();
+
+        if quarantined {
+            labels.push(RESERVED_LABEL_QUARANTINE);
+        } else {
+            labels.retain(|l| *l != RESERVED_LABEL_QUARANTINE);
+        }
+
+        self.update_profile_labels(&ua.id, labels).await?;
+
+        // audit
+        self.audit(
+            &moderator.id,
+            &if quarantined {
+                format!("Quarantined a user: [{}](/+u/{})", ua.id, ua.id)
+            } else {
+                format!("Lifted a quarantine: [{}](/+u/{})", ua.id, ua.id)
+            },
+        )
+        .await
+    }
+
     /// Update a [`Profile`]'s links by its `id`
     pub async fn update_profile_links(
         &self,
@@ -1013,13 +1324,20 @@ impl Database {
     }
 
     /// Update a [`Profile`]'s links by its `id`
-    pub async fn update_profile_layout(&self, id: &str, layout: LayoutComponent) -> Result<()> {
+    pub async fn update_profile_layout(&self, id: &str, mut layout: LayoutComponent) -> Result<()> {
         // make sure user exists
         let ua = match self.get_profile(&id).await {
             Ok(ua) => ua,
             Err(e) => return Err(e),
         };
 
+        // check layout (see `LayoutComponent::validate` for the reason)
+        if layout.validate().is_err() {
+            return Err(DatabaseError::ValueError);
+        }
+
+        layout.sanitize_css();
+
         // update user
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
             "UPDATE \"xprofiles\" SET \"layout\" = ? WHERE \"id\" = ?"
@@ -1133,6 +1451,8 @@ impl Database {
 
     /// Update a [`Profile`]'s coins by its ID
     ///
+    /// Coins are granted from (or returned to) the system account through the ledger.
+    ///
     /// # Arguments
     /// * `coins` - the amount to ADD to the existing coins value
     pub async fn update_profile_coins(&self, id: &str, coins: i32) -> Result<()> {
@@ -1142,34 +1462,21 @@ impl Database {
             Err(e) => return Err(e),
         };
 
-        // update user
-        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            "UPDATE \"xprofiles\" SET \"coins\" = ? WHERE \"id\" = ?"
-        } else {
-            "UPDATE \"xprofiles\" SET (\"coins\") = ($1) WHERE \"id\" = $2"
-        };
+        let system = Profile::system();
 
-        let c = &self.base.db.client;
-        match sqlquery(query)
-            .bind::<i32>(ua.coins + coins)
-            .bind::<&str>(&id)
-            .execute(c)
+        // update user
+        match self
+            .transfer_coins(
+                &AlmostSnowflake::new(self.config.snowflake_server_id).to_string(),
+                vec![(&ua, coins), (&system, -coins)],
+                "grant",
+                "",
+                None,
+            )
             .await
         {
-            Ok(_) => {
-                self.base
-                    .cache
-                    .remove(format!("rbeam.auth.profile:{}", ua.username))
-                    .await;
-
-                self.base
-                    .cache
-                    .remove(format!("rbeam.auth.profile:{}", ua.id))
-                    .await;
-
-                Ok(())
-            }
-            Err(_) => Err(DatabaseError::Other),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
         }
     }
 
@@ -1350,6 +1657,23 @@ impl Database {
                     return Err(DatabaseError::Other);
                 };
 
+                let query: &str =
+                    if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                        "DELETE FROM \"xfollow_requests\" WHERE \"user\" = ? OR \"following\" = ?"
+                    } else {
+                        "DELETE FROM \"xfollow_requests\" WHERE \"user\" = $1 OR \"following\" = $2"
+                    };
+
+                if sqlquery(query)
+                    .bind::<&str>(id)
+                    .bind::<&str>(id)
+                    .execute(c)
+                    .await
+                    .is_err()
+                {
+                    return Err(DatabaseError::Other);
+                };
+
                 // rainbeam crate stuff
                 // questions to user
                 let query: &str =
@@ -1435,6 +1759,24 @@ impl Database {
                     .remove(format!("rbeam.app.friends_count:{}", id))
                     .await;
 
+                // mutes involving user
+                let query: &str =
+                    if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                        "DELETE FROM \"xmutes\" WHERE \"user\" = ? OR \"muted\" = ?"
+                    } else {
+                        "DELETE FROM \"xmutes\" WHERE \"user\" = $1 OR \"muted\" = $2"
+                    };
+
+                if sqlquery(query)
+                    .bind::<&str>(id)
+                    .bind::<&str>(id)
+                    .execute(c)
+                    .await
+                    .is_err()
+                {
+                    return Err(DatabaseError::Other);
+                };
+
                 // ipblocks by user
                 let query: &str =
                     if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
@@ -1545,7 +1887,26 @@ impl Database {
         }
 
         // delete
-        self.delete_profile(id).await
+        self.delete_profile(id).await?;
+
+        // run hooks
+        let hooks = self.profile_delete_hooks.read().unwrap().clone();
+
+        for hook in hooks {
+            hook(id.to_string()).await;
+        }
+
+        Ok(())
+    }
+
+    /// Register a hook to run after a profile is deleted
+    ///
+    /// Used by dependent crates to clean up data they own.
+    ///
+    /// # Arguments
+    /// * `hook`
+    pub fn on_profile_delete(&self, hook: ProfileDeleteHook) {
+        self.profile_delete_hooks.write().unwrap().push(hook);
     }
 
     // groups
@@ -1928,8 +2289,9 @@ impl Database {
         };
 
         // make sure both users exist
-        if let Err(e) = self.get_profile(&props.following).await {
-            return Err(e);
+        let user_2 = match self.get_profile(&props.following).await {
+            Ok(ua) => ua,
+            Err(e) => return Err(e),
         };
 
         // check if follow exists
@@ -1965,7 +2327,52 @@ impl Database {
             };
         }
 
-        // return
+        // check if followers need to be approved
+        if user_2.metadata.settings().approve_followers {
+            // toggle follow request
+            if self
+                .get_follow_request(&props.user, &props.following)
+                .await
+                .is_ok()
+            {
+                return self
+                    .delete_follow_request(&props.user, &props.following)
+                    .await;
+            }
+
+            return self.create_follow_request(&user_1, &user_2).await;
+        }
+
+        // follow
+        self.create_user_follow(&props.user, &props.following).await?;
+
+        // create notification
+        if self.is_muted(&props.following, &props.user).await {
+            return Ok(());
+        }
+
+        self.create_notification(
+            NotificationCreate {
+                title: format!("[@{}](/+u/{}) followed you!", user_1.username, user_1.id),
+                content: String::new(),
+                address: format!("/@{}/followers", user_2.username),
+                recipient: props.following.clone(),
+                kind: NotificationKind::Follow,
+            },
+            None,
+        )
+        .await?;
+
+        // return
+        Ok(())
+    }
+
+    /// Insert a [`UserFollow`] of `user` on `following` and bump counts, without any checks
+    ///
+    /// # Arguments:
+    /// * `user`
+    /// * `following`
+    async fn create_user_follow(&self, user: &str, following: &str) -> Result<()> {
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
             "INSERT INTO \"xfollows\" VALUES (?, ?)"
         } else {
@@ -1973,9 +2380,9 @@ impl Database {
         };
 
         let c = &self.base.db.client;
-        match sqlquery(&query)
-            .bind::<&str>(&props.user)
-            .bind::<&str>(&props.following)
+        match sqlquery(query)
+            .bind::<&str>(user)
+            .bind::<&str>(following)
             .execute(c)
             .await
         {
@@ -1983,34 +2390,14 @@ impl Database {
                 // bump counts
                 self.base
                     .cache
-                    .incr(format!("rbeam.auth.following_count:{}", props.user))
+                    .incr(format!("rbeam.auth.following_count:{}", user))
                     .await;
 
                 self.base
                     .cache
-                    .incr(format!("rbeam.auth.followers_count:{}", props.following))
+                    .incr(format!("rbeam.auth.followers_count:{}", following))
                     .await;
 
-                // create notification
-                if let Err(e) = self
-                    .create_notification(
-                        NotificationCreate {
-                            title: format!(
-                                "[@{}](/+u/{}) followed you!",
-                                user_1.username, user_1.id
-                            ),
-                            content: String::new(),
-                            address: format!("/+u/{}", user_1.id),
-                            recipient: props.following.clone(),
-                        },
-                        None,
-                    )
-                    .await
-                {
-                    return Err(e);
-                };
-
-                // return
                 Ok(())
             }
             Err(_) => Err(DatabaseError::Other),
@@ -2065,8 +2452,369 @@ impl Database {
         Ok(())
     }
 
+    // follow requests
+
+    // GET
+    /// Get an existing [`FollowRequest`]
+    ///
+    /// # Arguments:
+    /// * `user`
+    /// * `following`
+    pub async fn get_follow_request(&self, user: &str, following: &str) -> Result<FollowRequest> {
+        // fetch from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xfollow_requests\" WHERE \"user\" = ? AND \"following\" = ?"
+        } else {
+            "SELECT * FROM \"xfollow_requests\" WHERE \"user\" = $1 AND \"following\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        let row = match sqlquery(query)
+            .bind::<&str>(user)
+            .bind::<&str>(following)
+            .fetch_one(c)
+            .await
+        {
+            Ok(u) => self.base.textify_row(u).0,
+            Err(_) => return Err(DatabaseError::NotFound),
+        };
+
+        // return
+        Ok(FollowRequest {
+            user: from_row!(row->user()),
+            following: from_row!(row->following()),
+            timestamp: from_row!(row->timestamp(u128); 0),
+        })
+    }
+
+    /// Get all pending [`FollowRequest`]s to follow `user`, along with the profile of the
+    /// user requesting to follow
+    ///
+    /// # Arguments:
+    /// * `user`
+    pub async fn get_follow_requests(
+        &self,
+        user: &str,
+    ) -> Result<Vec<(FollowRequest, Box<Profile>)>> {
+        // fetch from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xfollow_requests\" WHERE \"following\" = ? ORDER BY \"timestamp\" DESC"
+        } else {
+            "SELECT * FROM \"xfollow_requests\" WHERE \"following\" = $1 ORDER BY \"timestamp\" DESC"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(user).fetch_all(c).await {
+            Ok(u) => {
+                let mut out = Vec::new();
+
+                for row in u {
+                    let row = self.base.textify_row(row).0;
+
+                    let request = FollowRequest {
+                        user: from_row!(row->user()),
+                        following: from_row!(row->following()),
+                        timestamp: from_row!(row->timestamp(u128); 0),
+                    };
+
+                    let profile = match self.get_profile(&request.user).await {
+                        Ok(ua) => ua,
+                        Err(_) => continue,
+                    };
+
+                    out.push((request, profile));
+                }
+
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Check if `viewer` is allowed to view the content of `owner`, given
+    /// `rainbeam:approve_followers`
+    ///
+    /// Approved followers, friends, staff and `owner` can always view.
+    ///
+    /// # Arguments:
+    /// * `owner`
+    /// * `viewer`
+    pub async fn is_approved_viewer(&self, owner: &Profile, viewer: Option<&Profile>) -> bool {
+        if !owner.metadata.settings().approve_followers {
+            return true;
+        }
+
+        let viewer = match viewer {
+            Some(ua) => ua,
+            None => return false,
+        };
+
+        if viewer.id == owner.id {
+            return true;
+        }
+
+        if self.get_follow(&viewer.id, &owner.id).await.is_ok() {
+            return true;
+        }
+
+        if self.get_user_relationship(&owner.id, &viewer.id).await.0 == RelationshipStatus::Friends
+        {
+            return true;
+        }
+
+        // staff can view everything
+        match self.get_group_by_id(viewer.group).await {
+            Ok(g) => g.permissions.check_helper(),
+            Err(_) => false,
+        }
+    }
+
+    /// Check if `viewer` is allowed to view the content of `owner`, given
+    /// [`RESERVED_LABEL_QUARANTINE`]
+    ///
+    /// Followers, staff and `owner` can always view.
+    ///
+    /// # Arguments:
+    /// * `owner`
+    /// * `viewer`
+    pub async fn is_quarantine_viewer(&self, owner: &Profile, viewer: Option<&Profile>) -> bool {
+        if !owner.is_quarantined() {
+            return true;
+        }
+
+        let viewer = match viewer {
+            Some(ua) => ua,
+            None => return false,
+        };
+
+        if viewer.id == owner.id {
+            return true;
+        }
+
+        if self.get_follow(&viewer.id, &owner.id).await.is_ok() {
+            return true;
+        }
+
+        // staff can view everything
+        match self.get_group_by_id(viewer.group).await {
+            Ok(g) => g.permissions.check_helper(),
+            Err(_) => false,
+        }
+    }
+
+    /// Check if `viewer` is allowed to view the content of `owner`, given
+    /// `rainbeam:view_password`
+    ///
+    /// Staff and `owner` can always view, everybody else needs a valid unlock cookie
+    /// (see [`Database::unlock_profile`]).
+    ///
+    /// # Arguments:
+    /// * `owner`
+    /// * `viewer`
+    /// * `jar` - the cookies of the request
+    pub async fn is_unlocked_viewer(
+        &self,
+        owner: &Profile,
+        viewer: Option<&Profile>,
+        jar: &CookieJar,
+    ) -> bool {
+        if !owner.metadata.settings().has_view_password() {
+            return true;
+        }
+
+        if let Some(cookie) = jar.get(&unlock_cookie(&owner.id)) {
+            if self.unlock_key.verify(owner, cookie.value_trimmed()) {
+                return true;
+            }
+        }
+
+        let viewer = match viewer {
+            Some(ua) => ua,
+            None => return false,
+        };
+
+        if viewer.id == owner.id {
+            return true;
+        }
+
+        // staff can view everything
+        match self.get_group_by_id(viewer.group).await {
+            Ok(g) => g.permissions.check_helper(),
+            Err(_) => false,
+        }
+    }
+
+    /// Unlock `owner` using its `rainbeam:view_password`
+    ///
+    /// Each IP gets [`UNLOCK_ATTEMPTS_MAX`] failed attempts per profile, which reset an
+    /// hour after the last one.
+    ///
+    /// # Arguments:
+    /// * `owner`
+    /// * `password` - the unhashed password
+    /// * `ip` - the IP address of the user unlocking (or the address of their connection)
+    ///
+    /// # Returns
+    /// The value of the profile's [`unlock_cookie`]
+    pub async fn unlock_profile(
+        &self,
+        owner: &Profile,
+        password: &str,
+        ip: &str,
+    ) -> Result<String> {
+        let settings = owner.metadata.settings();
+
+        if !settings.has_view_password() {
+            return Err(DatabaseError::NotFound);
+        }
+
+        // check attempts
+        let key = format!("rbeam.auth.unlock_attempts:{}:{}", owner.id, ip);
+        let attempts = match self.base.cache.get_timed::<usize>(key.clone()).await {
+            Some(a) => a.1,
+            None => 0,
+        };
+
+        if attempts >= UNLOCK_ATTEMPTS_MAX {
+            return Err(DatabaseError::TooManyAttempts);
+        }
+
+        // check password
+        if !settings.check_view_password(password) {
+            self.base.cache.set_timed(key, attempts + 1).await;
+            return Err(DatabaseError::IncorrectPassword);
+        }
+
+        self.base.cache.remove(key).await;
+        Ok(self.unlock_key.sign(owner))
+    }
+
+    // SET
+    /// Create a [`FollowRequest`] from `user` to `following`
+    ///
+    /// # Arguments:
+    /// * `user`
+    /// * `following`
+    async fn create_follow_request(&self, user: &Profile, following: &Profile) -> Result<()> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "INSERT INTO \"xfollow_requests\" VALUES (?, ?, ?)"
+        } else {
+            "INSERT INTO \"xfollow_requests\" VALUES ($1, $2, $3)"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(&user.id)
+            .bind::<&str>(&following.id)
+            .bind::<&str>(&rainbeam_shared::unix_epoch_timestamp().to_string())
+            .execute(c)
+            .await
+        {
+            Ok(_) => {
+                // create notification
+                if self.is_muted(&following.id, &user.id).await {
+                    return Ok(());
+                }
+
+                self.create_notification(
+                    NotificationCreate {
+                        title: format!(
+                            "[@{}](/+u/{}) has requested to follow you!",
+                            user.username, user.id
+                        ),
+                        content: String::new(),
+                        address: format!("/@{}/followers/requests", following.username),
+                        recipient: following.id.clone(),
+                        kind: NotificationKind::Follow,
+                    },
+                    None,
+                )
+                .await
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Delete (cancel or deny) the [`FollowRequest`] from `user` to `following`
+    ///
+    /// # Arguments:
+    /// * `user`
+    /// * `following`
+    pub async fn delete_follow_request(&self, user: &str, following: &str) -> Result<()> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "DELETE FROM \"xfollow_requests\" WHERE \"user\" = ? AND \"following\" = ?"
+        } else {
+            "DELETE FROM \"xfollow_requests\" WHERE \"user\" = $1 AND \"following\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(user)
+            .bind::<&str>(following)
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Accept the [`FollowRequest`] from `user` to `following`, creating the [`UserFollow`]
+    ///
+    /// # Arguments:
+    /// * `user`
+    /// * `following`
+    pub async fn accept_follow_request(&self, user: &str, following: &str) -> Result<()> {
+        // make sure the request exists
+        self.get_follow_request(user, following).await?;
+
+        let user_1 = self.get_profile(user).await?;
+        let user_2 = self.get_profile(following).await?;
+
+        // follow
+        self.delete_follow_request(user, following).await?;
+
+        if self.get_follow(user, following).await.is_err() {
+            self.create_user_follow(user, following).await?;
+        }
+
+        // create notification
+        self.create_notification(
+            NotificationCreate {
+                title: format!(
+                    "[@{}](/+u/{}) has accepted your follow request!",
+                    user_2.username, user_2.id
+                ),
+                content: String::new(),
+                address: format!("/+u/{}", user_2.id),
+                recipient: user_1.id.clone(),
+                kind: NotificationKind::Follow,
+            },
+            None,
+        )
+        .await
+    }
+
     // notifications
 
+    /// Get a [`Notification`] from a database result
+    pub fn gimme_notification(&self, res: BTreeMap<String, String>) -> Notification {
+        Notification {
+            title: from_row!(res->title()),
+            content: from_row!(res->content()),
+            address: from_row!(res->address()),
+            timestamp: from_row!(res->timestamp(u128); 0),
+            id: from_row!(res->id()),
+            recipient: from_row!(res->recipient()),
+            kind: match res.get("kind") {
+                Some(k) => serde_json::from_str(k).unwrap_or_default(),
+                None => NotificationKind::default(),
+            },
+            read: from_row!(res->read(bool); false),
+            others: from_row!(res->others(usize); 0),
+        }
+    }
+
     // GET
     /// Get an existing notification
     ///
@@ -2098,14 +2846,7 @@ impl Database {
         };
 
         // return
-        let notification = Notification {
-            title: from_row!(res->title()),
-            content: from_row!(res->content()),
-            address: from_row!(res->address()),
-            timestamp: from_row!(res->timestamp(u128); 0),
-            id: from_row!(res->id()),
-            recipient: from_row!(res->recipient()),
-        };
+        let notification = self.gimme_notification(res);
 
         // store in cache
         self.base
@@ -2146,14 +2887,7 @@ impl Database {
 
                 for row in p {
                     let res = self.base.textify_row(row).0;
-                    out.push(Notification {
-                        title: from_row!(res->title()),
-                        content: from_row!(res->content()),
-                        address: from_row!(res->address()),
-                        timestamp: from_row!(res->timestamp(u128); 0),
-                        id: from_row!(res->id()),
-                        recipient: from_row!(res->recipient()),
-                    });
+                    out.push(self.gimme_notification(res));
                 }
 
                 out
@@ -2165,7 +2899,7 @@ impl Database {
         Ok(res)
     }
 
-    /// Get the number of notifications by their recipient
+    /// Get the number of unread notifications by their recipient
     ///
     /// # Arguments
     /// * `recipient`
@@ -2185,7 +2919,9 @@ impl Database {
             .get_notifications_by_recipient(recipient)
             .await
             .unwrap_or(Vec::new())
-            .len();
+            .iter()
+            .filter(|n| !n.read)
+            .count();
 
         self.base
             .cache
@@ -2237,14 +2973,7 @@ impl Database {
 
                 for row in p {
                     let res = self.base.textify_row(row).0;
-                    out.push(Notification {
-                        title: from_row!(res->title()),
-                        content: from_row!(res->content()),
-                        address: from_row!(res->address()),
-                        timestamp: from_row!(res->timestamp(u128); 0),
-                        id: from_row!(res->id()),
-                        recipient: from_row!(res->recipient()),
-                    });
+                    out.push(self.gimme_notification(res));
                 }
 
                 out
@@ -2256,76 +2985,318 @@ impl Database {
         Ok(res)
     }
 
-    // SET
-    /// Create a new notification
+    /// Get the unread notification of the given `kind` at `address` for `recipient`
     ///
     /// # Arguments
-    /// * `props` - [`NotificationCreate`]
-    pub async fn create_notification(
+    /// * `recipient`
+    /// * `kind`
+    /// * `address`
+    pub async fn get_unread_notification_by_address(
         &self,
-        props: NotificationCreate,
+        recipient: &str,
+        kind: &NotificationKind,
+        address: &str,
+    ) -> Result<Notification> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xnotifications\" WHERE \"recipient\" = ? AND \"kind\" = ? AND \"address\" = ? AND \"read\" = 'false' ORDER BY \"timestamp\" DESC"
+        } else {
+            "SELECT * FROM \"xnotifications\" WHERE \"recipient\" = $1 AND \"kind\" = $2 AND \"address\" = $3 AND \"read\" = 'false' ORDER BY \"timestamp\" DESC"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(recipient)
+            .bind::<&str>(&serde_json::to_string(kind).unwrap())
+            .bind::<&str>(address)
+            .fetch_one(c)
+            .await
+        {
+            Ok(p) => Ok(self.gimme_notification(self.base.textify_row(p).0)),
+            Err(_) => Err(DatabaseError::NotFound),
+        }
+    }
+
+    /// Recount the unread notifications of the given recipient, updating the cached count
+    /// and [`Profile::notification_count`]
+    ///
+    /// # Arguments
+    /// * `recipient`
+    async fn recount_unread_notifications(&self, recipient: &str) -> Result<()> {
+        let count = self
+            .get_notifications_by_recipient(recipient)
+            .await?
+            .iter()
+            .filter(|n| !n.read)
+            .count();
+
+        self.base
+            .cache
+            .set(
+                format!("rbeam.auth.notification_count:{}", recipient),
+                count.to_string(),
+            )
+            .await;
+
+        self.update_profile_notification_count(recipient, count)
+            .await
+    }
+
+    // SET
+    /// Create a new notification
+    ///
+    /// Notifications of a kind the recipient has muted are silently dropped. Notifications
+    /// which can be aggregated ([`NotificationKind::is_aggregated`]) are merged into the
+    /// recipient's existing unread notification with the same address.
+    ///
+    /// # Arguments
+    /// * `props` - [`NotificationCreate`]
+    pub async fn create_notification(
+        &self,
+        props: NotificationCreate,
         id: Option<String>,
     ) -> Result<()> {
-        let notification = Notification {
-            title: props.title,
-            content: props.content,
-            address: props.address,
-            timestamp: utility::unix_epoch_timestamp(),
-            id: if let Some(id) = id {
-                id
+        let push = PushMessage::new(&props.title, &props.content, &props.address);
+        let kind = props.kind.clone();
+
+        // check recipient preferences
+        let recipient = if !props.recipient.starts_with("*") {
+            let recipient = simplify!(self.get_profile(&props.recipient).await; Result);
+
+            if recipient.metadata.settings().is_muted(&props.kind) {
+                // recipient doesn't want this kind of notification
+                return Ok(());
+            }
+
+            Some(recipient)
+        } else {
+            None
+        };
+
+        // aggregate
+        let existing = if props.kind.is_aggregated() {
+            self.get_unread_notification_by_address(&props.recipient, &props.kind, &props.address)
+                .await
+                .ok()
+        } else {
+            None
+        };
+
+        // aggregated notifications are already counted
+        let inserted = existing.is_none();
+
+        let res = if let Some(existing) = existing {
+            let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                "UPDATE \"xnotifications\" SET \"title\" = ?, \"content\" = ?, \"timestamp\" = ?, \"others\" = ? WHERE \"id\" = ?"
             } else {
-                AlmostSnowflake::new(self.config.snowflake_server_id).to_string()
-            },
-            recipient: props.recipient,
+                "UPDATE \"xnotifications\" SET (\"title\", \"content\", \"timestamp\", \"others\") = ($1, $2, $3, $4) WHERE \"id\" = $5"
+            };
+
+            let c = &self.base.db.client;
+            let res = sqlquery(query)
+                .bind::<&str>(&props.title)
+                .bind::<&str>(&props.content)
+                .bind::<&str>(&utility::unix_epoch_timestamp().to_string())
+                .bind::<&str>(&(existing.others + 1).to_string())
+                .bind::<&str>(&existing.id)
+                .execute(c)
+                .await;
+
+            self.base
+                .cache
+                .remove(format!("rbeam.auth.notification:{}", existing.id))
+                .await;
+
+            res
+        } else {
+            let notification = Notification {
+                title: props.title,
+                content: props.content,
+                address: props.address,
+                timestamp: utility::unix_epoch_timestamp(),
+                id: if let Some(id) = id {
+                    id
+                } else {
+                    AlmostSnowflake::new(self.config.snowflake_server_id).to_string()
+                },
+                recipient: props.recipient.clone(),
+                kind: props.kind,
+                read: false,
+                others: 0,
+            };
+
+            // create notification
+            let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                "INSERT INTO \"xnotifications\" VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            } else {
+                "INSERT INTO \"xnotifications\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+            };
+
+            let c = &self.base.db.client;
+            sqlquery(query)
+                .bind::<&str>(&notification.title)
+                .bind::<&str>(&notification.content)
+                .bind::<&str>(&notification.address)
+                .bind::<&str>(&notification.timestamp.to_string())
+                .bind::<&str>(&notification.id)
+                .bind::<&str>(&notification.recipient)
+                .bind::<&str>(&serde_json::to_string(&notification.kind).unwrap())
+                .bind::<&str>(&notification.read.to_string())
+                .bind::<&str>(&notification.others.to_string())
+                .execute(c)
+                .await
         };
 
-        // create notification
+        match res {
+            Ok(_) => {
+                // incr notifications count
+                if inserted {
+                    self.base
+                        .cache
+                        .incr(format!("rbeam.auth.notification_count:{}", props.recipient))
+                        .await;
+                }
+
+                // check recipient
+                if let Some(recipient) = recipient {
+                    if inserted {
+                        simplify!(
+                            self.update_profile_notification_count(
+                                &recipient.id,
+                                recipient.notification_count + 1,
+                            )
+                            .await; Err
+                        );
+                    }
+
+                    // push
+                    self.queue_push(recipient, kind, push);
+                }
+
+                // ...
+                Ok(())
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Mark an existing notification as read (or unread)
+    ///
+    /// Notifications can only be marked by their recipient.
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the notification
+    /// * `read` - if the notification is read
+    /// * `user` - the user doing this
+    pub async fn update_notification_read(
+        &self,
+        id: &str,
+        read: bool,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        // make sure notification exists
+        let notification = self.get_notification(id).await?;
+
+        // check username
+        if user.id != notification.recipient {
+            // check permission
+            let group = match self.get_group_by_id(user.group).await {
+                Ok(g) => g,
+                Err(_) => return Err(DatabaseError::Other),
+            };
+
+            if !group
+                .permissions
+                .check(FinePermission::MANAGE_NOTIFICATIONS)
+            {
+                return Err(DatabaseError::NotAllowed);
+            }
+        }
+
+        // update notification
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            "INSERT INTO \"xnotifications\" VALUES (?, ?, ?, ?, ?, ?)"
+            "UPDATE \"xnotifications\" SET \"read\" = ? WHERE \"id\" = ?"
         } else {
-            "INSERT INTO \"xnotifications\" VALUES ($1, $2, $3, $4, $5, $6)"
+            "UPDATE \"xnotifications\" SET (\"read\") = ($1) WHERE \"id\" = $2"
         };
 
         let c = &self.base.db.client;
-        match sqlquery(&query)
-            .bind::<&str>(&notification.title)
-            .bind::<&str>(&notification.content)
-            .bind::<&str>(&notification.address)
-            .bind::<&str>(&notification.timestamp.to_string())
-            .bind::<&str>(&notification.id)
-            .bind::<&str>(&notification.recipient)
+        match sqlquery(query)
+            .bind::<&str>(&read.to_string())
+            .bind::<&str>(id)
             .execute(c)
             .await
         {
             Ok(_) => {
-                // incr notifications count
                 self.base
                     .cache
-                    .incr(format!(
-                        "rbeam.auth.notification_count:{}",
-                        notification.recipient
-                    ))
+                    .remove(format!("rbeam.auth.notification:{}", id))
                     .await;
 
-                // check recipient
-                if !notification.recipient.starts_with("*") {
-                    let recipient =
-                        simplify!(self.get_profile(&notification.recipient).await; Result);
-
-                    simplify!(
-                        self.update_profile_notification_count(
-                            &notification.recipient,
-                            recipient.notification_count + 1,
-                        )
-                        .await; Err
-                    );
+                // update unread count
+                if read != notification.read {
+                    self.recount_unread_notifications(&notification.recipient)
+                        .await?;
                 }
 
-                // ...
-                return Ok(());
+                Ok(())
             }
-            Err(_) => return Err(DatabaseError::Other),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Mark all existing notifications by their recipient as read
+    ///
+    /// # Arguments
+    /// * `recipient`
+    /// * `user` - the user doing this
+    pub async fn update_notifications_read_by_recipient(
+        &self,
+        recipient: &str,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        // make sure notifications exists
+        let notifications = self.get_notifications_by_recipient(recipient).await?;
+
+        // check username
+        if user.id != recipient {
+            // check permission
+            let group = match self.get_group_by_id(user.group).await {
+                Ok(g) => g,
+                Err(_) => return Err(DatabaseError::Other),
+            };
+
+            if !group
+                .permissions
+                .check(FinePermission::MANAGE_NOTIFICATIONS)
+            {
+                return Err(DatabaseError::NotAllowed);
+            }
+        }
+
+        // update notifications
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "UPDATE \"xnotifications\" SET \"read\" = 'true' WHERE \"recipient\" = ?"
+        } else {
+            "UPDATE \"xnotifications\" SET (\"read\") = ('true') WHERE \"recipient\" = $1"
         };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(recipient).execute(c).await {
+            Ok(_) => {
+                // clear cache for all updated notifications
+                for notification in notifications {
+                    self.base
+                        .cache
+                        .remove(format!("rbeam.auth.notification:{}", notification.id))
+                        .await;
+                }
+
+                // update unread count
+                self.recount_unread_notifications(recipient).await
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
     }
 
     /// Delete an existing notification
@@ -2369,13 +3340,10 @@ impl Database {
         match sqlquery(&query).bind::<&str>(&id).execute(c).await {
             Ok(_) => {
                 // decr notifications count
-                self.base
-                    .cache
-                    .decr(format!(
-                        "rbeam.auth.notification_count:{}",
-                        notification.recipient
-                    ))
-                    .await;
+                if !notification.read {
+                    self.recount_unread_notifications(&notification.recipient)
+                        .await?;
+                }
 
                 // remove from cache
                 self.base
@@ -2433,10 +3401,7 @@ impl Database {
         match sqlquery(&query).bind::<&str>(&recipient).execute(c).await {
             Ok(_) => {
                 // clear notifications count
-                self.base
-                    .cache
-                    .remove(format!("rbeam.auth.notification_count:{}", recipient))
-                    .await;
+                self.recount_unread_notifications(recipient).await?;
 
                 // clear cache for all deleted notifications
                 for notification in notifications {
@@ -2456,6 +3421,26 @@ impl Database {
 
     // warnings
 
+    /// Get a [`Warning`] from a database result
+    async fn gimme_warning(&self, res: BTreeMap<String, String>) -> Result<Warning> {
+        Ok(Warning {
+            id: from_row!(res->id()),
+            content: from_row!(res->content()),
+            timestamp: from_row!(res->timestamp(u128); 0),
+            recipient: from_row!(res->recipient()),
+            moderator: self
+                .get_profile_by_id(res.get("moderator").unwrap())
+                .await?,
+            severity: match res.get("severity") {
+                Some(s) => serde_json::from_str(s).unwrap_or_default(),
+                None => WarningSeverity::default(),
+            },
+            rule: from_row!(res->rule()),
+            strikes: from_row!(res->strikes(usize); 1),
+            acknowledged: from_row!(res->acknowledged(u128); 0),
+        })
+    }
+
     // GET
     /// Get an existing warning
     ///
@@ -2487,16 +3472,7 @@ impl Database {
         };
 
         // return
-        let warning = Warning {
-            id: from_row!(res->id()),
-            content: from_row!(res->content()),
-            timestamp: from_row!(res->timestamp(u128); 0),
-            recipient: from_row!(res->recipient()),
-            moderator: match self.get_profile_by_id(res.get("moderator").unwrap()).await {
-                Ok(ua) => ua,
-                Err(e) => return Err(e),
-            },
-        };
+        let warning = self.gimme_warning(res).await?;
 
         // store in cache
         self.base
@@ -2513,6 +3489,8 @@ impl Database {
 
     /// Get all warnings by their recipient
     ///
+    /// Warnings can only be viewed by their recipient or moderators.
+    ///
     /// # Arguments
     /// * `recipient`
     /// * `user` - the user doing this
@@ -2521,14 +3499,16 @@ impl Database {
         recipient: &str,
         user: Box<Profile>,
     ) -> Result<Vec<Warning>> {
-        // make sure user is a manager
-        let group = match self.get_group_by_id(user.group).await {
-            Ok(g) => g,
-            Err(_) => return Err(DatabaseError::Other),
-        };
+        // make sure user is the recipient or a manager
+        if user.id != recipient {
+            let group = match self.get_group_by_id(user.group).await {
+                Ok(g) => g,
+                Err(_) => return Err(DatabaseError::Other),
+            };
 
-        if !group.permissions.check(FinePermission::MANAGE_WARNINGS) {
-            return Err(DatabaseError::NotAllowed);
+            if !group.permissions.check(FinePermission::MANAGE_WARNINGS) {
+                return Err(DatabaseError::NotAllowed);
+            }
         }
 
         // pull from database
@@ -2549,17 +3529,10 @@ impl Database {
 
                 for row in p {
                     let res = self.base.textify_row(row).0;
-                    out.push(Warning {
-                        id: from_row!(res->id()),
-                        content: from_row!(res->content()),
-                        timestamp: from_row!(res->timestamp(u128); 0),
-                        recipient: from_row!(res->recipient()),
-                        moderator: match self.get_profile_by_id(res.get("moderator").unwrap()).await
-                        {
-                            Ok(ua) => ua,
-                            Err(_) => continue,
-                        },
-                    });
+                    match self.gimme_warning(res).await {
+                        Ok(w) => out.push(w),
+                        Err(_) => continue,
+                    }
                 }
 
                 out
@@ -2571,37 +3544,121 @@ impl Database {
         Ok(res)
     }
 
-    // SET
-    /// Create a new warning
+    /// Check if `recipient` has any warnings they haven't acknowledged yet
     ///
     /// # Arguments
-    /// * `props` - [`WarningCreate`]
-    /// * `user` - the user creating this warning
-    pub async fn create_warning(&self, props: WarningCreate, user: Box<Profile>) -> Result<()> {
-        // make sure user is a manager
-        let group = match self.get_group_by_id(user.group).await {
-            Ok(g) => g,
-            Err(_) => return Err(DatabaseError::Other),
+    /// * `recipient`
+    pub async fn has_unacknowledged_warnings(&self, recipient: &str) -> bool {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xwarnings\" WHERE \"recipient\" = ? AND (\"acknowledged\" = '0' OR \"acknowledged\" IS NULL) LIMIT 1"
+        } else {
+            "SELECT * FROM \"xwarnings\" WHERE \"recipient\" = $1 AND (\"acknowledged\" = '0' OR \"acknowledged\" IS NULL) LIMIT 1"
         };
 
-        if !group.permissions.check(FinePermission::MANAGE_WARNINGS) {
-            return Err(DatabaseError::NotAllowed);
-        }
+        let c = &self.base.db.client;
+        sqlquery(query)
+            .bind::<&str>(recipient)
+            .fetch_one(c)
+            .await
+            .is_ok()
+    }
 
-        // ...
-        let warning = Warning {
-            id: utility::random_id(),
-            content: props.content,
-            timestamp: utility::unix_epoch_timestamp(),
-            recipient: props.recipient,
+    /// Count the strikes `recipient` received since the given timestamp
+    ///
+    /// # Arguments
+    /// * `recipient`
+    /// * `since`
+    pub async fn get_strike_count(&self, recipient: &str, since: u128) -> usize {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xwarnings\" WHERE \"recipient\" = ?"
+        } else {
+            "SELECT * FROM \"xwarnings\" WHERE \"recipient\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(recipient).fetch_all(c).await {
+            Ok(p) => {
+                let mut strikes = 0;
+
+                for row in p {
+                    let res = self.base.textify_row(row).0;
+
+                    if from_row!(res->timestamp(u128); 0) < since {
+                        continue;
+                    }
+
+                    strikes += from_row!(res->strikes(usize); 1);
+                }
+
+                strikes
+            }
+            Err(_) => 0,
+        }
+    }
+
+    /// Check if `user` is allowed to post (create questions, responses, comments, etc.)
+    ///
+    /// Users cannot post while suspended, or while they have unacknowledged warnings.
+    ///
+    /// # Arguments
+    /// * `user`
+    pub async fn check_standing(&self, user: &Profile) -> Result<()> {
+        if let Ok(suspension) = self.get_suspension(&user.id).await {
+            if suspension.is_active() {
+                return Err(DatabaseError::Suspended);
+            }
+        }
+
+        if self.has_unacknowledged_warnings(&user.id).await {
+            return Err(DatabaseError::UnacknowledgedWarnings);
+        }
+
+        Ok(())
+    }
+
+    // SET
+    /// Create a new warning
+    ///
+    /// # Arguments
+    /// * `props` - [`WarningCreate`]
+    /// * `user` - the user creating this warning
+    pub async fn create_warning(&self, props: WarningCreate, user: Box<Profile>) -> Result<()> {
+        // make sure user is a manager
+        let group = match self.get_group_by_id(user.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        if !group.permissions.check(FinePermission::MANAGE_WARNINGS) {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        if props.rule.len() > 256 {
+            return Err(DatabaseError::TooLong);
+        }
+
+        // ...
+        let warning = Warning {
+            id: utility::random_id(),
+            content: props.content,
+            timestamp: utility::unix_epoch_timestamp(),
+            recipient: props.recipient,
             moderator: user,
+            strikes: if props.no_strike {
+                0
+            } else {
+                props.severity.strikes()
+            },
+            severity: props.severity,
+            rule: props.rule,
+            acknowledged: 0,
         };
 
         // create notification
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            "INSERT INTO \"xwarnings\" VALUES (?, ?, ?, ?, ?)"
+            "INSERT INTO \"xwarnings\" VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
         } else {
-            "INSERT INTO \"xwarnings\" VALUES ($1, $2, $3, $4, $5)"
+            "INSERT INTO \"xwarnings\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
         };
 
         let c = &self.base.db.client;
@@ -2611,6 +3668,10 @@ impl Database {
             .bind::<&str>(&warning.timestamp.to_string())
             .bind::<&str>(&warning.recipient)
             .bind::<&str>(&warning.moderator.id)
+            .bind::<&str>(&serde_json::to_string(&warning.severity).unwrap())
+            .bind::<&str>(&warning.rule)
+            .bind::<&str>(&warning.strikes.to_string())
+            .bind::<&str>(&warning.acknowledged.to_string())
             .execute(c)
             .await
         {
@@ -2621,8 +3682,9 @@ impl Database {
                         NotificationCreate {
                             title: "You have received an account warning!".to_string(),
                             content: warning.content,
-                            address: String::new(),
-                            recipient: warning.recipient,
+                            address: "/warnings".to_string(),
+                            recipient: warning.recipient.clone(),
+                            kind: NotificationKind::System,
                         },
                         None,
                     )
@@ -2631,6 +3693,11 @@ impl Database {
                     return Err(e);
                 };
 
+                // apply strike policy
+                if warning.strikes > 0 {
+                    self.apply_strike_policy(&warning.recipient).await?;
+                }
+
                 // ...
                 return Ok(());
             }
@@ -2638,6 +3705,95 @@ impl Database {
         };
     }
 
+    /// Acknowledge an existing warning
+    ///
+    /// Warnings can only be acknowledged by their recipient.
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the warning
+    /// * `user` - the user doing this
+    pub async fn acknowledge_warning(&self, id: &str, user: Box<Profile>) -> Result<()> {
+        // make sure warning exists
+        let warning = self.get_warning(id).await?;
+
+        if user.id != warning.recipient {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        if warning.is_acknowledged() {
+            return Ok(());
+        }
+
+        // update warning
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "UPDATE \"xwarnings\" SET \"acknowledged\" = ? WHERE \"id\" = ?"
+        } else {
+            "UPDATE \"xwarnings\" SET (\"acknowledged\") = ($1) WHERE \"id\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(&utility::unix_epoch_timestamp().to_string())
+            .bind::<&str>(id)
+            .execute(c)
+            .await
+        {
+            Ok(_) => {
+                self.base
+                    .cache
+                    .remove(format!("rbeam.auth.warning:{}", id))
+                    .await;
+
+                Ok(())
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Suspend `recipient` if they've reached the configured [`StrikePolicy`]
+    ///
+    /// # Arguments
+    /// * `recipient`
+    async fn apply_strike_policy(&self, recipient: &str) -> Result<()> {
+        let policy = &self.config.strike_policy;
+
+        if !policy.enabled | (policy.strikes == 0) {
+            return Ok(());
+        }
+
+        // only count strikes in the window, and after the last suspension
+        let mut since = utility::unix_epoch_timestamp().saturating_sub(policy.window);
+
+        if let Ok(suspension) = self.get_suspension(recipient).await {
+            if suspension.is_active() {
+                // already suspended
+                return Ok(());
+            }
+
+            since = since.max(suspension.timestamp);
+        }
+
+        let strikes = self.get_strike_count(recipient, since).await;
+
+        if strikes < policy.strikes {
+            return Ok(());
+        }
+
+        // suspend
+        self.create_suspension(
+            recipient,
+            SuspensionCreate {
+                duration: policy.duration,
+                reason: format!(
+                    "Automatically suspended after receiving {strikes} strikes in {} days.",
+                    policy.window / (24 * 60 * 60 * 1000)
+                ),
+            },
+            String::new(),
+        )
+        .await
+    }
+
     /// Delete an existing warning
     ///
     /// Warnings can only be deleted by their moderator or admins.
@@ -2688,6 +3844,191 @@ impl Database {
         };
     }
 
+    // suspensions
+
+    // GET
+    /// Get the latest [`Suspension`] of `user` (which may have already ended)
+    ///
+    /// # Arguments
+    /// * `user`
+    pub async fn get_suspension(&self, user: &str) -> Result<Suspension> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xsuspensions\" WHERE \"user\" = ?"
+        } else {
+            "SELECT * FROM \"xsuspensions\" WHERE \"user\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let res = match sqlquery(query).bind::<&str>(user).fetch_one(c).await {
+            Ok(p) => self.base.textify_row(p).0,
+            Err(_) => return Err(DatabaseError::NotFound),
+        };
+
+        Ok(Suspension {
+            user: from_row!(res->user()),
+            reason: from_row!(res->reason()),
+            moderator: from_row!(res->moderator()),
+            timestamp: from_row!(res->timestamp(u128); 0),
+            expires: from_row!(res->expires(u128); 0),
+        })
+    }
+
+    // SET
+    /// Suspend `user`, replacing their previous suspension
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `props` - [`SuspensionCreate`]
+    /// * `moderator` - the ID of the moderator suspending the user (empty for automatic suspensions)
+    async fn create_suspension(
+        &self,
+        user: &str,
+        props: SuspensionCreate,
+        moderator: String,
+    ) -> Result<()> {
+        let timestamp = utility::unix_epoch_timestamp();
+        let suspension = Suspension {
+            user: user.to_string(),
+            reason: props.reason,
+            moderator,
+            timestamp,
+            expires: timestamp + props.duration,
+        };
+
+        // remove previous suspension
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "DELETE FROM \"xsuspensions\" WHERE \"user\" = ?"
+        } else {
+            "DELETE FROM \"xsuspensions\" WHERE \"user\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        if sqlquery(query).bind::<&str>(user).execute(c).await.is_err() {
+            return Err(DatabaseError::Other);
+        }
+
+        // create
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "INSERT INTO \"xsuspensions\" VALUES (?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xsuspensions\" VALUES ($1, $2, $3, $4, $5)"
+        };
+
+        match sqlquery(query)
+            .bind::<&str>(&suspension.user)
+            .bind::<&str>(&suspension.reason)
+            .bind::<&str>(&suspension.moderator)
+            .bind::<&str>(&suspension.timestamp.to_string())
+            .bind::<&str>(&suspension.expires.to_string())
+            .execute(c)
+            .await
+        {
+            Ok(_) => {
+                self.create_notification(
+                    NotificationCreate {
+                        title: "Your account has been suspended.".to_string(),
+                        content: suspension.reason,
+                        address: "/warnings".to_string(),
+                        recipient: suspension.user,
+                        kind: NotificationKind::System,
+                    },
+                    None,
+                )
+                .await
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Suspend `user` as a moderator, overriding the [`StrikePolicy`]
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `props` - [`SuspensionCreate`]
+    /// * `moderator` - the user doing this
+    pub async fn suspend_user(
+        &self,
+        user: &str,
+        props: SuspensionCreate,
+        moderator: Box<Profile>,
+    ) -> Result<()> {
+        // make sure moderator is a manager
+        let group = match self.get_group_by_id(moderator.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        if !group.permissions.check(FinePermission::MANAGE_WARNINGS) {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // make sure user exists
+        let user = self.get_profile(user).await?;
+
+        if props.duration == 0 {
+            return Err(DatabaseError::ValueError);
+        }
+
+        self.audit(
+            &moderator.id,
+            &format!("Suspended a user: [{}](/+u/{})", user.id, user.id),
+        )
+        .await?;
+
+        self.create_suspension(&user.id, props, moderator.id.clone())
+            .await
+    }
+
+    /// Lift the active suspension of `user` early
+    ///
+    /// The suspension is ended (not removed), so strikes from before it are still ignored.
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `moderator` - the user doing this
+    pub async fn lift_suspension(&self, user: &str, moderator: Box<Profile>) -> Result<()> {
+        // make sure moderator is a manager
+        let group = match self.get_group_by_id(moderator.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        if !group.permissions.check(FinePermission::MANAGE_WARNINGS) {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // make sure suspension is active
+        let suspension = self.get_suspension(user).await?;
+
+        if !suspension.is_active() {
+            return Err(DatabaseError::NotFound);
+        }
+
+        // update suspension
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "UPDATE \"xsuspensions\" SET \"expires\" = ? WHERE \"user\" = ?"
+        } else {
+            "UPDATE \"xsuspensions\" SET (\"expires\") = ($1) WHERE \"user\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(&utility::unix_epoch_timestamp().to_string())
+            .bind::<&str>(user)
+            .execute(c)
+            .await
+        {
+            Ok(_) => {
+                self.audit(
+                    &moderator.id,
+                    &format!("Lifted a suspension: [{}](/+u/{})", user, user),
+                )
+                .await
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
     // ip bans
 
     // GET
@@ -2854,6 +4195,7 @@ impl Database {
                         content: format!("Banned an IP: {}", props.ip),
                         address: format!("/+u/{actor_id}"),
                         recipient: "*(audit)".to_string(), // all staff, audit
+                        kind: NotificationKind::System,
                     },
                     None,
                 )
@@ -2930,6 +4272,7 @@ impl Database {
                             content: format!("Unbanned an IP: {}", ipban.ip),
                             address: format!("/+u/{actor_id}"),
                             recipient: "*(audit)".to_string(), // all staff, audit
+                            kind: NotificationKind::System,
                         },
                         None,
                     )
@@ -3126,7 +4469,7 @@ impl Database {
             }
             RelationshipStatus::Pending => {
                 // check utwo permissions
-                if utwo.metadata.is_true("sparkler:limited_friend_requests") {
+                if utwo.metadata.settings().limited_friend_requests {
                     // make sure utwo is following uone
                     if let Err(_) = self.get_follow(&utwo.id, &uone.id).await {
                         return Err(DatabaseError::NotAllowed);
@@ -3165,6 +4508,7 @@ impl Database {
                                 content: format!("{} wants to be your friend.", uone.username),
                                 address: format!("/@{}/relationship/friend_accept", uone.id),
                                 recipient: utwo.id,
+                                kind: NotificationKind::FriendRequest,
                             },
                             None,
                         )
@@ -3216,6 +4560,7 @@ impl Database {
                                 ),
                                 address: String::new(),
                                 recipient: uone.id,
+                                kind: NotificationKind::FriendRequest,
                             },
                             None,
                         )
@@ -3490,34 +4835,241 @@ impl Database {
         count
     }
 
-    // ip blocks
+    // mutes
 
-    // GET
-    /// Get an existing [`IpBlock`]
+    /// Get the active mute of `muted` by `user`
+    ///
+    /// Expired mutes are removed and treated as not found.
     ///
     /// # Arguments
-    /// * `id`
-    pub async fn get_ipblock(&self, id: &str) -> Result<IpBlock> {
-        // check in cache
-        match self
-            .base
-            .cache
-            .get(format!("rbeam.auth.ipblock:{}", id))
-            .await
-        {
-            Some(c) => return Ok(serde_json::from_str::<IpBlock>(c.as_str()).unwrap()),
-            None => (),
-        };
-
+    /// * `user` - the ID of the user who created the mute
+    /// * `muted` - the ID of the user who was muted
+    pub async fn get_user_mute(&self, user: &str, muted: &str) -> Result<UserMute> {
         // pull from database
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            "SELECT * FROM \"xipblocks\" WHERE \"id\" = ?"
+            "SELECT * FROM \"xmutes\" WHERE \"user\" = ? AND \"muted\" = ?"
         } else {
-            "SELECT * FROM \"xipblocks\" WHERE \"id\" = $1"
+            "SELECT * FROM \"xmutes\" WHERE \"user\" = $1 AND \"muted\" = $2"
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query).bind::<&str>(&id).fetch_one(c).await {
+        let res = match sqlquery(query)
+            .bind::<&str>(user)
+            .bind::<&str>(muted)
+            .fetch_one(c)
+            .await
+        {
+            Ok(p) => self.base.textify_row(p).0,
+            Err(_) => return Err(DatabaseError::NotFound),
+        };
+
+        // return
+        let mute = UserMute {
+            user: from_row!(res->user()),
+            muted: from_row!(res->muted()),
+            timestamp: from_row!(res->timestamp(u128); 0),
+            expires: from_row!(res->expires(u128); 0),
+        };
+
+        if !mute.is_active() {
+            // timed mute is over
+            self.unmute_user(user, muted).await?;
+            return Err(DatabaseError::NotFound);
+        }
+
+        Ok(mute)
+    }
+
+    /// Check if `user` has muted `other`
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `other`
+    pub async fn is_muted(&self, user: &str, other: &str) -> bool {
+        self.get_user_mute(user, other).await.is_ok()
+    }
+
+    /// Get all active mutes created by `user`
+    ///
+    /// # Arguments
+    /// * `user`
+    pub async fn get_user_mutes(&self, user: &str) -> Result<Vec<(Box<Profile>, UserMute)>> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xmutes\" WHERE \"user\" = ? ORDER BY \"timestamp\" DESC"
+        } else {
+            "SELECT * FROM \"xmutes\" WHERE \"user\" = $1 ORDER BY \"timestamp\" DESC"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(user).fetch_all(c).await {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    let res = self.base.textify_row(row).0;
+
+                    let mute = UserMute {
+                        user: from_row!(res->user()),
+                        muted: from_row!(res->muted()),
+                        timestamp: from_row!(res->timestamp(u128); 0),
+                        expires: from_row!(res->expires(u128); 0),
+                    };
+
+                    if !mute.is_active() {
+                        continue;
+                    }
+
+                    // get profile
+                    let profile = match self.get_profile(&mute.muted).await {
+                        Ok(c) => c,
+                        Err(_) => continue,
+                    };
+
+                    // add to out
+                    out.push((profile, mute));
+                }
+
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get the IDs of all users `user` currently has muted
+    ///
+    /// # Arguments
+    /// * `user`
+    pub async fn get_muted_ids(&self, user: &str) -> Vec<String> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xmutes\" WHERE \"user\" = ?"
+        } else {
+            "SELECT * FROM \"xmutes\" WHERE \"user\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(user).fetch_all(c).await {
+            Ok(p) => {
+                let now = rainbeam_shared::unix_epoch_timestamp();
+                let mut out = Vec::new();
+
+                for row in p {
+                    let res = self.base.textify_row(row).0;
+                    let expires = from_row!(res->expires(u128); 0);
+
+                    if (expires != 0) && (expires <= now) {
+                        continue;
+                    }
+
+                    out.push(from_row!(res->muted()));
+                }
+
+                out
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Mute `muted` for `user`, replacing any existing mute
+    ///
+    /// # Arguments
+    /// * `user` - the ID of the user creating the mute
+    /// * `muted` - the ID of the user being muted
+    /// * `duration` - how long the mute lasts in milliseconds (`0` for forever)
+    pub async fn mute_user(&self, user: &str, muted: &str, duration: u128) -> Result<()> {
+        // users cannot be the same
+        if user == muted {
+            return Err(DatabaseError::Other);
+        }
+
+        // make sure both users exist
+        self.get_profile(user).await?;
+        self.get_profile(muted).await?;
+
+        // remove existing mute
+        self.unmute_user(user, muted).await?;
+
+        // add
+        let timestamp = rainbeam_shared::unix_epoch_timestamp();
+        let expires = if duration == 0 {
+            0
+        } else {
+            timestamp + duration
+        };
+
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "INSERT INTO \"xmutes\" VALUES (?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xmutes\" VALUES ($1, $2, $3, $4)"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(user)
+            .bind::<&str>(muted)
+            .bind::<&str>(&timestamp.to_string())
+            .bind::<&str>(&expires.to_string())
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Remove the mute of `muted` by `user`
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `muted`
+    pub async fn unmute_user(&self, user: &str, muted: &str) -> Result<()> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "DELETE FROM \"xmutes\" WHERE \"user\" = ? AND \"muted\" = ?"
+        } else {
+            "DELETE FROM \"xmutes\" WHERE \"user\" = $1 AND \"muted\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(user)
+            .bind::<&str>(muted)
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    // ip blocks
+
+    // GET
+    /// Get an existing [`IpBlock`]
+    ///
+    /// # Arguments
+    /// * `id`
+    pub async fn get_ipblock(&self, id: &str) -> Result<IpBlock> {
+        // check in cache
+        match self
+            .base
+            .cache
+            .get(format!("rbeam.auth.ipblock:{}", id))
+            .await
+        {
+            Some(c) => return Ok(serde_json::from_str::<IpBlock>(c.as_str()).unwrap()),
+            None => (),
+        };
+
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xipblocks\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \"xipblocks\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let res = match sqlquery(&query).bind::<&str>(&id).fetch_one(c).await {
             Ok(p) => self.base.textify_row(p).0,
             Err(_) => return Err(DatabaseError::NotFound),
         };
@@ -3696,6 +5248,7 @@ impl Database {
                             content: format!("Unblocked an IP: {}", block.ip),
                             address: format!("/+u/{actor_id}"),
                             recipient: "*(audit)".to_string(), // all staff, audit
+                            kind: NotificationKind::System,
                         },
                         None,
                     )
@@ -4147,6 +5700,9 @@ impl Database {
     // SET
     /// Create a new transaction
     ///
+    /// The transaction row and the ledger entries moving the coins are written together,
+    /// so either everything is applied or nothing is.
+    ///
     /// # Arguments
     /// * `props` - [`TransactionCreate`]
     /// * `customer` - the user in the `customer` field of the transaction
@@ -4187,390 +5743,1092 @@ impl Database {
         };
 
         // create transaction
-        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            "INSERT INTO \"xugc_transactions\" VALUES (?, ?, ?, ?, ?, ?)"
-        } else {
-            "INSERT INTO \"xugc_transactions\" VALUES ($1, $2, $3, $4, $5, $6)"
-        };
-
-        let c = &self.base.db.client;
-        match sqlquery(&query)
-            .bind::<&str>(&transaction.id)
-            .bind::<i32>(transaction.amount)
-            .bind::<&str>(&transaction.item)
-            .bind::<&str>(&transaction.timestamp.to_string())
-            .bind::<&str>(&transaction.customer)
-            .bind::<&str>(&transaction.merchant)
-            .execute(c)
+        // the customer receives `amount` (negative when buying), the merchant receives the opposite
+        let transfer = match self
+            .transfer_coins(
+                &transaction.id,
+                vec![
+                    (&customer, transaction.amount),
+                    (&merchant, -transaction.amount),
+                ],
+                &transaction.item,
+                &props.idempotency_key,
+                Some(&transaction),
+            )
             .await
         {
-            Ok(_) => {
-                // update balances
-                if let Err(e) = self
-                    .update_profile_coins(&customer.id, transaction.amount)
-                    .await
-                {
-                    return Err(e);
-                };
+            Ok(t) => t,
+            Err(e) => return Err(e),
+        };
 
-                if let Err(e) = self
-                    .update_profile_coins(&merchant.id, transaction.amount.abs())
-                    .await
-                {
-                    return Err(e);
-                };
+        if transfer != transaction.id {
+            // this idempotency key was already used, return the original transaction
+            return match self.get_transaction(&transfer).await {
+                Ok(t) => Ok(t.0),
+                Err(e) => Err(e),
+            };
+        }
 
-                // send notification
-                if (customer.id != merchant.id) && (merchant.id != "0") {
-                    if let Err(e) = self
-                        .create_notification(
-                            NotificationCreate {
-                                title: "Purchased data now available!".to_string(),
-                                content: "Data from an item you purchased is now available."
-                                    .to_string(),
-                                address: format!(
-                                    "/market/item/{}#/preview",
-                                    transaction.item.clone()
-                                ),
-                                recipient: customer.id,
-                            },
-                            None,
-                        )
-                        .await
-                    {
-                        return Err(e);
-                    }
-                }
+        self.base
+            .cache
+            .remove(format!(
+                "rbeam.auth.econ.item_purchases:{}",
+                transaction.item
+            ))
+            .await;
 
-                // ...
-                return Ok(transaction);
-            }
-            Err(_) => return Err(DatabaseError::Other),
-        };
+        // send notification (only transactions for market items have data to show)
+        if (customer.id != merchant.id)
+            && (merchant.id != "0")
+            && self.get_item(&transaction.item).await.is_ok()
+        {
+            self.create_notification(
+                NotificationCreate {
+                    title: "Purchased data now available!".to_string(),
+                    content: "Data from an item you purchased is now available.".to_string(),
+                    address: format!("/market/item/{}#/preview", transaction.item.clone()),
+                    recipient: customer.id.clone(),
+                    kind: NotificationKind::System,
+                },
+                None,
+            )
+            .await?;
+        }
+
+        // ...
+        Ok(transaction)
     }
 
-    // ugc items
+    // ledger
 
-    // Get transaction given the `row` data
-    pub fn gimme_item(&self, row: BTreeMap<String, String>) -> Result<Item> {
-        Ok(Item {
+    /// Get a [`LedgerEntry`] from a database result
+    pub fn gimme_ledger_entry(&self, row: BTreeMap<String, String>) -> LedgerEntry {
+        LedgerEntry {
             id: from_row!(row->id()),
-            name: from_row!(row->name()),
-            description: from_row!(row->description()),
-            cost: row.get("cost").unwrap().parse::<i32>().unwrap_or(0),
-            content: from_row!(row->content()),
-            r#type: match serde_json::from_str(row.get("type").unwrap()) {
-                Ok(v) => v,
-                Err(_) => return Err(DatabaseError::ValueError),
-            },
-            status: match serde_json::from_str(row.get("status").unwrap()) {
-                Ok(v) => v,
-                Err(_) => return Err(DatabaseError::ValueError),
-            },
-            timestamp: row.get("timestamp").unwrap().parse::<u128>().unwrap(),
-            creator: from_row!(row->creator()),
-        })
+            transfer: from_row!(row->transfer()),
+            account: from_row!(row->account()),
+            amount: from_row!(row->amount(i32); 0),
+            reference: from_row!(row->reference()),
+            idempotency: from_row!(row->idempotency()),
+            timestamp: from_row!(row->timestamp(u128); 0),
+        }
     }
 
-    // GET
-    /// Get an existing item
+    /// Get all ledger entries of the given transfer
     ///
     /// # Arguments
-    /// * `id`
-    pub async fn get_item(&self, id: &str) -> Result<Item> {
-        if id == "0" {
-            // this item can be charged for things that don't relate to an item
-            return Ok(Item {
-                id: "0".to_string(),
-                name: "System cost".to_string(),
-                description: String::new(),
-                cost: -1,
-                content: String::new(),
-                r#type: ItemType::Text,
-                status: ItemStatus::Approved,
-                timestamp: 0,
-                creator: "0".to_string(),
-            });
+    /// * `transfer` - the ID of the transfer
+    pub async fn get_ledger_entries_by_transfer(&self, transfer: &str) -> Result<Vec<LedgerEntry>> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xugc_ledger\" WHERE \"transfer\" = ?"
+        } else {
+            "SELECT * FROM \"xugc_ledger\" WHERE \"transfer\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(transfer).fetch_all(c).await {
+            Ok(p) => Ok(p
+                .into_iter()
+                .map(|row| self.gimme_ledger_entry(self.base.textify_row(row).0))
+                .collect()),
+            Err(_) => Err(DatabaseError::Other),
         }
+    }
 
-        // check in cache
-        match self
-            .base
-            .cache
-            .get(format!("rbeam.auth.econ.item:{}", id))
-            .await
-        {
-            Some(c) => match serde_json::from_str::<Item>(c.as_str()) {
-                Ok(c) => return Ok(c),
-                Err(_) => {
-                    self.base
-                        .cache
-                        .remove(format!("rbeam.auth.econ.item:{}", id))
-                        .await;
-                }
-            },
-            None => (),
-        };
-
+    /// Get all ledger entries of the given account, 12 at a time
+    ///
+    /// # Arguments
+    /// * `account` - the ID of the account
+    /// * `page`
+    pub async fn get_ledger_entries_by_account_paginated(
+        &self,
+        account: &str,
+        page: i32,
+    ) -> Result<Vec<LedgerEntry>> {
         // pull from database
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            "SELECT * FROM \"xugc_items\" WHERE \"id\" = ?"
+            format!("SELECT * FROM \"xugc_ledger\" WHERE \"account\" = ? ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         } else {
-            "SELECT * FROM \"xugc_items\" WHERE \"id\" = $1"
+            format!("SELECT * FROM \"xugc_ledger\" WHERE \"account\" = $1 ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query).bind::<&str>(&id).fetch_one(c).await {
-            Ok(p) => self.base.textify_row(p).0,
-            Err(_) => return Err(DatabaseError::NotFound),
-        };
+        match sqlquery(&query).bind::<&str>(account).fetch_all(c).await {
+            Ok(p) => Ok(p
+                .into_iter()
+                .map(|row| self.gimme_ledger_entry(self.base.textify_row(row).0))
+                .collect()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
 
-        // return
-        let item = match self.gimme_item(res) {
-            Ok(t) => t,
-            Err(e) => return Err(e),
+    /// Get the ID of the transfer which used the given idempotency key
+    ///
+    /// # Arguments
+    /// * `account` - the ID of the account which used the key
+    /// * `key` - the idempotency key
+    pub async fn get_ledger_transfer_by_key(&self, account: &str, key: &str) -> Result<String> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT \"transfer\" FROM \"xugc_ledger\" WHERE \"account\" = ? AND \"idempotency\" = ?"
+        } else {
+            "SELECT \"transfer\" FROM \"xugc_ledger\" WHERE \"account\" = $1 AND \"idempotency\" = $2"
         };
 
-        // store in cache
-        self.base
-            .cache
-            .set(
-                format!("rbeam.auth.econ.item:{}", id),
-                serde_json::to_string::<Item>(&item).unwrap(),
-            )
-            .await;
-
-        // return
-        Ok(item)
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(account)
+            .bind::<&str>(key)
+            .fetch_one(c)
+            .await
+        {
+            Ok(p) => {
+                let res = self.base.textify_row(p).0;
+                Ok(from_row!(res->transfer()))
+            }
+            Err(_) => Err(DatabaseError::NotFound),
+        }
     }
 
-    /// Get all items by their creator, 12 at a time
-    ///
-    /// # Arguments
-    /// * `user`
-    /// * `page`
+    /// Get the balance of the given account according to the ledger
     ///
     /// # Returns
-    /// `Vec<(Item, Box<Profile>)>`
-    pub async fn get_items_by_creator_paginated(
-        &self,
-        user: &str,
-        page: i32,
-    ) -> Result<Vec<(Item, Box<Profile>)>> {
+    /// `None` if the account has no ledger entries yet
+    pub async fn get_ledger_balance(&self, account: &str) -> Result<Option<i64>> {
         // pull from database
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            format!("SELECT * FROM \"xugc_items\" WHERE \"creator\" = ? ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            "SELECT \"amount\" FROM \"xugc_ledger\" WHERE \"account\" = ?"
         } else {
-            format!("SELECT * FROM \"xugc_items\" WHERE \"creator\" = $1 ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            "SELECT \"amount\" FROM \"xugc_ledger\" WHERE \"account\" = $1"
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query).bind::<&str>(&user).fetch_all(c).await {
+        match sqlquery(query).bind::<&str>(account).fetch_all(c).await {
             Ok(p) => {
-                let mut out = Vec::new();
+                if p.is_empty() {
+                    return Ok(None);
+                }
+
+                let mut balance: i64 = 0;
 
                 for row in p {
                     let res = self.base.textify_row(row).0;
-                    let item = match self.gimme_item(res) {
-                        Ok(t) => t,
-                        Err(e) => return Err(e),
-                    };
-
-                    let creator = item.creator.clone();
-
-                    out.push((
-                        item,
-                        match self.get_profile(&creator).await {
-                            Ok(ua) => ua,
-                            Err(_) => continue,
-                        },
-                    ));
+                    balance += from_row!(res->amount(i64); 0);
                 }
 
-                out
+                Ok(Some(balance))
             }
-            Err(_) => return Err(DatabaseError::Other),
-        };
-
-        // return
-        Ok(res)
+            Err(_) => Err(DatabaseError::Other),
+        }
     }
 
-    /// Get all items by their creator and type, 12 at a time
+    /// Check every stored balance against the ledger
     ///
-    /// # Arguments
-    /// * `user`
-    /// * `type`
-    /// * `page`
+    /// Accounts without any ledger entries haven't been opened yet, so they're only
+    /// flagged if their balance is negative.
     ///
-    /// # Returns
-    /// `Vec<(Item, Box<Profile>)>`
-    pub async fn get_items_by_creator_type_paginated(
-        &self,
-        user: &str,
-        r#type: ItemType,
-        page: i32,
-    ) -> Result<Vec<(Item, Box<Profile>)>> {
-        // pull from database
-        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            format!("SELECT * FROM \"xugc_items\" WHERE \"creator\" = ? AND \"type\" = ? ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
-        } else {
-            format!("SELECT * FROM \"xugc_items\" WHERE \"creator\" = $1 AND \"type\" = $2 ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+    /// # Arguments
+    /// * `user` - the user requesting the report
+    pub async fn get_ledger_report(&self, user: Box<Profile>) -> Result<LedgerReport> {
+        // check permission
+        let group = match self.get_group_by_id(user.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
         };
 
+        if !group.permissions.check(FinePermission::ECON_MASTER) {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // sum ledger
         let c = &self.base.db.client;
-        let res = match sqlquery(&query)
-            .bind::<&str>(&user)
-            .bind::<&str>(&serde_json::to_string(&r#type).unwrap())
+        let mut sums: HashMap<String, i64> = HashMap::new();
+        let mut imbalance: i64 = 0;
+
+        match sqlquery("SELECT \"account\", \"amount\" FROM \"xugc_ledger\"")
             .fetch_all(c)
             .await
         {
             Ok(p) => {
-                let mut out = Vec::new();
-
                 for row in p {
                     let res = self.base.textify_row(row).0;
-                    let item = match self.gimme_item(res) {
-                        Ok(t) => t,
-                        Err(e) => return Err(e),
-                    };
-
-                    let creator = item.creator.clone();
+                    let amount = from_row!(res->amount(i64); 0);
 
-                    out.push((
-                        item,
-                        match self.get_profile(&creator).await {
-                            Ok(ua) => ua,
-                            Err(_) => continue,
-                        },
-                    ));
+                    imbalance += amount;
+                    *sums.entry(from_row!(res->account())).or_insert(0) += amount;
                 }
-
-                out
             }
             Err(_) => return Err(DatabaseError::Other),
         };
 
-        // return
-        Ok(res)
-    }
-
-    /// Get all items by their status, 12 at a time
-    ///
-    /// # Arguments
-    /// * `user`
-    /// * `page`
-    ///
-    /// # Returns
-    /// `Vec<(Item, Box<Profile>)>`
-    pub async fn get_items_by_status_searched_paginated(
-        &self,
-        status: ItemStatus,
-        page: i32,
-        search: &str,
-    ) -> Result<Vec<(Item, Box<Profile>)>> {
-        // pull from database
-        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            format!("SELECT * FROM \"xugc_items\" WHERE \"status\" = ? AND \"name\" LIKE ? AND \"cost\" != '-1' ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
-        } else {
-            format!("SELECT * FROM \"xugc_items\" WHERE \"status\" = $1 AND \"name\" LIKE $2 AND \"cost\" != '-1' ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
-        };
+        // check balances
+        let mut drift = Vec::new();
+        let mut negative = Vec::new();
 
-        let c = &self.base.db.client;
-        let res = match sqlquery(&query)
-            .bind::<&str>(&serde_json::to_string(&status).unwrap())
-            .bind::<&str>(&format!("%{search}%"))
+        match sqlquery("SELECT \"id\", \"username\", \"coins\" FROM \"xprofiles\"")
             .fetch_all(c)
             .await
         {
             Ok(p) => {
-                let mut out = Vec::new();
-
                 for row in p {
                     let res = self.base.textify_row(row).0;
-                    let item = match self.gimme_item(res) {
-                        Ok(t) => t,
-                        Err(e) => return Err(e),
+                    let account: String = from_row!(res->id());
+
+                    let flagged = LedgerDrift {
+                        ledger: sums.get(&account).copied(),
+                        account,
+                        username: from_row!(res->username()),
+                        coins: from_row!(res->coins(i32); 0),
                     };
 
-                    let creator = item.creator.clone();
+                    if flagged.coins < 0 {
+                        negative.push(flagged.clone());
+                    }
 
-                    out.push((
-                        item,
-                        match self.get_profile(&creator).await {
-                            Ok(ua) => ua,
-                            Err(_) => continue,
-                        },
-                    ));
+                    if let Some(ledger) = flagged.ledger {
+                        if ledger != flagged.coins as i64 {
+                            drift.push(flagged);
+                        }
+                    }
                 }
-
-                out
             }
             Err(_) => return Err(DatabaseError::Other),
         };
 
         // return
-        Ok(res)
+        Ok(LedgerReport {
+            drift,
+            negative,
+            imbalance,
+            minted: -sums.get("0").copied().unwrap_or(0),
+            timestamp: utility::unix_epoch_timestamp(),
+        })
     }
 
-    /// Get all items by their type, 12 at a time
+    /// Reset the given [`Profile`]'s coins to their balance in the ledger
     ///
     /// # Arguments
-    /// * `type`
-    /// * `page`
+    /// * `id` - the ID of the profile
+    /// * `user` - the user doing this
     ///
     /// # Returns
-    /// `Vec<(Item, Box<Profile>)>`
-    pub async fn get_items_by_type_paginated(
-        &self,
-        r#type: ItemType,
-        page: i32,
-    ) -> Result<Vec<(Item, Box<Profile>)>> {
-        // pull from database
+    /// The new balance
+    pub async fn reconcile_profile_coins(&self, id: &str, user: Box<Profile>) -> Result<i32> {
+        // check permission
+        let group = match self.get_group_by_id(user.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        if !group.permissions.check(FinePermission::ECON_MASTER) {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // make sure user exists
+        let ua = match self.get_profile(id).await {
+            Ok(ua) => ua,
+            Err(e) => return Err(e),
+        };
+
+        let balance = match self.get_ledger_balance(&ua.id).await {
+            Ok(Some(b)) => match i32::try_from(b) {
+                Ok(b) => b,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            // nothing to reconcile against
+            Ok(None) => return Err(DatabaseError::NotFound),
+            Err(e) => return Err(e),
+        };
+
+        // update user
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            format!("SELECT * FROM \"xugc_items\" WHERE \"type\" = ? ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            "UPDATE \"xprofiles\" SET \"coins\" = ? WHERE \"id\" = ?"
         } else {
-            format!("SELECT * FROM \"xugc_items\" WHERE \"type\" = $2 ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            "UPDATE \"xprofiles\" SET (\"coins\") = ($1) WHERE \"id\" = $2"
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query)
-            .bind::<&str>(&serde_json::to_string(&r#type).unwrap())
-            .fetch_all(c)
+        if sqlquery(query)
+            .bind::<&str>(&balance.to_string())
+            .bind::<&str>(&ua.id)
+            .execute(c)
             .await
+            .is_err()
         {
-            Ok(p) => {
-                let mut out = Vec::new();
-
-                for row in p {
-                    let res = self.base.textify_row(row).0;
-                    let item = match self.gimme_item(res) {
-                        Ok(t) => t,
-                        Err(e) => return Err(e),
-                    };
+            return Err(DatabaseError::Other);
+        };
 
-                    let creator = item.creator.clone();
+        self.base
+            .cache
+            .remove(format!("rbeam.auth.profile:{}", ua.username))
+            .await;
 
-                    out.push((
-                        item,
-                        match self.get_profile(&creator).await {
-                            Ok(ua) => ua,
-                            Err(_) => continue,
-                        },
-                    ));
-                }
+        self.base
+            .cache
+            .remove(format!("rbeam.auth.profile:{}", ua.id))
+            .await;
 
-                out
-            }
-            Err(_) => return Err(DatabaseError::Other),
-        };
+        // ...
+        self.audit(
+            &user.id,
+            &format!(
+                "Reconciled user coin balance ({} -> {}): [{}](/+u/{})",
+                ua.coins, balance, ua.id, ua.id
+            ),
+        )
+        .await?;
 
-        // return
-        Ok(res)
+        Ok(balance)
     }
 
-    // SET
-    /// Create a new item
+    /// Move coins between accounts as one balanced transfer
+    ///
+    /// Balance updates, ledger entries and the given `transaction` row are written in a
+    /// single database transaction. Balances are updated with a compare-and-swap, so a
+    /// concurrent change to the same account fails the whole transfer instead of
+    /// overwriting it.
+    ///
+    /// # Arguments
+    /// * `transfer` - the ID of the transfer
+    /// * `moves` - the amount to add to each account (must sum to `0`)
+    /// * `reference` - what the transfer is for
+    /// * `idempotency_key` - recorded on the first account's entry, empty for none
+    /// * `transaction` - the [`Transaction`] to store alongside the entries
+    ///
+    /// # Returns
+    /// The ID of the transfer, which is the ID of the original transfer if `idempotency_key`
+    /// was already used by the first account
+    async fn transfer_coins(
+        &self,
+        transfer: &str,
+        moves: Vec<(&Profile, i32)>,
+        reference: &str,
+        idempotency_key: &str,
+        transaction: Option<&Transaction>,
+    ) -> Result<String> {
+        if moves.iter().map(|m| m.1 as i64).sum::<i64>() != 0 {
+            return Err(DatabaseError::ValueError);
+        }
+
+        let sqlite_or_mysql = (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql");
+        let timestamp = utility::unix_epoch_timestamp();
+
+        let c = &self.base.db.client;
+        let mut tx = match c.begin().await {
+            Ok(tx) => tx,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        // check idempotency key
+        if !idempotency_key.is_empty() {
+            let query = if sqlite_or_mysql {
+                "SELECT \"transfer\" FROM \"xugc_ledger\" WHERE \"account\" = ? AND \"idempotency\" = ?"
+            } else {
+                "SELECT \"transfer\" FROM \"xugc_ledger\" WHERE \"account\" = $1 AND \"idempotency\" = $2"
+            };
+
+            match sqlquery(query)
+                .bind::<&str>(&moves[0].0.id)
+                .bind::<&str>(idempotency_key)
+                .fetch_optional(&mut *tx)
+                .await
+            {
+                Ok(Some(row)) => {
+                    let res = self.base.textify_row(row).0;
+                    return Ok(from_row!(res->transfer()));
+                }
+                Ok(None) => (),
+                Err(_) => return Err(DatabaseError::Other),
+            }
+        }
+
+        // create transaction
+        if let Some(transaction) = transaction {
+            let query = if sqlite_or_mysql {
+                "INSERT INTO \"xugc_transactions\" VALUES (?, ?, ?, ?, ?, ?)"
+            } else {
+                "INSERT INTO \"xugc_transactions\" VALUES ($1, $2, $3, $4, $5, $6)"
+            };
+
+            if sqlquery(query)
+                .bind::<&str>(&transaction.id)
+                .bind::<i32>(transaction.amount)
+                .bind::<&str>(&transaction.item)
+                .bind::<&str>(&transaction.timestamp.to_string())
+                .bind::<&str>(&transaction.customer)
+                .bind::<&str>(&transaction.merchant)
+                .execute(&mut *tx)
+                .await
+                .is_err()
+            {
+                return Err(DatabaseError::Other);
+            };
+        }
+
+        let entry_query = if sqlite_or_mysql {
+            "INSERT INTO \"xugc_ledger\" VALUES (?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xugc_ledger\" VALUES ($1, $2, $3, $4, $5, $6, $7)"
+        };
+
+        // entries are (transfer, account, amount, reference, idempotency)
+        let mut entries: Vec<(String, String, i32, String, String)> = Vec::new();
+
+        for (i, (account, amount)) in moves.iter().enumerate() {
+            let keyed = (i == 0) && !idempotency_key.is_empty();
+
+            if (*amount == 0) && !keyed {
+                continue;
+            }
+
+            if account.id != "0" {
+                // the system account only exists in the ledger, everyone else has a balance
+                let query = if sqlite_or_mysql {
+                    "SELECT \"coins\" FROM \"xprofiles\" WHERE \"id\" = ?"
+                } else {
+                    "SELECT \"coins\" FROM \"xprofiles\" WHERE \"id\" = $1"
+                };
+
+                let stored: String = match sqlquery(query)
+                    .bind::<&str>(&account.id)
+                    .fetch_one(&mut *tx)
+                    .await
+                {
+                    Ok(p) => {
+                        let res = self.base.textify_row(p).0;
+                        from_row!(res->coins())
+                    }
+                    Err(_) => return Err(DatabaseError::NotFound),
+                };
+
+                let coins = stored.parse::<i32>().unwrap_or(0);
+
+                // open the account in the ledger with its existing balance
+                if (coins != 0) && !entries.iter().any(|e| e.1 == account.id) {
+                    let query = if sqlite_or_mysql {
+                        "SELECT \"id\" FROM \"xugc_ledger\" WHERE \"account\" = ? LIMIT 1"
+                    } else {
+                        "SELECT \"id\" FROM \"xugc_ledger\" WHERE \"account\" = $1 LIMIT 1"
+                    };
+
+                    match sqlquery(query)
+                        .bind::<&str>(&account.id)
+                        .fetch_optional(&mut *tx)
+                        .await
+                    {
+                        Ok(Some(_)) => (),
+                        Ok(None) => {
+                            let opening =
+                                AlmostSnowflake::new(self.config.snowflake_server_id).to_string();
+
+                            entries.push((
+                                opening.clone(),
+                                account.id.clone(),
+                                coins,
+                                "opening".to_string(),
+                                String::new(),
+                            ));
+
+                            entries.push((
+                                opening,
+                                "0".to_string(),
+                                -coins,
+                                "opening".to_string(),
+                                String::new(),
+                            ));
+                        }
+                        Err(_) => return Err(DatabaseError::Other),
+                    }
+                }
+
+                // make sure the account can afford this
+                let new = match coins.checked_add(*amount) {
+                    Some(n) => n,
+                    None => return Err(DatabaseError::ValueError),
+                };
+
+                if new < 0 {
+                    return Err(DatabaseError::TooExpensive);
+                }
+
+                // update balance
+                let query = if sqlite_or_mysql {
+                    "UPDATE \"xprofiles\" SET \"coins\" = ? WHERE \"id\" = ? AND \"coins\" = ?"
+                } else {
+                    "UPDATE \"xprofiles\" SET (\"coins\") = ($1) WHERE \"id\" = $2 AND \"coins\" = $3"
+                };
+
+                match sqlquery(query)
+                    .bind::<&str>(&new.to_string())
+                    .bind::<&str>(&account.id)
+                    .bind::<&str>(&stored)
+                    .execute(&mut *tx)
+                    .await
+                {
+                    Ok(r) => {
+                        if r.rows_affected() != 1 {
+                            // balance changed since we read it
+                            return Err(DatabaseError::Other);
+                        }
+                    }
+                    Err(_) => return Err(DatabaseError::Other),
+                };
+            }
+
+            entries.push((
+                transfer.to_string(),
+                account.id.clone(),
+                *amount,
+                reference.to_string(),
+                if keyed {
+                    idempotency_key.to_string()
+                } else {
+                    String::new()
+                },
+            ));
+        }
+
+        // create entries
+        for (transfer, account, amount, reference, idempotency) in entries {
+            let id = AlmostSnowflake::new(self.config.snowflake_server_id).to_string();
+
+            if sqlquery(entry_query)
+                .bind::<&str>(&id)
+                .bind::<&str>(&transfer)
+                .bind::<&str>(&account)
+                .bind::<&str>(&amount.to_string())
+                .bind::<&str>(&reference)
+                .bind::<&str>(if idempotency.is_empty() {
+                    &id
+                } else {
+                    &idempotency
+                })
+                .bind::<&str>(&timestamp.to_string())
+                .execute(&mut *tx)
+                .await
+                .is_err()
+            {
+                return Err(DatabaseError::Other);
+            };
+        }
+
+        // commit
+        if tx.commit().await.is_err() {
+            return Err(DatabaseError::Other);
+        }
+
+        for (account, _) in moves {
+            self.base
+                .cache
+                .remove(format!("rbeam.auth.profile:{}", account.username))
+                .await;
+
+            self.base
+                .cache
+                .remove(format!("rbeam.auth.profile:{}", account.id))
+                .await;
+        }
+
+        Ok(transfer.to_string())
+    }
+
+    // ugc items
+
+    // Get transaction given the `row` data
+    pub fn gimme_item(&self, row: BTreeMap<String, String>) -> Result<Item> {
+        Ok(Item {
+            id: from_row!(row->id()),
+            name: from_row!(row->name()),
+            description: from_row!(row->description()),
+            cost: row.get("cost").unwrap().parse::<i32>().unwrap_or(0),
+            content: from_row!(row->content()),
+            r#type: match serde_json::from_str(row.get("type").unwrap()) {
+                Ok(v) => v,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            status: match serde_json::from_str(row.get("status").unwrap()) {
+                Ok(v) => v,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            timestamp: row.get("timestamp").unwrap().parse::<u128>().unwrap(),
+            creator: from_row!(row->creator()),
+        })
+    }
+
+    // GET
+    /// Get an existing item
+    ///
+    /// # Arguments
+    /// * `id`
+    pub async fn get_item(&self, id: &str) -> Result<Item> {
+        if id == "0" {
+            // this item can be charged for things that don't relate to an item
+            return Ok(Item {
+                id: "0".to_string(),
+                name: "System cost".to_string(),
+                description: String::new(),
+                cost: -1,
+                content: String::new(),
+                r#type: ItemType::Text,
+                status: ItemStatus::Approved,
+                timestamp: 0,
+                creator: "0".to_string(),
+            });
+        }
+
+        // check in cache
+        match self
+            .base
+            .cache
+            .get(format!("rbeam.auth.econ.item:{}", id))
+            .await
+        {
+            Some(c) => match serde_json::from_str::<Item>(c.as_str()) {
+                Ok(c) => return Ok(c),
+                Err(_) => {
+                    self.base
+                        .cache
+                        .remove(format!("rbeam.auth.econ.item:{}", id))
+                        .await;
+                }
+            },
+            None => (),
+        };
+
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xugc_items\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \"xugc_items\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let res = match sqlquery(&query).bind::<&str>(&id).fetch_one(c).await {
+            Ok(p) => self.base.textify_row(p).0,
+            Err(_) => return Err(DatabaseError::NotFound),
+        };
+
+        // return
+        let item = match self.gimme_item(res) {
+            Ok(t) => t,
+            Err(e) => return Err(e),
+        };
+
+        // store in cache
+        self.base
+            .cache
+            .set(
+                format!("rbeam.auth.econ.item:{}", id),
+                serde_json::to_string::<Item>(&item).unwrap(),
+            )
+            .await;
+
+        // return
+        Ok(item)
+    }
+
+    /// Get all items by their creator, 12 at a time
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `page`
+    ///
+    /// # Returns
+    /// `Vec<(Item, Box<Profile>)>`
+    pub async fn get_items_by_creator_paginated(
+        &self,
+        user: &str,
+        page: i32,
+    ) -> Result<Vec<(Item, Box<Profile>)>> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            format!("SELECT * FROM \"xugc_items\" WHERE \"creator\" = ? ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+        } else {
+            format!("SELECT * FROM \"xugc_items\" WHERE \"creator\" = $1 ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+        };
+
+        let c = &self.base.db.client;
+        let res = match sqlquery(&query).bind::<&str>(&user).fetch_all(c).await {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    let res = self.base.textify_row(row).0;
+                    let item = match self.gimme_item(res) {
+                        Ok(t) => t,
+                        Err(e) => return Err(e),
+                    };
+
+                    let creator = item.creator.clone();
+
+                    out.push((
+                        item,
+                        match self.get_profile(&creator).await {
+                            Ok(ua) => ua,
+                            Err(_) => continue,
+                        },
+                    ));
+                }
+
+                out
+            }
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        // return
+        Ok(res)
+    }
+
+    /// Get all items by their creator and type, 12 at a time
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `type`
+    /// * `page`
+    ///
+    /// # Returns
+    /// `Vec<(Item, Box<Profile>)>`
+    pub async fn get_items_by_creator_type_paginated(
+        &self,
+        user: &str,
+        r#type: ItemType,
+        page: i32,
+    ) -> Result<Vec<(Item, Box<Profile>)>> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            format!("SELECT * FROM \"xugc_items\" WHERE \"creator\" = ? AND \"type\" = ? ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+        } else {
+            format!("SELECT * FROM \"xugc_items\" WHERE \"creator\" = $1 AND \"type\" = $2 ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+        };
+
+        let c = &self.base.db.client;
+        let res = match sqlquery(&query)
+            .bind::<&str>(&user)
+            .bind::<&str>(&serde_json::to_string(&r#type).unwrap())
+            .fetch_all(c)
+            .await
+        {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    let res = self.base.textify_row(row).0;
+                    let item = match self.gimme_item(res) {
+                        Ok(t) => t,
+                        Err(e) => return Err(e),
+                    };
+
+                    let creator = item.creator.clone();
+
+                    out.push((
+                        item,
+                        match self.get_profile(&creator).await {
+                            Ok(ua) => ua,
+                            Err(_) => continue,
+                        },
+                    ));
+                }
+
+                out
+            }
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        // return
+        Ok(res)
+    }
+
+    /// Get the tags of an item
+    ///
+    /// # Arguments
+    /// * `item`
+    pub async fn get_item_tags(&self, item: &str) -> Vec<String> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT \"tag\" FROM \"xugc_item_tags\" WHERE \"item\" = ?"
+        } else {
+            "SELECT \"tag\" FROM \"xugc_item_tags\" WHERE \"item\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(item).fetch_all(c).await {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    let res = self.base.textify_row(row).0;
+                    out.push(from_row!(res->tag()));
+                }
+
+                out.sort();
+                out
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get the number of times an item has been bought (not counting its creator)
+    ///
+    /// # Arguments
+    /// * `item`
+    pub async fn get_item_purchase_count(&self, item: &str) -> usize {
+        // check in cache
+        if let Some(c) = self
+            .base
+            .cache
+            .get(format!("rbeam.auth.econ.item_purchases:{}", item))
+            .await
+        {
+            if let Ok(c) = c.parse::<usize>() {
+                return c;
+            }
+        }
+
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT \"id\" FROM \"xugc_transactions\" WHERE \"item\" = ? AND \"customer\" != \"merchant\""
+        } else {
+            "SELECT \"id\" FROM \"xugc_transactions\" WHERE \"item\" = $1 AND \"customer\" != \"merchant\""
+        };
+
+        let c = &self.base.db.client;
+        let count = match sqlquery(query).bind::<&str>(item).fetch_all(c).await {
+            Ok(p) => p.len(),
+            Err(_) => return 0,
+        };
+
+        self.base
+            .cache
+            .set(
+                format!("rbeam.auth.econ.item_purchases:{}", item),
+                count.to_string(),
+            )
+            .await;
+
+        count
+    }
+
+    /// Search items, 12 at a time
+    ///
+    /// # Arguments
+    /// * `props` - [`ItemSearch`]
+    ///
+    /// # Returns
+    /// `Vec<(Item, Box<Profile>, ItemRating)>`
+    pub async fn search_items(
+        &self,
+        props: ItemSearch,
+    ) -> Result<Vec<(Item, Box<Profile>, ItemRating)>> {
+        let sqlite_or_mysql = (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql");
+        let mut binds: Vec<String> = Vec::new();
+
+        // build query
+        let mut placeholder = |value: String| {
+            binds.push(value);
+
+            if sqlite_or_mysql {
+                "?".to_string()
+            } else {
+                format!("${}", binds.len())
+            }
+        };
+
+        let mut query = format!(
+            "SELECT * FROM \"xugc_items\" WHERE \"status\" = {} AND \"cost\" != '-1'",
+            placeholder(serde_json::to_string(&props.status).unwrap())
+        );
+
+        if let Some(ref r#type) = props.r#type {
+            query.push_str(&format!(
+                " AND \"type\" = {}",
+                placeholder(serde_json::to_string(r#type).unwrap())
+            ));
+        }
+
+        for word in props.q.to_lowercase().split_whitespace().take(8) {
+            let pattern = format!("%{word}%");
+            query.push_str(&format!(
+                " AND (LOWER(\"name\") LIKE {} OR LOWER(\"description\") LIKE {})",
+                placeholder(pattern.clone()),
+                placeholder(pattern)
+            ));
+        }
+
+        if !props.tag.is_empty() {
+            query.push_str(&format!(
+                " AND \"id\" IN (SELECT \"item\" FROM \"xugc_item_tags\" WHERE \"tag\" = {})",
+                placeholder(props.tag.to_lowercase())
+            ));
+        }
+
+        // costs are whole numbers, so they're safe to put in the query directly
+        if let Some(min) = props.min_cost {
+            query.push_str(&format!(" AND CAST(\"cost\" AS INT) >= {min}"));
+        }
+
+        if let Some(max) = props.max_cost {
+            query.push_str(&format!(" AND CAST(\"cost\" AS INT) <= {max}"));
+        }
+
+        // sort
+        let purchases = "(SELECT COUNT(*) FROM \"xugc_transactions\" WHERE \"xugc_transactions\".\"item\" = \"xugc_items\".\"id\" AND \"customer\" != \"merchant\")";
+        let rating = "COALESCE((SELECT AVG(CAST(\"rating\" AS INT)) FROM \"xugc_reviews\" WHERE \"xugc_reviews\".\"item\" = \"xugc_items\".\"id\"), 0)";
+        let reviews = "(SELECT COUNT(*) FROM \"xugc_reviews\" WHERE \"xugc_reviews\".\"item\" = \"xugc_items\".\"id\")";
+
+        let order = match props.sort {
+            ItemSort::Newest => String::new(),
+            ItemSort::PriceAsc => "CAST(\"cost\" AS INT) ASC, ".to_string(),
+            ItemSort::PriceDesc => "CAST(\"cost\" AS INT) DESC, ".to_string(),
+            ItemSort::Popular => format!("{purchases} DESC, "),
+            ItemSort::Rating => format!("{rating} DESC, {reviews} DESC, "),
+        };
+
+        query.push_str(&format!(
+            " ORDER BY {order}\"timestamp\" DESC LIMIT 12 OFFSET {}",
+            props.page.max(0) * 12
+        ));
+
+        // pull from database
+        let c = &self.base.db.client;
+        let mut q = sqlquery(&query);
+
+        for bind in &binds {
+            q = q.bind::<&str>(bind);
+        }
+
+        let rows = match q.fetch_all(c).await {
+            Ok(p) => p,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        let mut items = Vec::new();
+
+        for row in rows {
+            let res = self.base.textify_row(row).0;
+            items.push(self.gimme_item(res)?);
+        }
+
+        // fill creators and ratings
+        let mut out = Vec::new();
+
+        for item in items {
+            let creator = match self.get_profile(&item.creator).await {
+                Ok(ua) => ua,
+                Err(_) => continue,
+            };
+
+            let rating = self.get_item_rating(&item.id).await;
+            out.push((item, creator, rating));
+        }
+
+        // return
+        Ok(out)
+    }
+
+    /// If the given string is a valid [`LayoutComponent`] (see [`LayoutComponent::validate`])
+    pub fn is_valid_layout(content: &str) -> bool {
+        match serde_json::from_str::<LayoutComponent>(content) {
+            Ok(l) => l.validate().is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Sanitize the CSS in a serialized [`LayoutComponent`] (see [`LayoutComponent::sanitize_css`])
+    ///
+    /// # Returns
+    /// The layout (serialized again)
+    pub fn sanitize_layout(content: &str) -> Result<String> {
+        let mut layout = match serde_json::from_str::<LayoutComponent>(content) {
+            Ok(l) => l,
+            Err(_) => return Err(DatabaseError::ValueError),
+        };
+
+        layout.sanitize_css();
+
+        match serde_json::to_string(&layout) {
+            Ok(l) => Ok(l),
+            Err(_) => Err(DatabaseError::ValueError),
+        }
+    }
+
+    /// Validate and normalize a list of item tags
+    ///
+    /// Tags are lowercase, up to 32 characters of letters, numbers, `-` and `_`.
+    /// Items can have up to 8 tags.
+    pub fn normalize_item_tags(tags: Vec<String>) -> Result<Vec<String>> {
+        let mut out: Vec<String> = Vec::new();
+
+        for tag in tags {
+            let tag = tag.trim().to_lowercase().replace(" ", "-");
+
+            if tag.is_empty() {
+                continue;
+            }
+
+            if tag.len() > 32 {
+                return Err(DatabaseError::TooLong);
+            }
+
+            if !tag
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() | (c == '-') | (c == '_'))
+            {
+                return Err(DatabaseError::ValueError);
+            }
+
+            if !out.contains(&tag) {
+                out.push(tag);
+            }
+        }
+
+        if out.len() > 8 {
+            return Err(DatabaseError::TooLong);
+        }
+
+        Ok(out)
+    }
+
+    /// Replace the tags of an item
+    ///
+    /// # Arguments
+    /// * `item`
+    /// * `tags` - normalized tags (see [`Database::normalize_item_tags`])
+    async fn set_item_tags(&self, item: &str, tags: Vec<String>) -> Result<()> {
+        let c = &self.base.db.client;
+
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "DELETE FROM \"xugc_item_tags\" WHERE \"item\" = ?"
+        } else {
+            "DELETE FROM \"xugc_item_tags\" WHERE \"item\" = $1"
+        };
+
+        if sqlquery(query).bind::<&str>(item).execute(c).await.is_err() {
+            return Err(DatabaseError::Other);
+        }
+
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "INSERT INTO \"xugc_item_tags\" VALUES (?, ?)"
+        } else {
+            "INSERT INTO \"xugc_item_tags\" VALUES ($1, $2)"
+        };
+
+        for tag in tags {
+            if sqlquery(query)
+                .bind::<&str>(item)
+                .bind::<&str>(&tag)
+                .execute(c)
+                .await
+                .is_err()
+            {
+                return Err(DatabaseError::Other);
+            }
+        }
+
+        Ok(())
+    }
+
+    // SET
+    /// Create a new item
     ///
     /// # Arguments
     /// * `props` - [`ItemCreate`]
@@ -4581,349 +6839,1515 @@ impl Database {
             return Err(DatabaseError::TooLong);
         }
 
-        if props.content.len() < 2 {
-            return Err(DatabaseError::ValueError);
+        if props.content.len() < 2 {
+            return Err(DatabaseError::ValueError);
+        }
+
+        if props.name.len() > (64 * 2) {
+            return Err(DatabaseError::TooLong);
+        }
+
+        if props.name.len() < 2 {
+            return Err(DatabaseError::ValueError);
+        }
+
+        if props.description.len() > (64 * 128) {
+            return Err(DatabaseError::TooLong);
+        }
+
+        // if we're creating a module, the cost HAS to be -1 (offsale)
+        if props.r#type == ItemType::Module && props.cost != -1 {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // ...
+        if props.cost.is_negative() && props.cost != -1 {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // layouts have to be valid before anybody can use them
+        if (props.r#type == ItemType::Layout) && !Self::is_valid_layout(&props.content) {
+            return Err(DatabaseError::ValueError);
+        }
+
+        // themes (and the css in layouts) are sanitized like any other custom css
+        let content = match props.r#type {
+            ItemType::UserTheme => sanitize_css(&props.content).0,
+            ItemType::Layout => Self::sanitize_layout(&props.content)?,
+            _ => props.content,
+        };
+
+        let tags = Self::normalize_item_tags(props.tags)?;
+
+        let item = Item {
+            // id: utility::random_id(),
+            id: AlmostSnowflake::new(self.config.snowflake_server_id).to_string(),
+            name: props.name,
+            description: props.description,
+            cost: props.cost,
+            content,
+            r#type: props.r#type,
+            status: ItemStatus::Pending,
+            timestamp: utility::unix_epoch_timestamp(),
+            creator: creator.to_string(),
+        };
+
+        // subtract creation cost from creator
+        /* if let Err(e) = self
+            .create_transaction(
+                TransactionCreate {
+                    merchant: "0",
+                    item: "0",
+                    amount: -25,
+                },
+                creator.clone(),
+            )
+            .await
+        {
+            return Err(e);
+        }; */
+
+        // create item
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "INSERT INTO \"xugc_items\" VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xugc_items\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&str>(&item.id)
+            .bind::<&str>(&item.name)
+            .bind::<&str>(&item.description)
+            .bind::<i32>(item.cost)
+            .bind::<&str>(&item.content)
+            .bind::<&str>(&serde_json::to_string(&item.r#type).unwrap())
+            .bind::<&str>(&serde_json::to_string(&item.status).unwrap())
+            .bind::<&str>(&item.timestamp.to_string())
+            .bind::<&str>(&item.creator)
+            .execute(c)
+            .await
+        {
+            Ok(_) => {
+                // buy item (for free)
+                if let Err(e) = self
+                    .create_transaction(
+                        TransactionCreate {
+                            merchant: creator.to_string(),
+                            item: item.id.clone(),
+                            amount: 0,
+                            idempotency_key: String::new(),
+                        },
+                        creator,
+                    )
+                    .await
+                {
+                    return Err(e);
+                };
+
+                self.set_item_tags(&item.id, tags).await?;
+
+                // ...
+                return Ok(item);
+            }
+            Err(_) => return Err(DatabaseError::Other),
+        };
+    }
+
+    /// Update item status
+    ///
+    /// # Arguments
+    /// * `id`
+    /// * `status` - [`ItemStatus`]
+    /// * `user`
+    pub async fn update_item_status(
+        &self,
+        id: &str,
+        status: ItemStatus,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        // make sure item exists and check permission
+        let item = match self.get_item(id).await {
+            Ok(i) => i,
+            Err(e) => return Err(e),
+        };
+
+        // check permission
+        let group = match self.get_group_by_id(user.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        if !group.permissions.check(FinePermission::ECON_MASTER) {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // update item
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "UPDATE \"xugc_items\" SET \"status\" = ? WHERE \"id\" = ?"
+        } else {
+            "UPDATE \"xugc_items\" SET (\"status\") = ($1) WHERE \"id\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&str>(&serde_json::to_string(&status).unwrap())
+            .bind::<&str>(&id)
+            .execute(c)
+            .await
+        {
+            Ok(_) => {
+                // approving an item approves the version it was reviewed with
+                if (status == ItemStatus::Approved) | (status == ItemStatus::Featured) {
+                    if let Some(version) = self.get_item_versions(&item.id).await?.first() {
+                        if (version.status == ItemStatus::Pending)
+                            && (version.content == item.content)
+                        {
+                            let query = if (self.base.db.r#type == "sqlite")
+                                | (self.base.db.r#type == "mysql")
+                            {
+                                "UPDATE \"xugc_item_versions\" SET \"status\" = ? WHERE \"id\" = ?"
+                            } else {
+                                "UPDATE \"xugc_item_versions\" SET (\"status\") = ($1) WHERE \"id\" = $2"
+                            };
+
+                            if sqlquery(query)
+                                .bind::<&str>(
+                                    &serde_json::to_string(&ItemStatus::Approved).unwrap(),
+                                )
+                                .bind::<&str>(&version.id)
+                                .execute(c)
+                                .await
+                                .is_err()
+                            {
+                                return Err(DatabaseError::Other);
+                            }
+                        }
+                    }
+                }
+
+                if let Err(e) = self
+                    .create_notification(
+                        NotificationCreate {
+                            title: "Item status updated!".to_string(),
+                            content: format!(
+                                "An item you created has been updated to the status of \"{}\"",
+                                status.to_string()
+                            ),
+                            address: format!("/market/item/{}", item.id.clone()),
+                            recipient: item.creator,
+                            kind: NotificationKind::System,
+                        },
+                        None,
+                    )
+                    .await
+                {
+                    return Err(e);
+                }
+
+                // remove from cache
+                self.base
+                    .cache
+                    .remove(format!("rbeam.auth.econ.item:{}", id))
+                    .await;
+
+                // ...
+                return Ok(());
+            }
+            Err(_) => return Err(DatabaseError::Other),
+        };
+    }
+
+    /// Update item fields
+    ///
+    /// # Arguments
+    /// * `id`
+    /// * `props` - [`ItemEdit`]
+    /// * `user`
+    pub async fn update_item(&self, id: &str, props: ItemEdit, user: Box<Profile>) -> Result<()> {
+        // make sure item exists and check permission
+        let item = match self.get_item(id).await {
+            Ok(i) => i,
+            Err(e) => return Err(e),
+        };
+
+        // check values
+        if props.name.len() > (64 * 2) {
+            return Err(DatabaseError::TooLong);
+        }
+
+        if props.name.len() < 2 {
+            return Err(DatabaseError::ValueError);
+        }
+
+        if props.description.len() > (64 * 128) {
+            return Err(DatabaseError::TooLong);
+        }
+
+        // if we're creating a module, the cost HAS to be -1 (offsale)
+        if item.r#type == ItemType::Module && props.cost != -1 {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // ...
+        if props.cost.is_negative() && props.cost != -1 {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // check permission
+        let group = match self.get_group_by_id(user.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        if user.id != item.creator {
+            if !group.permissions.check(FinePermission::ECON_MASTER) {
+                return Err(DatabaseError::NotAllowed);
+            }
+        }
+
+        let tags = match props.tags {
+            Some(tags) => Some(Self::normalize_item_tags(tags)?),
+            None => None,
+        };
+
+        // update item
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "UPDATE \"xugc_items\" SET \"name\" = ?, \"description\" = ?, \"cost\" = ? WHERE \"id\" = ?"
+        } else {
+            "UPDATE \"xugc_items\" SET (\"name\", \"description\", \"cost\") = ($1, $2, $3) WHERE \"id\" = $4"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&str>(&props.name)
+            .bind::<&str>(&props.description)
+            .bind::<i32>(props.cost)
+            .bind::<&str>(&id)
+            .execute(c)
+            .await
+        {
+            Ok(_) => {
+                if let Some(tags) = tags {
+                    self.set_item_tags(id, tags).await?;
+                }
+
+                // remove from cache
+                self.base
+                    .cache
+                    .remove(format!("rbeam.auth.econ.item:{}", id))
+                    .await;
+
+                // ...
+                return Ok(());
+            }
+            Err(_) => return Err(DatabaseError::Other),
+        };
+    }
+
+    /// Submit a new version of an item's content
+    ///
+    /// Versions are never changed after they're created. A new version of an approved item
+    /// has to be approved before it becomes the item's current content (see
+    /// [`Database::update_item_version_status`]), items which aren't approved yet are
+    /// updated right away.
+    ///
+    /// # Arguments
+    /// * `id`
+    /// * `props` - [`ItemEditContent`]
+    /// * `user`
+    pub async fn update_item_content(
+        &self,
+        id: &str,
+        props: ItemEditContent,
+        user: Box<Profile>,
+    ) -> Result<ItemVersion> {
+        // make sure item exists and check permission
+        let item = match self.get_item(id).await {
+            Ok(i) => i,
+            Err(e) => return Err(e),
+        };
+
+        // we cannot change the content of a module
+        // this would allow people to lie about the checksum of their wasm package
+        //
+        // doing this also ensures that people create a new asset for each version of their package,
+        // meaning old versions will still verify properly
+        if item.r#type == ItemType::Module {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // check values
+        if props.content.len() > (64 * 128 * 2) {
+            return Err(DatabaseError::TooLong);
+        }
+
+        if props.content.len() < 2 {
+            return Err(DatabaseError::ValueError);
+        }
+
+        if props.changelog.len() > (64 * 64) {
+            return Err(DatabaseError::TooLong);
+        }
+
+        if (item.r#type == ItemType::Layout) && !Self::is_valid_layout(&props.content) {
+            return Err(DatabaseError::ValueError);
+        }
+
+        let content = match item.r#type {
+            ItemType::UserTheme => sanitize_css(&props.content).0,
+            ItemType::Layout => Self::sanitize_layout(&props.content)?,
+            _ => props.content,
+        };
+
+        // check permission
+        let group = match self.get_group_by_id(user.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        if user.id != item.creator {
+            if !group.permissions.check(FinePermission::ECON_MASTER) {
+                return Err(DatabaseError::NotAllowed);
+            }
+        }
+
+        // create version
+        let versions = self.get_item_versions_or_initial(&item).await?;
+        let is_approved =
+            (item.status == ItemStatus::Approved) | (item.status == ItemStatus::Featured);
+
+        let version = ItemVersion {
+            id: AlmostSnowflake::new(self.config.snowflake_server_id).to_string(),
+            item: item.id.clone(),
+            number: versions.iter().map(|v| v.number).max().unwrap_or(0) + 1,
+            content,
+            changelog: props.changelog,
+            status: ItemStatus::Pending,
+            timestamp: utility::unix_epoch_timestamp(),
+        };
+
+        self.insert_item_version(&version).await?;
+
+        if is_approved {
+            // the current content stays until the version is approved
+            return Ok(version);
+        }
+
+        // update item
+        self.set_item_content(id, &version.content).await?;
+        Ok(version)
+    }
+
+    /// Set the current content of an item
+    async fn set_item_content(&self, id: &str, content: &str) -> Result<()> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "UPDATE \"xugc_items\" SET \"content\" = ? WHERE \"id\" = ?"
+        } else {
+            "UPDATE \"xugc_items\" SET (\"content\") = ($1) WHERE \"id\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(content)
+            .bind::<&str>(id)
+            .execute(c)
+            .await
+        {
+            Ok(_) => {
+                // remove from cache
+                self.base
+                    .cache
+                    .remove(format!("rbeam.auth.econ.item:{}", id))
+                    .await;
+
+                Ok(())
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Delete an existing item
+    ///
+    /// Items can only be deleted by their creator.
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the item
+    /// * `user` - the user doing this
+    pub async fn delete_item(&self, id: &str, user: Box<Profile>) -> Result<()> {
+        // make sure item exists
+        let item = match self.get_item(id).await {
+            Ok(n) => n,
+            Err(e) => return Err(e),
+        };
+
+        // check username
+        if item.creator != user.id {
+            // check permission
+            let group = match self.get_group_by_id(user.group).await {
+                Ok(g) => g,
+                Err(_) => return Err(DatabaseError::Other),
+            };
+
+            if !group.permissions.check(FinePermission::ECON_MASTER) {
+                return Err(DatabaseError::NotAllowed);
+            }
+        }
+
+        // delete item
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "DELETE FROM \"xugc_items\" WHERE \"id\" = ?"
+        } else {
+            "DELETE FROM \"xugc_items\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&str>(&id).execute(c).await {
+            Ok(_) => {
+                // delete reviews
+                let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+                {
+                    "DELETE FROM \"xugc_reviews\" WHERE \"item\" = ?"
+                } else {
+                    "DELETE FROM \"xugc_reviews\" WHERE \"item\" = $1"
+                };
+
+                if sqlquery(query).bind::<&str>(id).execute(c).await.is_err() {
+                    return Err(DatabaseError::Other);
+                }
+
+                // delete tags
+                self.set_item_tags(id, Vec::new()).await?;
+
+                // delete versions and installs
+                for table in ["xugc_item_versions", "xugc_item_pins", "xugc_item_installs"] {
+                    let query =
+                        if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                            format!("DELETE FROM \"{table}\" WHERE \"item\" = ?")
+                        } else {
+                            format!("DELETE FROM \"{table}\" WHERE \"item\" = $1")
+                        };
+
+                    if sqlquery(&query).bind::<&str>(id).execute(c).await.is_err() {
+                        return Err(DatabaseError::Other);
+                    }
+                }
+
+                // remove from cache
+                self.base
+                    .cache
+                    .remove(format!("rbeam.auth.econ.item:{}", id))
+                    .await;
+
+                self.base
+                    .cache
+                    .remove(format!("rbeam.auth.econ.item_rating:{}", id))
+                    .await;
+
+                self.base
+                    .cache
+                    .remove(format!("rbeam.auth.econ.item_purchases:{}", id))
+                    .await;
+
+                // return
+                return Ok(());
+            }
+            Err(_) => return Err(DatabaseError::Other),
+        };
+    }
+
+    // ugc versions
+
+    /// Get an [`ItemVersion`] from a database result
+    pub fn gimme_item_version(&self, row: BTreeMap<String, String>) -> Result<ItemVersion> {
+        Ok(ItemVersion {
+            id: from_row!(row->id()),
+            item: from_row!(row->item()),
+            number: from_row!(row->number(i32); 0),
+            content: from_row!(row->content()),
+            changelog: from_row!(row->changelog()),
+            status: match serde_json::from_str(row.get("status").unwrap()) {
+                Ok(v) => v,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            timestamp: from_row!(row->timestamp(u128); 0),
+        })
+    }
+
+    // GET
+    /// Get an existing item version
+    ///
+    /// # Arguments
+    /// * `id`
+    pub async fn get_item_version(&self, id: &str) -> Result<ItemVersion> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xugc_item_versions\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \"xugc_item_versions\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(id).fetch_one(c).await {
+            Ok(p) => self.gimme_item_version(self.base.textify_row(p).0),
+            Err(_) => Err(DatabaseError::NotFound),
+        }
+    }
+
+    /// Get all versions of an item (newest first)
+    ///
+    /// # Arguments
+    /// * `item`
+    pub async fn get_item_versions(&self, item: &str) -> Result<Vec<ItemVersion>> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xugc_item_versions\" WHERE \"item\" = ?"
+        } else {
+            "SELECT * FROM \"xugc_item_versions\" WHERE \"item\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let mut out = match sqlquery(query).bind::<&str>(item).fetch_all(c).await {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    out.push(self.gimme_item_version(self.base.textify_row(row).0)?);
+                }
+
+                out
+            }
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        // numbers are stored as text, so they're sorted here
+        out.sort_by_key(|v| std::cmp::Reverse(v.number));
+        Ok(out)
+    }
+
+    /// Get all versions of an item, creating the first version from the item's
+    /// content if the item was created before versions existed
+    ///
+    /// # Arguments
+    /// * `item`
+    async fn get_item_versions_or_initial(&self, item: &Item) -> Result<Vec<ItemVersion>> {
+        let versions = self.get_item_versions(&item.id).await?;
+
+        if !versions.is_empty() {
+            return Ok(versions);
+        }
+
+        let version = ItemVersion {
+            id: AlmostSnowflake::new(self.config.snowflake_server_id).to_string(),
+            item: item.id.clone(),
+            number: 1,
+            content: item.content.clone(),
+            changelog: String::new(),
+            status: match item.status {
+                ItemStatus::Approved | ItemStatus::Featured => ItemStatus::Approved,
+                _ => ItemStatus::Pending,
+            },
+            timestamp: item.timestamp,
+        };
+
+        self.insert_item_version(&version).await?;
+        Ok(vec![version])
+    }
+
+    /// Get all versions waiting for review, oldest first
+    ///
+    /// # Returns
+    /// `Vec<(ItemVersion, Item)>`
+    pub async fn get_pending_item_versions(&self) -> Result<Vec<(ItemVersion, Item)>> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xugc_item_versions\" WHERE \"status\" = ? ORDER BY \"timestamp\" ASC LIMIT 24"
+        } else {
+            "SELECT * FROM \"xugc_item_versions\" WHERE \"status\" = $1 ORDER BY \"timestamp\" ASC LIMIT 24"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(&serde_json::to_string(&ItemStatus::Pending).unwrap())
+            .fetch_all(c)
+            .await
+        {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    let version = self.gimme_item_version(self.base.textify_row(row).0)?;
+                    let item = match self.get_item(&version.item).await {
+                        Ok(i) => i,
+                        Err(_) => continue,
+                    };
+
+                    // versions of unapproved items are reviewed with the item
+                    if (item.status != ItemStatus::Approved)
+                        && (item.status != ItemStatus::Featured)
+                    {
+                        continue;
+                    }
+
+                    out.push((version, item));
+                }
+
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
         }
+    }
 
-        if props.name.len() > (64 * 2) {
-            return Err(DatabaseError::TooLong);
+    /// Get the version of an item the given user has pinned
+    ///
+    /// # Arguments
+    /// * `item`
+    /// * `user`
+    pub async fn get_item_version_pin(&self, item: &str, user: &str) -> Option<ItemVersion> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xugc_item_pins\" WHERE \"item\" = ? AND \"user\" = ?"
+        } else {
+            "SELECT * FROM \"xugc_item_pins\" WHERE \"item\" = $1 AND \"user\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        let version = match sqlquery(query)
+            .bind::<&str>(item)
+            .bind::<&str>(user)
+            .fetch_one(c)
+            .await
+        {
+            Ok(p) => {
+                let res = self.base.textify_row(p).0;
+                from_row!(res->version())
+            }
+            Err(_) => return None,
+        };
+
+        self.get_item_version(&version).await.ok()
+    }
+
+    /// Get the content of an item for the given user (the version they've pinned, or
+    /// the item's current content)
+    ///
+    /// # Arguments
+    /// * `item`
+    /// * `user`
+    pub async fn get_item_content(&self, item: &Item, user: &str) -> String {
+        match self.get_item_version_pin(&item.id, user).await {
+            Some(v) if v.status == ItemStatus::Approved => v.content,
+            _ => item.content.clone(),
         }
+    }
 
-        if props.name.len() < 2 {
+    /// Get the IDs of everybody who bought an item (not including its creator)
+    ///
+    /// # Arguments
+    /// * `item`
+    pub async fn get_item_customers(&self, item: &str) -> Vec<String> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT \"customer\" FROM \"xugc_transactions\" WHERE \"item\" = ? AND \"customer\" != \"merchant\""
+        } else {
+            "SELECT \"customer\" FROM \"xugc_transactions\" WHERE \"item\" = $1 AND \"customer\" != \"merchant\""
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(item).fetch_all(c).await {
+            Ok(p) => {
+                let mut out: Vec<String> = Vec::new();
+
+                for row in p {
+                    let res = self.base.textify_row(row).0;
+                    let customer = from_row!(res->customer());
+
+                    if !out.contains(&customer) {
+                        out.push(customer);
+                    }
+                }
+
+                out
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // SET
+    /// Insert an item version
+    async fn insert_item_version(&self, version: &ItemVersion) -> Result<()> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "INSERT INTO \"xugc_item_versions\" VALUES (?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xugc_item_versions\" VALUES ($1, $2, $3, $4, $5, $6, $7)"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(&version.id)
+            .bind::<&str>(&version.item)
+            .bind::<&str>(&version.number.to_string())
+            .bind::<&str>(&version.content)
+            .bind::<&str>(&version.changelog)
+            .bind::<&str>(&serde_json::to_string(&version.status).unwrap())
+            .bind::<&str>(&version.timestamp.to_string())
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Approve or reject an item version
+    ///
+    /// Approving a version newer than the item's current version makes it the current
+    /// version, and everybody who bought the item is notified.
+    ///
+    /// # Arguments
+    /// * `id`
+    /// * `status` - [`ItemStatus`] (`Approved` or `Rejected`)
+    /// * `user`
+    pub async fn update_item_version_status(
+        &self,
+        id: &str,
+        status: ItemStatus,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        // make sure version exists
+        let version = self.get_item_version(id).await?;
+        let item = self.get_item(&version.item).await?;
+
+        if (status != ItemStatus::Approved) && (status != ItemStatus::Rejected) {
             return Err(DatabaseError::ValueError);
         }
 
-        if props.description.len() > (64 * 128) {
-            return Err(DatabaseError::TooLong);
+        // check permission
+        let group = match self.get_group_by_id(user.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        if !group.permissions.check(FinePermission::ECON_MASTER) {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // update version
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "UPDATE \"xugc_item_versions\" SET \"status\" = ? WHERE \"id\" = ?"
+        } else {
+            "UPDATE \"xugc_item_versions\" SET (\"status\") = ($1) WHERE \"id\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        if sqlquery(query)
+            .bind::<&str>(&serde_json::to_string(&status).unwrap())
+            .bind::<&str>(id)
+            .execute(c)
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        self.create_notification(
+            NotificationCreate {
+                title: "Item version reviewed!".to_string(),
+                content: format!(
+                    "Version {} of \"{}\" has been updated to the status of \"{}\"",
+                    version.number,
+                    item.name,
+                    status.to_string()
+                ),
+                address: format!("/market/item/{}#/versions", item.id),
+                recipient: item.creator.clone(),
+                kind: NotificationKind::System,
+            },
+            None,
+        )
+        .await?;
+
+        if status != ItemStatus::Approved {
+            return Ok(());
+        }
+
+        // make this the current version if it's the newest approved version
+        let newest = self
+            .get_item_versions(&item.id)
+            .await?
+            .into_iter()
+            .filter(|v| (v.status == ItemStatus::Approved) | (v.id == version.id))
+            .map(|v| v.number)
+            .max()
+            .unwrap_or(0);
+
+        if version.number < newest {
+            return Ok(());
+        }
+
+        self.set_item_content(&item.id, &version.content).await?;
+
+        // notify customers
+        for customer in self.get_item_customers(&item.id).await {
+            self.create_notification(
+                NotificationCreate {
+                    title: "Item update available!".to_string(),
+                    content: format!(
+                        "Version {} of \"{}\" is now available.",
+                        version.number, item.name
+                    ),
+                    address: format!("/market/item/{}#/versions", item.id),
+                    recipient: customer,
+                    kind: NotificationKind::System,
+                },
+                None,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Pin the version of an item the given user uses
+    ///
+    /// # Arguments
+    /// * `item`
+    /// * `props` - [`ItemVersionPin`]
+    /// * `user`
+    pub async fn pin_item_version(
+        &self,
+        item: &str,
+        props: ItemVersionPin,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        // make sure the item exists and was bought
+        let item = self.get_item(item).await?;
+
+        if self
+            .get_transaction_by_customer_item(&user.id, &item.id)
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        if !props.version.is_empty() {
+            // make sure the version exists and is approved
+            let version = self.get_item_version(&props.version).await?;
+
+            if (version.item != item.id) | (version.status != ItemStatus::Approved) {
+                return Err(DatabaseError::NotAllowed);
+            }
+        }
+
+        // remove the old pin
+        let c = &self.base.db.client;
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "DELETE FROM \"xugc_item_pins\" WHERE \"item\" = ? AND \"user\" = ?"
+        } else {
+            "DELETE FROM \"xugc_item_pins\" WHERE \"item\" = $1 AND \"user\" = $2"
+        };
+
+        if sqlquery(query)
+            .bind::<&str>(&item.id)
+            .bind::<&str>(&user.id)
+            .execute(c)
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        if props.version.is_empty() {
+            // always use the current version
+            return Ok(());
+        }
+
+        // pin
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "INSERT INTO \"xugc_item_pins\" VALUES (?, ?, ?)"
+        } else {
+            "INSERT INTO \"xugc_item_pins\" VALUES ($1, $2, $3)"
+        };
+
+        match sqlquery(query)
+            .bind::<&str>(&item.id)
+            .bind::<&str>(&user.id)
+            .bind::<&str>(&props.version)
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    // ugc installs
+
+    /// Get the [`ItemInstall`] of the given type for a user (the item of that type they
+    /// applied most recently)
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `type`
+    pub async fn get_item_install(&self, user: &str, r#type: &ItemType) -> Option<ItemInstall> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xugc_item_installs\" WHERE \"user\" = ? AND \"type\" = ?"
+        } else {
+            "SELECT * FROM \"xugc_item_installs\" WHERE \"user\" = $1 AND \"type\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(user)
+            .bind::<&str>(&r#type.to_string())
+            .fetch_one(c)
+            .await
+        {
+            Ok(p) => {
+                let res = self.base.textify_row(p).0;
+                Some(ItemInstall {
+                    user: from_row!(res->user()),
+                    r#type: r#type.to_owned(),
+                    item: from_row!(res->item()),
+                    previous: from_row!(res->previous()),
+                    timestamp: from_row!(res->timestamp(u128); 0),
+                })
+            }
+            Err(_) => None,
+        }
+    }
+
+    /// Remove the [`ItemInstall`] of the given type for a user
+    async fn delete_item_install(&self, user: &str, r#type: &ItemType) -> Result<()> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "DELETE FROM \"xugc_item_installs\" WHERE \"user\" = ? AND \"type\" = ?"
+        } else {
+            "DELETE FROM \"xugc_item_installs\" WHERE \"user\" = $1 AND \"type\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(user)
+            .bind::<&str>(&r#type.to_string())
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Set a user's layout or market theme to the given value
+    async fn set_installed_content(
+        &self,
+        user: &Profile,
+        r#type: &ItemType,
+        content: &str,
+    ) -> Result<()> {
+        match r#type {
+            ItemType::Layout => {
+                let layout: LayoutComponent = match serde_json::from_str(content) {
+                    Ok(l) => l,
+                    Err(_) => return Err(DatabaseError::ValueError),
+                };
+
+                self.update_profile_layout(&user.id, layout).await
+            }
+            ItemType::UserTheme => {
+                let mut metadata = user.metadata.clone();
+                let mut settings = metadata.settings();
+                settings.market_theme_template = content.to_string();
+                metadata.set_settings(&settings);
+
+                self.update_profile_metadata(&user.id, metadata).await
+            }
+            _ => Err(DatabaseError::ValueError),
+        }
+    }
+
+    /// Apply an [`ItemType::Layout`] or [`ItemType::UserTheme`] item to a user's profile
+    ///
+    /// The user's current layout (or theme) is recorded so it can be restored with
+    /// [`Database::revert_item`]. If an item of the same type is already applied, the
+    /// layout (or theme) recorded by that install is kept instead.
+    ///
+    /// # Arguments
+    /// * `item`
+    /// * `user`
+    pub async fn apply_item(&self, item: &str, user: Box<Profile>) -> Result<ItemInstall> {
+        // make sure the item exists and was bought
+        let item = self.get_item(item).await?;
+
+        if (item.r#type != ItemType::Layout) && (item.r#type != ItemType::UserTheme) {
+            return Err(DatabaseError::ValueError);
         }
 
-        // if we're creating a module, the cost HAS to be -1 (offsale)
-        if props.r#type == ItemType::Module && props.cost != -1 {
+        if self
+            .get_transaction_by_customer_item(&user.id, &item.id)
+            .await
+            .is_err()
+        {
             return Err(DatabaseError::NotAllowed);
         }
 
-        // ...
-        if props.cost.is_negative() && props.cost != -1 {
-            return Err(DatabaseError::NotAllowed);
+        // apply
+        // an existing install of the same type already holds what the user had
+        // before any item was applied, so keep that instead of the current content
+        let previous = match self.get_item_install(&user.id, &item.r#type).await {
+            Some(install) => install.previous,
+            None => match item.r#type {
+                ItemType::Layout => serde_json::to_string(&user.layout).unwrap(),
+                _ => user.metadata.settings().market_theme_template,
+            },
+        };
+
+        let mut content = self.get_item_content(&item, &user.id).await;
+
+        if item.r#type == ItemType::Layout {
+            // mark that this layout came from this item
+            let mut layout: LayoutComponent = match serde_json::from_str(&content) {
+                Ok(l) => l,
+                Err(_) => return Err(DatabaseError::ValueError),
+            };
+
+            layout
+                .options
+                .insert(MARKET_ID_OPTION.to_string(), item.id.clone());
+
+            content = serde_json::to_string(&layout).unwrap();
         }
 
-        let item = Item {
-            // id: utility::random_id(),
-            id: AlmostSnowflake::new(self.config.snowflake_server_id).to_string(),
-            name: props.name,
-            description: props.description,
-            cost: props.cost,
-            content: props.content,
-            r#type: props.r#type,
-            status: ItemStatus::Pending,
+        self.set_installed_content(&user, &item.r#type, &content)
+            .await?;
+
+        // record install
+        let install = ItemInstall {
+            user: user.id.clone(),
+            r#type: item.r#type.clone(),
+            item: item.id.clone(),
+            previous,
             timestamp: utility::unix_epoch_timestamp(),
-            creator: creator.to_string(),
         };
 
-        // subtract creation cost from creator
-        /* if let Err(e) = self
-            .create_transaction(
-                TransactionCreate {
-                    merchant: "0",
-                    item: "0",
-                    amount: -25,
-                },
-                creator.clone(),
-            )
-            .await
-        {
-            return Err(e);
-        }; */
+        self.delete_item_install(&user.id, &item.r#type).await?;
 
-        // create item
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            "INSERT INTO \"xugc_items\" VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO \"xugc_item_installs\" VALUES (?, ?, ?, ?, ?)"
         } else {
-            "INSERT INTO \"xugc_items\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+            "INSERT INTO \"xugc_item_installs\" VALUES ($1, $2, $3, $4, $5)"
         };
 
         let c = &self.base.db.client;
-        match sqlquery(&query)
-            .bind::<&str>(&item.id)
-            .bind::<&str>(&item.name)
-            .bind::<&str>(&item.description)
-            .bind::<i32>(item.cost)
-            .bind::<&str>(&item.content)
-            .bind::<&str>(&serde_json::to_string(&item.r#type).unwrap())
-            .bind::<&str>(&serde_json::to_string(&item.status).unwrap())
-            .bind::<&str>(&item.timestamp.to_string())
-            .bind::<&str>(&item.creator)
+        match sqlquery(query)
+            .bind::<&str>(&install.user)
+            .bind::<&str>(&install.r#type.to_string())
+            .bind::<&str>(&install.item)
+            .bind::<&str>(&install.previous)
+            .bind::<&str>(&install.timestamp.to_string())
             .execute(c)
             .await
         {
-            Ok(_) => {
-                // buy item (for free)
-                if let Err(e) = self
-                    .create_transaction(
-                        TransactionCreate {
-                            merchant: creator.to_string(),
-                            item: item.id.clone(),
-                            amount: 0,
-                        },
-                        creator,
-                    )
-                    .await
-                {
-                    return Err(e);
-                };
+            Ok(_) => Ok(install),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
 
-                // ...
-                return Ok(item);
-            }
-            Err(_) => return Err(DatabaseError::Other),
+    /// Restore the layout (or theme) a user had before applying an item
+    ///
+    /// Only the most recently applied item of each type can be reverted.
+    ///
+    /// # Arguments
+    /// * `item`
+    /// * `user`
+    pub async fn revert_item(&self, item: &str, user: Box<Profile>) -> Result<()> {
+        let item = self.get_item(item).await?;
+
+        let install = match self.get_item_install(&user.id, &item.r#type).await {
+            Some(i) => i,
+            None => return Err(DatabaseError::NotFound),
         };
+
+        if install.item != item.id {
+            // a different item was applied after this one
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        self.set_installed_content(&user, &item.r#type, &install.previous)
+            .await?;
+
+        self.delete_item_install(&user.id, &item.r#type).await
     }
 
-    /// Update item status
+    // ugc reviews
+
+    /// Get an [`ItemReview`] from a database result
+    pub fn gimme_review(&self, row: BTreeMap<String, String>) -> ItemReview {
+        ItemReview {
+            id: from_row!(row->id()),
+            item: from_row!(row->item()),
+            author: from_row!(row->author()),
+            purchase: from_row!(row->purchase()),
+            rating: from_row!(row->rating(u8); 0),
+            content: from_row!(row->content()),
+            reply: from_row!(row->reply()),
+            timestamp: from_row!(row->timestamp(u128); 0),
+            replied: from_row!(row->replied(u128); 0),
+        }
+    }
+
+    // GET
+    /// Get an existing review
     ///
     /// # Arguments
     /// * `id`
-    /// * `status` - [`ItemStatus`]
-    /// * `user`
-    pub async fn update_item_status(
-        &self,
-        id: &str,
-        status: ItemStatus,
-        user: Box<Profile>,
-    ) -> Result<()> {
-        // make sure item exists and check permission
-        let item = match self.get_item(id).await {
-            Ok(i) => i,
-            Err(e) => return Err(e),
+    pub async fn get_review(&self, id: &str) -> Result<ItemReview> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xugc_reviews\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \"xugc_reviews\" WHERE \"id\" = $1"
         };
 
-        // check permission
-        let group = match self.get_group_by_id(user.group).await {
-            Ok(g) => g,
-            Err(_) => return Err(DatabaseError::Other),
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(id).fetch_one(c).await {
+            Ok(p) => Ok(self.gimme_review(self.base.textify_row(p).0)),
+            Err(_) => Err(DatabaseError::NotFound),
+        }
+    }
+
+    /// Get the review the given user wrote for the given item
+    ///
+    /// # Arguments
+    /// * `author`
+    /// * `item`
+    pub async fn get_review_by_author_item(&self, author: &str, item: &str) -> Result<ItemReview> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xugc_reviews\" WHERE \"author\" = ? AND \"item\" = ?"
+        } else {
+            "SELECT * FROM \"xugc_reviews\" WHERE \"author\" = $1 AND \"item\" = $2"
         };
 
-        if !group.permissions.check(FinePermission::ECON_MASTER) {
-            return Err(DatabaseError::NotAllowed);
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(author)
+            .bind::<&str>(item)
+            .fetch_one(c)
+            .await
+        {
+            Ok(p) => Ok(self.gimme_review(self.base.textify_row(p).0)),
+            Err(_) => Err(DatabaseError::NotFound),
         }
+    }
 
-        // update item
+    /// Get all reviews of the given item, 12 at a time
+    ///
+    /// # Arguments
+    /// * `item`
+    /// * `page`
+    ///
+    /// # Returns
+    /// `Vec<(ItemReview, Author)>`
+    pub async fn get_reviews_by_item_paginated(
+        &self,
+        item: &str,
+        page: i32,
+    ) -> Result<Vec<(ItemReview, Box<Profile>)>> {
+        // pull from database
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            "UPDATE \"xugc_items\" SET \"status\" = ? WHERE \"id\" = ?"
+            format!("SELECT * FROM \"xugc_reviews\" WHERE \"item\" = ? ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         } else {
-            "UPDATE \"xugc_items\" SET (\"status\") = ($1) WHERE \"id\" = $2"
+            format!("SELECT * FROM \"xugc_reviews\" WHERE \"item\" = $1 ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         };
 
         let c = &self.base.db.client;
-        match sqlquery(&query)
-            .bind::<&str>(&serde_json::to_string(&status).unwrap())
-            .bind::<&str>(&id)
-            .execute(c)
+        match sqlquery(&query).bind::<&str>(item).fetch_all(c).await {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    let review = self.gimme_review(self.base.textify_row(row).0);
+
+                    let author = match self.get_profile(&review.author).await {
+                        Ok(ua) => ua,
+                        Err(_) => continue,
+                    };
+
+                    out.push((review, author));
+                }
+
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get the average rating of the given item
+    ///
+    /// # Arguments
+    /// * `item`
+    pub async fn get_item_rating(&self, item: &str) -> ItemRating {
+        // check in cache
+        if let Some(c) = self
+            .base
+            .cache
+            .get(format!("rbeam.auth.econ.item_rating:{}", item))
             .await
         {
-            Ok(_) => {
-                if let Err(e) = self
-                    .create_notification(
-                        NotificationCreate {
-                            title: "Item status updated!".to_string(),
-                            content: format!(
-                                "An item you created has been updated to the status of \"{}\"",
-                                status.to_string()
-                            ),
-                            address: format!("/market/item/{}", item.id.clone()),
-                            recipient: item.creator,
-                        },
-                        None,
-                    )
-                    .await
-                {
-                    return Err(e);
-                }
+            if let Ok(r) = serde_json::from_str::<ItemRating>(c.as_str()) {
+                return r;
+            }
+        }
 
-                // remove from cache
-                self.base
-                    .cache
-                    .remove(format!("rbeam.auth.econ.item:{}", id))
-                    .await;
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT \"rating\" FROM \"xugc_reviews\" WHERE \"item\" = ?"
+        } else {
+            "SELECT \"rating\" FROM \"xugc_reviews\" WHERE \"item\" = $1"
+        };
 
-                // ...
-                return Ok(());
+        let c = &self.base.db.client;
+        let rating = match sqlquery(query).bind::<&str>(item).fetch_all(c).await {
+            Ok(p) => {
+                let count = p.len();
+                let mut total: usize = 0;
+
+                for row in p {
+                    let res = self.base.textify_row(row).0;
+                    total += from_row!(res->rating(usize); 0);
+                }
+
+                ItemRating {
+                    average: if count == 0 {
+                        0.0
+                    } else {
+                        total as f32 / count as f32
+                    },
+                    count,
+                }
             }
-            Err(_) => return Err(DatabaseError::Other),
+            Err(_) => return ItemRating::default(),
         };
+
+        self.base
+            .cache
+            .set(
+                format!("rbeam.auth.econ.item_rating:{}", item),
+                serde_json::to_string::<ItemRating>(&rating).unwrap(),
+            )
+            .await;
+
+        rating
     }
 
-    /// Update item fields
+    // SET
+    /// Review an item
+    ///
+    /// Only users who bought the item (through a [`Transaction`]) can review it, once.
     ///
     /// # Arguments
-    /// * `id`
-    /// * `props` - [`ItemEdit`]
-    /// * `user`
-    pub async fn update_item(&self, id: &str, props: ItemEdit, user: Box<Profile>) -> Result<()> {
-        // make sure item exists and check permission
-        let item = match self.get_item(id).await {
+    /// * `item` - the ID of the item
+    /// * `props` - [`ItemReviewCreate`]
+    /// * `user` - the user doing this
+    pub async fn create_review(
+        &self,
+        item: &str,
+        props: ItemReviewCreate,
+        user: Box<Profile>,
+    ) -> Result<ItemReview> {
+        self.check_standing(&user).await?;
+
+        // make sure item exists
+        let item = match self.get_item(item).await {
             Ok(i) => i,
             Err(e) => return Err(e),
         };
 
-        // check values
-        if props.name.len() > (64 * 2) {
-            return Err(DatabaseError::TooLong);
+        if item.creator == user.id {
+            // cannot review our own items
+            return Err(DatabaseError::NotAllowed);
         }
 
-        if props.name.len() < 2 {
-            return Err(DatabaseError::ValueError);
+        // make sure the user bought this item
+        let transaction = match self
+            .get_transaction_by_customer_item(&user.id, &item.id)
+            .await
+        {
+            Ok(t) => t.0,
+            Err(_) => return Err(DatabaseError::NotAllowed),
+        };
+
+        // make sure the user hasn't reviewed this item already
+        if self
+            .get_review_by_author_item(&user.id, &item.id)
+            .await
+            .is_ok()
+        {
+            return Err(DatabaseError::MustBeUnique);
         }
 
-        if props.description.len() > (64 * 128) {
-            return Err(DatabaseError::TooLong);
+        // check values
+        if (props.rating < 1) | (props.rating > 5) {
+            return Err(DatabaseError::ValueError);
         }
 
-        // if we're creating a module, the cost HAS to be -1 (offsale)
-        if item.r#type == ItemType::Module && props.cost != -1 {
-            return Err(DatabaseError::NotAllowed);
+        if props.content.len() > 4096 {
+            return Err(DatabaseError::TooLong);
         }
 
         // ...
-        if props.cost.is_negative() && props.cost != -1 {
-            return Err(DatabaseError::NotAllowed);
-        }
-
-        // check permission
-        let group = match self.get_group_by_id(user.group).await {
-            Ok(g) => g,
-            Err(_) => return Err(DatabaseError::Other),
+        let review = ItemReview {
+            id: AlmostSnowflake::new(self.config.snowflake_server_id).to_string(),
+            item: item.id.clone(),
+            author: user.id.clone(),
+            purchase: transaction.id,
+            rating: props.rating,
+            content: props.content.trim().to_string(),
+            reply: String::new(),
+            timestamp: utility::unix_epoch_timestamp(),
+            replied: 0,
         };
 
-        if user.id != item.creator {
-            if !group.permissions.check(FinePermission::ECON_MASTER) {
-                return Err(DatabaseError::NotAllowed);
-            }
-        }
-
-        // update item
+        // create review
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            "UPDATE \"xugc_items\" SET \"name\" = ?, \"description\" = ?, \"cost\" = ? WHERE \"id\" = ?"
+            "INSERT INTO \"xugc_reviews\" VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
         } else {
-            "UPDATE \"xugc_items\" SET (\"name\", \"description\", \"cost\") = ($1, $2, $3) WHERE \"id\" = $4"
+            "INSERT INTO \"xugc_reviews\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
         };
 
         let c = &self.base.db.client;
-        match sqlquery(&query)
-            .bind::<&str>(&props.name)
-            .bind::<&str>(&props.description)
-            .bind::<i32>(props.cost)
-            .bind::<&str>(&id)
+        match sqlquery(query)
+            .bind::<&str>(&review.id)
+            .bind::<&str>(&review.item)
+            .bind::<&str>(&review.author)
+            .bind::<&str>(&review.purchase)
+            .bind::<&str>(&review.rating.to_string())
+            .bind::<&str>(&review.content)
+            .bind::<&str>(&review.reply)
+            .bind::<&str>(&review.timestamp.to_string())
+            .bind::<&str>(&review.replied.to_string())
             .execute(c)
             .await
         {
             Ok(_) => {
-                // remove from cache
                 self.base
                     .cache
-                    .remove(format!("rbeam.auth.econ.item:{}", id))
+                    .remove(format!("rbeam.auth.econ.item_rating:{}", item.id))
                     .await;
 
-                // ...
-                return Ok(());
+                // notify creator
+                self.create_notification(
+                    NotificationCreate {
+                        title: format!(
+                            "[@{}](/+u/{}) reviewed your item ({}/5)",
+                            user.username, user.id, review.rating
+                        ),
+                        content: review.content.clone(),
+                        address: format!("/market/item/{}#/reviews", item.id),
+                        recipient: item.creator,
+                        kind: NotificationKind::System,
+                    },
+                    None,
+                )
+                .await?;
+
+                Ok(review)
             }
-            Err(_) => return Err(DatabaseError::Other),
-        };
+            Err(_) => Err(DatabaseError::Other),
+        }
     }
 
-    /// Update item content
+    /// Reply to a review as the creator of the reviewed item
     ///
     /// # Arguments
-    /// * `id`
-    /// * `props` - [`ItemEditContent`]
-    /// * `user`
-    pub async fn update_item_content(
+    /// * `id` - the ID of the review
+    /// * `props` - [`ItemReviewReply`] (empty content removes the reply)
+    /// * `user` - the user doing this
+    pub async fn update_review_reply(
         &self,
         id: &str,
-        props: ItemEditContent,
+        props: ItemReviewReply,
         user: Box<Profile>,
     ) -> Result<()> {
-        // make sure item exists and check permission
-        let item = match self.get_item(id).await {
+        self.check_standing(&user).await?;
+
+        // make sure review exists
+        let review = match self.get_review(id).await {
+            Ok(r) => r,
+            Err(e) => return Err(e),
+        };
+
+        let item = match self.get_item(&review.item).await {
             Ok(i) => i,
             Err(e) => return Err(e),
         };
 
-        // we cannot change the content of a module
-        // this would allow people to lie about the checksum of their wasm package
-        //
-        // doing this also ensures that people create a new asset for each version of their package,
-        // meaning old versions will still verify properly
-        if item.r#type == ItemType::Module {
+        if item.creator != user.id {
             return Err(DatabaseError::NotAllowed);
         }
 
         // check values
-        if props.content.len() > (64 * 128 * 2) {
-            return Err(DatabaseError::TooLong);
-        }
+        let content = props.content.trim().to_string();
 
-        if props.content.len() < 2 {
-            return Err(DatabaseError::ValueError);
-        }
-
-        // check permission
-        let group = match self.get_group_by_id(user.group).await {
-            Ok(g) => g,
-            Err(_) => return Err(DatabaseError::Other),
-        };
-
-        if user.id != item.creator {
-            if !group.permissions.check(FinePermission::ECON_MASTER) {
-                return Err(DatabaseError::NotAllowed);
-            }
+        if content.len() > 4096 {
+            return Err(DatabaseError::TooLong);
         }
 
-        // update item
+        // update review
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            "UPDATE \"xugc_items\" SET \"content\" = ? WHERE \"id\" = ?"
+            "UPDATE \"xugc_reviews\" SET \"reply\" = ?, \"replied\" = ? WHERE \"id\" = ?"
         } else {
-            "UPDATE \"xugc_items\" SET (\"content\") = ($1) WHERE \"id\" = $2"
+            "UPDATE \"xugc_reviews\" SET (\"reply\", \"replied\") = ($1, $2) WHERE \"id\" = $3"
         };
 
         let c = &self.base.db.client;
-        match sqlquery(&query)
-            .bind::<&str>(&props.content)
-            .bind::<&str>(&id)
+        match sqlquery(query)
+            .bind::<&str>(&content)
+            .bind::<&str>(&utility::unix_epoch_timestamp().to_string())
+            .bind::<&str>(id)
             .execute(c)
             .await
         {
             Ok(_) => {
-                // remove from cache
-                self.base
-                    .cache
-                    .remove(format!("rbeam.auth.econ.item:{}", id))
-                    .await;
+                if !content.is_empty() && review.reply.is_empty() {
+                    // notify reviewer
+                    self.create_notification(
+                        NotificationCreate {
+                            title: format!(
+                                "[@{}](/+u/{}) replied to your review",
+                                user.username, user.id
+                            ),
+                            content,
+                            address: format!("/market/item/{}#/reviews", item.id),
+                            recipient: review.author,
+                            kind: NotificationKind::System,
+                        },
+                        None,
+                    )
+                    .await?;
+                }
 
-                // ...
-                return Ok(());
+                Ok(())
             }
-            Err(_) => return Err(DatabaseError::Other),
-        };
+            Err(_) => Err(DatabaseError::Other),
+        }
     }
 
-    /// Delete an existing item
+    /// Delete an existing review
     ///
-    /// Items can only be deleted by their creator.
+    /// Reviews can be deleted by their author, or by staff with the `ECON_MASTER` permission.
     ///
     /// # Arguments
-    /// * `id` - the ID of the item
+    /// * `id` - the ID of the review
     /// * `user` - the user doing this
-    pub async fn delete_item(&self, id: &str, user: Box<Profile>) -> Result<()> {
-        // make sure item exists
-        let item = match self.get_item(id).await {
-            Ok(n) => n,
+    pub async fn delete_review(&self, id: &str, user: Box<Profile>) -> Result<()> {
+        // make sure review exists
+        let review = match self.get_review(id).await {
+            Ok(r) => r,
             Err(e) => return Err(e),
         };
 
-        // check username
-        if item.creator != user.id {
-            // check permission
+        // check permission
+        if review.author != user.id {
             let group = match self.get_group_by_id(user.group).await {
                 Ok(g) => g,
                 Err(_) => return Err(DatabaseError::Other),
@@ -4932,29 +8356,267 @@ impl Database {
             if !group.permissions.check(FinePermission::ECON_MASTER) {
                 return Err(DatabaseError::NotAllowed);
             }
+
+            self.audit(
+                &user.id,
+                &format!(
+                    "Deleted an item review: [{}](/market/item/{}#/reviews)",
+                    review.id, review.item
+                ),
+            )
+            .await?;
         }
 
-        // delete item
+        // delete review
         let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
-            "DELETE FROM \"xugc_items\" WHERE \"id\" = ?"
+            "DELETE FROM \"xugc_reviews\" WHERE \"id\" = ?"
         } else {
-            "DELETE FROM \"xugc_items\" WHERE \"id\" = $1"
+            "DELETE FROM \"xugc_reviews\" WHERE \"id\" = $1"
         };
 
         let c = &self.base.db.client;
-        match sqlquery(&query).bind::<&str>(&id).execute(c).await {
+        match sqlquery(query).bind::<&str>(id).execute(c).await {
             Ok(_) => {
-                // remove from cache
                 self.base
                     .cache
-                    .remove(format!("rbeam.auth.econ.item:{}", id))
+                    .remove(format!("rbeam.auth.econ.item_rating:{}", review.item))
                     .await;
 
-                // return
-                return Ok(());
+                Ok(())
             }
-            Err(_) => return Err(DatabaseError::Other),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    // push subscriptions
+
+    /// Get a [`PushSubscription`] from a database result
+    fn gimme_push_subscription(&self, row: BTreeMap<String, String>) -> PushSubscription {
+        PushSubscription {
+            id: from_row!(row->id()),
+            user: from_row!(row->user()),
+            session: from_row!(row->session()),
+            endpoint: from_row!(row->endpoint()),
+            p256dh: from_row!(row->p256dh()),
+            auth: from_row!(row->auth()),
+            timestamp: from_row!(row->timestamp(u128); 0),
+        }
+    }
+
+    // GET
+    /// Get all [`PushSubscription`]s of the given user
+    ///
+    /// # Arguments
+    /// * `user`
+    pub async fn get_push_subscriptions_by_user(
+        &self,
+        user: &str,
+    ) -> Result<Vec<PushSubscription>> {
+        // pull from database
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xpush_subscriptions\" WHERE \"user\" = ?"
+        } else {
+            "SELECT * FROM \"xpush_subscriptions\" WHERE \"user\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(user).fetch_all(c).await {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    let res = self.base.textify_row(row).0;
+                    out.push(self.gimme_push_subscription(res));
+                }
+
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    // SET
+    /// Register a [`PushSubscription`] for the given session
+    ///
+    /// Registering an endpoint which is already registered replaces the existing subscription.
+    ///
+    /// # Arguments
+    /// * `props` - [`PushSubscriptionCreate`]
+    /// * `user` - the user registering the subscription
+    /// * `session` - the hashed token of the session registering the subscription
+    pub async fn create_push_subscription(
+        &self,
+        props: PushSubscriptionCreate,
+        user: Box<Profile>,
+        session: &str,
+    ) -> Result<PushSubscription> {
+        let push = match self.push {
+            Some(ref p) => p,
+            None => return Err(DatabaseError::NotAllowed),
+        };
+
+        // check values
+        if (props.endpoint.len() > 1024) | !push.is_valid_endpoint(&props.endpoint) {
+            return Err(DatabaseError::ValueError);
+        }
+
+        if (props.keys.p256dh.len() > 128) | (props.keys.auth.len() > 64) {
+            return Err(DatabaseError::ValueError);
+        }
+
+        // make sure the keys are usable
+        if push
+            .encrypt(&props.keys.p256dh, &props.keys.auth, &[])
+            .is_none()
+        {
+            return Err(DatabaseError::ValueError);
+        }
+
+        // remove existing
+        self.delete_push_subscription_by_endpoint(&props.endpoint, user.clone())
+            .await?;
+
+        // create
+        let subscription = PushSubscription {
+            id: AlmostSnowflake::new(self.config.snowflake_server_id).to_string(),
+            user: user.id.clone(),
+            session: session.to_string(),
+            endpoint: props.endpoint,
+            p256dh: props.keys.p256dh,
+            auth: props.keys.auth,
+            timestamp: utility::unix_epoch_timestamp(),
+        };
+
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "INSERT INTO \"xpush_subscriptions\" VALUES (?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xpush_subscriptions\" VALUES ($1, $2, $3, $4, $5, $6, $7)"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(&subscription.id)
+            .bind::<&str>(&subscription.user)
+            .bind::<&str>(&subscription.session)
+            .bind::<&str>(&subscription.endpoint)
+            .bind::<&str>(&subscription.p256dh)
+            .bind::<&str>(&subscription.auth)
+            .bind::<&str>(&subscription.timestamp.to_string())
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(subscription),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Remove the [`PushSubscription`] with the given endpoint
+    ///
+    /// # Arguments
+    /// * `endpoint`
+    /// * `user` - the user doing this
+    pub async fn delete_push_subscription_by_endpoint(
+        &self,
+        endpoint: &str,
+        user: Box<Profile>,
+    ) -> Result<()> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "DELETE FROM \"xpush_subscriptions\" WHERE \"endpoint\" = ? AND \"user\" = ?"
+        } else {
+            "DELETE FROM \"xpush_subscriptions\" WHERE \"endpoint\" = $1 AND \"user\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(endpoint)
+            .bind::<&str>(&user.id)
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Remove a [`PushSubscription`] by its ID
+    ///
+    /// # Arguments
+    /// * `id`
+    async fn delete_push_subscription(&self, id: &str) -> Result<()> {
+        let query = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "DELETE FROM \"xpush_subscriptions\" WHERE \"id\" = ?"
+        } else {
+            "DELETE FROM \"xpush_subscriptions\" WHERE \"id\" = $1"
         };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(id).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Deliver a [`PushMessage`] to every [`PushSubscription`] of `recipient`
+    ///
+    /// Subscriptions of expired sessions, and subscriptions the push service no longer
+    /// knows about, are removed.
+    ///
+    /// # Arguments
+    /// * `recipient`
+    /// * `kind` - the [`NotificationKind`] of the message, checked against the recipient's preferences
+    /// * `message`
+    pub async fn send_push(
+        &self,
+        recipient: &Profile,
+        kind: &NotificationKind,
+        message: &PushMessage,
+    ) -> Result<()> {
+        let push = match self.push {
+            Some(ref p) => p,
+            None => return Ok(()),
+        };
+
+        // check recipient preferences
+        if recipient.metadata.settings().is_push_muted(kind) {
+            return Ok(());
+        }
+
+        // send
+        for subscription in self.get_push_subscriptions_by_user(&recipient.id).await? {
+            if !recipient.tokens.contains(&subscription.session) {
+                // session is gone
+                self.delete_push_subscription(&subscription.id).await?;
+                continue;
+            }
+
+            if push.send(&subscription, message).await == PushResult::Expired {
+                self.delete_push_subscription(&subscription.id).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deliver a [`PushMessage`] to `recipient` in the background (see [`Database::send_push`])
+    ///
+    /// # Arguments
+    /// * `recipient`
+    /// * `kind`
+    /// * `message`
+    pub fn queue_push(
+        &self,
+        recipient: Box<Profile>,
+        kind: NotificationKind,
+        message: PushMessage,
+    ) {
+        if self.push.is_none() {
+            return;
+        }
+
+        let database = self.clone();
+        tokio::spawn(async move {
+            let _ = database.send_push(&recipient, &kind, &message).await;
+        });
     }
 
     // totp
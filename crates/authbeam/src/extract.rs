@@ -0,0 +1,167 @@
+//! Authentication extractors
+use std::marker::PhantomData;
+
+use axum::{
+    extract::{FromRef, FromRequestParts, OptionalFromRequestParts},
+    http::{header::AUTHORIZATION, request::Parts},
+};
+use axum_extra::extract::cookie::CookieJar;
+
+use crate::database::{Database, Result};
+use crate::model::{DatabaseError, Profile, TokenContext, TokenPermission};
+
+/// The name of the cookie storing the session token.
+pub const TOKEN_COOKIE: &str = "__Secure-Token";
+
+/// A [`TokenPermission`] which a route requires the request token to have.
+pub trait TokenScope {
+    /// The required permission, `None` accepts any valid token.
+    const PERMISSION: Option<TokenPermission>;
+}
+
+/// Marker types for [`TokenScope`]s, used as `Auth<scope::ManageAssets>`.
+pub mod scope {
+    use super::TokenScope;
+    use crate::model::TokenPermission;
+
+    macro_rules! token_scope {
+        ($(#[$meta:meta])* $name:ident => $permission:expr) => {
+            $(#[$meta])*
+            pub struct $name;
+
+            impl TokenScope for $name {
+                const PERMISSION: Option<TokenPermission> = $permission;
+            }
+        };
+    }
+
+    token_scope!(
+        /// Any valid token
+        Any => None
+    );
+    token_scope!(
+        /// [`TokenPermission::ManageAssets`]
+        ManageAssets => Some(TokenPermission::ManageAssets)
+    );
+    token_scope!(
+        /// [`TokenPermission::ManageProfile`]
+        ManageProfile => Some(TokenPermission::ManageProfile)
+    );
+    token_scope!(
+        /// [`TokenPermission::ManageAccount`]
+        ManageAccount => Some(TokenPermission::ManageAccount)
+    );
+    token_scope!(
+        /// [`TokenPermission::Moderator`]
+        Moderator => Some(TokenPermission::Moderator)
+    );
+    token_scope!(
+        /// [`TokenPermission::GenerateTokens`]
+        GenerateTokens => Some(TokenPermission::GenerateTokens)
+    );
+}
+
+/// Get the unhashed session token of a request.
+///
+/// An `Authorization: Bearer` header takes priority over the `__Secure-Token` cookie.
+pub fn token_from_parts(parts: &Parts) -> Option<String> {
+    if let Some(header) = parts.headers.get(AUTHORIZATION) {
+        if let Some(token) = header.to_str().unwrap_or_default().strip_prefix("Bearer ") {
+            let token = token.trim();
+
+            if !token.is_empty() {
+                return Some(token.to_string());
+            }
+        }
+    }
+
+    CookieJar::from_headers(&parts.headers)
+        .get(TOKEN_COOKIE)
+        .map(|c| c.value_trimmed().to_string())
+}
+
+/// An authenticated user whose token has the permission required by `S`.
+///
+/// Rejects with [`DatabaseError::NotAllowed`] when the request has no valid token,
+/// or when the token is missing the [`TokenScope`]'s permission. Use `Option<Auth<S>>`
+/// for routes which also serve anonymous users (a valid token missing the permission
+/// is still rejected, it is never treated as anonymous).
+pub struct Auth<S: TokenScope = scope::Any> {
+    /// The user the token belongs to
+    pub user: Box<Profile>,
+    /// The context of the token used for this request
+    pub token: TokenContext,
+    /// The hashed token used for this request, identifies the session
+    pub session: String,
+    scope: PhantomData<S>,
+}
+
+impl<S: TokenScope> Auth<S> {
+    /// Check if the request token has the given [`TokenPermission`]
+    pub fn can_do(&self, permission: TokenPermission) -> bool {
+        self.token.can_do(permission)
+    }
+
+    /// Resolve the user and token context of the given request `parts`
+    ///
+    /// Returns `None` when the request has no valid token.
+    async fn authenticate(parts: &Parts, database: &Database) -> Result<Option<Self>> {
+        let token = match token_from_parts(parts) {
+            Some(t) => t,
+            None => return Ok(None),
+        };
+
+        let user = match database.get_profile_by_unhashed(&token).await {
+            Ok(ua) => ua,
+            Err(_) => return Ok(None),
+        };
+
+        // check token permission
+        let session = databeam::utility::hash(token.clone());
+        let token = user.token_context_from_token(&token);
+
+        if let Some(permission) = S::PERMISSION {
+            if !token.can_do(permission) {
+                return Err(DatabaseError::NotAllowed);
+            }
+        }
+
+        // return
+        Ok(Some(Self {
+            user,
+            token,
+            session,
+            scope: PhantomData,
+        }))
+    }
+}
+
+impl<S, T> FromRequestParts<S> for Auth<T>
+where
+    Database: FromRef<S>,
+    S: Send + Sync,
+    T: TokenScope,
+{
+    type Rejection = DatabaseError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        match Self::authenticate(parts, &Database::from_ref(state)).await {
+            Ok(Some(auth)) => Ok(auth),
+            Ok(None) => Err(DatabaseError::NotAllowed),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<S, T> OptionalFromRequestParts<S> for Auth<T>
+where
+    Database: FromRef<S>,
+    S: Send + Sync,
+    T: TokenScope,
+{
+    type Rejection = DatabaseError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Option<Self>> {
+        Self::authenticate(parts, &Database::from_ref(state)).await
+    }
+}
@@ -6,6 +6,7 @@ use std::{
     sync::{LazyLock, RwLock},
 };
 
+use crate::css::{sanitize_css, CssRemoval};
 use crate::model::{Profile, RelationshipStatus};
 use reva_axum::Template;
 use rainbeam_shared::config::Config;
@@ -13,6 +14,214 @@ use rainbeam_shared::config::Config;
 pub static LAYOUTS: LazyLock<RwLock<HashMap<String, String>>> =
     LazyLock::new(|| RwLock::new(HashMap::new()));
 
+/// The names of the layout templates in `./.config/layouts` which can be imported
+/// through [`LayoutComponent::json`].
+///
+/// Only plain file names ending in `.json` are registered (see [`is_template_name`]).
+pub static LAYOUT_TEMPLATES: LazyLock<Vec<String>> = LazyLock::new(|| {
+    let mut out = Vec::new();
+
+    if let Ok(dir) = std::fs::read_dir(PathBufD::current().extend(&[".config", "layouts"])) {
+        for entry in dir.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            if is_template_name(&name) {
+                out.push(name);
+            }
+        }
+    }
+
+    out
+});
+
+/// The maximum size of a serialized layout (in bytes).
+pub const LAYOUT_MAX_BYTES: usize = 64 * 1024;
+/// The maximum nesting depth of a layout.
+pub const LAYOUT_MAX_DEPTH: usize = 16;
+/// The maximum number of components in a layout.
+pub const LAYOUT_MAX_COMPONENTS: usize = 256;
+/// The option which records the market item a layout was cloned from.
+pub const MARKET_ID_OPTION: &str = "#rainbeam:market_id";
+/// The maximum value of the `limit` option of list components ([`ComponentName::Drawings`],
+/// [`ComponentName::Tags`] and [`ComponentName::Questions`]).
+pub const LAYOUT_MAX_LIMIT: usize = 24;
+
+/// If the given name can be a layout template name (a file name made of letters,
+/// numbers, `-` and `_`, ending in `.json`).
+pub fn is_template_name(name: &str) -> bool {
+    match name.strip_suffix(".json") {
+        Some(stem) => {
+            !stem.is_empty()
+                && stem
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() | (c == '-') | (c == '_'))
+        }
+        None => false,
+    }
+}
+
+/// The reason a layout failed [`LayoutComponent::validate`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LayoutErrorKind {
+    /// The serialized layout is larger than [`LAYOUT_MAX_BYTES`].
+    TooLarge { max: usize },
+    /// The layout is nested deeper than [`LAYOUT_MAX_DEPTH`].
+    TooDeep { max: usize },
+    /// The layout has more than [`LAYOUT_MAX_COMPONENTS`] components.
+    TooManyComponents { max: usize },
+    /// The component imports a template which isn't in [`LAYOUT_TEMPLATES`].
+    UnknownTemplate { name: String },
+    /// The component doesn't support this option.
+    UnknownOption { key: String },
+    /// The value of this option isn't allowed.
+    InvalidOption { key: String },
+    /// The value of this option is too long.
+    OptionTooLong { key: String, max: usize },
+    /// Only [`ComponentName::Flex`] components can have children.
+    ChildrenNotAllowed,
+}
+
+impl LayoutErrorKind {
+    pub fn message(&self) -> String {
+        use LayoutErrorKind::*;
+        match self {
+            TooLarge { max } => format!("Layout is larger than {max} bytes."),
+            TooDeep { max } => format!("Layout is nested deeper than {max} levels."),
+            TooManyComponents { max } => format!("Layout has more than {max} components."),
+            UnknownTemplate { name } => format!("\"{name}\" is not a layout template."),
+            UnknownOption { key } => format!("Component doesn't support the \"{key}\" option."),
+            InvalidOption { key } => format!("Value of the \"{key}\" option is invalid."),
+            OptionTooLong { key, max } => {
+                format!("Value of the \"{key}\" option is longer than {max} bytes.")
+            }
+            ChildrenNotAllowed => "Only flex components can have children.".to_string(),
+        }
+    }
+}
+
+/// A problem found by [`LayoutComponent::validate`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LayoutError {
+    /// The path to the component (`/` for the root, `/children/0` for its first child)
+    pub path: String,
+    #[serde(flatten)]
+    pub kind: LayoutErrorKind,
+    /// A readable description of the error
+    pub message: String,
+}
+
+impl LayoutError {
+    pub fn new(path: &str, kind: LayoutErrorKind) -> Self {
+        Self {
+            path: if path.is_empty() {
+                "/".to_string()
+            } else {
+                path.to_string()
+            },
+            message: kind.message(),
+            kind,
+        }
+    }
+}
+
+/// The values allowed for a component option.
+enum OptionRule {
+    /// One of the given values.
+    OneOf(&'static [&'static str]),
+    /// Class names (letters, numbers, spaces, `-`, `_` and `:`), up to the given length.
+    Classes(usize),
+    /// Inline CSS (anything which can't leave the attribute), up to the given length.
+    Style(usize),
+    /// Any text, up to the given length.
+    Text(usize),
+    /// A whole number between the given minimum and maximum (inclusive).
+    Number(usize, usize),
+}
+
+impl OptionRule {
+    fn check(&self, key: &str, value: &str) -> Option<LayoutErrorKind> {
+        use OptionRule::*;
+
+        let max = match self {
+            OneOf(values) => {
+                if value.is_empty() | values.contains(&value) {
+                    return None;
+                }
+
+                return Some(LayoutErrorKind::InvalidOption {
+                    key: key.to_string(),
+                });
+            }
+            Number(min, max) => {
+                if value.is_empty()
+                    | value
+                        .parse::<usize>()
+                        .is_ok_and(|n| (n >= *min) & (n <= *max))
+                {
+                    return None;
+                }
+
+                return Some(LayoutErrorKind::InvalidOption {
+                    key: key.to_string(),
+                });
+            }
+            Classes(max) | Style(max) | Text(max) => *max,
+        };
+
+        if value.len() > max {
+            return Some(LayoutErrorKind::OptionTooLong {
+                key: key.to_string(),
+                max,
+            });
+        }
+
+        let valid = match self {
+            Classes(_) => value.chars().all(|c| {
+                c.is_ascii_alphanumeric() | (c == ' ') | (c == '-') | (c == '_') | (c == ':')
+            }),
+            Style(_) => !value.contains(['"', '<', '>']),
+            _ => true,
+        };
+
+        if !valid {
+            return Some(LayoutErrorKind::InvalidOption {
+                key: key.to_string(),
+            });
+        }
+
+        None
+    }
+}
+
+/// The options each [`ComponentName`] supports.
+fn option_rules(component: &ComponentName) -> &'static [(&'static str, OptionRule)] {
+    use ComponentName as T;
+    use OptionRule::*;
+
+    match component {
+        T::Flex => &[
+            ("direction", OneOf(&["row", "col"])),
+            ("gap", OneOf(&["1", "2", "3", "4", "5", "6", "7", "8"])),
+            ("collapse", OneOf(&["yes", "no"])),
+            ("width", OneOf(&["full", "content"])),
+            ("mobile", Classes(256)),
+            ("class", Classes(256)),
+            ("id", Classes(64)),
+            ("style", Style(1024)),
+        ],
+        T::Markdown => &[("text", Text(16 * 1024)), ("class", Classes(256))],
+        T::Divider => &[("class", Classes(256))],
+        T::Style => &[("data", Text(32 * 1024))],
+        T::Pins | T::Links | T::Badges | T::Stats => &[("class", Classes(256))],
+        T::Drawings | T::Tags | T::Questions => &[
+            ("limit", Number(1, LAYOUT_MAX_LIMIT)),
+            ("class", Classes(256)),
+        ],
+        _ => &[],
+    }
+}
+
 #[derive(Template)]
 #[template(path = "profile/layout_components/renderer.html")]
 pub struct RendererTemplate<'a> {
@@ -91,6 +300,27 @@ pub enum ComponentName {
     /// The site footer.
     #[serde(alias = "footer")]
     Footer,
+    /// The profile's pinned responses.
+    #[serde(alias = "pins")]
+    Pins,
+    /// The profile's links.
+    #[serde(alias = "links")]
+    Links,
+    /// The profile's badges and labels.
+    #[serde(alias = "badges")]
+    Badges,
+    /// The profile's response, question and social counts.
+    #[serde(alias = "stats")]
+    Stats,
+    /// A gallery of the drawings the profile has responded to.
+    #[serde(alias = "drawings")]
+    Drawings,
+    /// The tags the profile uses most on their responses.
+    #[serde(alias = "tags")]
+    Tags,
+    /// The global questions the profile asked most recently.
+    #[serde(alias = "questions")]
+    Questions,
 }
 
 impl Default for ComponentName {
@@ -137,41 +367,176 @@ impl LayoutComponent {
 
     /// Follow component template to get full template.
     ///
-    /// All imports are relative to `./.config/layouts`.
+    /// Only templates registered in [`LAYOUT_TEMPLATES`] (files in `./.config/layouts`)
+    /// can be imported. Unknown or malformed templates become an empty component, and
+    /// templates can't import other templates.
     pub fn fill(&self) -> LayoutComponent {
+        if self.json.is_empty() {
+            return self.to_owned();
+        }
+
+        if !LAYOUT_TEMPLATES.contains(&self.json) {
+            return LayoutComponent::default();
+        }
+
+        // check cache
+        match LAYOUTS.read() {
+            Ok(reader) => {
+                if let Some(l) = (*reader).get(&self.json) {
+                    return Self::from_template(l);
+                }
+            }
+            Err(_) => {
+                LAYOUTS.clear_poison();
+                return LayoutComponent::default();
+            }
+        };
+
+        // read template
+        let l = match std::fs::read_to_string(PathBufD::current().extend(&[
+            ".config",
+            "layouts",
+            self.json.as_str(),
+        ])) {
+            Ok(l) => l,
+            Err(_) => return LayoutComponent::default(),
+        };
+
+        if let Ok(mut writer) = LAYOUTS.write() {
+            (*writer).insert(self.json.clone(), l.clone());
+        }
+
+        Self::from_template(&l)
+    }
+
+    /// Parse the contents of a layout template.
+    fn from_template(l: &str) -> LayoutComponent {
+        match serde_json::from_str::<LayoutComponent>(l) {
+            Ok(mut c) => {
+                c.json = String::new();
+                c
+            }
+            Err(_) => LayoutComponent::default(),
+        }
+    }
+
+    /// Check the layout against [`LAYOUT_MAX_BYTES`], [`LAYOUT_MAX_DEPTH`],
+    /// [`LAYOUT_MAX_COMPONENTS`], the options each [`ComponentName`] supports, and
+    /// [`LAYOUT_TEMPLATES`].
+    pub fn validate(&self) -> Result<(), Vec<LayoutError>> {
+        let size = match serde_json::to_string(self) {
+            Ok(s) => s.len(),
+            Err(_) => usize::MAX,
+        };
+
+        if size > LAYOUT_MAX_BYTES {
+            return Err(vec![LayoutError::new(
+                "",
+                LayoutErrorKind::TooLarge {
+                    max: LAYOUT_MAX_BYTES,
+                },
+            )]);
+        }
+
+        let mut errors = Vec::new();
+        let mut count: usize = 0;
+        self.validate_component("", 1, &mut count, &mut errors);
+
+        if count > LAYOUT_MAX_COMPONENTS {
+            errors.insert(
+                0,
+                LayoutError::new(
+                    "",
+                    LayoutErrorKind::TooManyComponents {
+                        max: LAYOUT_MAX_COMPONENTS,
+                    },
+                ),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            errors.truncate(32);
+            Err(errors)
+        }
+    }
+
+    fn validate_component(
+        &self,
+        path: &str,
+        depth: usize,
+        count: &mut usize,
+        errors: &mut Vec<LayoutError>,
+    ) {
+        *count += 1;
+
+        if depth > LAYOUT_MAX_DEPTH {
+            errors.push(LayoutError::new(
+                path,
+                LayoutErrorKind::TooDeep {
+                    max: LAYOUT_MAX_DEPTH,
+                },
+            ));
+
+            return;
+        }
+
+        // json import
         if !self.json.is_empty() {
-            let reader = match LAYOUTS.read() {
-                Ok(r) => r,
-                Err(_) => {
-                    LAYOUTS.clear_poison();
-                    return LayoutComponent::default();
+            if !LAYOUT_TEMPLATES.contains(&self.json) {
+                errors.push(LayoutError::new(
+                    path,
+                    LayoutErrorKind::UnknownTemplate {
+                        name: self.json.clone(),
+                    },
+                ));
+            }
+
+            // the import replaces everything else in the component
+            return;
+        }
+
+        // options
+        let rules = option_rules(&self.component);
+
+        for (key, value) in &self.options {
+            if key == MARKET_ID_OPTION {
+                if !value.chars().all(|c| c.is_ascii_digit()) {
+                    errors.push(LayoutError::new(
+                        path,
+                        LayoutErrorKind::InvalidOption { key: key.clone() },
+                    ));
                 }
-            };
 
-            return {
-                if let Some(l) = (*reader).get(&self.json) {
-                    serde_json::from_str::<LayoutComponent>(l)
-                } else {
-                    let l = match std::fs::read_to_string(PathBufD::current().extend(&[
-                        ".config",
-                        "layouts",
-                        self.json.as_str(),
-                    ])) {
-                        Ok(l) => l,
-                        Err(_) => return LayoutComponent::default(),
-                    };
-
-                    drop(reader); // drop the reader so we can create a writer
-                    let mut writer = LAYOUTS.write().unwrap();
-                    (*writer).insert(self.json.clone(), l.clone());
-
-                    serde_json::from_str::<LayoutComponent>(&l)
+                continue;
+            }
+
+            match rules.iter().find(|(k, _)| k == key) {
+                Some((_, rule)) => {
+                    if let Some(kind) = rule.check(key, value) {
+                        errors.push(LayoutError::new(path, kind));
+                    }
                 }
-                .unwrap()
-            };
+                None => errors.push(LayoutError::new(
+                    path,
+                    LayoutErrorKind::UnknownOption { key: key.clone() },
+                )),
+            }
+        }
+
+        // children
+        if !self.children.is_empty() && (self.component != ComponentName::Flex) {
+            errors.push(LayoutError::new(path, LayoutErrorKind::ChildrenNotAllowed));
         }
 
-        self.to_owned()
+        for (i, child) in self.children.iter().enumerate() {
+            if *count > LAYOUT_MAX_COMPONENTS {
+                return;
+            }
+
+            child.validate_component(&format!("{path}/children/{i}"), depth + 1, count, errors);
+        }
     }
 
     /// Get the value of an option in the `options` map. Accepts a default substitute.
@@ -188,6 +553,47 @@ impl LayoutComponent {
         }
     }
 
+    /// Sanitize the CSS in the layout (the `data` of [`ComponentName::Style`] components
+    /// and the `style` of [`ComponentName::Flex`] components) with [`sanitize_css`]
+    ///
+    /// # Returns
+    /// Everything removed from the CSS
+    pub fn sanitize_css(&mut self) -> Vec<CssRemoval> {
+        let mut removed = Vec::new();
+
+        let key = match self.component {
+            ComponentName::Style => Some("data"),
+            ComponentName::Flex => Some("style"),
+            _ => None,
+        };
+
+        if let Some(value) = key.and_then(|k| self.options.get_mut(k)) {
+            let (css, mut report) = sanitize_css(value);
+            *value = css;
+            removed.append(&mut report);
+        }
+
+        for child in self.children.iter_mut() {
+            removed.append(&mut child.sanitize_css());
+        }
+
+        removed
+    }
+
+    /// Get the `data` option of a [`ComponentName::Style`] component, sanitized for a `<style>` element.
+    fn style_data(&self) -> String {
+        sanitize_css(&self.option("data", None)).0
+    }
+
+    /// Get the `style` option of a [`ComponentName::Flex`] component, sanitized for a `style` attribute.
+    fn style_attribute(&self) -> String {
+        // sanitized urls are quoted, which would close the attribute
+        sanitize_css(&self.option("style", None))
+            .0
+            .replace('&', "&amp;")
+            .replace('"', "&quot;")
+    }
+
     /// Render the component as HTML. Skips rendering with extra junk.
     ///
     /// See [`FreeRendererTemplate`].
@@ -238,7 +644,7 @@ impl LayoutComponent {
                     }
                 },
                 self.option("class", None),
-                self.option("style", None),
+                self.style_attribute(),
                 self.option("id", None),
                 // children
                 {
@@ -257,10 +663,7 @@ impl LayoutComponent {
                 self.option("class", None),
                 rainbeam_shared::ui::render_markdown(&self.option("text", None))
             ),
-            T::Style => format!(
-                "<style>{}</style>",
-                self.option("data", None).replace("</", "")
-            ),
+            T::Style => format!("<style>{}</style>", self.style_data()),
             T::Empty => String::new(),
             _ => format!("ComponentName::{:?}", self.component),
         }
@@ -356,7 +759,7 @@ impl LayoutComponent {
                     }
                 },
                 self.option("class", None),
-                self.option("style", None),
+                self.style_attribute(),
                 self.option("id", None),
                 // children
                 {
@@ -408,10 +811,7 @@ impl LayoutComponent {
                 self.option("class", None),
                 rainbeam_shared::ui::render_markdown(&self.option("text", None))
             ),
-            T::Style => format!(
-                "<style>{}</style>",
-                self.option("data", None).replace("</", "")
-            ),
+            T::Style => format!("<style>{}</style>", self.style_data()),
             T::Empty => String::new(),
             _ => format!("ComponentName::{:?}", self.component),
         }
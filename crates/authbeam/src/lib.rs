@@ -3,11 +3,16 @@
 #![doc(issue_tracker_base_url = "https://github.com/swmff/rainbeam/issues/")]
 pub mod api;
 pub mod avif;
+pub mod css;
 pub mod database;
+pub mod extract;
 pub mod layout;
 pub mod macros;
 pub mod model;
 pub mod permissions;
+pub mod push;
+pub mod settings;
+pub mod unlock;
 
-pub use database::{Database, ServerOptions};
+pub use database::{Database, ServerOptions, StrikePolicy};
 pub use databeam::DatabaseOpts;
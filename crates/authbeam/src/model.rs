@@ -11,7 +11,9 @@ use axum::{
 use serde::{Serialize, Deserialize};
 use databeam::prelude::DefaultReturn;
 
+use crate::css::{sanitize_css, CssRemoval};
 use crate::layout::LayoutComponent;
+use crate::settings::ProfileSettings;
 
 /// Basic user structure
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -150,6 +152,11 @@ impl Profile {
         self.labels.contains(&id)
     }
 
+    /// Check if the user is quarantined ([`RESERVED_LABEL_QUARANTINE`]).
+    pub fn is_quarantined(&self) -> bool {
+        self.has_label(RESERVED_LABEL_QUARANTINE)
+    }
+
     // totp
 
     /// Get a [`TOTP`] from the profile's `totp` secret value.
@@ -305,6 +312,16 @@ impl ProfileMetadata {
         self.kv.get(key).unwrap().to_owned()
     }
 
+    /// Get the typed [`ProfileSettings`] stored in `kv`
+    pub fn settings(&self) -> ProfileSettings {
+        ProfileSettings::from_kv(&self.kv)
+    }
+
+    /// Store the given [`ProfileSettings`] in `kv`
+    pub fn set_settings(&mut self, settings: &ProfileSettings) {
+        settings.write_kv(&mut self.kv);
+    }
+
     /// Check `kv` lengths
     ///
     /// # Returns
@@ -328,6 +345,64 @@ impl ProfileMetadata {
 
         true
     }
+
+    /// Check `email`
+    ///
+    /// The address can be empty, or a single `local@domain` address of at most 254
+    /// characters (without quoting, comments or whitespace).
+    ///
+    /// # Returns
+    /// * `true`: ok
+    /// * `false`: invalid
+    pub fn check_email(&self) -> bool {
+        if self.email.is_empty() {
+            return true;
+        }
+
+        if self.email.len() > 254 {
+            return false;
+        }
+
+        let (local, domain) = match self.email.split_once('@') {
+            Some(parts) => parts,
+            None => return false,
+        };
+
+        if local.is_empty() | (local.len() > 64) | local.starts_with('.') | local.ends_with('.') {
+            return false;
+        }
+
+        local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() | "!#$%&'*+-/=?^_`{|}~.".contains(c))
+            && domain.contains('.')
+            && domain.split('.').all(|label| {
+                !label.is_empty()
+                    && (label.len() <= 63)
+                    && label
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() | (c == '-'))
+            })
+    }
+
+    /// Sanitize the CSS values in `kv` (`sparkler:custom_css` and `rainbeam:market_theme_template`)
+    /// with [`sanitize_css`]
+    ///
+    /// # Returns
+    /// Everything removed from the CSS
+    pub fn sanitize_css(&mut self) -> Vec<CssRemoval> {
+        let mut removed = Vec::new();
+
+        for key in ["sparkler:custom_css", "rainbeam:market_theme_template"] {
+            if let Some(value) = self.kv.get_mut(key) {
+                let (css, mut report) = sanitize_css(value);
+                *value = css;
+                removed.append(&mut report);
+            }
+        }
+
+        removed
+    }
 }
 
 impl ProfileMetadata {
@@ -359,6 +434,46 @@ pub struct UserFollow {
     pub following: String,
 }
 
+/// A pending follow of a profile which requires followers to be approved
+/// (`rainbeam:approve_followers`)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FollowRequest {
+    /// The ID of the user requesting to follow
+    pub user: String,
+    /// The ID of the user they are requesting to follow
+    pub following: String,
+    /// The timestamp of when the request was created
+    pub timestamp: u128,
+}
+
+/// The kind of a [`Notification`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub enum NotificationKind {
+    /// A new question was sent to the recipient
+    NewQuestion,
+    /// Somebody reacted to an asset the recipient created
+    Reaction,
+    /// Somebody responded to, commented on, or replied to an asset the recipient created
+    Comment,
+    /// Somebody mentioned the recipient
+    Mention,
+    /// Somebody followed (or requested to follow) the recipient
+    Follow,
+    /// Somebody sent (or accepted) a friend request
+    FriendRequest,
+    /// Anything else (warnings, reports, purchases, etc.)
+    #[default]
+    System,
+}
+
+impl NotificationKind {
+    /// If unread notifications of this kind with the same address are aggregated
+    /// into a single notification
+    pub fn is_aggregated(&self) -> bool {
+        matches!(self, Self::Reaction | Self::Comment | Self::Follow)
+    }
+}
+
 /// Basic notification structure
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Notification {
@@ -374,6 +489,69 @@ pub struct Notification {
     pub id: String,
     /// The recipient of the notification
     pub recipient: String,
+    /// The kind of the notification
+    #[serde(default)]
+    pub kind: NotificationKind,
+    /// If the notification has been read by its recipient
+    #[serde(default)]
+    pub read: bool,
+    /// The number of other notifications which were aggregated into this one
+    #[serde(default)]
+    pub others: usize,
+}
+
+/// A browser's Web Push subscription, registered for a single session
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PushSubscription {
+    /// The ID of the subscription
+    pub id: String,
+    /// The ID of the user the subscription belongs to
+    pub user: String,
+    /// The hashed token of the session which registered the subscription
+    ///
+    /// The subscription is removed once this token is no longer valid.
+    #[serde(skip_serializing)]
+    pub session: String,
+    /// The push service URL messages are sent to
+    pub endpoint: String,
+    /// The user agent's P-256 public key (base64url)
+    pub p256dh: String,
+    /// The user agent's authentication secret (base64url)
+    #[serde(skip_serializing)]
+    pub auth: String,
+    /// The timestamp of when the subscription was created
+    pub timestamp: u128,
+}
+
+/// The payload of a push notification, shown by the service worker
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PushMessage {
+    /// The title of the notification
+    pub title: String,
+    /// The body of the notification
+    pub body: String,
+    /// The address opened when the notification is clicked
+    pub url: String,
+}
+
+impl PushMessage {
+    /// Create a new [`PushMessage`] from markdown `title` and `body`
+    ///
+    /// Markdown links are replaced by their text, and the body is shortened.
+    pub fn new(title: &str, body: &str, url: &str) -> Self {
+        let links = regex::Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap();
+
+        let mut body = links.replace_all(body, "$1").to_string();
+        if body.chars().count() > 256 {
+            body = format!("{}...", body.chars().take(256).collect::<String>());
+        }
+
+        Self {
+            title: links.replace_all(title, "$1").to_string(),
+            body,
+            url: url.to_string(),
+        }
+    }
 }
 
 /// Basic warning structure
@@ -389,6 +567,71 @@ pub struct Warning {
     pub recipient: String,
     /// The moderator who warned the recipient
     pub moderator: Box<Profile>,
+    /// The severity of the warning
+    #[serde(default)]
+    pub severity: WarningSeverity,
+    /// A reference to the rule which was broken
+    #[serde(default)]
+    pub rule: String,
+    /// The number of strikes the warning counts as
+    #[serde(default)]
+    pub strikes: usize,
+    /// The timestamp of when the recipient acknowledged the warning (`0` if they haven't)
+    #[serde(default)]
+    pub acknowledged: u128,
+}
+
+impl Warning {
+    /// If the recipient has acknowledged the warning
+    pub fn is_acknowledged(&self) -> bool {
+        self.acknowledged != 0
+    }
+}
+
+/// The severity of a [`Warning`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub enum WarningSeverity {
+    /// Counts as 1 strike
+    #[default]
+    Minor,
+    /// Counts as 2 strikes
+    Major,
+    /// Counts as 3 strikes
+    Severe,
+}
+
+impl WarningSeverity {
+    /// The number of strikes a warning of this severity counts as
+    pub fn strikes(&self) -> usize {
+        match self {
+            Self::Minor => 1,
+            Self::Major => 2,
+            Self::Severe => 3,
+        }
+    }
+}
+
+/// A temporary suspension, suspended users cannot post
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Suspension {
+    /// The ID of the suspended user
+    pub user: String,
+    /// The reason for the suspension
+    pub reason: String,
+    /// The ID of the moderator who suspended the user (empty if the suspension was
+    /// issued by the strike policy)
+    pub moderator: String,
+    /// The timestamp of when the suspension was created
+    pub timestamp: u128,
+    /// The timestamp of when the suspension ends
+    pub expires: u128,
+}
+
+impl Suspension {
+    /// If the suspension is still in effect
+    pub fn is_active(&self) -> bool {
+        rainbeam_shared::unix_epoch_timestamp() < self.expires
+    }
 }
 
 /// Basic IP ban
@@ -441,6 +684,29 @@ pub struct Relationship {
     pub timestamp: u128,
 }
 
+/// A one-sided mute of user `muted` by user `user`
+///
+/// Unlike [`RelationshipStatus::Blocked`], the muted user is not informed and can
+/// still interact with the user who muted them. Their content is just hidden from `user`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserMute {
+    /// The user who created the mute
+    pub user: String,
+    /// The user who was muted
+    pub muted: String,
+    /// The timestamp of the mute's creation
+    pub timestamp: u128,
+    /// The timestamp the mute expires at (`0` for never)
+    pub expires: u128,
+}
+
+impl UserMute {
+    /// Check if the mute is still in effect
+    pub fn is_active(&self) -> bool {
+        (self.expires == 0) | (self.expires > rainbeam_shared::unix_epoch_timestamp())
+    }
+}
+
 /// An IP-based block
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct IpBlock {
@@ -476,6 +742,8 @@ impl Default for Group {
     }
 }
 
+/// Quarantined users' content is only shown to their followers, and the questions
+/// they ask are delivered to the filtered section of the recipient's inbox
 pub const RESERVED_LABEL_QUARANTINE: i64 = -1;
 
 /// A label which describes a user
@@ -508,6 +776,56 @@ pub struct Transaction {
     pub merchant: String,
 }
 
+/// A single side of a coin transfer in the ledger
+///
+/// The entries of a transfer always sum to `0`. The system account (`"0"`) is the
+/// only account allowed to go negative, as it's where new coins are minted from.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LedgerEntry {
+    /// The ID of the entry (unique)
+    pub id: String,
+    /// The ID of the transfer this entry belongs to (the [`Transaction`] ID for purchases)
+    pub transfer: String,
+    /// The ID of the account this entry applies to
+    pub account: String,
+    /// The amount added to (or removed from) the account
+    pub amount: i32,
+    /// What the transfer was for (an item ID, `"grant"` or `"opening"`)
+    pub reference: String,
+    /// The idempotency key of the transfer (the entry ID if no key was given)
+    pub idempotency: String,
+    /// The timestamp of when the entry was created
+    pub timestamp: u128,
+}
+
+/// An account flagged during ledger reconciliation
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LedgerDrift {
+    /// The ID of the account
+    pub account: String,
+    /// The username of the account
+    pub username: String,
+    /// The balance stored on the profile
+    pub coins: i32,
+    /// The sum of the account's ledger entries (`None` if the account has none)
+    pub ledger: Option<i64>,
+}
+
+/// The result of checking every stored balance against the ledger
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LedgerReport {
+    /// Accounts whose balance doesn't match the sum of their ledger entries
+    pub drift: Vec<LedgerDrift>,
+    /// Accounts with a negative balance
+    pub negative: Vec<LedgerDrift>,
+    /// The sum of every entry in the ledger (anything other than `0` is a bug)
+    pub imbalance: i64,
+    /// The number of coins minted by the system account
+    pub minted: i64,
+    /// The timestamp of when the report was generated
+    pub timestamp: u128,
+}
+
 /// A marketplace item type
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum ItemType {
@@ -595,6 +913,136 @@ pub struct Item {
     pub creator: String,
 }
 
+/// An immutable version of the content of an [`Item`]
+///
+/// New versions have to be approved before they become the item's current content.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ItemVersion {
+    /// The ID of the version (unique)
+    pub id: String,
+    /// The ID of the item
+    pub item: String,
+    /// The number of this version (starting at 1)
+    pub number: i32,
+    /// The content of the item at this version
+    pub content: String,
+    /// What changed in this version
+    pub changelog: String,
+    /// The review status of this version
+    pub status: ItemStatus,
+    /// The timestamp of when the version was created
+    pub timestamp: u128,
+}
+
+/// An [`Item`] applied to a profile (a [`ItemType::Layout`] or [`ItemType::UserTheme`])
+///
+/// Only the most recent item of each type is recorded, along with what it replaced
+/// so it can be reverted.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ItemInstall {
+    /// The ID of the user the item was applied to
+    pub user: String,
+    /// The type of the item
+    pub r#type: ItemType,
+    /// The ID of the item
+    pub item: String,
+    /// The layout (serialized) or theme the item replaced
+    pub previous: String,
+    /// The timestamp of when the item was applied
+    pub timestamp: u128,
+}
+
+/// A rating and review of an [`Item`] by somebody who bought it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ItemReview {
+    /// The ID of the review (unique)
+    pub id: String,
+    /// The ID of the reviewed item
+    pub item: String,
+    /// The ID of the user who wrote the review
+    pub author: String,
+    /// The ID of the [`Transaction`] the author bought the item with
+    pub purchase: String,
+    /// The rating given to the item (1-5)
+    pub rating: u8,
+    /// The content of the review
+    pub content: String,
+    /// The item creator's reply to the review (empty for no reply)
+    pub reply: String,
+    /// The timestamp of when the review was created
+    pub timestamp: u128,
+    /// The timestamp of when the reply was last changed
+    pub replied: u128,
+}
+
+/// The average rating of an [`Item`]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ItemRating {
+    /// The average of all review ratings (`0.0` if there are no reviews)
+    pub average: f32,
+    /// The number of reviews
+    pub count: usize,
+}
+
+impl ItemRating {
+    /// The average rating rounded to one decimal place, for display
+    pub fn display(&self) -> String {
+        format!("{:.1}", self.average)
+    }
+}
+
+/// The order of marketplace search results
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub enum ItemSort {
+    /// Newest items first
+    #[default]
+    #[serde(alias = "newest")]
+    Newest,
+    /// Items with the most purchases first
+    #[serde(alias = "popular")]
+    Popular,
+    /// Cheapest items first
+    #[serde(alias = "price_asc")]
+    PriceAsc,
+    /// Most expensive items first
+    #[serde(alias = "price_desc")]
+    PriceDesc,
+    /// Items with the highest average rating first
+    #[serde(alias = "rating")]
+    Rating,
+}
+
+impl std::fmt::Display for ItemSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A marketplace search
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ItemSearch {
+    /// Words which must all appear in the name or description of the item
+    #[serde(default)]
+    pub q: String,
+    /// A tag the item must have (empty for any)
+    #[serde(default)]
+    pub tag: String,
+    #[serde(default)]
+    pub status: ItemStatus,
+    #[serde(default)]
+    pub r#type: Option<ItemType>,
+    #[serde(default)]
+    pub sort: ItemSort,
+    /// The lowest cost to include
+    #[serde(default)]
+    pub min_cost: Option<i32>,
+    /// The highest cost to include
+    #[serde(default)]
+    pub max_cost: Option<i32>,
+    #[serde(default)]
+    pub page: i32,
+}
+
 // props
 #[derive(Serialize, Deserialize, Debug, Hcaptcha)]
 pub struct ProfileCreate {
@@ -655,6 +1103,11 @@ pub struct SetProfileTier {
     pub tier: i32,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UnlockProfile {
+    pub password: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SetProfileCoins {
     pub coins: i32,
@@ -678,12 +1131,46 @@ pub struct NotificationCreate {
     pub content: String,
     pub address: String,
     pub recipient: String,
+    #[serde(default)]
+    pub kind: NotificationKind,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+/// A browser `PushSubscription` (as given by `PushSubscription.toJSON()`)
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PushSubscriptionCreate {
+    pub endpoint: String,
+    pub keys: PushSubscriptionKeys,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PushSubscriptionDelete {
+    pub endpoint: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WarningCreate {
     pub content: String,
     pub recipient: String,
+    #[serde(default)]
+    pub severity: WarningSeverity,
+    #[serde(default)]
+    pub rule: String,
+    /// If the warning shouldn't count towards the strike policy
+    #[serde(default)]
+    pub no_strike: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SuspensionCreate {
+    /// How long the suspension lasts in milliseconds
+    pub duration: u128,
+    pub reason: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -692,6 +1179,13 @@ pub struct IpBanCreate {
     pub reason: String,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserMuteCreate {
+    /// How long the mute should last for in milliseconds (`0` for forever)
+    #[serde(default)]
+    pub duration: u128,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct IpBlockCreate {
     pub ip: String,
@@ -704,6 +1198,9 @@ pub struct TransactionCreate {
     pub merchant: String,
     pub item: String,
     pub amount: i32,
+    /// Repeating a transaction with the same key returns the original transaction
+    #[serde(default)]
+    pub idempotency_key: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -713,6 +1210,8 @@ pub struct ItemCreate {
     pub content: String,
     pub cost: i32,
     pub r#type: ItemType,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -720,11 +1219,35 @@ pub struct ItemEdit {
     pub name: String,
     pub description: String,
     pub cost: i32,
+    /// The new tags of the item (`None` to keep the current tags)
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ItemEditContent {
     pub content: String,
+    #[serde(default)]
+    pub changelog: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ItemVersionPin {
+    /// The ID of the version to pin (empty to always use the current version)
+    #[serde(default)]
+    pub version: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ItemReviewCreate {
+    pub rating: u8,
+    #[serde(default)]
+    pub content: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ItemReviewReply {
+    pub content: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -756,6 +1279,9 @@ pub enum DatabaseError {
     ValueError,
     NotFound,
     TooLong,
+    UnacknowledgedWarnings,
+    Suspended,
+    TooManyAttempts,
     Other,
 }
 
@@ -775,6 +1301,11 @@ impl DatabaseError {
             ValueError => String::from("One of the field values given is invalid."),
             NotFound => String::from("No asset with this ID could be found."),
             TooLong => String::from("Given data is too long."),
+            UnacknowledgedWarnings => String::from(
+                "You must acknowledge your account warnings before doing this. Visit /warnings to view them.",
+            ),
+            Suspended => String::from("Your account is currently suspended."),
+            TooManyAttempts => String::from("Too many attempts. Please try again later."),
             _ => String::from("An unspecified error has occured"),
         }
     }
@@ -810,6 +1341,15 @@ impl IntoResponse for DatabaseError {
                 }),
             )
                 .into_response(),
+            TooManyAttempts => (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(DefaultReturn::<u16> {
+                    success: false,
+                    message: self.to_string(),
+                    payload: 429,
+                }),
+            )
+                .into_response(),
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(DefaultReturn::<u16> {
@@ -0,0 +1,307 @@
+//! Web Push delivery (RFC 8030) with VAPID (RFC 8292) and `aes128gcm` encrypted payloads (RFC 8291)
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use pathbufd::PathBufD;
+use rainbeam_shared::fs;
+use reqwest::{Client as HttpClient, StatusCode, Url};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use ring::{aead, agreement, hkdf};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{PushMessage, PushSubscription};
+
+/// The record size of encrypted payloads, every payload is sent as a single record
+const RECORD_SIZE: u32 = 4096;
+
+/// How long (in seconds) VAPID tokens and pushed messages are valid for
+const TTL: u64 = 60 * 60 * 12;
+
+/// Push configuration file
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PushConfig {
+    /// If push notifications are enabled
+    #[serde(default)]
+    pub enabled: bool,
+    /// The contact sent to push services with every request (a `mailto:` or `https:` URL)
+    #[serde(default)]
+    pub subject: String,
+    /// The VAPID private key (PKCS#8, base64url)
+    #[serde(default)]
+    pub private_key: String,
+    /// If subscriptions with `http:` endpoints are allowed
+    ///
+    /// Only useful for testing against a local mock push service.
+    #[serde(default)]
+    pub allow_insecure_endpoints: bool,
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            subject: String::new(),
+            private_key: Self::generate_key(),
+            allow_insecure_endpoints: false,
+        }
+    }
+}
+
+impl PushConfig {
+    /// Generate a new VAPID private key
+    pub fn generate_key() -> String {
+        let document =
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+                .expect("failed to generate VAPID key");
+
+        URL_SAFE_NO_PAD.encode(document.as_ref())
+    }
+
+    /// Read configuration file into [`PushConfig`]
+    pub fn read(contents: String) -> Self {
+        toml::from_str::<Self>(&contents).unwrap()
+    }
+
+    /// Pull configuration file
+    pub fn get_config() -> Self {
+        let path = PathBufD::current().extend(&[".config", "push", "config.toml"]);
+
+        match fs::read(path) {
+            Ok(c) => {
+                let mut config = PushConfig::read(c);
+
+                if config.private_key.is_empty() {
+                    config.private_key = Self::generate_key();
+                    Self::update_config(config.clone()).expect("failed to write push config");
+                }
+
+                config
+            }
+            Err(_) => {
+                let config = Self::default();
+                Self::update_config(config.clone()).expect("failed to write default push config");
+                config
+            }
+        }
+    }
+
+    /// Update configuration file
+    pub fn update_config(contents: Self) -> std::io::Result<()> {
+        let dir = PathBufD::current().extend(&[".config", "push"]);
+        fs::mkdir(&dir)?;
+
+        fs::write(
+            dir.join("config.toml"),
+            toml::to_string_pretty::<Self>(&contents).unwrap(),
+        )
+    }
+}
+
+/// The outcome of delivering a [`PushMessage`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum PushResult {
+    /// The push service accepted the message
+    Sent,
+    /// The subscription no longer exists and should be removed
+    Expired,
+    /// The message could not be delivered
+    Failed,
+}
+
+/// [`hkdf::KeyType`] for an arbitrary output length
+struct OkmLength(usize);
+
+impl hkdf::KeyType for OkmLength {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+/// HKDF-SHA-256 extract and expand
+fn hkdf_sha256(salt: &[u8], ikm: &[u8], info: &[u8], out: &mut [u8]) -> Option<()> {
+    hkdf::Salt::new(hkdf::HKDF_SHA256, salt)
+        .extract(ikm)
+        .expand(&[info], OkmLength(out.len()))
+        .ok()?
+        .fill(out)
+        .ok()
+}
+
+/// Web Push client
+pub struct WebPush {
+    key: EcdsaKeyPair,
+    rng: SystemRandom,
+    http: HttpClient,
+    pub config: PushConfig,
+}
+
+impl WebPush {
+    /// Create a new [`WebPush`] client from the given [`PushConfig`]
+    ///
+    /// Returns `None` when push is disabled or the VAPID key is invalid.
+    pub fn new(config: PushConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+
+        let rng = SystemRandom::new();
+        let pkcs8 = URL_SAFE_NO_PAD.decode(&config.private_key).ok()?;
+        let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &pkcs8, &rng).ok()?;
+
+        Some(Self {
+            key,
+            rng,
+            http: HttpClient::new(),
+            config,
+        })
+    }
+
+    /// The VAPID public key (uncompressed point, base64url), given to browsers as the
+    /// `applicationServerKey`
+    pub fn public_key(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.key.public_key().as_ref())
+    }
+
+    /// Check if the given subscription endpoint can be pushed to
+    pub fn is_valid_endpoint(&self, endpoint: &str) -> bool {
+        match Url::parse(endpoint) {
+            Ok(url) => {
+                url.host_str().is_some()
+                    && ((url.scheme() == "https")
+                        | ((url.scheme() == "http") && self.config.allow_insecure_endpoints))
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Build the VAPID `Authorization` header for the given endpoint
+    fn vapid(&self, endpoint: &Url) -> Option<String> {
+        let audience = endpoint.origin().ascii_serialization();
+        let expires = (rainbeam_shared::unix_epoch_timestamp() / 1000) as u64 + TTL;
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(
+            serde_json::json!({
+                "aud": audience,
+                "exp": expires,
+                "sub": self.config.subject,
+            })
+            .to_string(),
+        );
+
+        let unsigned = format!("{header}.{claims}");
+        let signature = self.key.sign(&self.rng, unsigned.as_bytes()).ok()?;
+
+        Some(format!(
+            "vapid t={unsigned}.{}, k={}",
+            URL_SAFE_NO_PAD.encode(signature.as_ref()),
+            self.public_key()
+        ))
+    }
+
+    /// Encrypt `payload` for the user agent with the given `p256dh` public key and
+    /// `auth` secret (both base64url)
+    pub fn encrypt(&self, p256dh: &str, auth: &str, payload: &[u8]) -> Option<Vec<u8>> {
+        let ua_public = URL_SAFE_NO_PAD.decode(p256dh.trim_end_matches('=')).ok()?;
+        let auth_secret = URL_SAFE_NO_PAD.decode(auth.trim_end_matches('=')).ok()?;
+
+        // the payload and padding delimiter must fit in one record with the tag
+        if payload.len() + 1 + 16 > RECORD_SIZE as usize {
+            return None;
+        }
+
+        // ephemeral key agreement
+        let as_private =
+            agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, &self.rng).ok()?;
+        let as_public = as_private.compute_public_key().ok()?.as_ref().to_vec();
+
+        let ecdh_secret = agreement::agree_ephemeral(
+            as_private,
+            &agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, &ua_public),
+            |secret| secret.to_vec(),
+        )
+        .ok()?;
+
+        // input keying material
+        let mut key_info = b"WebPush: info\0".to_vec();
+        key_info.extend_from_slice(&ua_public);
+        key_info.extend_from_slice(&as_public);
+
+        let mut ikm = [0u8; 32];
+        hkdf_sha256(&auth_secret, &ecdh_secret, &key_info, &mut ikm)?;
+
+        // content encryption key and nonce
+        let mut salt = [0u8; 16];
+        self.rng.fill(&mut salt).ok()?;
+
+        let mut cek = [0u8; 16];
+        hkdf_sha256(&salt, &ikm, b"Content-Encoding: aes128gcm\0", &mut cek)?;
+
+        let mut nonce = [0u8; 12];
+        hkdf_sha256(&salt, &ikm, b"Content-Encoding: nonce\0", &mut nonce)?;
+
+        // encrypt (a single record, so the padding delimiter is 2)
+        let mut record = payload.to_vec();
+        record.push(2);
+
+        let key = aead::LessSafeKey::new(aead::UnboundKey::new(&aead::AES_128_GCM, &cek).ok()?);
+        key.seal_in_place_append_tag(
+            aead::Nonce::assume_unique_for_key(nonce),
+            aead::Aad::empty(),
+            &mut record,
+        )
+        .ok()?;
+
+        // header (salt, record size, key id) and record
+        let mut out = Vec::with_capacity(16 + 4 + 1 + as_public.len() + record.len());
+        out.extend_from_slice(&salt);
+        out.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+        out.push(as_public.len() as u8);
+        out.extend_from_slice(&as_public);
+        out.extend_from_slice(&record);
+
+        Some(out)
+    }
+
+    /// Deliver a [`PushMessage`] to the given [`PushSubscription`]
+    pub async fn send(&self, subscription: &PushSubscription, message: &PushMessage) -> PushResult {
+        let endpoint = match Url::parse(&subscription.endpoint) {
+            Ok(u) => u,
+            Err(_) => return PushResult::Expired,
+        };
+
+        let authorization = match self.vapid(&endpoint) {
+            Some(a) => a,
+            None => return PushResult::Failed,
+        };
+
+        let body = match self.encrypt(
+            &subscription.p256dh,
+            &subscription.auth,
+            serde_json::to_string(message).unwrap().as_bytes(),
+        ) {
+            Some(b) => b,
+            None => return PushResult::Failed,
+        };
+
+        match self
+            .http
+            .post(endpoint)
+            .header("Authorization", authorization)
+            .header("TTL", TTL.to_string())
+            .header("Urgency", "normal")
+            .header("Content-Encoding", "aes128gcm")
+            .header("Content-Type", "application/octet-stream")
+            .body(body)
+            .send()
+            .await
+        {
+            Ok(res) => match res.status() {
+                s if s.is_success() => PushResult::Sent,
+                StatusCode::NOT_FOUND | StatusCode::GONE => PushResult::Expired,
+                _ => PushResult::Failed,
+            },
+            Err(_) => PushResult::Failed,
+        }
+    }
+}
@@ -0,0 +1,586 @@
+//! Typed profile settings.
+//!
+//! Settings are stored in [`ProfileMetadata::kv`](crate::model::ProfileMetadata::kv) under
+//! their `sparkler:` and `rainbeam:` keys (which is also how clients send them), but
+//! should always be read through [`ProfileSettings`] so a mistyped key is a compile
+//! error instead of a setting which silently doesn't apply.
+//!
+//! Keys outside of the `sparkler:` and `rainbeam:` namespaces are extension data and
+//! are kept in `kv` as they are.
+use rainbeam_shared::hash::{hash_salted, salt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::model::NotificationKind;
+
+/// Namespaces reserved for settings, keys in these namespaces must be in [`ProfileSettings::KEYS`]
+/// or [`COLOR_KEYS`].
+pub const RESERVED_NAMESPACES: &[&str] = &["sparkler:", "rainbeam:"];
+
+/// The keys of the theme colors a profile can set (rendered as CSS variables).
+pub const COLOR_KEYS: &[&str] = &[
+    "sparkler:color_surface",
+    "sparkler:color_lowered",
+    "sparkler:color_super_lowered",
+    "sparkler:color_raised",
+    "sparkler:color_super_raised",
+    "sparkler:color_text",
+    "sparkler:color_text_raised",
+    "sparkler:color_text_lowered",
+    "sparkler:color_link",
+    "sparkler:color_primary",
+    "sparkler:color_primary_lowered",
+    "sparkler:color_primary_raised",
+    "sparkler:color_text_primary",
+    "sparkler:color_shadow",
+];
+
+/// The maximum number of extension keys (keys outside of [`RESERVED_NAMESPACES`]) in `kv`.
+pub const EXTENSION_KEYS_MAX: usize = 32;
+/// The maximum length of an extension key.
+pub const EXTENSION_KEY_MAX_LENGTH: usize = 64;
+/// The maximum number of pinned responses.
+pub const PINNED_MAX: usize = 32;
+/// The prefix of a hashed [`ProfileSettings::view_password`] (`sha256${salt}${hash}`).
+pub const VIEW_PASSWORD_PREFIX: &str = "sha256$";
+
+/// If the given key is a setting (in [`ProfileSettings::KEYS`] or [`COLOR_KEYS`]).
+pub fn is_setting_key(key: &str) -> bool {
+    ProfileSettings::KEYS.contains(&key) | COLOR_KEYS.contains(&key)
+}
+
+/// If the given key is in one of the [`RESERVED_NAMESPACES`].
+pub fn is_reserved_key(key: &str) -> bool {
+    RESERVED_NAMESPACES.iter().any(|ns| key.starts_with(ns))
+}
+
+/// The reason `kv` failed [`ProfileSettings::parse`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SettingsErrorKind {
+    /// The key is in one of the [`RESERVED_NAMESPACES`], but it isn't a setting.
+    UnknownKey,
+    /// The value can't be parsed as the setting's type (or isn't allowed).
+    InvalidValue,
+    /// There are more than [`EXTENSION_KEYS_MAX`] extension keys.
+    TooManyKeys { max: usize },
+    /// The extension key is longer than [`EXTENSION_KEY_MAX_LENGTH`].
+    KeyTooLong { max: usize },
+}
+
+impl SettingsErrorKind {
+    pub fn message(&self, key: &str) -> String {
+        use SettingsErrorKind::*;
+        match self {
+            UnknownKey => format!("\"{key}\" is not a profile setting."),
+            InvalidValue => format!("Value of the \"{key}\" setting is invalid."),
+            TooManyKeys { max } => format!("Profiles can't have more than {max} extension keys."),
+            KeyTooLong { max } => format!("\"{key}\" is longer than {max} characters."),
+        }
+    }
+}
+
+/// A problem found by [`ProfileSettings::parse`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SettingsError {
+    /// The key with the problem
+    pub key: String,
+    #[serde(flatten)]
+    pub kind: SettingsErrorKind,
+    /// A readable description of the error
+    pub message: String,
+}
+
+impl SettingsError {
+    pub fn new(key: &str, kind: SettingsErrorKind) -> Self {
+        Self {
+            key: key.to_string(),
+            message: kind.message(key),
+            kind,
+        }
+    }
+}
+
+/// A type which can be stored as a setting in `kv`.
+pub trait SettingValue: Sized + Default + PartialEq {
+    /// Parse a `kv` value, `None` if it's invalid.
+    fn parse(value: &str) -> Option<Self>;
+    /// The `kv` value of this setting.
+    fn to_value(&self) -> String;
+}
+
+impl SettingValue for bool {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "true" => Some(true),
+            "false" | "" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn to_value(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SettingValue for String {
+    fn parse(value: &str) -> Option<Self> {
+        Some(value.to_string())
+    }
+
+    fn to_value(&self) -> String {
+        self.to_owned()
+    }
+}
+
+/// A list of IDs, stored separated by commas.
+impl SettingValue for Vec<String> {
+    fn parse(value: &str) -> Option<Self> {
+        let mut out = Vec::new();
+
+        for id in value.split(",") {
+            let id = id.trim();
+
+            if id.is_empty() {
+                continue;
+            }
+
+            if !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return None;
+            }
+
+            out.push(id.to_string());
+        }
+
+        Some(out)
+    }
+
+    fn to_value(&self) -> String {
+        self.join(",")
+    }
+}
+
+/// A URL to an image (or another page).
+///
+/// URLs have to be absolute (`https://` or `http://`), start with `/`, or be `rb://`
+/// (an uploaded image).
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct SettingUrl(pub String);
+
+impl SettingUrl {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl SettingValue for SettingUrl {
+    fn parse(value: &str) -> Option<Self> {
+        let lower = value.to_lowercase();
+
+        if value.is_empty()
+            | (lower == "rb://")
+            | lower.starts_with("https://")
+            | lower.starts_with("http://")
+            | (value.starts_with("/") && !value.starts_with("//"))
+        {
+            return Some(Self(value.to_string()));
+        }
+
+        None
+    }
+
+    fn to_value(&self) -> String {
+        self.0.to_owned()
+    }
+}
+
+/// The way a profile banner is fit onto the image element.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BannerFit {
+    #[default]
+    Cover,
+    Fill,
+    Contain,
+}
+
+impl SettingValue for BannerFit {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "cover" | "" => Some(Self::Cover),
+            "fill" => Some(Self::Fill),
+            "contain" => Some(Self::Contain),
+            _ => None,
+        }
+    }
+
+    fn to_value(&self) -> String {
+        match self {
+            Self::Cover => "cover",
+            Self::Fill => "fill",
+            Self::Contain => "contain",
+        }
+        .to_string()
+    }
+}
+
+/// The theme a profile is always shown in.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileTheme {
+    /// The viewer's own theme
+    #[default]
+    UserChoice,
+    Light,
+    Dark,
+    DarkDim,
+}
+
+impl SettingValue for ProfileTheme {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "" => Some(Self::UserChoice),
+            "light" => Some(Self::Light),
+            "dark" => Some(Self::Dark),
+            "dark dim" => Some(Self::DarkDim),
+            _ => None,
+        }
+    }
+
+    fn to_value(&self) -> String {
+        match self {
+            Self::UserChoice => "",
+            Self::Light => "light",
+            Self::Dark => "dark",
+            Self::DarkDim => "dark dim",
+        }
+        .to_string()
+    }
+}
+
+/// Write a setting into `kv`. Settings which were never set aren't added while
+/// they're still their default value.
+fn write_setting<T: SettingValue>(kv: &mut HashMap<String, String>, key: &str, value: &T) {
+    if !kv.contains_key(key) && (*value == T::default()) {
+        return;
+    }
+
+    kv.insert(key.to_string(), value.to_value());
+}
+
+/// Define [`ProfileSettings`] and its `kv` keys.
+macro_rules! profile_settings {
+    ($($(#[$meta:meta])* $field:ident: $ty:ty = $key:literal,)*) => {
+        /// A profile's settings.
+        ///
+        /// Every field is stored in [`ProfileMetadata::kv`](crate::model::ProfileMetadata::kv)
+        /// under the key listed in its documentation, and is its default value when the key
+        /// doesn't exist. Booleans are stored as `"true"` or `"false"`.
+        #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+        #[serde(default)]
+        pub struct ProfileSettings {
+            $(
+                $(#[$meta])*
+                #[doc = ""]
+                #[doc = concat!("Key: `", $key, "`")]
+                pub $field: $ty,
+            )*
+        }
+
+        impl ProfileSettings {
+            /// The `kv` key of every setting (besides [`COLOR_KEYS`])
+            pub const KEYS: &'static [&'static str] = &[$($key),*];
+
+            /// Read settings from `kv`
+            ///
+            /// Values which can't be parsed are read as their default value, so this
+            /// works on any existing metadata.
+            pub fn from_kv(kv: &HashMap<String, String>) -> Self {
+                Self {
+                    $(
+                        $field: kv
+                            .get($key)
+                            .and_then(|v| SettingValue::parse(v))
+                            .unwrap_or_default(),
+                    )*
+                }
+            }
+
+            /// If `value` is a valid value for the setting `key` (any value is valid
+            /// for keys which aren't in [`ProfileSettings::KEYS`])
+            pub fn is_valid_value(key: &str, value: &str) -> bool {
+                match key {
+                    $($key => <$ty as SettingValue>::parse(value).is_some(),)*
+                    _ => true,
+                }
+            }
+
+            /// Write settings into `kv`
+            pub fn write_kv(&self, kv: &mut HashMap<String, String>) {
+                $(write_setting(kv, $key, &self.$field);)*
+            }
+        }
+    };
+}
+
+profile_settings! {
+    // profile
+    /// The name shown instead of the username
+    display_name: String = "sparkler:display_name",
+    /// A short status shown on the profile
+    status_note: String = "sparkler:status_note",
+    /// The emoji shown next to [`ProfileSettings::status_note`]
+    status_emoji: String = "sparkler:status_emoji",
+    /// The profile's about section (markdown)
+    biography: String = "sparkler:biography",
+    /// Extra markdown shown in the profile sidebar
+    sidebar: String = "sparkler:sidebar",
+    /// A warning visitors have to accept before viewing the profile
+    warning: String = "sparkler:warning",
+    /// The header shown on the profile's timeline
+    motivational_header: String = "sparkler:motivational_header",
+    /// The profile's avatar (`rb://` for an uploaded avatar)
+    avatar_url: SettingUrl = "sparkler:avatar_url",
+    /// The profile's banner (`rb://` for an uploaded banner)
+    banner_url: SettingUrl = "sparkler:banner_url",
+    /// How the banner is fit onto the image element
+    banner_fit: BannerFit = "sparkler:banner_fit",
+    /// The responses pinned to the profile
+    pinned: Vec<String> = "sparkler:pinned",
+    /// The name anonymous questions from this user are shown with
+    anonymous_username: String = "sparkler:anonymous_username",
+    /// The avatar anonymous questions from this user are shown with
+    anonymous_avatar: SettingUrl = "sparkler:anonymous_avatar",
+    /// The link shown with the "verified" check on the profile
+    verify_url: SettingUrl = "rainbeam:verify_url",
+    /// The code the page at [`ProfileSettings::verify_url`] has to include
+    verify_code: String = "rainbeam:verify_code",
+    /// The hashtag added to shared responses
+    share_hashtag: String = "rainbeam:share_hashtag",
+
+    // theme
+    /// The user's preferred theme for the rest of the site
+    website_theme: String = "sparkler:website_theme",
+    /// If the user sees other profiles' themes
+    allow_profile_themes: bool = "sparkler:allow_profile_themes",
+    /// The theme the profile is always shown in
+    profile_theme: ProfileTheme = "sparkler:profile_theme",
+    /// The logo shown on the desktop timeline
+    desktop_tl_logo: SettingUrl = "sparkler:desktop_tl_logo",
+    /// The profile's custom CSS (see [`crate::css::sanitize_css`])
+    custom_css: String = "sparkler:custom_css",
+    /// The CSS of the market theme applied to the profile
+    market_theme_template: String = "rainbeam:market_theme_template",
+    /// If the profile is always shown with the default layout
+    force_default_layout: bool = "rainbeam:force_default_layout",
+
+    // privacy
+    /// If responses from the profile are hidden from public timelines
+    private_profile: bool = "sparkler:private_profile",
+    /// If the profile is marked as NSFW
+    nsfw_profile: bool = "rainbeam:nsfw_profile",
+    /// If only signed in users can view the profile
+    authenticated_only: bool = "rainbeam:authenticated_only",
+    /// The password required to view the profile, hashed (see [`ProfileSettings::hash_view_password`])
+    view_password: String = "rainbeam:view_password",
+    /// If the profile doesn't accept new questions
+    lock_profile: bool = "sparkler:lock_profile",
+    /// If the profile doesn't accept anonymous questions
+    disallow_anonymous: bool = "sparkler:disallow_anonymous",
+    /// If the profile doesn't accept anonymous comments
+    disallow_anonymous_comments: bool = "sparkler:disallow_anonymous_comments",
+    /// If nobody can comment on the profile's responses
+    disallow_response_comments: bool = "rainbeam:disallow_response_comments",
+    /// If only signed in users can ask the profile questions
+    require_account: bool = "sparkler:require_account",
+    /// If the profile's followers, following and friends are hidden
+    private_social: bool = "sparkler:private_social",
+    /// If only users the profile follows can send it friend requests
+    limited_friend_requests: bool = "sparkler:limited_friend_requests",
+    /// If the profile has to approve new followers
+    approve_followers: bool = "rainbeam:approve_followers",
+    /// If the profile accepts drawings with questions
+    allow_drawings: bool = "sparkler:allow_drawings",
+    /// Phrases (one per line, lines starting with `#` are ignored) which questions
+    /// to the profile can't include (see [`ProfileSettings::filters`])
+    filter: String = "sparkler:filter",
+
+    // inbox
+    /// If global questions from followed users aren't sent to the inbox
+    do_not_send_global_questions_to_inbox: bool = "rainbeam:do_not_send_global_questions_to_inbox",
+    /// If viewing the inbox doesn't clear its count
+    do_not_clear_inbox_count_on_view: bool = "rainbeam:do_not_clear_inbox_count_on_view",
+    /// If the user's global questions aren't sent to their friends' inboxes
+    do_not_send_global_questions_to_friends: bool = "rainbeam:do_not_send_global_questions_to_friends",
+
+    // notifications
+    mute_question_notifications: bool = "rainbeam:mute_question_notifications",
+    mute_reaction_notifications: bool = "rainbeam:mute_reaction_notifications",
+    mute_comment_notifications: bool = "rainbeam:mute_comment_notifications",
+    mute_mention_notifications: bool = "rainbeam:mute_mention_notifications",
+    mute_follow_notifications: bool = "rainbeam:mute_follow_notifications",
+    mute_friend_request_notifications: bool = "rainbeam:mute_friend_request_notifications",
+    mute_question_push: bool = "rainbeam:mute_question_push",
+    mute_reaction_push: bool = "rainbeam:mute_reaction_push",
+    mute_comment_push: bool = "rainbeam:mute_comment_push",
+    mute_mention_push: bool = "rainbeam:mute_mention_push",
+    mute_follow_push: bool = "rainbeam:mute_follow_push",
+    mute_friend_request_push: bool = "rainbeam:mute_friend_request_push",
+}
+
+impl ProfileSettings {
+    /// Read and validate settings from `kv`
+    ///
+    /// Every key in one of the [`RESERVED_NAMESPACES`] has to be a setting with a valid
+    /// value, other keys are extension data.
+    pub fn parse(kv: &HashMap<String, String>) -> Result<Self, SettingsError> {
+        Self::parse_changes(&HashMap::new(), kv)
+    }
+
+    /// Read settings from `kv`, only validating the values which are different in `old`
+    ///
+    /// Values which were already stored before settings were validated are read
+    /// like [`ProfileSettings::from_kv`] does, so they don't stop the profile from
+    /// changing anything else.
+    pub fn parse_changes(
+        old: &HashMap<String, String>,
+        kv: &HashMap<String, String>,
+    ) -> Result<Self, SettingsError> {
+        let mut extensions = 0;
+
+        for (key, value) in kv {
+            if !is_reserved_key(key) {
+                extensions += 1;
+            }
+
+            if old.get(key) == Some(value) {
+                continue;
+            }
+
+            if is_reserved_key(key) {
+                if !is_setting_key(key) {
+                    return Err(SettingsError::new(key, SettingsErrorKind::UnknownKey));
+                }
+
+                if !Self::is_valid_value(key, value) {
+                    return Err(SettingsError::new(key, SettingsErrorKind::InvalidValue));
+                }
+
+                continue;
+            }
+
+            if key.chars().count() > EXTENSION_KEY_MAX_LENGTH {
+                return Err(SettingsError::new(
+                    key,
+                    SettingsErrorKind::KeyTooLong {
+                        max: EXTENSION_KEY_MAX_LENGTH,
+                    },
+                ));
+            }
+        }
+
+        if extensions > EXTENSION_KEYS_MAX {
+            return Err(SettingsError::new(
+                "",
+                SettingsErrorKind::TooManyKeys {
+                    max: EXTENSION_KEYS_MAX,
+                },
+            ));
+        }
+
+        let settings = Self::from_kv(kv);
+
+        if (settings.pinned.len() > PINNED_MAX)
+            && (old.get("sparkler:pinned") != kv.get("sparkler:pinned"))
+        {
+            return Err(SettingsError::new(
+                "sparkler:pinned",
+                SettingsErrorKind::InvalidValue,
+            ));
+        }
+
+        Ok(settings)
+    }
+
+    /// The phrases in [`ProfileSettings::filter`] (lowercase)
+    pub fn filters(&self) -> Vec<String> {
+        self.filter
+            .split("\n")
+            .map(|f| f.trim().to_lowercase())
+            .filter(|f| !f.is_empty() && !f.starts_with("#"))
+            .collect()
+    }
+
+    /// If the profile has a [`ProfileSettings::view_password`]
+    pub fn has_view_password(&self) -> bool {
+        !self.view_password.is_empty()
+    }
+
+    /// If [`ProfileSettings::view_password`] is stored hashed
+    ///
+    /// Passwords saved before they were hashed are plaintext until the server starts
+    /// again (see [`crate::Database::hash_view_passwords`]).
+    pub fn is_view_password_hashed(&self) -> bool {
+        self.view_password.starts_with(VIEW_PASSWORD_PREFIX)
+    }
+
+    /// Replace [`ProfileSettings::view_password`] with its salted hash
+    pub fn hash_view_password(&mut self) {
+        if self.view_password.is_empty() {
+            return;
+        }
+
+        let salt = salt();
+        self.view_password = format!(
+            "{VIEW_PASSWORD_PREFIX}{salt}${}",
+            hash_salted(self.view_password.clone(), salt.clone())
+        );
+    }
+
+    /// Check if `input` is the profile's [`ProfileSettings::view_password`]
+    pub fn check_view_password(&self, input: &str) -> bool {
+        if !self.has_view_password() | input.is_empty() {
+            return false;
+        }
+
+        match self
+            .view_password
+            .strip_prefix(VIEW_PASSWORD_PREFIX)
+            .and_then(|hashed| hashed.split_once('$'))
+        {
+            Some((salt, hash)) => hash_salted(input.to_string(), salt.to_string()) == hash,
+            None => false,
+        }
+    }
+
+    /// If notifications of the given kind are muted
+    pub fn is_muted(&self, kind: &NotificationKind) -> bool {
+        match kind {
+            NotificationKind::NewQuestion => self.mute_question_notifications,
+            NotificationKind::Reaction => self.mute_reaction_notifications,
+            NotificationKind::Comment => self.mute_comment_notifications,
+            NotificationKind::Mention => self.mute_mention_notifications,
+            NotificationKind::Follow => self.mute_follow_notifications,
+            NotificationKind::FriendRequest => self.mute_friend_request_notifications,
+            NotificationKind::System => false,
+        }
+    }
+
+    /// If push notifications of the given kind are disabled
+    pub fn is_push_muted(&self, kind: &NotificationKind) -> bool {
+        match kind {
+            NotificationKind::NewQuestion => self.mute_question_push,
+            NotificationKind::Reaction => self.mute_reaction_push,
+            NotificationKind::Comment => self.mute_comment_push,
+            NotificationKind::Mention => self.mute_mention_push,
+            NotificationKind::Follow => self.mute_follow_push,
+            NotificationKind::FriendRequest => self.mute_friend_request_push,
+            NotificationKind::System => false,
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Unlocking profiles which require a view password (`rainbeam:view_password`).
+//!
+//! Entering the correct password sets a signed cookie scoped to that profile, which is
+//! valid for [`UNLOCK_TTL`] or until the profile changes its password. The password
+//! itself is never sent again after unlocking.
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ring::hmac;
+use ring::rand::SystemRandom;
+
+use crate::model::Profile;
+
+/// How long (in seconds) an unlock lasts
+pub const UNLOCK_TTL: i64 = 60 * 60;
+
+/// How many failed attempts an IP can make on a single profile before it has to wait
+pub const UNLOCK_ATTEMPTS_MAX: usize = 5;
+
+/// The name of the cookie storing the unlock for the given profile
+pub fn unlock_cookie(profile: &str) -> String {
+    format!("__Secure-Unlock-{profile}")
+}
+
+/// The key used to sign unlock cookies
+///
+/// Generated on startup, so restarting the server locks every profile again.
+#[derive(Clone)]
+pub struct UnlockKey(hmac::Key);
+
+impl UnlockKey {
+    /// Generate a new random [`UnlockKey`]
+    pub fn generate() -> Self {
+        Self(
+            hmac::Key::generate(hmac::HMAC_SHA256, &SystemRandom::new())
+                .expect("failed to generate unlock key"),
+        )
+    }
+
+    /// The message signed for the given profile and expiration time
+    ///
+    /// Includes the (hashed) password so changing it invalidates every existing unlock.
+    fn message(profile: &Profile, expires: i64) -> String {
+        format!(
+            "{}.{expires}.{}",
+            profile.id,
+            profile.metadata.settings().view_password
+        )
+    }
+
+    /// Create the value of the unlock cookie for `profile`
+    pub fn sign(&self, profile: &Profile) -> String {
+        let expires = rainbeam_shared::epoch_timestamp(2024) + UNLOCK_TTL * 1000;
+        let tag = hmac::sign(&self.0, Self::message(profile, expires).as_bytes());
+        format!("{expires}.{}", URL_SAFE_NO_PAD.encode(tag.as_ref()))
+    }
+
+    /// Check if `value` is a valid (and unexpired) unlock cookie for `profile`
+    pub fn verify(&self, profile: &Profile, value: &str) -> bool {
+        let (expires, tag) = match value.split_once('.') {
+            Some(v) => v,
+            None => return false,
+        };
+
+        let expires = match expires.parse::<i64>() {
+            Ok(e) => e,
+            Err(_) => return false,
+        };
+
+        if expires < rainbeam_shared::epoch_timestamp(2024) {
+            return false;
+        }
+
+        let tag = match URL_SAFE_NO_PAD.decode(tag) {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        hmac::verify(&self.0, Self::message(profile, expires).as_bytes(), &tag).is_ok()
+    }
+}
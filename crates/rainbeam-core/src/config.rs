@@ -1 +1,115 @@
 pub use rainbeam_shared::config::*;
+
+use pathbufd::PathBufD;
+use rainbeam_shared::fs;
+use serde::{Deserialize, Serialize};
+
+/// The retention policy of edit history ([`Revision`](crate::model::Revision)s)
+///
+/// Revisions are kept after their response or comment is deleted (so staff can still
+/// review them) until they're older than `max_age`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RevisionPolicy {
+    /// If revisions are recorded
+    #[serde(default)]
+    pub enabled: bool,
+    /// The most revisions kept for a single asset, the oldest are removed first (`0` for no limit)
+    pub max_revisions: usize,
+    /// The time (in milliseconds) revisions are kept for (`0` to keep them forever)
+    pub max_age: u128,
+}
+
+impl Default for RevisionPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_revisions: 50,
+            max_age: 180 * 24 * 60 * 60 * 1000,
+        }
+    }
+}
+
+impl RevisionPolicy {
+    /// Pull configuration file
+    pub fn get_config() -> Self {
+        let path = PathBufD::current().extend(&[".config", "revisions", "config.toml"]);
+
+        match fs::read(path) {
+            Ok(c) => toml::from_str::<Self>(&c).unwrap(),
+            Err(_) => {
+                let config = Self::default();
+                Self::update_config(config.clone())
+                    .expect("failed to write default revision policy");
+                config
+            }
+        }
+    }
+
+    /// Update configuration file
+    pub fn update_config(contents: Self) -> std::io::Result<()> {
+        let dir = PathBufD::current().extend(&[".config", "revisions"]);
+        fs::mkdir(&dir)?;
+
+        fs::write(
+            dir.join("config.toml"),
+            toml::to_string_pretty::<Self>(&contents).unwrap(),
+        )
+    }
+
+    /// If a revision made at `timestamp` has expired
+    pub fn is_expired(&self, timestamp: u128) -> bool {
+        (self.max_age != 0)
+            && (rainbeam_shared::unix_epoch_timestamp().saturating_sub(timestamp) > self.max_age)
+    }
+}
+
+/// The limits on image [`Attachment`](crate::model::Attachment)s
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AttachmentPolicy {
+    /// The most images a single response or comment can have
+    pub max_per_asset: usize,
+    /// The largest image (in bytes) which can be uploaded, before it's re-encoded
+    pub max_size: usize,
+    /// The total size (in bytes) of the re-encoded images a user can store (`0` for no limit)
+    ///
+    /// Users with the `double_limits` tier get double this.
+    pub quota: u64,
+}
+
+impl Default for AttachmentPolicy {
+    fn default() -> Self {
+        Self {
+            max_per_asset: 4,
+            max_size: 8 * 1024 * 1024,
+            quota: 256 * 1024 * 1024,
+        }
+    }
+}
+
+impl AttachmentPolicy {
+    /// Pull configuration file
+    pub fn get_config() -> Self {
+        let path = PathBufD::current().extend(&[".config", "attachments", "config.toml"]);
+
+        match fs::read(path) {
+            Ok(c) => toml::from_str::<Self>(&c).unwrap(),
+            Err(_) => {
+                let config = Self::default();
+                Self::update_config(config.clone())
+                    .expect("failed to write default attachment policy");
+                config
+            }
+        }
+    }
+
+    /// Update configuration file
+    pub fn update_config(contents: Self) -> std::io::Result<()> {
+        let dir = PathBufD::current().extend(&[".config", "attachments"]);
+        fs::mkdir(&dir)?;
+
+        fs::write(
+            dir.join("config.toml"),
+            toml::to_string_pretty::<Self>(&contents).unwrap(),
+        )
+    }
+}
@@ -1,19 +1,26 @@
 use async_recursion::async_recursion;
+use axum::extract::FromRef;
+use axum_extra::extract::CookieJar;
 use authbeam::ignore;
 use pathbufd::pathd;
 use rainbeam_shared::snow::AlmostSnowflake;
 use std::collections::{BTreeMap, HashMap};
 
-use crate::config::Config;
+use crate::config::{AttachmentPolicy, Config, RevisionPolicy};
+use crate::mail::{Mail, MailConfig, MailTransport};
 use crate::model::*;
 use crate::model::{DatabaseError, Question};
 
 use authbeam::{
     simplify, from_row,
-    model::{FinePermission, NotificationCreate, Profile, RelationshipStatus},
+    model::{
+        FinePermission, Notification, NotificationCreate, NotificationKind, Profile,
+        PushMessage, RelationshipStatus, TransactionCreate,
+    },
 };
 use databeam::{utility, query as sqlquery, prelude::*};
 use langbeam::LangFile;
+use std::sync::Arc;
 
 pub type Result<T> = std::result::Result<T, DatabaseError>;
 
@@ -23,19 +30,37 @@ pub struct Database {
     pub base: StarterDatabase,
     pub auth: authbeam::Database,
     pub config: Config,
+    pub mail: MailConfig,
+    pub revisions: RevisionPolicy,
+    pub attachments: AttachmentPolicy,
+    mailer: Option<Arc<dyn MailTransport>>,
     langs: HashMap<String, LangFile>,
 }
 
+/// Allows [`authbeam::extract`] extractors to be used on routes with this state.
+impl FromRef<Database> for authbeam::Database {
+    fn from_ref(input: &Database) -> Self {
+        input.auth.clone()
+    }
+}
+
 impl Database {
     pub async fn new(
         opts: databeam::DatabaseOpts,
         auth: authbeam::Database,
         config: Config,
+        mail: MailConfig,
+        revisions: RevisionPolicy,
+        attachments: AttachmentPolicy,
     ) -> Self {
         Self {
             base: StarterDatabase::new(opts).await,
             auth,
             config,
+            mailer: mail.transport(),
+            mail,
+            revisions,
+            attachments,
             langs: langbeam::read_langs(),
         }
     }
@@ -106,6 +131,145 @@ impl Database {
         )
         .execute(c)
         .await;
+
+        // create removals table
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xremovals\" (
+                id         TEXT,
+                asset_type TEXT,
+                asset      TEXT,
+                author     TEXT,
+                moderator  TEXT,
+                rows       TEXT,
+                timestamp  TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        // create appeals table
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xappeals\" (
+                id        TEXT,
+                author    TEXT,
+                kind      TEXT,
+                target    TEXT,
+                content   TEXT,
+                status    TEXT,
+                reviewer  TEXT,
+                reason    TEXT,
+                timestamp TEXT,
+                decided   TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        // create tips table
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xtips\" (
+                id          TEXT,
+                author      TEXT,
+                recipient   TEXT,
+                asset       TEXT,
+                asset_type  TEXT,
+                amount      TEXT,
+                message     TEXT,
+                transfer    TEXT,
+                timestamp   TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        // create revisions table
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xrevisions\" (
+                id         TEXT,
+                asset_type TEXT,
+                asset      TEXT,
+                author     TEXT,
+                editor     TEXT,
+                content    TEXT,
+                kind       TEXT,
+                timestamp  TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        // create attachments table
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xattachments\" (
+                id         TEXT,
+                asset      TEXT,
+                asset_type TEXT,
+                owner      TEXT,
+                alt        TEXT,
+                size       TEXT,
+                timestamp  TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        // create polls table
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xpolls\" (
+                asset      TEXT,
+                asset_type TEXT,
+                author     TEXT,
+                options    TEXT,
+                multiple   TEXT,
+                anonymous  TEXT,
+                closes     TEXT,
+                closed     TEXT,
+                timestamp  TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xpoll_votes\" (
+                poll      TEXT,
+                user      TEXT,
+                options   TEXT,
+                timestamp TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        // create digests table
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xdigests\" (
+                user      TEXT,
+                frequency TEXT,
+                last_sent TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        // create confirmed digest emails table
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"xdigest_emails\" (
+                user  TEXT,
+                email TEXT
+            )",
+        )
+        .execute(c)
+        .await;
+
+        // clean up attachments when their owner is deleted
+        let database = self.clone();
+        self.auth.on_profile_delete(Arc::new(move |id| {
+            let database = database.clone();
+            Box::pin(async move {
+                let _ = database.delete_attachments_by_owner(&id).await;
+            })
+        }));
     }
 
     // language
@@ -269,7 +433,7 @@ impl Database {
                 None
             },
             responses: if options.responses | options.all {
-                match self.get_responses_by_author(&user).await {
+                match self.get_responses_by_author_unchecked(&user).await {
                     Ok(r) => Some(r),
                     Err(e) => return Err(e),
                 }
@@ -859,25 +1023,32 @@ impl Database {
     /// # Arguments
     /// * `page`
     /// * `search`
+    /// * `viewer`
     pub async fn get_global_questions_searched_paginated(
         &self,
         page: i32,
         search: String,
+        viewer: Option<&Profile>,
     ) -> Result<Vec<(Question, usize, usize)>> {
+        let (muted, muted_ids) = self.muted_condition(viewer, 1).await;
+
         // pull from database
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
-            format!("SELECT * FROM \"xquestions\" WHERE \"recipient\" = '@' AND \"content\" LIKE ? ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xquestions\" WHERE \"recipient\" = '@' AND \"content\" LIKE ?{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         } else {
-            format!("SELECT * FROM \"xquestions\" WHERE \"recipient\" = '@' AND \"content\" LIKE $1 ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xquestions\" WHERE \"recipient\" = '@' AND \"content\" LIKE $1{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query)
-            .bind::<&String>(&format!("%{search}%"))
-            .fetch_all(c)
-            .await
-        {
+        let search = format!("%{search}%");
+        let mut q = sqlquery(&query).bind::<&String>(&search);
+
+        for muted_id in &muted_ids {
+            q = q.bind::<&str>(muted_id);
+        }
+
+        let res = match q.fetch_all(c).await {
             Ok(p) => {
                 let mut out: Vec<(Question, usize, usize)> = Vec::new();
 
@@ -951,6 +1122,8 @@ impl Database {
             return Err(DatabaseError::NotAllowed);
         }
 
+        let (muted, muted_ids) = self.muted_condition(Some(&user), 1).await;
+
         // build string
         let mut query_string = String::new();
 
@@ -962,17 +1135,20 @@ impl Database {
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
             // we're also going to include our own responses so we don't have to do any complicated stuff to detect if we should start with "OR" (previous)
-            format!("SELECT * FROM \"xquestions\" WHERE (\"author\" = ?{query_string}) AND \"recipient\" = '@'  ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xquestions\" WHERE (\"author\" = ?{query_string}) AND \"recipient\" = '@'{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         } else {
-            format!( "SELECT * FROM \"xquestions\" WHERE (\"author\" = $1{query_string}) AND \"recipient\" = '@' ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!( "SELECT * FROM \"xquestions\" WHERE (\"author\" = $1{query_string}) AND \"recipient\" = '@'{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query)
-            .bind::<&String>(&user.id.to_lowercase())
-            .fetch_all(c)
-            .await
-        {
+        let user_id = user.id.to_lowercase();
+        let mut q = sqlquery(&query).bind::<&String>(&user_id);
+
+        for muted_id in &muted_ids {
+            q = q.bind::<&str>(muted_id);
+        }
+
+        let res = match q.fetch_all(c).await {
             Ok(p) => {
                 let mut out: Vec<(Question, usize, usize)> = Vec::new();
 
@@ -1021,21 +1197,31 @@ impl Database {
     ///
     /// # Arguments
     /// * `page`
+    /// * `viewer`
     pub async fn get_global_questions_paginated(
         &self,
         page: i32,
+        viewer: Option<&Profile>,
     ) -> Result<Vec<(Question, usize, usize)>> {
+        let (muted, muted_ids) = self.muted_condition(viewer, 0).await;
+
         // pull from database
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
             // we're also going to include our own responses so we don't have to do any complicated stuff to detect if we should start with "OR" (previous)
-            format!("SELECT * FROM \"xquestions\" WHERE \"recipient\" = '@' ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xquestions\" WHERE \"recipient\" = '@'{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         } else {
-            format!( "SELECT * FROM \"xquestions\" WHERE \"recipient\" = '@' ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!( "SELECT * FROM \"xquestions\" WHERE \"recipient\" = '@'{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query).fetch_all(c).await {
+        let mut q = sqlquery(&query);
+
+        for muted_id in &muted_ids {
+            q = q.bind::<&str>(muted_id);
+        }
+
+        let res = match q.fetch_all(c).await {
             Ok(p) => {
                 let mut out: Vec<(Question, usize, usize)> = Vec::new();
 
@@ -1198,7 +1384,7 @@ impl Database {
 
         // fetch from database
         let count = self
-            .get_responses_by_question(id.clone())
+            .get_responses_by_question_unchecked(id.clone())
             .await
             .unwrap_or(Vec::new())
             .len();
@@ -1235,6 +1421,15 @@ impl Database {
         // check recipient
         // "@" is the recipient we use for global questions (questions anybody can respond to)
         let tag = Database::anonymous_tag(&author);
+
+        // questions from quarantined users (even when asked anonymously) are filtered
+        let filtered = match self
+            .get_profile(if tag.0 { tag.1.clone() } else { author.clone() })
+            .await
+        {
+            Ok(ua) => ua.is_quarantined(),
+            Err(_) => false,
+        };
         let mut use_tier = 0;
         if props.recipient != "@" {
             // profile
@@ -1245,8 +1440,9 @@ impl Database {
 
             use_tier = recipient.tier;
 
-            let profile_locked = recipient.metadata.is_true("sparkler:lock_profile");
-            let block_anonymous = recipient.metadata.is_true("sparkler:disallow_anonymous");
+            let settings = recipient.metadata.settings();
+            let profile_locked = settings.lock_profile;
+            let block_anonymous = settings.disallow_anonymous;
 
             if profile_locked {
                 return Err(DatabaseError::ProfileLocked);
@@ -1298,34 +1494,24 @@ impl Database {
             }
 
             // check filter
-            for filter_string in recipient
-                .metadata
-                .kv
-                .get("sparkler:filter")
-                .unwrap_or(&"".to_string())
-                .split("\n")
-            {
-                if filter_string.is_empty() | filter_string.starts_with("#") {
-                    continue;
-                }
+            let content = props.content.to_lowercase();
 
-                if props
-                    .content
-                    .to_lowercase()
-                    .contains(&filter_string.to_lowercase())
-                {
+            for filter_string in settings.filters() {
+                if content.contains(&filter_string) {
                     // return ok so the client thinks it worked, but really we lied
                     return Ok(String::new());
                 }
             }
 
             // incr recipient inbox count
-            simplify!(
-                self.auth
-                    .update_profile_inbox_count(&recipient.id, recipient.inbox_count + 1)
-                    .await;
-                Err; Err(DatabaseError::Other)
-            )
+            if !filtered {
+                simplify!(
+                    self.auth
+                        .update_profile_inbox_count(&recipient.id, recipient.inbox_count + 1)
+                        .await;
+                    Err; Err(DatabaseError::Other)
+                )
+            }
         } else {
             // anonymous users cannot ask global questions
             if tag.0 == true {
@@ -1333,6 +1519,15 @@ impl Database {
             }
         }
 
+        // check poll (global questions only)
+        if let Some(ref poll) = props.poll {
+            if props.recipient != "@" {
+                return Err(DatabaseError::NotAllowed);
+            }
+
+            Database::check_poll(poll)?;
+        }
+
         // check author permissions
         if tag.0 == false {
             let author = match self.get_profile(author.clone()).await {
@@ -1345,6 +1540,9 @@ impl Database {
                 return Err(DatabaseError::NotAllowed);
             }
 
+            // check standing
+            self.auth.check_standing(&author).await?;
+
             // check content length
             if (props.content.trim().len() < 2) && props.ref_id.is_empty() {
                 return Err(DatabaseError::ContentTooShort);
@@ -1379,6 +1577,9 @@ impl Database {
                     return Err(DatabaseError::NotAllowed);
                 }
 
+                // check standing
+                self.auth.check_standing(&author).await?;
+
                 // check content length
                 if (props.content.trim().len() < 2) && props.ref_id.is_empty() {
                     return Err(DatabaseError::ContentTooShort);
@@ -1439,6 +1640,8 @@ impl Database {
                 media: props.media.len().to_string(),
                 ref_id: props.ref_id,
                 source_id: String::new(),
+                filtered,
+                poll: props.poll.is_some(),
             },
         };
 
@@ -1464,6 +1667,12 @@ impl Database {
             .await
         {
             Ok(_) => {
+                // create poll
+                if let Some(poll) = props.poll.take() {
+                    self.create_poll(AssetType::Question, &question.id, &question.author.id, poll)
+                        .await?;
+                }
+
                 // incr questions count
                 if question.recipient.username == "@" {
                     self.base
@@ -1483,7 +1692,8 @@ impl Database {
                         | !question
                             .author
                             .metadata
-                            .is_true("rainbeam:do_not_send_global_questions_to_friends")
+                            .settings()
+                            .do_not_send_global_questions_to_friends
                     {
                         let friends = simplify!(
                             self.auth
@@ -1501,7 +1711,8 @@ impl Database {
 
                             if friend
                                 .metadata
-                                .is_true("rainbeam:do_not_send_global_questions_to_inbox")
+                                .settings()
+                                .do_not_send_global_questions_to_inbox
                             {
                                 continue;
                             }
@@ -1514,6 +1725,7 @@ impl Database {
                                         anonymous: false,
                                         media: Vec::new(),
                                         ref_id: question.id.clone(),
+                                        poll: None,
                                     },
                                     question.author.id.clone(),
                                     ip.clone(),
@@ -1537,6 +1749,22 @@ impl Database {
                     .expect("failed to write carpgraph image");
                 }
 
+                // push
+                if (props.recipient != "@")
+                    && (question.recipient.username != "anonymous")
+                    && !filtered
+                {
+                    self.auth.queue_push(
+                        question.recipient.clone(),
+                        NotificationKind::NewQuestion,
+                        PushMessage::new(
+                            "You've received a new question!",
+                            &question.content,
+                            "/inbox",
+                        ),
+                    );
+                }
+
                 // ...
                 return Ok(question.id.clone());
             }
@@ -1573,6 +1801,36 @@ impl Database {
             if !group.permissions.check(FinePermission::MANAGE_QUESTIONS) {
                 return Err(DatabaseError::NotAllowed);
             }
+
+            // keep question (and the responses to it if it's global) so it can be restored
+            let mut rows = self.snapshot_rows("xquestions", "id", &id, false).await;
+
+            if question.recipient.username == "@" {
+                rows.append(
+                    &mut self
+                        .snapshot_rows(
+                            "xresponses",
+                            "question",
+                            &format!("%\"id\":\"{}\"%", question.id),
+                            true,
+                        )
+                        .await,
+                );
+            }
+
+            if question.context.poll {
+                rows.append(&mut self.snapshot_rows("xpolls", "asset", &id, false).await);
+                rows.append(&mut self.snapshot_rows("xpoll_votes", "poll", &id, false).await);
+            }
+
+            self.create_removal(
+                AssetType::Question,
+                &question.id,
+                &question.author.id,
+                &user.id,
+                rows,
+            )
+            .await?;
         }
 
         // delete question
@@ -1628,6 +1886,11 @@ impl Database {
                     }
                 }
 
+                // remove poll
+                if question.context.poll {
+                    self.delete_poll(&question.id).await?;
+                }
+
                 // remove image
                 if !question.context.media.is_empty()
                     && !question.context.media.starts_with("--CARP")
@@ -1697,8 +1960,317 @@ impl Database {
         Ok(())
     }
 
+    /// Build an SQL condition which excludes assets created by the users `viewer` has muted
+    ///
+    /// Returns an empty condition if `viewer` is `None` or hasn't muted anybody.
+    ///
+    /// # Arguments
+    /// * `viewer`
+    /// * `offset` - the number of parameters bound before the condition
+    ///
+    /// # Returns
+    /// `(condition, ids)`, the IDs must be bound (in order) after the other parameters
+    async fn muted_condition(
+        &self,
+        viewer: Option<&Profile>,
+        offset: usize,
+    ) -> (String, Vec<String>) {
+        let viewer = match viewer {
+            Some(ua) => ua,
+            None => return (String::new(), Vec::new()),
+        };
+
+        let muted = self.auth.get_muted_ids(&viewer.id).await;
+
+        if muted.is_empty() {
+            return (String::new(), Vec::new());
+        }
+
+        let placeholders: Vec<String> = (1..=muted.len())
+            .map(|i| {
+                if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                    "?".to_string()
+                } else {
+                    format!("${}", offset + i)
+                }
+            })
+            .collect();
+
+        (
+            format!(" AND \"author\" NOT IN ({})", placeholders.join(", ")),
+            muted,
+        )
+    }
+
     // responses
 
+    /// Check if `viewer` is included in the given [`Audience`] of an asset created by `author`
+    ///
+    /// The author and staff can always view the asset.
+    ///
+    /// # Arguments
+    /// * `author`
+    /// * `audience`
+    /// * `viewer`
+    pub async fn in_audience(
+        &self,
+        author: &Profile,
+        audience: &Audience,
+        viewer: Option<&Profile>,
+    ) -> bool {
+        if *audience == Audience::Public {
+            return true;
+        }
+
+        let viewer = match viewer {
+            Some(ua) => ua,
+            None => return false,
+        };
+
+        if viewer.id == author.id {
+            return true;
+        }
+
+        let in_audience = match audience {
+            Audience::Public => true,
+            Audience::Followers => {
+                self.auth.get_follow(&viewer.id, &author.id).await.is_ok()
+                    || (self
+                        .auth
+                        .get_user_relationship(&author.id, &viewer.id)
+                        .await
+                        .0
+                        == RelationshipStatus::Friends)
+            }
+            Audience::Friends => {
+                self.auth
+                    .get_user_relationship(&author.id, &viewer.id)
+                    .await
+                    .0
+                    == RelationshipStatus::Friends
+            }
+            Audience::Custom(users) => users.contains(&viewer.id),
+        };
+
+        if in_audience {
+            return true;
+        }
+
+        // staff can view everything
+        match self.auth.get_group_by_id(viewer.group).await {
+            Ok(g) => g.permissions.check_helper(),
+            Err(_) => false,
+        }
+    }
+
+    /// Check if `viewer` is allowed to view the given response
+    ///
+    /// Checks the [`Audience`] of the response, if its author requires followers
+    /// to be approved (`rainbeam:approve_followers`) and if its author is quarantined.
+    ///
+    /// # Arguments
+    /// * `response`
+    /// * `viewer`
+    pub async fn can_view_response(
+        &self,
+        response: &QuestionResponse,
+        viewer: Option<&Profile>,
+    ) -> bool {
+        self.auth.is_approved_viewer(&response.author, viewer).await
+            && self.auth.is_quarantine_viewer(&response.author, viewer).await
+            && self
+                .in_audience(&response.author, &response.context.audience, viewer)
+                .await
+    }
+
+    /// Check if `viewer` is allowed to view the given comment
+    ///
+    /// # Arguments
+    /// * `comment`
+    /// * `viewer`
+    pub async fn can_view_comment(
+        &self,
+        comment: &ResponseComment,
+        viewer: Option<&Profile>,
+    ) -> bool {
+        self.auth.is_approved_viewer(&comment.author, viewer).await
+            && self.auth.is_quarantine_viewer(&comment.author, viewer).await
+            && self
+                .in_audience(&comment.author, &comment.context.audience, viewer)
+                .await
+    }
+
+    /// Remove the responses `viewer` isn't allowed to see (see [`Database::can_view_response`])
+    ///
+    /// # Arguments
+    /// * `responses`
+    /// * `viewer`
+    pub async fn filter_visible_responses(
+        &self,
+        responses: Vec<FullResponse>,
+        viewer: Option<&Profile>,
+    ) -> Vec<FullResponse> {
+        let mut approved: HashMap<String, bool> = HashMap::new();
+        let mut out = Vec::new();
+
+        for response in responses {
+            let author = &response.1.author;
+
+            let is_approved = match approved.get(&author.id) {
+                Some(a) => *a,
+                None => {
+                    let a = self.auth.is_approved_viewer(author, viewer).await
+                        && self.auth.is_quarantine_viewer(author, viewer).await;
+                    approved.insert(author.id.clone(), a);
+                    a
+                }
+            };
+
+            if is_approved
+                && self
+                    .in_audience(author, &response.1.context.audience, viewer)
+                    .await
+            {
+                out.push(response);
+            }
+        }
+
+        out
+    }
+
+    /// Remove the comments `viewer` isn't allowed to see (see [`Database::can_view_comment`])
+    ///
+    /// # Arguments
+    /// * `comments`
+    /// * `viewer`
+    pub async fn filter_visible_comments(
+        &self,
+        comments: Vec<(ResponseComment, usize, usize)>,
+        viewer: Option<&Profile>,
+    ) -> Vec<(ResponseComment, usize, usize)> {
+        let mut out = Vec::new();
+
+        for comment in comments {
+            if self.can_view_comment(&comment.0, viewer).await {
+                out.push(comment);
+            }
+        }
+
+        out
+    }
+
+    /// Check if `viewer` is allowed to view the given response, given the view password
+    /// (`rainbeam:view_password`) of its author
+    ///
+    /// See [`authbeam::Database::is_unlocked_viewer`].
+    ///
+    /// # Arguments
+    /// * `response`
+    /// * `viewer`
+    /// * `jar` - the cookies of the request
+    pub async fn is_unlocked_response(
+        &self,
+        response: &QuestionResponse,
+        viewer: Option<&Profile>,
+        jar: &CookieJar,
+    ) -> bool {
+        self.auth
+            .is_unlocked_viewer(&response.author, viewer, jar)
+            .await
+    }
+
+    /// Check if `viewer` is allowed to view the given comment, given the view password
+    /// of the author of its response (see [`Database::is_unlocked_response`])
+    ///
+    /// # Arguments
+    /// * `comment`
+    /// * `viewer`
+    /// * `jar` - the cookies of the request
+    pub async fn is_unlocked_comment(
+        &self,
+        comment: &ResponseComment,
+        viewer: Option<&Profile>,
+        jar: &CookieJar,
+    ) -> bool {
+        match self.get_response_short(comment.response.clone()).await {
+            Ok(r) => self.is_unlocked_response(&r, viewer, jar).await,
+            Err(_) => false,
+        }
+    }
+
+    /// Remove the responses `viewer` hasn't unlocked (see [`Database::is_unlocked_response`])
+    ///
+    /// # Arguments
+    /// * `responses`
+    /// * `viewer`
+    /// * `jar` - the cookies of the request
+    pub async fn filter_unlocked_responses(
+        &self,
+        responses: Vec<FullResponse>,
+        viewer: Option<&Profile>,
+        jar: &CookieJar,
+    ) -> Vec<FullResponse> {
+        let mut unlocked: HashMap<String, bool> = HashMap::new();
+        let mut out = Vec::new();
+
+        for response in responses {
+            let author = &response.1.author;
+
+            let is_unlocked = match unlocked.get(&author.id) {
+                Some(u) => *u,
+                None => {
+                    let u = self.auth.is_unlocked_viewer(author, viewer, jar).await;
+                    unlocked.insert(author.id.clone(), u);
+                    u
+                }
+            };
+
+            if is_unlocked {
+                out.push(response);
+            }
+        }
+
+        out
+    }
+
+    /// Check if `viewer` is allowed to view the given question
+    ///
+    /// The recipient of a question can always view it.
+    ///
+    /// # Arguments
+    /// * `question`
+    /// * `viewer`
+    pub async fn can_view_question(&self, question: &Question, viewer: Option<&Profile>) -> bool {
+        if viewer.is_some_and(|ua| ua.id == question.recipient.id) {
+            return true;
+        }
+
+        self.auth
+            .is_quarantine_viewer(&question.author, viewer)
+            .await
+    }
+
+    /// Remove the global questions `viewer` isn't allowed to see (see [`Database::can_view_question`])
+    ///
+    /// # Arguments
+    /// * `questions`
+    /// * `viewer`
+    pub async fn filter_visible_questions(
+        &self,
+        questions: Vec<(Question, usize, usize)>,
+        viewer: Option<&Profile>,
+    ) -> Vec<(Question, usize, usize)> {
+        let mut out = Vec::new();
+
+        for question in questions {
+            if self.can_view_question(&question.0, viewer).await {
+                out.push(question);
+            }
+        }
+
+        out
+    }
+
     /// Get a response from a database result
     pub async fn gimme_response(&self, res: BTreeMap<String, String>) -> Result<FullResponse> {
         let question = res.get("question").unwrap().to_string();
@@ -1816,11 +2388,29 @@ impl Database {
         })
     }
 
-    /// Get an existing response
+    /// Get an existing response as `viewer`
+    ///
+    /// Returns [`DatabaseError::NotAllowed`] if `viewer` isn't allowed to see the
+    /// response (see [`Database::can_view_response`]).
+    ///
+    /// # Arguments
+    /// * `id`
+    /// * `viewer`
+    pub async fn get_response(&self, id: String, viewer: Option<&Profile>) -> Result<FullResponse> {
+        let response = self.get_response_unchecked(id).await?;
+
+        if !self.can_view_response(&response.1, viewer).await {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        Ok(response)
+    }
+
+    /// Get an existing response without checking who can view it
     ///
     /// # Arguments
     /// * `id`
-    pub async fn get_response(&self, id: String) -> Result<FullResponse> {
+    pub(crate) async fn get_response_unchecked(&self, id: String) -> Result<FullResponse> {
         // check in cache
         match self
             .base
@@ -1995,17 +2585,30 @@ impl Database {
     ///
     /// # Arguments
     /// * `page`
-    pub async fn get_responses_paginated(&self, page: i32) -> Result<Vec<FullResponse>> {
+    /// * `viewer`
+    pub async fn get_responses_paginated(
+        &self,
+        page: i32,
+        viewer: Option<&Profile>,
+    ) -> Result<Vec<FullResponse>> {
+        let (muted, muted_ids) = self.muted_condition(viewer, 0).await;
+
         // pull from database
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
-            format!("SELECT * FROM \"xresponses\" WHERE \"context\" NOT LIKE '%\"unlisted\":true%' ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xresponses\" WHERE \"context\" NOT LIKE '%\"unlisted\":true%'{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         } else {
-            format!("SELECT * FROM \"xresponses\" WHERE \"context\" NOT LIKE '%\"unlisted\":true%' ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xresponses\" WHERE \"context\" NOT LIKE '%\"unlisted\":true%'{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query).fetch_all(c).await {
+        let mut q = sqlquery(&query);
+
+        for muted_id in &muted_ids {
+            q = q.bind::<&str>(muted_id);
+        }
+
+        let res = match q.fetch_all(c).await {
             Ok(p) => {
                 let mut out: Vec<FullResponse> = Vec::new();
 
@@ -2023,14 +2626,31 @@ impl Database {
         };
 
         // return
-        Ok(res)
+        Ok(self.filter_visible_responses(res, viewer).await)
+    }
+
+    /// Get all responses by their author which `viewer` is allowed to see
+    ///
+    /// # Arguments
+    /// * `author`
+    /// * `viewer`
+    pub async fn get_responses_by_author(
+        &self,
+        author: &str,
+        viewer: Option<&Profile>,
+    ) -> Result<Vec<FullResponse>> {
+        let responses = self.get_responses_by_author_unchecked(author).await?;
+        Ok(self.filter_visible_responses(responses, viewer).await)
     }
 
-    /// Get all responses by their author
+    /// Get all responses by their author without checking who can view them
     ///
     /// # Arguments
     /// * `author`
-    pub async fn get_responses_by_author(&self, author: &str) -> Result<Vec<FullResponse>> {
+    pub(crate) async fn get_responses_by_author_unchecked(
+        &self,
+        author: &str,
+    ) -> Result<Vec<FullResponse>> {
         // pull from database
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
@@ -2070,10 +2690,12 @@ impl Database {
     ///
     /// # Arguments
     /// * `author`
+    /// * `viewer`
     pub async fn get_responses_by_author_paginated(
         &self,
         author: String,
         page: i32,
+        viewer: Option<&Profile>,
     ) -> Result<Vec<FullResponse>> {
         // pull from database
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
@@ -2106,18 +2728,20 @@ impl Database {
         };
 
         // return
-        Ok(res)
+        Ok(self.filter_visible_responses(res, viewer).await)
     }
 
     /// Get all responses by their author and content search, 12 at a time
     ///
     /// # Arguments
     /// * `author`
+    /// * `viewer`
     pub async fn get_responses_by_author_searched_paginated(
         &self,
         author: String,
         search: String,
         page: i32,
+        viewer: Option<&Profile>,
     ) -> Result<Vec<FullResponse>> {
         // pull from database
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
@@ -2151,7 +2775,7 @@ impl Database {
         };
 
         // return
-        Ok(res)
+        Ok(self.filter_visible_responses(res, viewer).await)
     }
 
     /// Get all responses by their author and tag, 12 at a time
@@ -2159,11 +2783,13 @@ impl Database {
     /// # Arguments
     /// * `author`
     /// * `tag`
+    /// * `viewer`
     pub async fn get_responses_by_author_tagged_paginated(
         &self,
         author: String,
         tag: String,
         page: i32,
+        viewer: Option<&Profile>,
     ) -> Result<Vec<FullResponse>> {
         // pull from database
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
@@ -2197,7 +2823,7 @@ impl Database {
         };
 
         // return
-        Ok(res)
+        Ok(self.filter_visible_responses(res, viewer).await)
     }
 
     /// Get all responses by their tag, 12 at a time
@@ -2205,25 +2831,32 @@ impl Database {
     /// # Arguments
     /// * `author`
     /// * `tag`
+    /// * `viewer`
     pub async fn get_responses_tagged_paginated(
         &self,
         tag: String,
         page: i32,
+        viewer: Option<&Profile>,
     ) -> Result<Vec<FullResponse>> {
+        let (muted, muted_ids) = self.muted_condition(viewer, 1).await;
+
         // pull from database
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
-            format!("SELECT * FROM \"xresponses\" WHERE \"tags\" LIKE ? ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xresponses\" WHERE \"tags\" LIKE ?{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         } else {
-            format!("SELECT * FROM \"xresponses\" WHERE \"tags\" LIKE $2 ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xresponses\" WHERE \"tags\" LIKE $1{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query)
-            .bind::<&String>(&format!("%\"{}\"%", tag))
-            .fetch_all(c)
-            .await
-        {
+        let tag = format!("%\"{}\"%", tag);
+        let mut q = sqlquery(&query).bind::<&String>(&tag);
+
+        for muted_id in &muted_ids {
+            q = q.bind::<&str>(muted_id);
+        }
+
+        let res = match q.fetch_all(c).await {
             Ok(p) => {
                 let mut out: Vec<FullResponse> = Vec::new();
 
@@ -2241,7 +2874,7 @@ impl Database {
         };
 
         // return
-        Ok(res)
+        Ok(self.filter_visible_responses(res, viewer).await)
     }
 
     /// Get the number of responses by their author
@@ -2261,7 +2894,7 @@ impl Database {
 
         // fetch from database
         let count = self
-            .get_responses_by_author(author)
+            .get_responses_by_author_unchecked(author)
             .await
             .unwrap_or(Vec::new())
             .len();
@@ -2282,25 +2915,32 @@ impl Database {
     /// # Arguments
     /// * `page`
     /// * `search`
+    /// * `viewer`
     pub async fn get_responses_searched_paginated(
         &self,
         page: i32,
         search: String,
+        viewer: Option<&Profile>,
     ) -> Result<Vec<FullResponse>> {
+        let (muted, muted_ids) = self.muted_condition(viewer, 1).await;
+
         // pull from database
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
-            format!("SELECT * FROM \"xresponses\" WHERE \"content\" LIKE ? AND \"context\" NOT LIKE '%\"unlisted\":true%' ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xresponses\" WHERE \"content\" LIKE ? AND \"context\" NOT LIKE '%\"unlisted\":true%'{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         } else {
-            format!("SELECT * FROM \"xresponses\" WHERE \"content\" LIKE $1 AND \"context\" NOT LIKE '%\"unlisted\":true%' ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xresponses\" WHERE \"content\" LIKE $1 AND \"context\" NOT LIKE '%\"unlisted\":true%'{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query)
-            .bind::<&String>(&format!("%{search}%"))
-            .fetch_all(c)
-            .await
-        {
+        let search = format!("%{search}%");
+        let mut q = sqlquery(&query).bind::<&String>(&search);
+
+        for muted_id in &muted_ids {
+            q = q.bind::<&str>(muted_id);
+        }
+
+        let res = match q.fetch_all(c).await {
             Ok(p) => {
                 let mut out: Vec<FullResponse> = Vec::new();
 
@@ -2318,7 +2958,7 @@ impl Database {
         };
 
         // return
-        Ok(res)
+        Ok(self.filter_visible_responses(res, viewer).await)
     }
 
     /// Get 50 responses from people `user` is following
@@ -2358,6 +2998,8 @@ impl Database {
             Err(_) => anonymous_profile(self.create_anonymous().1),
         };
 
+        let (muted, muted_ids) = self.muted_condition(Some(&user), 1).await;
+
         // build string
         let mut query_string = String::new();
 
@@ -2369,17 +3011,20 @@ impl Database {
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
             // we're also going to include our own responses so we don't have to do any complicated stuff to detect if we should start with "OR" (previous)
-            format!("SELECT * FROM \"xresponses\" WHERE \"author\" = ?{query_string} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xresponses\" WHERE (\"author\" = ?{query_string}){muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         } else {
-            format!( "SELECT * FROM \"xresponses\" WHERE \"author\" = $1{query_string} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!( "SELECT * FROM \"xresponses\" WHERE (\"author\" = $1{query_string}){muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query)
-            .bind::<&String>(&user.id.to_lowercase())
-            .fetch_all(c)
-            .await
-        {
+        let user_id = user.id.to_lowercase();
+        let mut q = sqlquery(&query).bind::<&String>(&user_id);
+
+        for muted_id in &muted_ids {
+            q = q.bind::<&str>(muted_id);
+        }
+
+        let res = match q.fetch_all(c).await {
             Ok(p) => {
                 let mut out: Vec<FullResponse> = Vec::new();
 
@@ -2396,6 +3041,8 @@ impl Database {
             Err(_) => return Err(DatabaseError::Other),
         };
 
+        let res = self.filter_visible_responses(res, Some(&user)).await;
+
         // return
         self.base
             .cache
@@ -2411,11 +3058,28 @@ impl Database {
         Ok(res)
     }
 
-    /// Get all responses by their question ID
+    /// Get all responses by their question ID which `viewer` is allowed to see
+    ///
+    /// # Arguments
+    /// * `id`
+    /// * `viewer`
+    pub async fn get_responses_by_question(
+        &self,
+        id: String,
+        viewer: Option<&Profile>,
+    ) -> Result<Vec<FullResponse>> {
+        let responses = self.get_responses_by_question_unchecked(id).await?;
+        Ok(self.filter_visible_responses(responses, viewer).await)
+    }
+
+    /// Get all responses by their question ID without checking who can view them
     ///
     /// # Arguments
     /// * `id`
-    pub async fn get_responses_by_question(&self, id: String) -> Result<Vec<FullResponse>> {
+    pub(crate) async fn get_responses_by_question_unchecked(
+        &self,
+        id: String,
+    ) -> Result<Vec<FullResponse>> {
         // pull from database
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
@@ -2482,6 +3146,9 @@ impl Database {
             return Err(DatabaseError::NotAllowed);
         }
 
+        // check standing
+        self.auth.check_standing(&author).await?;
+
         // check content length
         if props.content.trim().len() < 2 {
             return Err(DatabaseError::ContentTooShort);
@@ -2574,9 +3241,17 @@ impl Database {
 
         // check reply
         if !props.reply.is_empty() {
-            if let Err(e) = self.get_response(props.reply.trim().to_string()).await {
-                return Err(e);
+            self.get_response_unchecked(props.reply.trim().to_string())
+                .await?;
+        }
+
+        // check poll (posts only)
+        if let Some(ref poll) = props.poll {
+            if props.question != "0" {
+                return Err(DatabaseError::NotAllowed);
             }
+
+            Database::check_poll(poll)?;
         }
 
         // ...
@@ -2589,8 +3264,13 @@ impl Database {
             timestamp,
             tags: Vec::new(),
             context: ResponseContext {
-                unlisted: props.unlisted,
+                // restricted responses are never shown publicly
+                unlisted: props.unlisted || (props.audience != Audience::Public),
                 warning: props.warning,
+                audience: props.audience,
+                tips: 0,
+                poll: props.poll.is_some(),
+                attachments: Vec::new(),
             },
             question: question.id,
             reply: props.reply.trim().to_string(),
@@ -2599,9 +3279,7 @@ impl Database {
 
         // make sure reply exists
         if !response.reply.is_empty() {
-            if let Err(e) = self.get_response(response.reply.clone()).await {
-                return Err(e);
-            }
+            self.get_response_unchecked(response.reply.clone()).await?;
         }
 
         // parse mentions
@@ -2611,6 +3289,16 @@ impl Database {
                 Err(_) => continue,
             };
 
+            if self.auth.is_muted(&profile.id, &author.id).await {
+                // don't notify users who muted the author, but still link their profile
+                response.content = response.content.replace(
+                    &format!("@{} ", mention),
+                    &format!("[@{}](/+u/{}) ", profile.username, profile.id),
+                );
+
+                continue;
+            }
+
             if let Err(_) = self
                 .auth
                 .create_notification(
@@ -2622,6 +3310,7 @@ impl Database {
                         content: format!("You were mentioned in a response."),
                         address: format!("/response/{}", response.id),
                         recipient: profile.id.clone(),
+                        kind: NotificationKind::Mention,
                     },
                     None,
                 )
@@ -2665,6 +3354,12 @@ impl Database {
             .await
         {
             Ok(_) => {
+                // create poll
+                if let Some(poll) = props.poll {
+                    self.create_poll(AssetType::Response, &response.id, &author.id, poll)
+                        .await?;
+                }
+
                 // create notification
                 let tag = Database::anonymous_tag(&question.author.id);
                 let mut is_allowed_to_receive_notif = true;
@@ -2677,7 +3372,13 @@ impl Database {
                     }
                 }
 
-                if (question.recipient.id != question.author.id) && is_allowed_to_receive_notif {
+                if (question.recipient.id != question.author.id)
+                    && is_allowed_to_receive_notif
+                    && !self
+                        .auth
+                        .is_muted(&question.author.id, &response.author.id)
+                        .await
+                {
                     if let Err(_) = self
                         .auth
                         .create_notification(
@@ -2699,6 +3400,7 @@ impl Database {
                                 ),
                                 address: format!("/response/{}", response.id),
                                 recipient: question.author.id,
+                                kind: NotificationKind::Comment,
                             },
                             None,
                         )
@@ -2857,7 +3559,7 @@ impl Database {
             } else {
                 if let Err(e) = self
                     .audit(
-                        user.id,
+                        user.id.clone(),
                         format!(
                             "Edited a response: [{}](/response/{})",
                             response.id, response.id
@@ -2877,6 +3579,19 @@ impl Database {
             return Err(DatabaseError::ContentTooShort);
         }
 
+        // keep previous content
+        if content != response.content {
+            self.create_revision(
+                AssetType::Response,
+                &id,
+                &response.author.id,
+                &user.id,
+                &response.content,
+                RevisionKind::Edit,
+            )
+            .await?;
+        }
+
         // update response
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
@@ -3098,6 +3813,13 @@ impl Database {
                 if !group.permissions.check(FinePermission::MANAGE_RESPONSES) {
                     return Err(DatabaseError::NotAllowed);
                 }
+
+                // keep response so it can be restored
+                let mut rows = self.snapshot_rows("xresponses", "id", id, false).await;
+                Database::clear_snapshot_attachments(&mut rows);
+
+                self.create_removal(AssetType::Response, id, &response.author.id, &user.id, rows)
+                    .await?;
             }
         }
 
@@ -3129,6 +3851,9 @@ impl Database {
                         .cache
                         .remove(format!("rbeam.app.response:{id}"))
                         .await;
+
+                    self.delete_poll(&id).await?;
+                    self.delete_attachments_by_asset(&id).await?;
                 }
 
                 Ok(())
@@ -3146,7 +3871,7 @@ impl Database {
     pub async fn update_response_context(
         &self,
         id: String,
-        context: ResponseContext,
+        mut context: ResponseContext,
         user: Box<Profile>,
     ) -> Result<()> {
         // make sure the response exists
@@ -3184,6 +3909,20 @@ impl Database {
             }
         }
 
+        // restricted responses are never shown publicly
+        if context.audience != Audience::Public {
+            context.unlisted = true;
+        }
+
+        // tip totals are only changed by tipping
+        context.tips = response.context.tips;
+
+        // polls can't be attached or removed after the response is created
+        context.poll = response.context.poll;
+
+        // attachments are only changed by uploading or deleting them
+        context.attachments = response.context.attachments;
+
         // update response
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
@@ -3230,7 +3969,7 @@ impl Database {
         save_question: bool,
     ) -> Result<()> {
         // make sure response exists
-        let response = match self.get_response(id.clone()).await {
+        let response = match self.get_response_unchecked(id.clone()).await {
             Ok(q) => q,
             Err(e) => return Err(e),
         };
@@ -3248,7 +3987,7 @@ impl Database {
             } else {
                 if let Err(e) = self
                     .audit(
-                        user.id,
+                        user.id.clone(),
                         format!(
                             "Deleted a response by: [{}](/+u/{})",
                             response.1.author.id, response.1.author.id
@@ -3258,6 +3997,42 @@ impl Database {
                 {
                     return Err(e);
                 }
+
+                // keep response (and its question if it's going to be deleted) so it can be restored
+                let mut rows = self.snapshot_rows("xresponses", "id", &id, false).await;
+
+                if (response.0.recipient.username != "@") && !save_question {
+                    rows.append(
+                        &mut self
+                            .snapshot_rows("xquestions", "id", &response.0.id, false)
+                            .await,
+                    );
+                }
+
+                if response.1.context.poll {
+                    rows.append(&mut self.snapshot_rows("xpolls", "asset", &id, false).await);
+                    rows.append(&mut self.snapshot_rows("xpoll_votes", "poll", &id, false).await);
+                }
+
+                Database::clear_snapshot_attachments(&mut rows);
+
+                // reactions are cleared
+                for removed in rows.iter_mut() {
+                    if removed.table == "xresponses" {
+                        removed
+                            .row
+                            .insert("reaction_count".to_string(), "0".to_string());
+                    }
+                }
+
+                self.create_removal(
+                    AssetType::Response,
+                    &id,
+                    &response.1.author.id,
+                    &user.id,
+                    rows,
+                )
+                .await?;
             }
         }
 
@@ -3267,6 +4042,19 @@ impl Database {
             return Err(DatabaseError::NotAllowed);
         }
 
+        // complete the edit history of edited responses
+        if response.1.edited != 0 {
+            self.create_revision(
+                AssetType::Response,
+                &id,
+                &response.1.author.id,
+                &user.id,
+                &response.1.content,
+                RevisionKind::Deletion,
+            )
+            .await?;
+        }
+
         // delete response
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
@@ -3310,6 +4098,14 @@ impl Database {
                     };
                 }
 
+                // remove poll
+                if response.1.context.poll {
+                    self.delete_poll(&id).await?;
+                }
+
+                // remove attachments
+                self.delete_attachments_by_asset(&id).await?;
+
                 // clear reactions
                 if let Err(e) = self.clear_reactions(id).await {
                     return Err(e);
@@ -3329,7 +4125,7 @@ impl Database {
     /// * `user` - the user doing this
     pub async fn unsend_response(&self, id: String, user: Box<Profile>) -> Result<()> {
         // make sure the response exists
-        let res = match self.get_response(id.clone()).await {
+        let res = match self.get_response_unchecked(id.clone()).await {
             Ok(q) => q,
             Err(e) => return Err(e),
         };
@@ -3397,16 +4193,40 @@ impl Database {
 
     // comments
 
-    /// Get an existing comment
+    /// Get an existing comment as `viewer`
+    ///
+    /// Returns [`DatabaseError::NotAllowed`] if `viewer` isn't allowed to see the
+    /// comment (see [`Database::can_view_comment`]).
     ///
     /// # Arguments
     /// * `id`
     /// * `recurse` - should be FALSE when fetching counts to prevent a stack overflow
-    #[async_recursion]
+    /// * `viewer`
     pub async fn get_comment(
         &self,
         id: String,
         recurse: bool,
+        viewer: Option<&Profile>,
+    ) -> Result<(ResponseComment, usize, usize)> {
+        let comment = self.get_comment_unchecked(id, recurse).await?;
+
+        if !self.can_view_comment(&comment.0, viewer).await {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        Ok(comment)
+    }
+
+    /// Get an existing comment without checking who can view it
+    ///
+    /// # Arguments
+    /// * `id`
+    /// * `recurse` - should be FALSE when fetching counts to prevent a stack overflow
+    #[async_recursion]
+    pub(crate) async fn get_comment_unchecked(
+        &self,
+        id: String,
+        recurse: bool,
     ) -> Result<(ResponseComment, usize, usize)> {
         // check in cache
         match self
@@ -3475,7 +4295,7 @@ impl Database {
             reply: if reply.is_empty() {
                 None
             } else {
-                match Box::pin(self.get_comment(reply, recurse)).await {
+                match Box::pin(self.get_comment_unchecked(reply, recurse)).await {
                     Ok(r) => Some(Box::new(r.0)),
                     Err(_) => None,
                 }
@@ -3556,7 +4376,7 @@ impl Database {
                             reply: if reply.is_empty() {
                                 None
                             } else {
-                                match self.get_comment(reply, true).await {
+                                match self.get_comment_unchecked(reply, true).await {
                                     Ok(r) => Some(Box::new(r.0)),
                                     Err(_) => None,
                                 }
@@ -3583,25 +4403,32 @@ impl Database {
     ///
     /// # Arguments
     /// * `id`
+    /// * `viewer`
     pub async fn get_comments_by_response_paginated(
         &self,
         id: String,
         page: i32,
+        viewer: Option<&Profile>,
     ) -> Result<Vec<(ResponseComment, usize, usize)>> {
+        let (muted, muted_ids) = self.muted_condition(viewer, 1).await;
+
         // pull from database
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
-            format!("SELECT * FROM \"xcomments\" WHERE \"response\" = ? AND \"reply\" = '' ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xcomments\" WHERE \"response\" = ? AND \"reply\" = ''{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         } else {
-            format!("SELECT * FROM \"xcomments\" WHERE \"response\" = $1 AND \"reply\" = '' ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xcomments\" WHERE \"response\" = $1 AND \"reply\" = ''{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query)
-            .bind::<&String>(&id.to_lowercase())
-            .fetch_all(c)
-            .await
-        {
+        let id = id.to_lowercase();
+        let mut q = sqlquery(&query).bind::<&String>(&id);
+
+        for muted_id in &muted_ids {
+            q = q.bind::<&str>(muted_id);
+        }
+
+        let res = match q.fetch_all(c).await {
             Ok(p) => {
                 let mut out: Vec<(ResponseComment, usize, usize)> = Vec::new();
 
@@ -3627,7 +4454,7 @@ impl Database {
                             reply: if reply.is_empty() {
                                 None
                             } else {
-                                match self.get_comment(reply, true).await {
+                                match self.get_comment_unchecked(reply, true).await {
                                     Ok(r) => Some(Box::new(r.0)),
                                     Err(_) => None,
                                 }
@@ -3647,7 +4474,7 @@ impl Database {
         };
 
         // return
-        Ok(res)
+        Ok(self.filter_visible_comments(res, viewer).await)
     }
 
     /// Get the number of comments by their response ID
@@ -3724,7 +4551,7 @@ impl Database {
                             reply: if reply.is_empty() {
                                 None
                             } else {
-                                match self.get_comment(reply, true).await {
+                                match self.get_comment_unchecked(reply, true).await {
                                     Ok(r) => Some(Box::new(r.0)),
                                     Err(_) => None,
                                 }
@@ -3792,7 +4619,7 @@ impl Database {
                             reply: if reply.is_empty() {
                                 None
                             } else {
-                                match self.get_comment(reply, true).await {
+                                match self.get_comment_unchecked(reply, true).await {
                                     Ok(r) => Some(Box::new(r.0)),
                                     Err(_) => None,
                                 }
@@ -3862,7 +4689,7 @@ impl Database {
                             reply: if reply.is_empty() {
                                 None
                             } else {
-                                match self.get_comment(reply, recurse).await {
+                                match self.get_comment_unchecked(reply, recurse).await {
                                     Ok(r) => Some(Box::new(r.0)),
                                     Err(_) => None,
                                 }
@@ -3893,25 +4720,32 @@ impl Database {
     ///
     /// # Arguments
     /// * `id`
+    /// * `viewer`
     pub async fn get_replies_by_comment_paginated(
         &self,
         id: String,
         page: i32,
+        viewer: Option<&Profile>,
     ) -> Result<Vec<(ResponseComment, usize, usize)>> {
+        let (muted, muted_ids) = self.muted_condition(viewer, 1).await;
+
         // pull from database
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
-            format!("SELECT * FROM \"xcomments\" WHERE \"reply\" = ? ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xcomments\" WHERE \"reply\" = ?{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         } else {
-            format!("SELECT * FROM \"xcomments\" WHERE \"reply\" = $1 ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
+            format!("SELECT * FROM \"xcomments\" WHERE \"reply\" = $1{muted} ORDER BY \"timestamp\" DESC LIMIT 12 OFFSET {}", page * 12)
         };
 
         let c = &self.base.db.client;
-        let res = match sqlquery(&query)
-            .bind::<&String>(&id.to_lowercase())
-            .fetch_all(c)
-            .await
-        {
+        let id = id.to_lowercase();
+        let mut q = sqlquery(&query).bind::<&String>(&id);
+
+        for muted_id in &muted_ids {
+            q = q.bind::<&str>(muted_id);
+        }
+
+        let res = match q.fetch_all(c).await {
             Ok(p) => {
                 let mut out: Vec<(ResponseComment, usize, usize)> = Vec::new();
 
@@ -3937,7 +4771,7 @@ impl Database {
                             reply: if reply.is_empty() {
                                 None
                             } else {
-                                match self.get_comment(reply, true).await {
+                                match self.get_comment_unchecked(reply, true).await {
                                     Ok(r) => Some(Box::new(r.0)),
                                     Err(_) => None,
                                 }
@@ -3957,7 +4791,7 @@ impl Database {
         };
 
         // return
-        Ok(res)
+        Ok(self.filter_visible_comments(res, viewer).await)
     }
 
     /// Get the number of replies by their comment ID
@@ -3990,6 +4824,215 @@ impl Database {
         count
     }
 
+    /// Get the comments on a response as a tree, nested by what they're replying to
+    ///
+    /// Every comment on the response is fetched at once and filtered by what `viewer`
+    /// can see (replies to a hidden comment are hidden with it). Comments by users
+    /// `viewer` has muted are removed.
+    ///
+    /// # Arguments
+    /// * `id` - the ID of the response
+    /// * `props` - [`CommentTreeQuery`]
+    /// * `viewer`
+    pub async fn get_comment_tree(
+        &self,
+        id: String,
+        props: CommentTreeQuery,
+        viewer: Option<&Profile>,
+    ) -> Result<CommentTree> {
+        let response = self.get_response(id, viewer).await?.1;
+
+        if !props.parent.is_empty() {
+            let parent = self
+                .get_comment(props.parent.clone(), false, viewer)
+                .await?
+                .0;
+
+            if parent.response != response.id {
+                return Err(DatabaseError::NotFound);
+            }
+        }
+
+        // pull from database
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xcomments\" WHERE \"response\" = ?"
+        } else {
+            "SELECT * FROM \"xcomments\" WHERE \"response\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let rows = match sqlquery(query)
+            .bind::<&str>(&response.id)
+            .fetch_all(c)
+            .await
+        {
+            Ok(p) => p,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        let muted = match viewer {
+            Some(ua) => self.auth.get_muted_ids(&ua.id).await,
+            None => Vec::new(),
+        };
+
+        // group comments by the comment they're replying to
+        let mut profiles: HashMap<String, Box<Profile>> = HashMap::new();
+        let mut children: HashMap<String, Vec<ResponseComment>> = HashMap::new();
+
+        for row in rows {
+            let res = self.base.textify_row(row).0;
+            let author = from_row!(res->author());
+
+            if muted.contains(&author) {
+                continue;
+            }
+
+            let author = match profiles.get(&author) {
+                Some(ua) => ua.clone(),
+                None => {
+                    let ua = match self.get_profile(&author).await {
+                        Ok(ua) => ua,
+                        Err(_) => anonymous_profile("anonymous".to_string()),
+                    };
+
+                    profiles.insert(author, ua.clone());
+                    ua
+                }
+            };
+
+            let comment = ResponseComment {
+                author,
+                response: from_row!(res->response()),
+                content: from_row!(res->content()),
+                id: from_row!(res->id()),
+                timestamp: from_row!(res->timestamp(u128); 0),
+                reply: None,
+                edited: from_row!(res->edited(u128); 0),
+                ip: from_row!(res->ip()),
+                context: from_row!(res->context(json); DatabaseError::ValueError),
+            };
+
+            if !self.can_view_comment(&comment, viewer).await {
+                continue;
+            }
+
+            children
+                .entry(from_row!(res->reply()))
+                .or_default()
+                .push(comment);
+        }
+
+        // build tree
+        let total = children.get(&props.parent).map(|c| c.len()).unwrap_or(0);
+        let (comments, next) = self
+            .build_comment_tree(
+                &mut children,
+                &props.parent,
+                props.offset,
+                props.depth.clamp(1, COMMENT_TREE_MAX_DEPTH),
+                props.limit.clamp(1, COMMENT_TREE_MAX_LIMIT),
+                &props.sort,
+            )
+            .await;
+
+        Ok(CommentTree {
+            response: response.id,
+            parent: props.parent,
+            total,
+            comments,
+            next,
+        })
+    }
+
+    /// Build a level of a [`CommentTree`] from comments grouped by what they're replying to
+    ///
+    /// # Arguments
+    /// * `children` - comments grouped by the ID of the comment they're replying to
+    /// * `parent` - the ID of the comment to build from (empty for the top of the response)
+    /// * `offset` - how many comments to skip at this level
+    /// * `depth` - how many levels to build (including this one)
+    /// * `limit` - the most comments to take at each level
+    /// * `sort`
+    ///
+    /// # Returns
+    /// `(nodes, offset of the next comments at this level)`
+    #[async_recursion]
+    async fn build_comment_tree(
+        &self,
+        children: &mut HashMap<String, Vec<ResponseComment>>,
+        parent: &str,
+        offset: usize,
+        depth: usize,
+        limit: usize,
+        sort: &CommentSort,
+    ) -> (Vec<CommentTreeNode>, Option<usize>) {
+        let mut comments = match children.remove(parent) {
+            Some(c) => c,
+            None => return (Vec::new(), None),
+        };
+
+        let total = comments.len();
+
+        // sort comments (reaction counts are only fetched for everything when we need them)
+        let mut page: Vec<(ResponseComment, usize)> = Vec::new();
+
+        if *sort == CommentSort::Reactions {
+            for comment in comments {
+                let count = self.get_reaction_count_by_asset(comment.id.clone()).await;
+                page.push((comment, count));
+            }
+
+            page.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.timestamp.cmp(&a.0.timestamp)));
+            page = page.into_iter().skip(offset).take(limit).collect();
+        } else {
+            if *sort == CommentSort::Oldest {
+                comments.sort_by_key(|c| c.timestamp);
+            } else {
+                comments.sort_by_key(|c| std::cmp::Reverse(c.timestamp));
+            }
+
+            for comment in comments.into_iter().skip(offset).take(limit) {
+                let count = self.get_reaction_count_by_asset(comment.id.clone()).await;
+                page.push((comment, count));
+            }
+        }
+
+        // build replies
+        let mut out: Vec<CommentTreeNode> = Vec::new();
+
+        for (comment, reaction_count) in page {
+            let reply_count = children.get(&comment.id).map(|c| c.len()).unwrap_or(0);
+
+            let (replies, next) = if depth > 1 {
+                self.build_comment_tree(children, &comment.id, 0, depth - 1, limit, sort)
+                    .await
+            } else if reply_count > 0 {
+                // the rest of the thread is collapsed
+                (Vec::new(), Some(0))
+            } else {
+                (Vec::new(), None)
+            };
+
+            out.push(CommentTreeNode {
+                comment,
+                reply_count,
+                reaction_count,
+                replies,
+                next,
+            });
+        }
+
+        (
+            out,
+            if offset + limit < total {
+                Some(offset + limit)
+            } else {
+                None
+            },
+        )
+    }
+
     /// Create a new comment
     ///
     /// Comments can only be created by non-anonymous users.
@@ -3998,12 +5041,15 @@ impl Database {
     /// * `props` - [`CommentCreate`]
     /// * `author` - the ID of the user creating the comment
     /// * `ip` - the IP address of the user creating the comment
+    ///
+    /// # Returns
+    /// The ID of the new comment
     pub async fn create_comment(
         &self,
         props: CommentCreate,
         author: String,
         ip: String,
-    ) -> Result<()> {
+    ) -> Result<String> {
         // make sure the response exists
         let response = match self.get_response_short(props.response.clone()).await {
             Ok(q) => q,
@@ -4011,11 +5057,9 @@ impl Database {
         };
 
         // check if the response author allows comments at all
-        if response
-            .author
-            .metadata
-            .is_true("rainbeam:disallow_response_comments")
-        {
+        let settings = response.author.metadata.settings();
+
+        if settings.disallow_response_comments {
             return Err(DatabaseError::NotAllowed);
         }
 
@@ -4024,11 +5068,7 @@ impl Database {
 
         if tag.0 {
             // anonymous users cannot comment if disallowed by the response creator
-            if response
-                .author
-                .metadata
-                .is_true("sparkler:disallow_anonymous_comments")
-            {
+            if settings.disallow_anonymous_comments {
                 return Err(DatabaseError::NotAllowed);
             }
         }
@@ -4062,6 +5102,9 @@ impl Database {
             return Err(DatabaseError::NotAllowed);
         }
 
+        // check standing
+        self.auth.check_standing(&author).await?;
+
         // check relationship
         let relationship = self
             .auth
@@ -4072,6 +5115,11 @@ impl Database {
             return Err(DatabaseError::NotAllowed);
         }
 
+        // check audience
+        if !self.can_view_response(&response, Some(&author)).await {
+            return Err(DatabaseError::NotAllowed);
+        }
+
         // check content length
         if props.content.trim().len() < 2 {
             return Err(DatabaseError::ContentTooShort);
@@ -4106,7 +5154,11 @@ impl Database {
             reply: None,
             edited: timestamp,
             ip,
-            context: CommentContext::default(),
+            context: CommentContext {
+                audience: props.audience,
+                tips: 0,
+                attachments: Vec::new(),
+            },
         };
 
         // parse mentions
@@ -4116,8 +5168,18 @@ impl Database {
                 Err(_) => continue,
             };
 
-            if let Err(_) = self
-                .auth
+            if self.auth.is_muted(&profile.id, &author.id).await {
+                // don't notify users who muted the author, but still link their profile
+                comment.content = comment.content.replace(
+                    &format!("@{} ", mention),
+                    &format!("[@{}](/+u/{}) ", profile.username, profile.id),
+                );
+
+                continue;
+            }
+
+            if let Err(_) = self
+                .auth
                 .create_notification(
                     NotificationCreate {
                         title: format!(
@@ -4127,6 +5189,7 @@ impl Database {
                         content: format!("You were mentioned in a comment."),
                         address: format!("/comment/{}", comment.id),
                         recipient: profile.id.clone(),
+                        kind: NotificationKind::Mention,
                     },
                     None,
                 )
@@ -4169,14 +5232,20 @@ impl Database {
                 // create notification
                 if !props.reply.is_empty() {
                     // send notification
-                    let reply = match self.get_comment(props.reply.clone(), false).await {
+                    let reply = match self.get_comment_unchecked(props.reply.clone(), false).await {
                         Ok(r) => r.0,
                         Err(e) => return Err(e),
                     };
 
                     let author_tag = Database::anonymous_tag(&reply.author.username);
 
-                    if (reply.author.id != comment.author.id) && !author_tag.0 {
+                    if (reply.author.id != comment.author.id)
+                        && !author_tag.0
+                        && !self
+                            .auth
+                            .is_muted(&reply.author.id, &comment.author.id)
+                            .await
+                    {
                         if let Err(_) = self
                             .auth
                             .create_notification(
@@ -4202,6 +5271,7 @@ impl Database {
                                     ),
                                     address: format!("/comment/{}", comment.id),
                                     recipient: reply.author.id,
+                                    kind: NotificationKind::Comment,
                                 },
                                 None,
                             )
@@ -4216,7 +5286,12 @@ impl Database {
                         .cache
                         .incr(format!("rbeam.app.reply_count:{}", props.reply))
                         .await;
-                } else if response.author.id != comment.author.id {
+                } else if (response.author.id != comment.author.id)
+                    && !self
+                        .auth
+                        .is_muted(&response.author.id, &comment.author.id)
+                        .await
+                {
                     if let Err(_) = self
                         .auth
                         .create_notification(
@@ -4237,6 +5312,7 @@ impl Database {
                                 ),
                                 address: format!("/comment/{}", comment.id),
                                 recipient: response.author.id,
+                                kind: NotificationKind::Comment,
                             },
                             None,
                         )
@@ -4253,10 +5329,10 @@ impl Database {
                     .await;
 
                 // return
-                return Ok(());
+                Ok(comment.id)
             }
-            Err(_) => return Err(DatabaseError::Other),
-        };
+            Err(_) => Err(DatabaseError::Other),
+        }
     }
 
     /// Update an existing comment's content
@@ -4272,7 +5348,7 @@ impl Database {
         user: Box<Profile>,
     ) -> Result<()> {
         // make sure the response exists
-        let comment = match self.get_comment(id.clone(), false).await {
+        let comment = match self.get_comment_unchecked(id.clone(), false).await {
             Ok(q) => q.0,
             Err(e) => return Err(e),
         };
@@ -4304,7 +5380,7 @@ impl Database {
             } else {
                 if let Err(e) = self
                     .audit(
-                        user.id,
+                        user.id.clone(),
                         format!(
                             "Edited a comment: [{}](/comment/{})",
                             comment.id, comment.id
@@ -4324,6 +5400,19 @@ impl Database {
             return Err(DatabaseError::ContentTooShort);
         }
 
+        // keep previous content
+        if content != comment.content {
+            self.create_revision(
+                AssetType::Comment,
+                &id,
+                &comment.author.id,
+                &user.id,
+                &comment.content,
+                RevisionKind::Edit,
+            )
+            .await?;
+        }
+
         // update comment
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
@@ -4362,7 +5451,7 @@ impl Database {
     /// * `user` - the user doing this
     pub async fn delete_comment(&self, id: String, user: Box<Profile>) -> Result<()> {
         // make sure comment exists
-        let comment = match self.get_comment(id.clone(), false).await {
+        let comment = match self.get_comment_unchecked(id.clone(), false).await {
             Ok(q) => q.0,
             Err(e) => return Err(e),
         };
@@ -4388,7 +5477,7 @@ impl Database {
                 } else {
                     if let Err(e) = self
                         .audit(
-                            user.id,
+                            user.id.clone(),
                             format!(
                                 "Deleted a comment by: [{}](/+u/{})",
                                 comment.author.id, comment.author.id
@@ -4398,6 +5487,19 @@ impl Database {
                     {
                         return Err(e);
                     }
+
+                    // keep comment so it can be restored
+                    let mut rows = self.snapshot_rows("xcomments", "id", &id, false).await;
+                    Database::clear_snapshot_attachments(&mut rows);
+
+                    self.create_removal(
+                        AssetType::Comment,
+                        &id,
+                        &comment.author.id,
+                        &user.id,
+                        rows,
+                    )
+                    .await?;
                 }
             }
         }
@@ -4408,6 +5510,19 @@ impl Database {
             return Err(DatabaseError::NotAllowed);
         }
 
+        // complete the edit history of edited comments
+        if comment.edited != 0 {
+            self.create_revision(
+                AssetType::Comment,
+                &id,
+                &comment.author.id,
+                &user.id,
+                &comment.content,
+                RevisionKind::Deletion,
+            )
+            .await?;
+        }
+
         // delete comment
         let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
         {
@@ -4440,6 +5555,9 @@ impl Database {
                         .await;
                 }
 
+                // remove attachments
+                self.delete_attachments_by_asset(&id).await?;
+
                 // clear reactions
                 if let Err(e) = self.clear_reactions(id).await {
                     return Err(e);
@@ -4779,11 +5897,34 @@ impl Database {
 
     // discover
 
-    /// Get the top reacted-to responses (from the `cutoff`).
+    /// Get the top reacted-to responses (from the `cutoff`) which `viewer` is allowed to see.
+    ///
+    /// # Arguments
+    /// * `cutoff`
+    /// * `viewer`
+    pub async fn get_top_reacted_responses(
+        &self,
+        cutoff: u128,
+        viewer: Option<&Profile>,
+    ) -> Result<Vec<FullResponse>> {
+        let mut responses = self.get_top_reacted_responses_unchecked(cutoff).await?;
+
+        // hide muted users
+        if let Some(ua) = viewer {
+            let muted = self.auth.get_muted_ids(&ua.id).await;
+            responses.retain(|r| !muted.contains(&r.1.author.id));
+        }
+
+        Ok(self.filter_visible_responses(responses, viewer).await)
+    }
+
+    /// Get the top reacted-to responses (from the `cutoff`) without checking who can view them.
+    ///
+    /// The result is cached for every viewer.
     ///
     /// # Arguments
     /// * `cutoff`
-    pub async fn get_top_reacted_responses(&self, cutoff: u128) -> Result<Vec<FullResponse>> {
+    async fn get_top_reacted_responses_unchecked(&self, cutoff: u128) -> Result<Vec<FullResponse>> {
         // attempt to fetch from cache
         if let Some(res) = self
             .base
@@ -4871,6 +6012,11 @@ impl Database {
                         Err(_) => return Err(DatabaseError::Other),
                     };
 
+                    // quarantined users aren't shown in discover
+                    if p.is_quarantined() {
+                        continue;
+                    }
+
                     out.push((p.question_count, p));
                 }
 
@@ -4929,6 +6075,11 @@ impl Database {
                         Err(_) => return Err(DatabaseError::Other),
                     };
 
+                    // quarantined users aren't shown in discover
+                    if p.is_quarantined() {
+                        continue;
+                    }
+
                     out.push((p.response_count, p));
                 }
 
@@ -4949,4 +6100,2367 @@ impl Database {
         // return
         Ok(res)
     }
+
+    // removals
+
+    /// Get the rows of `table` where `column` matches `value` as [`RemovedRow`]s
+    ///
+    /// # Arguments
+    /// * `table`
+    /// * `column`
+    /// * `value`
+    /// * `like` - match `value` as a `LIKE` pattern
+    async fn snapshot_rows(
+        &self,
+        table: &str,
+        column: &str,
+        value: &str,
+        like: bool,
+    ) -> Vec<RemovedRow> {
+        let op = if like { "LIKE" } else { "=" };
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            format!("SELECT * FROM \"{table}\" WHERE \"{column}\" {op} ?")
+        } else {
+            format!("SELECT * FROM \"{table}\" WHERE \"{column}\" {op} $1")
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(&query).bind::<&str>(value).fetch_all(c).await {
+            Ok(rows) => rows
+                .into_iter()
+                .map(|row| RemovedRow {
+                    table: table.to_string(),
+                    row: self.base.textify_row(row).0,
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get a [`Removal`] from a database result
+    fn gimme_removal(&self, res: BTreeMap<String, String>) -> Result<Removal> {
+        Ok(Removal {
+            id: from_row!(res->id()),
+            asset_type: match serde_json::from_str(res.get("asset_type").unwrap()) {
+                Ok(t) => t,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            asset: from_row!(res->asset()),
+            author: from_row!(res->author()),
+            moderator: from_row!(res->moderator()),
+            rows: match serde_json::from_str(res.get("rows").unwrap()) {
+                Ok(r) => r,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            timestamp: from_row!(res->timestamp(u128); 0),
+        })
+    }
+
+    /// Get an existing [`Removal`]
+    ///
+    /// # Arguments
+    /// * `id`
+    pub async fn get_removal(&self, id: &str) -> Result<Removal> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xremovals\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \"xremovals\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(id).fetch_one(c).await {
+            Ok(p) => self.gimme_removal(self.base.textify_row(p).0),
+            Err(_) => Err(DatabaseError::NotFound),
+        }
+    }
+
+    /// Record the removal of an asset by staff and let its author know they can appeal
+    ///
+    /// # Arguments
+    /// * `asset_type`
+    /// * `asset` - the ID of the removed asset
+    /// * `author` - the ID of the author of the removed asset
+    /// * `moderator` - the ID of the staff member removing the asset
+    /// * `rows` - the rows being removed
+    async fn create_removal(
+        &self,
+        asset_type: AssetType,
+        asset: &str,
+        author: &str,
+        moderator: &str,
+        rows: Vec<RemovedRow>,
+    ) -> Result<()> {
+        let id = AlmostSnowflake::new(self.config.snowflake_server_id).to_string();
+
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \"xremovals\" VALUES (?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xremovals\" VALUES ($1, $2, $3, $4, $5, $6, $7)"
+        };
+
+        let c = &self.base.db.client;
+        if sqlquery(query)
+            .bind::<&str>(&id)
+            .bind::<&str>(&serde_json::to_string(&asset_type).unwrap())
+            .bind::<&str>(asset)
+            .bind::<&str>(author)
+            .bind::<&str>(moderator)
+            .bind::<&str>(&serde_json::to_string(&rows).unwrap())
+            .bind::<&str>(&utility::unix_epoch_timestamp().to_string())
+            .execute(c)
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        // notify author (anonymous authors have no profile to notify)
+        let _ = self
+            .auth
+            .create_notification(
+                NotificationCreate {
+                    title: "Some of your content was removed by staff".to_string(),
+                    content: "If you believe this was a mistake, you can appeal the removal."
+                        .to_string(),
+                    address: format!("/appeals?kind=Removal&target={id}"),
+                    recipient: author.to_string(),
+                    kind: NotificationKind::System,
+                },
+                None,
+            )
+            .await;
+
+        Ok(())
+    }
+
+    /// Insert the rows of a [`Removal`] back into the database
+    ///
+    /// Reactions are not restored.
+    ///
+    /// # Arguments
+    /// * `removal`
+    async fn restore_removal(&self, removal: &Removal) -> Result<()> {
+        let c = &self.base.db.client;
+
+        for removed in &removal.rows {
+            let columns: Vec<&String> = removed.row.keys().collect();
+            let values: Vec<String> =
+                if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                    columns.iter().map(|_| "?".to_string()).collect()
+                } else {
+                    (1..=columns.len()).map(|i| format!("${i}")).collect()
+                };
+
+            let query = format!(
+                "INSERT INTO \"{}\" ({}) VALUES ({})",
+                removed.table,
+                columns
+                    .iter()
+                    .map(|c| format!("\"{c}\""))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                values.join(", ")
+            );
+
+            let mut q = sqlquery(&query);
+            for value in removed.row.values() {
+                q = q.bind::<&str>(value);
+            }
+
+            if q.execute(c).await.is_err() {
+                return Err(DatabaseError::Other);
+            }
+        }
+
+        // update counts
+        match removal.asset_type {
+            AssetType::Response => {
+                self.base
+                    .cache
+                    .incr(format!("rbeam.app.response_count:{}", removal.author))
+                    .await;
+            }
+            AssetType::Comment => {
+                if let Some(response) = removal
+                    .rows
+                    .first()
+                    .and_then(|r| r.row.get("response"))
+                {
+                    self.base
+                        .cache
+                        .incr(format!("rbeam.app.comment_count:{}", response))
+                        .await;
+                }
+            }
+            _ => {}
+        }
+
+        // delete removal
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \"xremovals\" WHERE \"id\" = ?"
+        } else {
+            "DELETE FROM \"xremovals\" WHERE \"id\" = $1"
+        };
+
+        match sqlquery(query).bind::<&str>(&removal.id).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    // polls
+
+    /// Get a [`Poll`] from a database result
+    fn gimme_poll(&self, res: BTreeMap<String, String>) -> Result<Poll> {
+        Ok(Poll {
+            asset: from_row!(res->asset()),
+            asset_type: match serde_json::from_str(res.get("asset_type").unwrap()) {
+                Ok(t) => t,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            author: from_row!(res->author()),
+            options: match serde_json::from_str(res.get("options").unwrap()) {
+                Ok(o) => o,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            multiple: from_row!(res->multiple(bool); false),
+            anonymous: from_row!(res->anonymous(bool); false),
+            closes: from_row!(res->closes(u128); 0),
+            closed: from_row!(res->closed(bool); false),
+            timestamp: from_row!(res->timestamp(u128); 0),
+        })
+    }
+
+    /// Get a [`PollVote`] from a database result
+    fn gimme_poll_vote(&self, res: BTreeMap<String, String>) -> Result<PollVote> {
+        Ok(PollVote {
+            poll: from_row!(res->poll()),
+            user: from_row!(res->user()),
+            options: match serde_json::from_str(res.get("options").unwrap()) {
+                Ok(o) => o,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            timestamp: from_row!(res->timestamp(u128); 0),
+        })
+    }
+
+    /// Get the [`Poll`] attached to the given asset
+    ///
+    /// # Arguments
+    /// * `asset`
+    pub async fn get_poll(&self, asset: &str) -> Result<Poll> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xpolls\" WHERE \"asset\" = ?"
+        } else {
+            "SELECT * FROM \"xpolls\" WHERE \"asset\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(asset).fetch_one(c).await {
+            Ok(p) => self.gimme_poll(self.base.textify_row(p).0),
+            Err(_) => Err(DatabaseError::NotFound),
+        }
+    }
+
+    /// Get all [`PollVote`]s on the given poll
+    ///
+    /// # Arguments
+    /// * `poll`
+    pub async fn get_poll_votes(&self, poll: &str) -> Result<Vec<PollVote>> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xpoll_votes\" WHERE \"poll\" = ?"
+        } else {
+            "SELECT * FROM \"xpoll_votes\" WHERE \"poll\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(poll).fetch_all(c).await {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    out.push(self.gimme_poll_vote(self.base.textify_row(row).0)?);
+                }
+
+                // timestamps are stored as text, so they're sorted here
+                out.sort_by_key(|v| v.timestamp);
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Check if `viewer` is allowed to view the asset the given poll is attached to
+    ///
+    /// # Arguments
+    /// * `poll`
+    /// * `viewer`
+    pub async fn can_view_poll(&self, poll: &Poll, viewer: Option<&Profile>) -> Result<bool> {
+        match poll.asset_type {
+            AssetType::Response => {
+                let response = self.get_response_unchecked(poll.asset.clone()).await?;
+                Ok(self.can_view_response(&response.1, viewer).await)
+            }
+            AssetType::Question => {
+                let question = self.get_question(poll.asset.clone()).await?;
+                Ok(self.can_view_question(&question, viewer).await)
+            }
+            _ => Err(DatabaseError::ValueError),
+        }
+    }
+
+    /// Get the [`PollResults`] of the poll attached to the given asset
+    ///
+    /// # Arguments
+    /// * `asset`
+    /// * `viewer`
+    pub async fn get_poll_results(
+        &self,
+        asset: &str,
+        viewer: Option<&Profile>,
+    ) -> Result<PollResults> {
+        let poll = self.get_poll(asset).await?;
+
+        if !self.can_view_poll(&poll, viewer).await? {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        let votes = self.get_poll_votes(&poll.asset).await?;
+        let mut counts = vec![0; poll.options.len()];
+        let mut voters: Vec<Vec<Box<Profile>>> = if poll.anonymous {
+            Vec::new()
+        } else {
+            vec![Vec::new(); poll.options.len()]
+        };
+        let mut voted = Vec::new();
+
+        for vote in &votes {
+            if viewer.is_some_and(|ua| ua.id == vote.user) {
+                voted = vote.options.clone();
+            }
+
+            let profile = if poll.anonymous {
+                None
+            } else {
+                self.get_profile(vote.user.clone()).await.ok()
+            };
+
+            for option in &vote.options {
+                if let Some(count) = counts.get_mut(*option) {
+                    *count += 1;
+                }
+
+                if let Some(ref profile) = profile {
+                    if let Some(list) = voters.get_mut(*option) {
+                        list.push(profile.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(PollResults {
+            poll,
+            counts,
+            total: votes.len(),
+            voted,
+            voters,
+        })
+    }
+
+    /// Make sure the given [`PollCreate`] is valid
+    ///
+    /// # Arguments
+    /// * `props`
+    fn check_poll(props: &PollCreate) -> Result<()> {
+        if props.options.len() < POLL_MIN_OPTIONS {
+            return Err(DatabaseError::ContentTooShort);
+        }
+
+        if props.options.len() > POLL_MAX_OPTIONS {
+            return Err(DatabaseError::ContentTooLong);
+        }
+
+        for option in &props.options {
+            if option.trim().is_empty() {
+                return Err(DatabaseError::ContentTooShort);
+            }
+
+            if option.len() > POLL_OPTION_MAX_LENGTH {
+                return Err(DatabaseError::ContentTooLong);
+            }
+        }
+
+        if (props.duration < POLL_MIN_DURATION) | (props.duration > POLL_MAX_DURATION) {
+            return Err(DatabaseError::ValueError);
+        }
+
+        Ok(())
+    }
+
+    /// Attach a new [`Poll`] to an asset
+    ///
+    /// # Arguments
+    /// * `asset_type` - [`AssetType::Response`] or [`AssetType::Question`]
+    /// * `asset` - the ID of the asset
+    /// * `author` - the ID of the user creating the poll
+    /// * `props` - [`PollCreate`]
+    async fn create_poll(
+        &self,
+        asset_type: AssetType,
+        asset: &str,
+        author: &str,
+        props: PollCreate,
+    ) -> Result<()> {
+        Database::check_poll(&props)?;
+
+        let timestamp = utility::unix_epoch_timestamp();
+        let options: Vec<String> = props.options.iter().map(|o| o.trim().to_string()).collect();
+
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \"xpolls\" VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xpolls\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(asset)
+            .bind::<&str>(&serde_json::to_string(&asset_type).unwrap())
+            .bind::<&str>(author)
+            .bind::<&str>(&serde_json::to_string(&options).unwrap())
+            .bind::<&str>(&props.multiple.to_string())
+            .bind::<&str>(&props.anonymous.to_string())
+            .bind::<&str>(&(timestamp + props.duration).to_string())
+            .bind::<&str>("false")
+            .bind::<&str>(&timestamp.to_string())
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Vote on the poll attached to the given asset
+    ///
+    /// Votes cannot be changed once cast.
+    ///
+    /// # Arguments
+    /// * `asset`
+    /// * `props` - [`PollVoteCreate`]
+    /// * `user` - the user voting
+    pub async fn create_poll_vote(
+        &self,
+        asset: &str,
+        props: PollVoteCreate,
+        user: &Profile,
+    ) -> Result<()> {
+        if user.group == -1 {
+            // group -1 (even if it exists) is for marking users as banned
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        self.auth.check_standing(user).await?;
+
+        let poll = self.get_poll(asset).await?;
+
+        if !self.can_view_poll(&poll, Some(user)).await? {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        if poll.is_closed() {
+            return Err(DatabaseError::PollClosed);
+        }
+
+        // check options
+        let mut options = props.options;
+        options.sort_unstable();
+        options.dedup();
+
+        if options.is_empty() | (!poll.multiple && (options.len() > 1)) {
+            return Err(DatabaseError::ValueError);
+        }
+
+        if options.iter().any(|o| *o >= poll.options.len()) {
+            return Err(DatabaseError::ValueError);
+        }
+
+        // make sure we haven't already voted
+        if self
+            .get_poll_votes(&poll.asset)
+            .await?
+            .iter()
+            .any(|v| v.user == user.id)
+        {
+            return Err(DatabaseError::AlreadyVoted);
+        }
+
+        // create vote
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \"xpoll_votes\" VALUES (?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xpoll_votes\" VALUES ($1, $2, $3, $4)"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(&poll.asset)
+            .bind::<&str>(&user.id)
+            .bind::<&str>(&serde_json::to_string(&options).unwrap())
+            .bind::<&str>(&utility::unix_epoch_timestamp().to_string())
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Delete the poll attached to the given asset (and its votes)
+    ///
+    /// # Arguments
+    /// * `asset`
+    async fn delete_poll(&self, asset: &str) -> Result<()> {
+        let c = &self.base.db.client;
+
+        for table in ["xpoll_votes", "xpolls"] {
+            let column = if table == "xpolls" { "asset" } else { "poll" };
+            let query: String =
+                if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                    format!("DELETE FROM \"{table}\" WHERE \"{column}\" = ?")
+                } else {
+                    format!("DELETE FROM \"{table}\" WHERE \"{column}\" = $1")
+                };
+
+            if sqlquery(&query)
+                .bind::<&str>(asset)
+                .execute(c)
+                .await
+                .is_err()
+            {
+                return Err(DatabaseError::Other);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Close every poll past its closing time and notify its author and voters
+    pub async fn close_polls(&self) {
+        let query: &str = "SELECT * FROM \"xpolls\" WHERE \"closed\" = 'false'";
+
+        let c = &self.base.db.client;
+        let rows = match sqlquery(query).fetch_all(c).await {
+            Ok(r) => r,
+            Err(_) => return,
+        };
+
+        let now = utility::unix_epoch_timestamp();
+
+        for row in rows {
+            let poll = match self.gimme_poll(self.base.textify_row(row).0) {
+                Ok(p) => p,
+                Err(_) => continue,
+            };
+
+            if poll.closes > now {
+                continue;
+            }
+
+            // mark as closed
+            let query: &str =
+                if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+                    "UPDATE \"xpolls\" SET \"closed\" = 'true' WHERE \"asset\" = ?"
+                } else {
+                    "UPDATE \"xpolls\" SET (\"closed\") = ('true') WHERE \"asset\" = $1"
+                };
+
+            if sqlquery(query)
+                .bind::<&str>(&poll.asset)
+                .execute(c)
+                .await
+                .is_err()
+            {
+                continue;
+            }
+
+            // notify
+            let address = match poll.asset_type {
+                AssetType::Question => format!("/question/{}", poll.asset),
+                _ => format!("/response/{}", poll.asset),
+            };
+
+            let mut recipients = vec![poll.author.clone()];
+
+            if let Ok(votes) = self.get_poll_votes(&poll.asset).await {
+                for vote in votes {
+                    if !recipients.contains(&vote.user) {
+                        recipients.push(vote.user);
+                    }
+                }
+            }
+
+            for recipient in recipients {
+                let _ = self
+                    .auth
+                    .create_notification(
+                        NotificationCreate {
+                            title: if recipient == poll.author {
+                                "Your poll has closed!".to_string()
+                            } else {
+                                "A poll you voted on has closed!".to_string()
+                            },
+                            content: "The final results are in.".to_string(),
+                            address: address.clone(),
+                            recipient,
+                            kind: NotificationKind::System,
+                        },
+                        None,
+                    )
+                    .await;
+            }
+        }
+    }
+
+    // attachments
+
+    /// Get an [`Attachment`] from a database result
+    fn gimme_attachment(&self, res: BTreeMap<String, String>) -> Result<Attachment> {
+        Ok(Attachment {
+            id: from_row!(res->id()),
+            asset: from_row!(res->asset()),
+            asset_type: match serde_json::from_str(res.get("asset_type").unwrap()) {
+                Ok(t) => t,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            owner: from_row!(res->owner()),
+            alt: from_row!(res->alt()),
+            size: from_row!(res->size(u64); 0),
+            timestamp: from_row!(res->timestamp(u128); 0),
+        })
+    }
+
+    /// Get all [`Attachment`]s matching the given query with one bound value (oldest first)
+    async fn get_attachments_by_query(&self, query: &str, value: &str) -> Result<Vec<Attachment>> {
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(value).fetch_all(c).await {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    out.push(self.gimme_attachment(self.base.textify_row(row).0)?);
+                }
+
+                // timestamps are stored as text, so they're sorted here
+                out.sort_by_key(|a| a.timestamp);
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get an existing [`Attachment`]
+    ///
+    /// # Arguments
+    /// * `id`
+    pub async fn get_attachment(&self, id: &str) -> Result<Attachment> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xattachments\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \"xattachments\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(id).fetch_one(c).await {
+            Ok(p) => self.gimme_attachment(self.base.textify_row(p).0),
+            Err(_) => Err(DatabaseError::NotFound),
+        }
+    }
+
+    /// Get all [`Attachment`]s of the given asset
+    ///
+    /// # Arguments
+    /// * `asset`
+    pub async fn get_attachments_by_asset(&self, asset: &str) -> Result<Vec<Attachment>> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xattachments\" WHERE \"asset\" = ?"
+        } else {
+            "SELECT * FROM \"xattachments\" WHERE \"asset\" = $1"
+        };
+
+        self.get_attachments_by_query(query, asset).await
+    }
+
+    /// Get how much of their attachment quota the given user has used
+    ///
+    /// # Arguments
+    /// * `user`
+    pub async fn get_attachment_usage(&self, user: &Profile) -> Result<AttachmentUsage> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xattachments\" WHERE \"owner\" = ?"
+        } else {
+            "SELECT * FROM \"xattachments\" WHERE \"owner\" = $1"
+        };
+
+        let used = self
+            .get_attachments_by_query(query, &user.id)
+            .await?
+            .iter()
+            .map(|a| a.size)
+            .sum();
+
+        // we get an upgraded quota if we have the minimum tier
+        let quota = if user.tier >= self.config.tiers.double_limits {
+            self.attachments.quota * 2
+        } else {
+            self.attachments.quota
+        };
+
+        Ok(AttachmentUsage { used, quota })
+    }
+
+    /// Check if `viewer` is allowed to view the given attachment
+    ///
+    /// # Arguments
+    /// * `attachment`
+    /// * `viewer`
+    pub async fn can_view_attachment(
+        &self,
+        attachment: &Attachment,
+        viewer: Option<&Profile>,
+    ) -> Result<bool> {
+        match attachment.asset_type {
+            AssetType::Response => {
+                let response = self.get_response_short(attachment.asset.clone()).await?;
+                Ok(self.can_view_response(&response, viewer).await)
+            }
+            AssetType::Comment => {
+                let comment = self
+                    .get_comment_unchecked(attachment.asset.clone(), false)
+                    .await?;
+                Ok(self.can_view_comment(&comment.0, viewer).await)
+            }
+            _ => Err(DatabaseError::ValueError),
+        }
+    }
+
+    /// Get the path of the stored image of the given attachment
+    ///
+    /// # Arguments
+    /// * `id`
+    pub fn attachment_path(&self, id: &str) -> String {
+        pathd!("{}/attachments/{}.avif", self.config.media_dir, id).to_string()
+    }
+
+    /// Upload a new image to a response or comment
+    ///
+    /// The image is re-encoded as AVIF. Only the author of the asset can attach images to it.
+    ///
+    /// # Arguments
+    /// * `props` - [`AttachmentCreate`]
+    /// * `bytes` - the uploaded image
+    /// * `user` - the user uploading the image
+    pub async fn create_attachment(
+        &self,
+        props: AttachmentCreate,
+        bytes: Vec<u8>,
+        user: &Profile,
+    ) -> Result<Attachment> {
+        if user.group == -1 {
+            // group -1 (even if it exists) is for marking users as banned
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        self.auth.check_standing(user).await?;
+
+        // check asset
+        let author = match props.asset_type {
+            AssetType::Response => {
+                self.get_response_short(props.asset.clone())
+                    .await?
+                    .author
+                    .id
+            }
+            AssetType::Comment => {
+                self.get_comment_unchecked(props.asset.clone(), false)
+                    .await?
+                    .0
+                    .author
+                    .id
+            }
+            _ => return Err(DatabaseError::ValueError),
+        };
+
+        if author != user.id {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        if self.get_attachments_by_asset(&props.asset).await?.len()
+            >= self.attachments.max_per_asset
+        {
+            return Err(DatabaseError::TooManyAttachments);
+        }
+
+        // check image
+        if bytes.is_empty() {
+            return Err(DatabaseError::ContentTooShort);
+        }
+
+        if bytes.len() > self.attachments.max_size {
+            return Err(DatabaseError::ContentTooLong);
+        }
+
+        if props.alt.len() > ATTACHMENT_ALT_MAX_LENGTH {
+            return Err(DatabaseError::ContentTooLong);
+        }
+
+        let usage = self.get_attachment_usage(user).await?;
+
+        if (usage.quota != 0) && (usage.used >= usage.quota) {
+            return Err(DatabaseError::QuotaExceeded);
+        }
+
+        // save image
+        let attachment = Attachment {
+            id: AlmostSnowflake::new(self.config.snowflake_server_id).to_string(),
+            asset: props.asset,
+            asset_type: props.asset_type,
+            owner: user.id.clone(),
+            alt: props.alt.trim().to_string(),
+            size: 0,
+            timestamp: utility::unix_epoch_timestamp(),
+        };
+
+        let path = self.attachment_path(&attachment.id);
+
+        if authbeam::avif::save_avif_buffer(&path, bytes).is_err() {
+            let _ = std::fs::remove_file(&path);
+            return Err(DatabaseError::ValueError);
+        }
+
+        let attachment = Attachment {
+            size: match std::fs::metadata(&path) {
+                Ok(m) => m.len(),
+                Err(_) => return Err(DatabaseError::Other),
+            },
+            ..attachment
+        };
+
+        // the stored size is only known after re-encoding
+        if (usage.quota != 0) && (usage.used + attachment.size > usage.quota) {
+            let _ = std::fs::remove_file(&path);
+            return Err(DatabaseError::QuotaExceeded);
+        }
+
+        // create attachment
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \"xattachments\" VALUES (?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xattachments\" VALUES ($1, $2, $3, $4, $5, $6, $7)"
+        };
+
+        let c = &self.base.db.client;
+        if sqlquery(query)
+            .bind::<&str>(&attachment.id)
+            .bind::<&str>(&attachment.asset)
+            .bind::<&str>(&serde_json::to_string(&attachment.asset_type).unwrap())
+            .bind::<&str>(&attachment.owner)
+            .bind::<&str>(&attachment.alt)
+            .bind::<&str>(&attachment.size.to_string())
+            .bind::<&str>(&attachment.timestamp.to_string())
+            .execute(c)
+            .await
+            .is_err()
+        {
+            let _ = std::fs::remove_file(&path);
+            return Err(DatabaseError::Other);
+        }
+
+        self.update_asset_attachments(&attachment.asset_type, &attachment.asset)
+            .await?;
+
+        Ok(attachment)
+    }
+
+    /// Delete an existing [`Attachment`]
+    ///
+    /// Attachments can only be deleted by their owner or staff who can manage their asset.
+    ///
+    /// # Arguments
+    /// * `id`
+    /// * `user` - the user doing this
+    pub async fn delete_attachment(&self, id: &str, user: &Profile) -> Result<()> {
+        let attachment = self.get_attachment(id).await?;
+
+        if user.id != attachment.owner {
+            let group = match self.auth.get_group_by_id(user.group).await {
+                Ok(g) => g,
+                Err(_) => return Err(DatabaseError::Other),
+            };
+
+            let permission = match attachment.asset_type {
+                AssetType::Comment => FinePermission::MANAGE_COMMENTS,
+                _ => FinePermission::MANAGE_RESPONSES,
+            };
+
+            if !group.permissions.check(permission) {
+                return Err(DatabaseError::NotAllowed);
+            }
+
+            self.audit(
+                user.id.clone(),
+                format!(
+                    "Deleted an image attached by: [{}](/+u/{})",
+                    attachment.owner, attachment.owner
+                ),
+            )
+            .await?;
+        }
+
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \"xattachments\" WHERE \"id\" = ?"
+        } else {
+            "DELETE FROM \"xattachments\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        if sqlquery(query).bind::<&str>(id).execute(c).await.is_err() {
+            return Err(DatabaseError::Other);
+        }
+
+        let _ = std::fs::remove_file(self.attachment_path(id));
+
+        self.update_asset_attachments(&attachment.asset_type, &attachment.asset)
+            .await
+    }
+
+    /// Delete every [`Attachment`] of the given asset (and their images)
+    ///
+    /// # Arguments
+    /// * `asset`
+    async fn delete_attachments_by_asset(&self, asset: &str) -> Result<()> {
+        let attachments = self.get_attachments_by_asset(asset).await?;
+
+        if attachments.is_empty() {
+            return Ok(());
+        }
+
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \"xattachments\" WHERE \"asset\" = ?"
+        } else {
+            "DELETE FROM \"xattachments\" WHERE \"asset\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        if sqlquery(query)
+            .bind::<&str>(asset)
+            .execute(c)
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        for attachment in attachments {
+            let _ = std::fs::remove_file(self.attachment_path(&attachment.id));
+        }
+
+        Ok(())
+    }
+
+    /// Delete every [`Attachment`] owned by the given user (and their images)
+    ///
+    /// # Arguments
+    /// * `owner`
+    pub async fn delete_attachments_by_owner(&self, owner: &str) -> Result<()> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xattachments\" WHERE \"owner\" = ?"
+        } else {
+            "SELECT * FROM \"xattachments\" WHERE \"owner\" = $1"
+        };
+
+        let attachments = self.get_attachments_by_query(query, owner).await?;
+
+        if attachments.is_empty() {
+            return Ok(());
+        }
+
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "DELETE FROM \"xattachments\" WHERE \"owner\" = ?"
+        } else {
+            "DELETE FROM \"xattachments\" WHERE \"owner\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        if sqlquery(query)
+            .bind::<&str>(owner)
+            .execute(c)
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        for attachment in attachments {
+            let _ = std::fs::remove_file(self.attachment_path(&attachment.id));
+        }
+
+        Ok(())
+    }
+
+    /// Copy the attachments of the given asset into its context
+    ///
+    /// # Arguments
+    /// * `asset_type` - [`AssetType::Response`] or [`AssetType::Comment`]
+    /// * `id`
+    async fn update_asset_attachments(&self, asset_type: &AssetType, id: &str) -> Result<()> {
+        let attachments: Vec<(String, String)> = self
+            .get_attachments_by_asset(id)
+            .await?
+            .into_iter()
+            .map(|a| (a.id, a.alt))
+            .collect();
+
+        let (table, context) = match asset_type {
+            AssetType::Response => {
+                let mut context = self.get_response_short(id.to_string()).await?.context;
+                context.attachments = attachments;
+                ("xresponses", serde_json::to_string(&context))
+            }
+            AssetType::Comment => {
+                let mut context = self
+                    .get_comment_unchecked(id.to_string(), false)
+                    .await?
+                    .0
+                    .context;
+                context.attachments = attachments;
+                ("xcomments", serde_json::to_string(&context))
+            }
+            _ => return Err(DatabaseError::ValueError),
+        };
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            format!("UPDATE \"{table}\" SET \"context\" = ? WHERE \"id\" = ?")
+        } else {
+            format!("UPDATE \"{table}\" SET (\"context\") = ($1) WHERE \"id\" = $2")
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&str>(&match context {
+                Ok(t) => t,
+                Err(_) => return Err(DatabaseError::ValueError),
+            })
+            .bind::<&str>(id)
+            .execute(c)
+            .await
+        {
+            Ok(_) => {
+                match asset_type {
+                    AssetType::Comment => {
+                        self.base
+                            .cache
+                            .remove(format!("rbeam.app.comment:{id}"))
+                            .await
+                    }
+                    _ => {
+                        self.base
+                            .cache
+                            .remove(format!("rbeam.app.response:{id}"))
+                            .await
+                    }
+                };
+
+                Ok(())
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Remove the attachments from the context of snapshotted responses and comments
+    ///
+    /// Attached images are deleted with their asset, so restored assets don't have them.
+    ///
+    /// # Arguments
+    /// * `rows`
+    fn clear_snapshot_attachments(rows: &mut [RemovedRow]) {
+        for removed in rows.iter_mut() {
+            if (removed.table != "xresponses") && (removed.table != "xcomments") {
+                continue;
+            }
+
+            let Some(context) = removed.row.get_mut("context") else {
+                continue;
+            };
+
+            if let Ok(mut value) = serde_json::from_str::<serde_json::Value>(context) {
+                value["attachments"] = serde_json::Value::Array(Vec::new());
+                *context = value.to_string();
+            }
+        }
+    }
+
+    // revisions
+
+    /// Get a [`Revision`] from a database result
+    async fn gimme_revision(&self, res: BTreeMap<String, String>) -> Result<Revision> {
+        Ok(Revision {
+            id: from_row!(res->id()),
+            asset_type: match serde_json::from_str(res.get("asset_type").unwrap()) {
+                Ok(t) => t,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            asset: from_row!(res->asset()),
+            author: from_row!(res->author()),
+            editor: match self.get_profile(from_row!(res->editor())).await {
+                Ok(ua) => ua,
+                Err(_) => anonymous_profile("anonymous".to_string()),
+            },
+            content: from_row!(res->content()),
+            kind: match serde_json::from_str(res.get("kind").unwrap()) {
+                Ok(k) => k,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            timestamp: from_row!(res->timestamp(u128); 0),
+        })
+    }
+
+    /// Get an existing [`Revision`]
+    ///
+    /// # Arguments
+    /// * `id`
+    pub async fn get_revision(&self, id: &str) -> Result<Revision> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xrevisions\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \"xrevisions\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let revision = match sqlquery(query).bind::<&str>(id).fetch_one(c).await {
+            Ok(p) => self.gimme_revision(self.base.textify_row(p).0).await?,
+            Err(_) => return Err(DatabaseError::NotFound),
+        };
+
+        if self.revisions.is_expired(revision.timestamp) {
+            return Err(DatabaseError::NotFound);
+        }
+
+        Ok(revision)
+    }
+
+    /// Get all [`Revision`]s of the given asset (newest first)
+    ///
+    /// Revisions past the [`RevisionPolicy`] are skipped.
+    ///
+    /// # Arguments
+    /// * `asset`
+    pub async fn get_revisions_by_asset(&self, asset: &str) -> Result<Vec<Revision>> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xrevisions\" WHERE \"asset\" = ?"
+        } else {
+            "SELECT * FROM \"xrevisions\" WHERE \"asset\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(asset).fetch_all(c).await {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    let revision = self.gimme_revision(self.base.textify_row(row).0).await?;
+
+                    if self.revisions.is_expired(revision.timestamp) {
+                        continue;
+                    }
+
+                    out.push(revision);
+                }
+
+                // timestamps are stored as text, so they're sorted here
+                out.sort_by_key(|r| std::cmp::Reverse(r.timestamp));
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get the [`Revision`]s of a response or comment which `viewer` is allowed to see
+    ///
+    /// Anybody who can view the asset can view its revisions. Once the asset is deleted,
+    /// only staff who can manage that type of asset can.
+    ///
+    /// # Arguments
+    /// * `asset_type` - [`AssetType::Response`] or [`AssetType::Comment`]
+    /// * `asset`
+    /// * `viewer`
+    pub async fn get_asset_revisions(
+        &self,
+        asset_type: AssetType,
+        asset: &str,
+        viewer: Option<&Profile>,
+    ) -> Result<Vec<Revision>> {
+        let (exists, can_view, permission) = match asset_type {
+            AssetType::Response => match self.get_response_unchecked(asset.to_string()).await {
+                Ok(r) => (
+                    true,
+                    self.can_view_response(&r.1, viewer).await,
+                    FinePermission::MANAGE_RESPONSES,
+                ),
+                Err(DatabaseError::NotFound) => (false, false, FinePermission::MANAGE_RESPONSES),
+                Err(e) => return Err(e),
+            },
+            AssetType::Comment => {
+                match self.get_comment_unchecked(asset.to_string(), false).await {
+                    Ok(c) => (
+                        true,
+                        self.can_view_comment(&c.0, viewer).await,
+                        FinePermission::MANAGE_COMMENTS,
+                    ),
+                    Err(DatabaseError::NotFound) => (false, false, FinePermission::MANAGE_COMMENTS),
+                    Err(e) => return Err(e),
+                }
+            }
+            _ => return Err(DatabaseError::ValueError),
+        };
+
+        if exists && !can_view {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        if !exists {
+            // deleted content is staff only
+            let viewer = match viewer {
+                Some(ua) => ua,
+                None => return Err(DatabaseError::NotFound),
+            };
+
+            let group = match self.auth.get_group_by_id(viewer.group).await {
+                Ok(g) => g,
+                Err(_) => return Err(DatabaseError::Other),
+            };
+
+            if !group.permissions.check(permission) {
+                return Err(DatabaseError::NotFound);
+            }
+        }
+
+        let revisions = self.get_revisions_by_asset(asset).await?;
+
+        if !exists && revisions.is_empty() {
+            return Err(DatabaseError::NotFound);
+        }
+
+        Ok(revisions)
+    }
+
+    /// Record the content of a response or comment before it's replaced
+    ///
+    /// Does nothing if revisions are disabled. Removes the asset's oldest revisions past
+    /// [`RevisionPolicy::max_revisions`].
+    ///
+    /// # Arguments
+    /// * `asset_type` - [`AssetType::Response`] or [`AssetType::Comment`]
+    /// * `asset` - the ID of the asset
+    /// * `author` - the ID of the author of the asset
+    /// * `editor` - the ID of the user replacing the content
+    /// * `content` - the content being replaced
+    /// * `kind`
+    async fn create_revision(
+        &self,
+        asset_type: AssetType,
+        asset: &str,
+        author: &str,
+        editor: &str,
+        content: &str,
+        kind: RevisionKind,
+    ) -> Result<()> {
+        if !self.revisions.enabled {
+            return Ok(());
+        }
+
+        let id = AlmostSnowflake::new(self.config.snowflake_server_id).to_string();
+
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \"xrevisions\" VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xrevisions\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+        };
+
+        let c = &self.base.db.client;
+        if sqlquery(query)
+            .bind::<&str>(&id)
+            .bind::<&str>(&serde_json::to_string(&asset_type).unwrap())
+            .bind::<&str>(asset)
+            .bind::<&str>(author)
+            .bind::<&str>(editor)
+            .bind::<&str>(content)
+            .bind::<&str>(&serde_json::to_string(&kind).unwrap())
+            .bind::<&str>(&utility::unix_epoch_timestamp().to_string())
+            .execute(c)
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        // remove the oldest revisions
+        if self.revisions.max_revisions != 0 {
+            if let Ok(revisions) = self.get_revisions_by_asset(asset).await {
+                for revision in revisions.iter().skip(self.revisions.max_revisions) {
+                    let _ = self.delete_revision(&revision.id).await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete an existing [`Revision`]
+    ///
+    /// # Arguments
+    /// * `id`
+    async fn delete_revision(&self, id: &str) -> Result<()> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \"xrevisions\" WHERE \"id\" = ?"
+        } else {
+            "DELETE FROM \"xrevisions\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(id).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Delete every [`Revision`] past [`RevisionPolicy::max_age`]
+    pub async fn prune_revisions(&self) {
+        if self.revisions.max_age == 0 {
+            return;
+        }
+
+        let query: &str = "SELECT \"id\", \"timestamp\" FROM \"xrevisions\"";
+
+        let c = &self.base.db.client;
+        let rows = match sqlquery(query).fetch_all(c).await {
+            Ok(r) => r,
+            Err(_) => return,
+        };
+
+        for row in rows {
+            let row = self.base.textify_row(row).0;
+            let timestamp: u128 = from_row!(row->timestamp(u128); 0);
+
+            if self.revisions.is_expired(timestamp) {
+                let _ = self.delete_revision(&from_row!(row->id())).await;
+            }
+        }
+    }
+
+    // appeals
+
+    /// Get an [`Appeal`] from a database result
+    fn gimme_appeal(&self, res: BTreeMap<String, String>) -> Result<Appeal> {
+        Ok(Appeal {
+            id: from_row!(res->id()),
+            author: from_row!(res->author()),
+            kind: match serde_json::from_str(res.get("kind").unwrap()) {
+                Ok(k) => k,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            target: from_row!(res->target()),
+            content: from_row!(res->content()),
+            status: match serde_json::from_str(res.get("status").unwrap()) {
+                Ok(s) => s,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            reviewer: from_row!(res->reviewer()),
+            reason: from_row!(res->reason()),
+            timestamp: from_row!(res->timestamp(u128); 0),
+            decided: from_row!(res->decided(u128); 0),
+        })
+    }
+
+    /// Get all [`Appeal`]s matching the given query with one bound value
+    async fn get_appeals_by_query(&self, query: &str, value: &str) -> Result<Vec<Appeal>> {
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(value).fetch_all(c).await {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    out.push(self.gimme_appeal(self.base.textify_row(row).0)?);
+                }
+
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get an existing [`Appeal`]
+    ///
+    /// # Arguments
+    /// * `id`
+    pub async fn get_appeal(&self, id: &str) -> Result<Appeal> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xappeals\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \"xappeals\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(id).fetch_one(c).await {
+            Ok(p) => self.gimme_appeal(self.base.textify_row(p).0),
+            Err(_) => Err(DatabaseError::NotFound),
+        }
+    }
+
+    /// Get all [`Appeal`]s by the given user
+    ///
+    /// # Arguments
+    /// * `author`
+    pub async fn get_appeals_by_author(&self, author: &str) -> Result<Vec<Appeal>> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xappeals\" WHERE \"author\" = ? ORDER BY \"timestamp\" DESC"
+        } else {
+            "SELECT * FROM \"xappeals\" WHERE \"author\" = $1 ORDER BY \"timestamp\" DESC"
+        };
+
+        self.get_appeals_by_query(query, author).await
+    }
+
+    /// Get all [`Appeal`]s against the given action
+    ///
+    /// # Arguments
+    /// * `kind`
+    /// * `target`
+    async fn get_appeals_by_target(&self, kind: &AppealKind, target: &str) -> Result<Vec<Appeal>> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xappeals\" WHERE \"target\" = ?"
+        } else {
+            "SELECT * FROM \"xappeals\" WHERE \"target\" = $1"
+        };
+
+        let mut out = self.get_appeals_by_query(query, target).await?;
+        out.retain(|a| a.kind == *kind);
+        Ok(out)
+    }
+
+    /// Get all [`Appeal`]s waiting for a decision (oldest first)
+    ///
+    /// # Arguments
+    /// * `user` - the user doing this
+    pub async fn get_pending_appeals(&self, user: &Profile) -> Result<Vec<Appeal>> {
+        // check permission
+        let group = match self.auth.get_group_by_id(user.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        if !group.permissions.check(FinePermission::VIEW_REPORTS) {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // pull from database
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xappeals\" WHERE \"status\" = ? ORDER BY \"timestamp\" ASC"
+        } else {
+            "SELECT * FROM \"xappeals\" WHERE \"status\" = $1 ORDER BY \"timestamp\" ASC"
+        };
+
+        self.get_appeals_by_query(
+            query,
+            &serde_json::to_string(&AppealStatus::Pending).unwrap(),
+        )
+        .await
+    }
+
+    /// Get the permission needed to decide the given [`Appeal`]
+    async fn appeal_permission(&self, appeal: &Appeal) -> FinePermission {
+        match appeal.kind {
+            AppealKind::Warning | AppealKind::Suspension => FinePermission::MANAGE_WARNINGS,
+            AppealKind::IpBan => FinePermission::UNBAN_IP,
+            AppealKind::Removal => match self.get_removal(&appeal.target).await {
+                Ok(r) => match r.asset_type {
+                    AssetType::Question => FinePermission::MANAGE_QUESTIONS,
+                    AssetType::Comment => FinePermission::MANAGE_COMMENTS,
+                    _ => FinePermission::MANAGE_RESPONSES,
+                },
+                Err(_) => FinePermission::MANAGE_RESPONSES,
+            },
+        }
+    }
+
+    /// Appeal a moderation action
+    ///
+    /// Appeals can be made by users who are suspended or IP banned. Appeals against
+    /// IP bans always target the ban of the IP making the appeal, and can be made
+    /// while logged out.
+    ///
+    /// # Arguments
+    /// * `props` - [`AppealCreate`]
+    /// * `author` - the user appealing (`None` if logged out)
+    /// * `ip` - the IP address of the user appealing
+    pub async fn create_appeal(
+        &self,
+        props: AppealCreate,
+        author: Option<Box<Profile>>,
+        ip: &str,
+    ) -> Result<Appeal> {
+        let content = props.content.trim().to_string();
+
+        if content.len() < 2 {
+            return Err(DatabaseError::ContentTooShort);
+        }
+
+        if content.len() > 4096 {
+            return Err(DatabaseError::ContentTooLong);
+        }
+
+        // make sure the action exists and was taken against the user appealing
+        let mut since: u128 = 0;
+        let target = if props.kind == AppealKind::IpBan {
+            match self.auth.get_ipban_by_ip(ip).await {
+                Ok(b) => b.id,
+                Err(_) => return Err(DatabaseError::NotFound),
+            }
+        } else {
+            let author = match author {
+                Some(ref a) => a,
+                None => return Err(DatabaseError::NotAllowed),
+            };
+
+            match props.kind {
+                AppealKind::Warning => match self.auth.get_warning(&props.target).await {
+                    Ok(w) if w.recipient == author.id => w.id,
+                    _ => return Err(DatabaseError::NotFound),
+                },
+                AppealKind::Suspension => match self.auth.get_suspension(&author.id).await {
+                    Ok(s) if s.is_active() => {
+                        since = s.timestamp;
+                        author.id.clone()
+                    }
+                    _ => return Err(DatabaseError::NotFound),
+                },
+                _ => match self.get_removal(&props.target).await {
+                    Ok(r) if r.author == author.id => r.id,
+                    _ => return Err(DatabaseError::NotFound),
+                },
+            }
+        };
+
+        // every action can only be appealed once
+        if self
+            .get_appeals_by_target(&props.kind, &target)
+            .await?
+            .iter()
+            .any(|a| a.timestamp >= since)
+        {
+            return Err(DatabaseError::AlreadyAppealed);
+        }
+
+        // create appeal
+        let appeal = Appeal {
+            id: AlmostSnowflake::new(self.config.snowflake_server_id).to_string(),
+            author: match author {
+                Some(a) => a.id,
+                None => "anonymous".to_string(),
+            },
+            kind: props.kind,
+            target,
+            content,
+            status: AppealStatus::Pending,
+            reviewer: String::new(),
+            reason: String::new(),
+            timestamp: utility::unix_epoch_timestamp(),
+            decided: 0,
+        };
+
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \"xappeals\" VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xappeals\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(&appeal.id)
+            .bind::<&str>(&appeal.author)
+            .bind::<&str>(&serde_json::to_string(&appeal.kind).unwrap())
+            .bind::<&str>(&appeal.target)
+            .bind::<&str>(&appeal.content)
+            .bind::<&str>(&serde_json::to_string(&appeal.status).unwrap())
+            .bind::<&str>(&appeal.reviewer)
+            .bind::<&str>(&appeal.reason)
+            .bind::<&str>(&appeal.timestamp.to_string())
+            .bind::<&str>("0")
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(appeal),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Decide an [`Appeal`], reversing the appealed action if it is granted
+    ///
+    /// # Arguments
+    /// * `id`
+    /// * `props` - [`AppealDecide`]
+    /// * `reviewer` - the user doing this
+    pub async fn decide_appeal(
+        &self,
+        id: &str,
+        props: AppealDecide,
+        reviewer: Box<Profile>,
+    ) -> Result<()> {
+        let appeal = self.get_appeal(id).await?;
+
+        if appeal.status != AppealStatus::Pending {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // check permission
+        let group = match self.auth.get_group_by_id(reviewer.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        if !group
+            .permissions
+            .check(self.appeal_permission(&appeal).await)
+            | (appeal.author == reviewer.id)
+        {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        let reason = props.reason.trim().to_string();
+
+        if reason.len() < 2 {
+            return Err(DatabaseError::ContentTooShort);
+        }
+
+        if reason.len() > 4096 {
+            return Err(DatabaseError::ContentTooLong);
+        }
+
+        // reverse action
+        if props.granted {
+            match appeal.kind {
+                AppealKind::Warning => {
+                    self.auth
+                        .delete_warning(&appeal.target, reviewer.clone())
+                        .await?
+                }
+                AppealKind::Suspension => {
+                    // the suspension may have ended while the appeal was pending
+                    if let Ok(s) = self.auth.get_suspension(&appeal.target).await {
+                        if s.is_active() {
+                            self.auth
+                                .lift_suspension(&appeal.target, reviewer.clone())
+                                .await?
+                        }
+                    }
+                }
+                AppealKind::IpBan => {
+                    self.auth
+                        .delete_ipban(&appeal.target, reviewer.clone())
+                        .await?
+                }
+                AppealKind::Removal => {
+                    let removal = self.get_removal(&appeal.target).await?;
+                    self.restore_removal(&removal).await?
+                }
+            }
+        }
+
+        // update appeal
+        let status = if props.granted {
+            AppealStatus::Granted
+        } else {
+            AppealStatus::Denied
+        };
+
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "UPDATE \"xappeals\" SET \"status\" = ?, \"reviewer\" = ?, \"reason\" = ?, \"decided\" = ? WHERE \"id\" = ?"
+        } else {
+            "UPDATE \"xappeals\" SET (\"status\", \"reviewer\", \"reason\", \"decided\") = ($1, $2, $3, $4) WHERE \"id\" = $5"
+        };
+
+        let c = &self.base.db.client;
+        if sqlquery(query)
+            .bind::<&str>(&serde_json::to_string(&status).unwrap())
+            .bind::<&str>(&reviewer.id)
+            .bind::<&str>(&reason)
+            .bind::<&str>(&utility::unix_epoch_timestamp().to_string())
+            .bind::<&str>(id)
+            .execute(c)
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        // audit
+        let decision = if props.granted { "Granted" } else { "Denied" };
+
+        self.audit(
+            reviewer.id.clone(),
+            format!("{decision} an appeal: [{id}](/appeals/{id})"),
+        )
+        .await?;
+
+        // notify author
+        if appeal.author != "anonymous" {
+            let _ = self
+                .auth
+                .create_notification(
+                    NotificationCreate {
+                        title: format!("Your appeal was {}", decision.to_lowercase()),
+                        content: reason,
+                        address: format!("/appeals/{id}"),
+                        recipient: appeal.author,
+                        kind: NotificationKind::System,
+                    },
+                    None,
+                )
+                .await;
+        }
+
+        Ok(())
+    }
+
+    // tips
+
+    /// Get a [`Tip`] from a database result
+    async fn gimme_tip(&self, res: BTreeMap<String, String>) -> Result<Tip> {
+        Ok(Tip {
+            id: from_row!(res->id()),
+            author: self.get_profile(from_row!(res->author())).await?,
+            recipient: self.get_profile(from_row!(res->recipient())).await?,
+            asset: from_row!(res->asset()),
+            asset_type: match serde_json::from_str(res.get("asset_type").unwrap()) {
+                Ok(t) => t,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            amount: from_row!(res->amount(i32); 0),
+            message: from_row!(res->message()),
+            transfer: from_row!(res->transfer()),
+            timestamp: from_row!(res->timestamp(u128); 0),
+        })
+    }
+
+    /// Get all [`Tip`]s on the given asset
+    ///
+    /// # Arguments
+    /// * `asset`
+    pub async fn get_tips_by_asset(&self, asset: &str) -> Result<Vec<Tip>> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xtips\" WHERE \"asset\" = ? ORDER BY \"timestamp\" DESC"
+        } else {
+            "SELECT * FROM \"xtips\" WHERE \"asset\" = $1 ORDER BY \"timestamp\" DESC"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(asset).fetch_all(c).await {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    out.push(self.gimme_tip(self.base.textify_row(row).0).await?);
+                }
+
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get the amount of coins the given user has tipped in the last 24 hours
+    ///
+    /// # Arguments
+    /// * `author`
+    pub async fn get_tipped_today(&self, author: &str) -> i32 {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT \"amount\", \"timestamp\" FROM \"xtips\" WHERE \"author\" = ?"
+        } else {
+            "SELECT \"amount\", \"timestamp\" FROM \"xtips\" WHERE \"author\" = $1"
+        };
+
+        let since = utility::unix_epoch_timestamp().saturating_sub(24 * 60 * 60 * 1000);
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(author).fetch_all(c).await {
+            Ok(p) => {
+                let mut tipped = 0;
+
+                for row in p {
+                    let res = self.base.textify_row(row).0;
+
+                    if from_row!(res->timestamp(u128); 0) < since {
+                        continue;
+                    }
+
+                    tipped += from_row!(res->amount(i32); 0);
+                }
+
+                tipped
+            }
+            Err(_) => 0,
+        }
+    }
+
+    /// Tip the author of a response or comment
+    ///
+    /// The coins are moved with an `authbeam::model::Transaction` from `author` to the
+    /// asset's author, and the asset's tip total is bumped.
+    ///
+    /// # Arguments
+    /// * `props` - [`TipCreate`]
+    /// * `author` - the user sending the tip
+    pub async fn create_tip(&self, props: TipCreate, author: Box<Profile>) -> Result<Tip> {
+        // check author permissions
+        if author.group == -1 {
+            // group -1 (even if it exists) is for marking users as banned
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        self.auth.check_standing(&author).await?;
+
+        // check values
+        if props.amount < 1 {
+            return Err(DatabaseError::ValueError);
+        }
+
+        if props.message.len() > 256 {
+            return Err(DatabaseError::ContentTooLong);
+        }
+
+        if (self.get_tipped_today(&author.id).await + props.amount) > TIP_DAILY_LIMIT {
+            return Err(DatabaseError::TipLimitReached);
+        }
+
+        // get asset
+        let recipient = match props.asset_type {
+            AssetType::Response => {
+                let response = self.get_response_short(props.asset.clone()).await?;
+
+                if !self.can_view_response(&response, Some(&author)).await {
+                    return Err(DatabaseError::NotFound);
+                }
+
+                response.author
+            }
+            AssetType::Comment => {
+                let comment = self
+                    .get_comment_unchecked(props.asset.clone(), false)
+                    .await?
+                    .0;
+
+                if !self.can_view_comment(&comment, Some(&author)).await {
+                    return Err(DatabaseError::NotFound);
+                }
+
+                comment.author
+            }
+            _ => return Err(DatabaseError::ValueError),
+        };
+
+        if recipient.id == author.id {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // check relationship
+        let relationship = self
+            .auth
+            .get_user_relationship(&recipient.id, &author.id)
+            .await;
+
+        if relationship.0 == RelationshipStatus::Blocked {
+            return Err(DatabaseError::Blocked);
+        }
+
+        // move coins
+        let mut tip = Tip {
+            id: AlmostSnowflake::new(self.config.snowflake_server_id).to_string(),
+            author,
+            recipient,
+            asset: props.asset,
+            asset_type: props.asset_type,
+            amount: props.amount,
+            message: props.message.trim().to_string(),
+            transfer: String::new(),
+            timestamp: utility::unix_epoch_timestamp(),
+        };
+
+        tip.transfer = match self
+            .auth
+            .create_transaction(
+                TransactionCreate {
+                    merchant: tip.recipient.id.clone(),
+                    item: tip.id.clone(),
+                    amount: -tip.amount,
+                    idempotency_key: String::new(),
+                },
+                &tip.author.id,
+            )
+            .await
+        {
+            Ok(t) => t.id,
+            Err(e) => return Err(e.into()),
+        };
+
+        // create tip
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \"xtips\" VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"xtips\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+        };
+
+        let c = &self.base.db.client;
+        if sqlquery(query)
+            .bind::<&str>(&tip.id)
+            .bind::<&str>(&tip.author.id)
+            .bind::<&str>(&tip.recipient.id)
+            .bind::<&str>(&tip.asset)
+            .bind::<&str>(&serde_json::to_string(&tip.asset_type).unwrap())
+            .bind::<&str>(&tip.amount.to_string())
+            .bind::<&str>(&tip.message)
+            .bind::<&str>(&tip.transfer)
+            .bind::<&str>(&tip.timestamp.to_string())
+            .execute(c)
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        // bump tip total
+        self.add_asset_tips(&tip.asset_type, &tip.asset, tip.amount)
+            .await?;
+
+        // notify recipient
+        let address = match tip.asset_type {
+            AssetType::Comment => format!("/comment/{}", tip.asset),
+            _ => format!("/response/{}", tip.asset),
+        };
+
+        let _ = self
+            .auth
+            .create_notification(
+                NotificationCreate {
+                    title: format!(
+                        "[@{}](/+u/{}) tipped you {} coins!",
+                        tip.author.username, tip.author.id, tip.amount
+                    ),
+                    content: if tip.message.is_empty() {
+                        "You were tipped for your post.".to_string()
+                    } else {
+                        tip.message.clone()
+                    },
+                    address,
+                    recipient: tip.recipient.id.clone(),
+                    kind: NotificationKind::System,
+                },
+                None,
+            )
+            .await;
+
+        Ok(tip)
+    }
+
+    /// Add `amount` to the tip total in the context of the given asset
+    ///
+    /// # Arguments
+    /// * `asset_type` - [`AssetType::Response`] or [`AssetType::Comment`]
+    /// * `id`
+    /// * `amount`
+    async fn add_asset_tips(&self, asset_type: &AssetType, id: &str, amount: i32) -> Result<()> {
+        let (table, context) = match asset_type {
+            AssetType::Response => {
+                let mut context = self.get_response_short(id.to_string()).await?.context;
+                context.tips += amount;
+                ("xresponses", serde_json::to_string(&context))
+            }
+            AssetType::Comment => {
+                let mut context = self
+                    .get_comment_unchecked(id.to_string(), false)
+                    .await?
+                    .0
+                    .context;
+                context.tips += amount;
+                ("xcomments", serde_json::to_string(&context))
+            }
+            _ => return Err(DatabaseError::ValueError),
+        };
+
+        let query: String = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            format!("UPDATE \"{table}\" SET \"context\" = ? WHERE \"id\" = ?")
+        } else {
+            format!("UPDATE \"{table}\" SET (\"context\") = ($1) WHERE \"id\" = $2")
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(&query)
+            .bind::<&str>(&match context {
+                Ok(t) => t,
+                Err(_) => return Err(DatabaseError::ValueError),
+            })
+            .bind::<&str>(id)
+            .execute(c)
+            .await
+        {
+            Ok(_) => {
+                match asset_type {
+                    AssetType::Comment => {
+                        self.base
+                            .cache
+                            .remove(format!("rbeam.app.comment:{id}"))
+                            .await
+                    }
+                    _ => {
+                        self.base
+                            .cache
+                            .remove(format!("rbeam.app.response:{id}"))
+                            .await
+                    }
+                };
+
+                Ok(())
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    // digests
+
+    /// Get a [`DigestSubscription`] from a database result
+    fn gimme_digest_subscription(&self, res: BTreeMap<String, String>) -> Result<DigestSubscription> {
+        Ok(DigestSubscription {
+            user: from_row!(res->user()),
+            frequency: match serde_json::from_str(res.get("frequency").unwrap()) {
+                Ok(f) => f,
+                Err(_) => return Err(DatabaseError::ValueError),
+            },
+            last_sent: from_row!(res->last_sent(u128); 0),
+        })
+    }
+
+    /// Get the [`DigestSubscription`] of the given user
+    ///
+    /// # Arguments
+    /// * `user`
+    pub async fn get_digest_subscription(&self, user: &str) -> Result<DigestSubscription> {
+        // pull from database
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "SELECT * FROM \"xdigests\" WHERE \"user\" = ?"
+        } else {
+            "SELECT * FROM \"xdigests\" WHERE \"user\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(user).fetch_one(c).await {
+            Ok(p) => self.gimme_digest_subscription(self.base.textify_row(p).0),
+            Err(_) => Err(DatabaseError::NotFound),
+        }
+    }
+
+    /// Get all [`DigestSubscription`]s which are due to be sent
+    pub async fn get_due_digest_subscriptions(&self) -> Result<Vec<DigestSubscription>> {
+        // pull from database
+        let c = &self.base.db.client;
+        match sqlquery("SELECT * FROM \"xdigests\"").fetch_all(c).await {
+            Ok(p) => {
+                let mut out = Vec::new();
+
+                for row in p {
+                    let subscription =
+                        match self.gimme_digest_subscription(self.base.textify_row(row).0) {
+                            Ok(s) => s,
+                            Err(_) => continue,
+                        };
+
+                    if subscription.is_due() {
+                        out.push(subscription);
+                    }
+                }
+
+                Ok(out)
+            }
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Get the contents of the next digest of the given user
+    ///
+    /// # Returns
+    /// `(unread notifications, unanswered questions)`
+    ///
+    /// # Arguments
+    /// * `user`
+    pub async fn get_digest(&self, user: &Profile) -> (Vec<Notification>, Vec<Question>) {
+        let mut notifications = self
+            .auth
+            .get_notifications_by_recipient(&user.id)
+            .await
+            .unwrap_or_default();
+
+        notifications.retain(|n| !n.read);
+
+        let questions = self
+            .get_questions_by_recipient(&user.id)
+            .await
+            .unwrap_or_default();
+
+        (notifications, questions)
+    }
+
+    /// Get the token which allows the given user to unsubscribe from digests without
+    /// being logged in
+    ///
+    /// # Arguments
+    /// * `user`
+    pub fn digest_unsubscribe_token(&self, user: &str) -> String {
+        rainbeam_shared::hash::hash(format!("{}:{}", user, self.mail.secret))
+    }
+
+    /// Get the token which confirms the given user owns the given email address
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `email`
+    pub fn digest_confirm_token(&self, user: &str, email: &str) -> String {
+        rainbeam_shared::hash::hash(format!("{}:{}:confirm:{}", user, email, self.mail.secret))
+    }
+
+    /// Check if the current email address of the given user was confirmed
+    /// (see [`Database::confirm_digest_email`])
+    ///
+    /// Digests are only sent to confirmed addresses.
+    ///
+    /// # Arguments
+    /// * `user`
+    pub async fn is_digest_email_confirmed(&self, user: &Profile) -> bool {
+        if user.metadata.email.is_empty() {
+            return false;
+        }
+
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "SELECT * FROM \"xdigest_emails\" WHERE \"user\" = ? AND \"email\" = ?"
+        } else {
+            "SELECT * FROM \"xdigest_emails\" WHERE \"user\" = $1 AND \"email\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        sqlquery(query)
+            .bind::<&str>(&user.id)
+            .bind::<&str>(&user.metadata.email)
+            .fetch_one(c)
+            .await
+            .is_ok()
+    }
+
+    /// Confirm the current email address of the given user using a token from
+    /// [`Database::digest_confirm_token`]
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `token`
+    pub async fn confirm_digest_email(&self, user: &str, token: &str) -> Result<()> {
+        let profile = self.get_profile(user).await?;
+
+        if profile.metadata.email.is_empty()
+            || (token != self.digest_confirm_token(&profile.id, &profile.metadata.email))
+        {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        // remove previously confirmed address
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "DELETE FROM \"xdigest_emails\" WHERE \"user\" = ?"
+        } else {
+            "DELETE FROM \"xdigest_emails\" WHERE \"user\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        if sqlquery(query)
+            .bind::<&str>(&profile.id)
+            .execute(c)
+            .await
+            .is_err()
+        {
+            return Err(DatabaseError::Other);
+        }
+
+        // confirm
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql") {
+            "INSERT INTO \"xdigest_emails\" VALUES (?, ?)"
+        } else {
+            "INSERT INTO \"xdigest_emails\" VALUES ($1, $2)"
+        };
+
+        match sqlquery(query)
+            .bind::<&str>(&profile.id)
+            .bind::<&str>(&profile.metadata.email)
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Subscribe the given user to email digests, replacing their existing subscription
+    ///
+    /// The user must have an email address set. Digests are only sent once the address
+    /// is confirmed (see [`Database::is_digest_email_confirmed`]).
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `props` - [`DigestSubscriptionCreate`]
+    pub async fn create_digest_subscription(
+        &self,
+        user: &Profile,
+        props: DigestSubscriptionCreate,
+    ) -> Result<()> {
+        if user.metadata.email.is_empty() {
+            return Err(DatabaseError::ValueError);
+        }
+
+        // remove existing subscription
+        self.delete_digest_subscription(&user.id).await?;
+
+        // create subscription
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "INSERT INTO \"xdigests\" VALUES (?, ?, ?)"
+        } else {
+            "INSERT INTO \"xdigests\" VALUES ($1, $2, $3)"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(&user.id)
+            .bind::<&str>(&serde_json::to_string(&props.frequency).unwrap())
+            .bind::<&str>(&utility::unix_epoch_timestamp().to_string())
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Update the time the last digest of the given user was sent
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `last_sent`
+    pub async fn update_digest_subscription_last_sent(
+        &self,
+        user: &str,
+        last_sent: u128,
+    ) -> Result<()> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "UPDATE \"xdigests\" SET \"last_sent\" = ? WHERE \"user\" = ?"
+        } else {
+            "UPDATE \"xdigests\" SET (\"last_sent\") = ($1) WHERE \"user\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(&last_sent.to_string())
+            .bind::<&str>(user)
+            .execute(c)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Unsubscribe the given user from email digests
+    ///
+    /// # Arguments
+    /// * `user`
+    pub async fn delete_digest_subscription(&self, user: &str) -> Result<()> {
+        let query: &str = if (self.base.db.r#type == "sqlite") | (self.base.db.r#type == "mysql")
+        {
+            "DELETE FROM \"xdigests\" WHERE \"user\" = ?"
+        } else {
+            "DELETE FROM \"xdigests\" WHERE \"user\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&str>(user).execute(c).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DatabaseError::Other),
+        }
+    }
+
+    /// Unsubscribe the given user from email digests using a token from
+    /// [`Database::digest_unsubscribe_token`]
+    ///
+    /// # Arguments
+    /// * `user`
+    /// * `token`
+    pub async fn unsubscribe_digest(&self, user: &str, token: &str) -> Result<()> {
+        if token != self.digest_unsubscribe_token(user) {
+            return Err(DatabaseError::NotAllowed);
+        }
+
+        self.delete_digest_subscription(user).await
+    }
+
+    // mail
+
+    /// Send the given [`Mail`] through the configured transport
+    ///
+    /// # Arguments
+    /// * `mail`
+    pub async fn send_mail(&self, mail: Mail) -> Result<()> {
+        let transport = match self.mailer {
+            Some(ref t) => t.clone(),
+            None => return Err(DatabaseError::NotAllowed),
+        };
+
+        let from = self.mail.from.clone();
+        match tokio::task::spawn_blocking(move || transport.send(&from, &mail)).await {
+            Ok(Ok(_)) => Ok(()),
+            _ => Err(DatabaseError::Other),
+        }
+    }
 }
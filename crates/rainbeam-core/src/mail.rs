@@ -0,0 +1,194 @@
+//! Outgoing mail
+use std::io::{Error, ErrorKind, Result, Write};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use pathbufd::PathBufD;
+use rainbeam_shared::fs;
+use serde::{Deserialize, Serialize};
+
+/// A single outgoing email
+#[derive(Clone, Debug)]
+pub struct Mail {
+    /// The address the mail is sent to
+    pub to: String,
+    /// The subject line of the mail
+    pub subject: String,
+    /// The HTML body of the mail
+    pub body: String,
+    /// The URL which unsubscribes the recipient (sent as `List-Unsubscribe`)
+    pub unsubscribe: Option<String>,
+}
+
+impl Mail {
+    /// Render the full message (headers and body) with the given `from` address
+    ///
+    /// Fails if a header value contains a line break (which would add headers).
+    pub fn render(&self, from: &str) -> Result<String> {
+        let unsubscribe = self.unsubscribe.as_deref().unwrap_or_default();
+
+        for value in [from, &self.to, &self.subject, unsubscribe] {
+            if value.contains(['\r', '\n']) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "mail header contains a line break",
+                ));
+            }
+        }
+
+        let mut out = format!(
+            "From: {from}\r\nTo: {}\r\nSubject: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/html; charset=utf-8\r\n",
+            self.to, self.subject
+        );
+
+        if let Some(ref unsubscribe) = self.unsubscribe {
+            out.push_str(&format!(
+                "List-Unsubscribe: <{unsubscribe}>\r\nList-Unsubscribe-Post: List-Unsubscribe=One-Click\r\n"
+            ));
+        }
+
+        out.push_str("\r\n");
+        out.push_str(&self.body);
+        Ok(out)
+    }
+}
+
+/// Something which can deliver [`Mail`]
+pub trait MailTransport: Send + Sync {
+    /// Deliver the given mail, `from` is the configured sender address
+    fn send(&self, from: &str, mail: &Mail) -> Result<()>;
+}
+
+/// A [`MailTransport`] which writes every mail to a file in the given directory
+///
+/// Useful for testing and for instances without a mail server.
+pub struct FileTransport {
+    pub dir: PathBufD,
+}
+
+impl MailTransport for FileTransport {
+    fn send(&self, from: &str, mail: &Mail) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+
+        let name = format!(
+            "{}-{}.eml",
+            rainbeam_shared::unix_epoch_timestamp(),
+            rainbeam_shared::hash::salt()
+        );
+
+        fs::write(self.dir.join(name), mail.render(from)?)
+    }
+}
+
+/// A [`MailTransport`] which pipes every mail into a `sendmail` compatible command
+pub struct SendmailTransport {
+    pub command: String,
+}
+
+impl MailTransport for SendmailTransport {
+    fn send(&self, from: &str, mail: &Mail) -> Result<()> {
+        let message = mail.render(from)?;
+
+        let mut child = Command::new(&self.command)
+            .arg("-t")
+            .arg("-i")
+            .stdin(Stdio::piped())
+            .spawn()?;
+
+        if let Some(ref mut stdin) = child.stdin {
+            stdin.write_all(message.as_bytes())?;
+        }
+
+        let status = child.wait()?;
+
+        if !status.success() {
+            return Err(Error::other(format!(
+                "{} exited with {status}",
+                self.command
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// The [`MailTransport`] used to deliver mail
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub enum MailTransportKind {
+    /// Mail is never sent
+    #[default]
+    None,
+    /// [`FileTransport`]
+    File { dir: String },
+    /// [`SendmailTransport`]
+    Sendmail { command: String },
+}
+
+/// Mail configuration file
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct MailConfig {
+    /// The address mail is sent from
+    #[serde(default)]
+    pub from: String,
+    /// The transport used to send mail
+    #[serde(default)]
+    pub transport: MailTransportKind,
+    /// The secret used to sign unsubscribe links
+    #[serde(default)]
+    pub secret: String,
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        Self {
+            from: String::new(),
+            transport: MailTransportKind::default(),
+            secret: rainbeam_shared::hash::random_id(),
+        }
+    }
+}
+
+impl MailConfig {
+    /// Read configuration file into [`MailConfig`]
+    pub fn read(contents: String) -> Self {
+        toml::from_str::<Self>(&contents).unwrap()
+    }
+
+    /// Pull configuration file
+    pub fn get_config() -> Self {
+        let path = PathBufD::current().extend(&[".config", "mail", "config.toml"]);
+
+        match fs::read(path) {
+            Ok(c) => MailConfig::read(c),
+            Err(_) => {
+                let config = Self::default();
+                Self::update_config(config.clone()).expect("failed to write default mail config");
+                config
+            }
+        }
+    }
+
+    /// Update configuration file
+    pub fn update_config(contents: Self) -> Result<()> {
+        let dir = PathBufD::current().extend(&[".config", "mail"]);
+        fs::mkdir(&dir)?;
+
+        fs::write(
+            dir.join("config.toml"),
+            toml::to_string_pretty::<Self>(&contents).unwrap(),
+        )
+    }
+
+    /// Build the configured [`MailTransport`], `None` if mail is disabled
+    pub fn transport(&self) -> Option<Arc<dyn MailTransport>> {
+        match self.transport {
+            MailTransportKind::None => None,
+            MailTransportKind::File { ref dir } => Some(Arc::new(FileTransport {
+                dir: PathBufD::new().join(dir),
+            })),
+            MailTransportKind::Sendmail { ref command } => Some(Arc::new(SendmailTransport {
+                command: command.to_owned(),
+            })),
+        }
+    }
+}
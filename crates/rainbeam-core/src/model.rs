@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use axum::{
     http::StatusCode,
@@ -53,6 +53,11 @@ impl Question {
 
         return self.id.clone();
     }
+
+    /// If the question belongs in the filtered section of the recipient's inbox
+    pub fn is_filtered(&self) -> bool {
+        self.context.filtered | self.author.is_quarantined()
+    }
 }
 
 impl CtxAsset for Question {
@@ -142,6 +147,12 @@ pub struct QuestionContext {
     /// The source ID of this question (if pulled from a ref_id).
     #[serde(default)]
     pub source_id: String,
+    /// If the question was asked by a quarantined user (possibly anonymously)
+    #[serde(default)]
+    pub filtered: bool,
+    /// If the question has a [`Poll`] attached (global questions only)
+    #[serde(default)]
+    pub poll: bool,
 }
 
 impl Context for QuestionContext {}
@@ -152,6 +163,8 @@ impl Default for QuestionContext {
             media: String::new(),
             ref_id: String::new(),
             source_id: String::new(),
+            filtered: false,
+            poll: false,
         }
     }
 }
@@ -259,10 +272,26 @@ impl CtxAsset for FullResponse {
     }
 }
 
+/// The users who are allowed to view an asset (relative to its author)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub enum Audience {
+    /// Anybody can view the asset
+    #[default]
+    Public,
+    /// Only users following the author (and their friends) can view the asset
+    Followers,
+    /// Only friends of the author ([`RelationshipStatus::Friends`]) can view the asset
+    Friends,
+    /// Only the users with the given IDs can view the asset
+    Custom(Vec<String>),
+}
+
 /// Basic information which changes the way the response is deserialized
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ResponseContext {
     /// If the response is unlisted (not shown on PUBLIC timelines/searches)
+    ///
+    /// Responses with an [`Audience`] other than [`Audience::Public`] are always unlisted.
     #[serde(default)]
     pub unlisted: bool,
     /// The warning shown on the response. Users must accept this warning to view the response
@@ -270,6 +299,18 @@ pub struct ResponseContext {
     /// Empty means no warning.
     #[serde(default)]
     pub warning: String,
+    /// The users who can view the response
+    #[serde(default)]
+    pub audience: Audience,
+    /// The total amount of coins the author has been tipped for the response
+    #[serde(default)]
+    pub tips: i32,
+    /// If the response has a [`Poll`] attached (posts only)
+    #[serde(default)]
+    pub poll: bool,
+    /// The images attached to the response as `(id, alt text)` (see [`Attachment`])
+    #[serde(default)]
+    pub attachments: Vec<(String, String)>,
 }
 
 impl Context for ResponseContext {}
@@ -279,6 +320,10 @@ impl Default for ResponseContext {
         Self {
             unlisted: false,
             warning: String::new(),
+            audience: Audience::default(),
+            tips: 0,
+            poll: false,
+            attachments: Vec::new(),
         }
     }
 }
@@ -324,12 +369,26 @@ impl CtxAsset for ResponseComment {
 
 /// Basic information which changes the way the response is deserialized
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct CommentContext {}
+pub struct CommentContext {
+    /// The users who can view the comment
+    #[serde(default)]
+    pub audience: Audience,
+    /// The total amount of coins the author has been tipped for the comment
+    #[serde(default)]
+    pub tips: i32,
+    /// The images attached to the comment as `(id, alt text)` (see [`Attachment`])
+    #[serde(default)]
+    pub attachments: Vec<(String, String)>,
+}
 
 impl Context for CommentContext {}
 impl Default for CommentContext {
     fn default() -> Self {
-        Self {}
+        Self {
+            audience: Audience::default(),
+            tips: 0,
+            attachments: Vec::new(),
+        }
     }
 }
 
@@ -499,6 +558,325 @@ pub struct DataExportOptions {
     pub following: bool,
 }
 
+/// How often an email digest is sent
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum DigestFrequency {
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    /// The time between digests in milliseconds
+    pub fn interval(&self) -> u128 {
+        match self {
+            Self::Daily => 86_400_000,
+            Self::Weekly => 604_800_000,
+        }
+    }
+}
+
+/// A user's subscription to email digests of their unread notifications and unanswered questions
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DigestSubscription {
+    /// The ID of the subscribed user
+    pub user: String,
+    /// How often the digest is sent
+    pub frequency: DigestFrequency,
+    /// The timestamp of the last digest sent (or of the subscription if no digest was sent yet)
+    pub last_sent: u128,
+}
+
+impl DigestSubscription {
+    /// If the next digest should be sent
+    pub fn is_due(&self) -> bool {
+        rainbeam_shared::unix_epoch_timestamp() >= (self.last_sent + self.frequency.interval())
+    }
+}
+
+/// A row removed from the database, stored as it was so it can be inserted again
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RemovedRow {
+    /// The table the row was removed from
+    pub table: String,
+    /// The columns of the row
+    pub row: BTreeMap<String, String>,
+}
+
+/// Content deleted by staff, kept so the deletion can be reversed through an [`Appeal`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Removal {
+    /// The ID of the removal
+    pub id: String,
+    /// The type of the removed asset
+    pub asset_type: AssetType,
+    /// The ID of the removed asset
+    pub asset: String,
+    /// The ID of the author of the removed asset
+    pub author: String,
+    /// The ID of the staff member that removed the asset
+    pub moderator: String,
+    /// The rows removed along with the asset (a response's question, for example)
+    #[serde(skip_serializing)]
+    pub rows: Vec<RemovedRow>,
+    /// The time the asset was removed
+    pub timestamp: u128,
+}
+
+/// The moderation action an [`Appeal`] was made against
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum AppealKind {
+    /// An `authbeam::model::Warning` (target is the warning ID)
+    Warning,
+    /// An `authbeam::model::Suspension` (target is the suspended user's ID)
+    Suspension,
+    /// An `authbeam::model::IpBan` (target is the ban ID)
+    IpBan,
+    /// A [`Removal`] (target is the removal ID)
+    Removal,
+}
+
+/// The state of an [`Appeal`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum AppealStatus {
+    /// Waiting for a decision
+    Pending,
+    /// The action was reversed
+    Granted,
+    /// The action stands
+    Denied,
+}
+
+/// A request to reverse a moderation action
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Appeal {
+    /// The ID of the appeal
+    pub id: String,
+    /// The ID of the user that made the appeal; "anonymous" for appeals against
+    /// IP bans made while logged out
+    pub author: String,
+    /// The kind of action being appealed
+    pub kind: AppealKind,
+    /// The ID of the action being appealed (see [`AppealKind`])
+    pub target: String,
+    /// The appellant's statement
+    pub content: String,
+    /// The state of the appeal
+    pub status: AppealStatus,
+    /// The ID of the staff member that decided the appeal
+    pub reviewer: String,
+    /// The reason given for the decision
+    pub reason: String,
+    /// The time the appeal was made
+    pub timestamp: u128,
+    /// The time the appeal was decided (`0` while pending)
+    pub decided: u128,
+}
+
+/// The most coins a user can tip in total over 24 hours
+pub const TIP_DAILY_LIMIT: i32 = 500;
+
+/// Coins given to the author of a [`QuestionResponse`] or [`ResponseComment`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tip {
+    /// The ID of the tip
+    pub id: String,
+    /// The user who sent the tip
+    pub author: Box<Profile>,
+    /// The author of the tipped asset
+    pub recipient: Box<Profile>,
+    /// The ID of the tipped asset
+    pub asset: String,
+    /// The type of the tipped asset ([`AssetType::Response`] or [`AssetType::Comment`])
+    pub asset_type: AssetType,
+    /// The amount of coins tipped
+    pub amount: i32,
+    /// The message sent with the tip
+    pub message: String,
+    /// The ID of the `authbeam::model::Transaction` which moved the coins
+    pub transfer: String,
+    /// The time the tip was sent
+    pub timestamp: u128,
+}
+
+/// What replaced the content of a [`Revision`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum RevisionKind {
+    /// The asset was edited
+    Edit,
+    /// The asset was deleted
+    Deletion,
+}
+
+/// A previous version of the content of a [`QuestionResponse`] or [`ResponseComment`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Revision {
+    /// The ID of the revision
+    pub id: String,
+    /// The type of the asset ([`AssetType::Response`] or [`AssetType::Comment`])
+    pub asset_type: AssetType,
+    /// The ID of the asset
+    pub asset: String,
+    /// The ID of the author of the asset
+    pub author: String,
+    /// The user who edited (or deleted) the asset
+    pub editor: Box<Profile>,
+    /// The content of the asset before it was replaced
+    pub content: String,
+    /// What replaced the content
+    pub kind: RevisionKind,
+    /// The time the content was replaced
+    pub timestamp: u128,
+}
+
+/// The fewest options a [`Poll`] can have
+pub const POLL_MIN_OPTIONS: usize = 2;
+/// The most options a [`Poll`] can have
+pub const POLL_MAX_OPTIONS: usize = 10;
+/// The longest a single [`Poll`] option can be
+pub const POLL_OPTION_MAX_LENGTH: usize = 128;
+/// The shortest time a [`Poll`] can stay open for (5 minutes)
+pub const POLL_MIN_DURATION: u128 = 300_000;
+/// The longest time a [`Poll`] can stay open for (30 days)
+pub const POLL_MAX_DURATION: u128 = 2_592_000_000;
+
+/// A poll attached to a [`QuestionResponse`] post or a global [`Question`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Poll {
+    /// The ID of the asset the poll is attached to (also the ID of the poll)
+    pub asset: String,
+    /// The type of the asset ([`AssetType::Response`] or [`AssetType::Question`])
+    pub asset_type: AssetType,
+    /// The ID of the user who created the poll
+    pub author: String,
+    /// The options which can be voted for
+    pub options: Vec<String>,
+    /// If users can vote for more than one option
+    pub multiple: bool,
+    /// If the voters of each option are hidden
+    pub anonymous: bool,
+    /// The time the poll stops accepting votes
+    pub closes: u128,
+    /// If the poll has been closed (and its voters notified)
+    pub closed: bool,
+    /// The time the poll was created
+    pub timestamp: u128,
+}
+
+impl Poll {
+    /// If the poll no longer accepts votes
+    pub fn is_closed(&self) -> bool {
+        self.closed | (rainbeam_shared::unix_epoch_timestamp() >= self.closes)
+    }
+}
+
+/// A user's vote on a [`Poll`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PollVote {
+    /// The ID of the poll
+    pub poll: String,
+    /// The ID of the user who voted
+    pub user: String,
+    /// The indexes of the options the user voted for
+    pub options: Vec<usize>,
+    /// The time the vote was cast
+    pub timestamp: u128,
+}
+
+/// The current standing of a [`Poll`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PollResults {
+    /// The poll
+    pub poll: Poll,
+    /// The number of votes for each option (in the same order as [`Poll::options`])
+    pub counts: Vec<usize>,
+    /// The number of users who voted
+    pub total: usize,
+    /// The options the viewer voted for (empty if they haven't voted)
+    pub voted: Vec<usize>,
+    /// The users who voted for each option (always empty if the poll is anonymous)
+    pub voters: Vec<Vec<Box<Profile>>>,
+}
+
+/// The longest the alt text of an [`Attachment`] can be
+pub const ATTACHMENT_ALT_MAX_LENGTH: usize = 1024;
+
+/// An image uploaded to a [`QuestionResponse`] or [`ResponseComment`]
+///
+/// Images are re-encoded as AVIF and stored in `{media_dir}/attachments/{id}.avif`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Attachment {
+    /// The ID of the attachment
+    pub id: String,
+    /// The ID of the asset the image is attached to
+    pub asset: String,
+    /// The type of the asset ([`AssetType::Response`] or [`AssetType::Comment`])
+    pub asset_type: AssetType,
+    /// The ID of the user who uploaded the image
+    pub owner: String,
+    /// A description of the image for screen readers
+    pub alt: String,
+    /// The size (in bytes) of the stored image
+    pub size: u64,
+    /// The time the image was uploaded
+    pub timestamp: u128,
+}
+
+/// How much of their attachment quota a user has used
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AttachmentUsage {
+    /// The total size (in bytes) of the user's attachments
+    pub used: u64,
+    /// The total size (in bytes) the user can store (`0` for no limit)
+    pub quota: u64,
+}
+
+/// The deepest a [`CommentTree`] can be fetched in one request
+pub const COMMENT_TREE_MAX_DEPTH: usize = 8;
+/// The most replies that can be fetched for each comment in a [`CommentTree`]
+pub const COMMENT_TREE_MAX_LIMIT: usize = 50;
+
+/// How the comments at each level of a [`CommentTree`] are sorted
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub enum CommentSort {
+    /// Newest comments first
+    #[default]
+    Newest,
+    /// Oldest comments first
+    Oldest,
+    /// Comments with the most reactions first (newest first when tied)
+    Reactions,
+}
+
+/// A single comment in a [`CommentTree`], along with its replies
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommentTreeNode {
+    /// The comment (its `reply` is always `None`, the parent is the node above it)
+    pub comment: ResponseComment,
+    /// The number of replies to the comment the viewer can see
+    pub reply_count: usize,
+    /// The number of reactions to the comment
+    pub reaction_count: usize,
+    /// The replies which were loaded
+    pub replies: Vec<CommentTreeNode>,
+    /// The `offset` to load more replies to this comment from (`None` if every reply was loaded)
+    pub next: Option<usize>,
+}
+
+/// The comments on a [`QuestionResponse`], nested by what they're replying to
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommentTree {
+    /// The ID of the response the comments are on
+    pub response: String,
+    /// The ID of the comment the tree starts from (empty for the top of the response)
+    pub parent: String,
+    /// The number of comments at the top of the tree the viewer can see
+    pub total: usize,
+    /// The comments at the top of the tree
+    pub comments: Vec<CommentTreeNode>,
+    /// The `offset` to load more comments at the top of the tree from (`None` if every comment was loaded)
+    pub next: Option<usize>,
+}
+
 // ...
 
 /// Anonymous user profile
@@ -517,6 +895,8 @@ pub struct QuestionCreate {
     pub media: Vec<u8>,
     #[serde(default)]
     pub ref_id: String,
+    #[serde(default)]
+    pub poll: Option<PollCreate>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -532,7 +912,27 @@ pub struct ResponseCreate {
     #[serde(default)]
     pub unlisted: bool,
     #[serde(default)]
+    pub audience: Audience,
+    #[serde(default)]
     pub circle: String,
+    #[serde(default)]
+    pub poll: Option<PollCreate>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PollCreate {
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub multiple: bool,
+    #[serde(default)]
+    pub anonymous: bool,
+    /// How long the poll stays open for (in ms)
+    pub duration: u128,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PollVoteCreate {
+    pub options: Vec<usize>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -574,6 +974,8 @@ pub struct CommentCreate {
     pub reply: String,
     #[serde(default)]
     pub anonymous: bool,
+    #[serde(default)]
+    pub audience: Audience,
 }
 
 #[derive(Serialize, Deserialize, Debug, Hcaptcha)]
@@ -583,6 +985,64 @@ pub struct CircleCreate {
     pub token: String,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DigestSubscriptionCreate {
+    pub frequency: DigestFrequency,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AppealCreate {
+    pub kind: AppealKind,
+    #[serde(default)]
+    pub target: String,
+    pub content: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AppealDecide {
+    pub granted: bool,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TipCreate {
+    pub asset: String,
+    pub asset_type: AssetType,
+    pub amount: i32,
+    #[serde(default)]
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CommentTreeQuery {
+    #[serde(default)]
+    pub parent: String,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_comment_tree_depth")]
+    pub depth: usize,
+    #[serde(default = "default_comment_tree_limit")]
+    pub limit: usize,
+    #[serde(default)]
+    pub sort: CommentSort,
+}
+
+fn default_comment_tree_depth() -> usize {
+    3
+}
+
+fn default_comment_tree_limit() -> usize {
+    12
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AttachmentCreate {
+    pub asset: String,
+    pub asset_type: AssetType,
+    #[serde(default)]
+    pub alt: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EditCircleMetadata {
     pub metadata: CircleMetadata,
@@ -607,6 +1067,15 @@ pub enum DatabaseError {
     NotFound,
     Blocked,
     Banned,
+    UnacknowledgedWarnings,
+    Suspended,
+    AlreadyAppealed,
+    TooExpensive,
+    TipLimitReached,
+    AlreadyVoted,
+    PollClosed,
+    TooManyAttachments,
+    QuotaExceeded,
     Other,
 }
 
@@ -628,7 +1097,20 @@ impl DatabaseError {
                 String::from("Nothing with this path exists or you do not have access to it!")
             }
             Blocked => String::from("You're blocked."),
-            Banned => String::from("You're banned for suspected systems abuse or violating TOS."),
+            Banned => String::from(
+                "You're banned for suspected systems abuse or violating TOS. You can appeal this at /appeals.",
+            ),
+            UnacknowledgedWarnings => String::from(
+                "You must acknowledge your account warnings before doing this. Visit /warnings to view them.",
+            ),
+            Suspended => String::from("Your account is currently suspended."),
+            AlreadyAppealed => String::from("This action has already been appealed."),
+            TooExpensive => String::from("You cannot afford to do this."),
+            TipLimitReached => String::from("You've reached your tipping limit for today."),
+            AlreadyVoted => String::from("You've already voted on this poll."),
+            PollClosed => String::from("This poll is closed."),
+            TooManyAttachments => String::from("You can't attach any more images to this."),
+            QuotaExceeded => String::from("You've run out of space for images."),
             _ => String::from("An unspecified error has occured"),
         }
     }
@@ -688,7 +1170,13 @@ impl<T: Default> Into<DefaultReturn<T>> for DatabaseError {
 }
 
 impl From<authbeam::model::DatabaseError> for DatabaseError {
-    fn from(_: authbeam::model::DatabaseError) -> Self {
-        Self::Other
+    fn from(e: authbeam::model::DatabaseError) -> Self {
+        use authbeam::model::DatabaseError as AuthError;
+        match e {
+            AuthError::UnacknowledgedWarnings => Self::UnacknowledgedWarnings,
+            AuthError::Suspended => Self::Suspended,
+            AuthError::TooExpensive => Self::TooExpensive,
+            _ => Self::Other,
+        }
     }
 }
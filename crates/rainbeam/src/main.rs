@@ -15,10 +15,12 @@ use pathbufd::{PathBufD, pathd};
 
 pub use rb::database;
 pub use rb::config;
+pub use rb::mail;
 pub use rb::model;
 pub use rb::routing;
 
 use std::env::var;
+use std::net::SocketAddr;
 
 // mimalloc
 #[cfg(feature = "mimalloc")]
@@ -51,6 +53,8 @@ pub async fn main() {
         fs::mkdir(pathd!("{}/banners", config.media_dir)).expect("failed to create banners dir");
         fs::mkdir(pathd!("{}/carpgraph", config.media_dir))
             .expect("failed to create carpgraph dir");
+        fs::mkdir(pathd!("{}/attachments", config.media_dir))
+            .expect("failed to create attachments dir");
     }
 
     // create databases
@@ -65,7 +69,9 @@ pub async fn main() {
             host: config.host.clone(),
             snowflake_server_id: config.snowflake_server_id.clone(),
             blocked_hosts: config.blocked_hosts.clone(),
+            strike_policy: authbeam::StrikePolicy::get_config(),
         },
+        authbeam::push::PushConfig::get_config(),
     )
     .await;
     auth_database.init().await;
@@ -74,10 +80,34 @@ pub async fn main() {
         DataConf::get_config().connection,
         auth_database.clone(),
         config.clone(),
+        mail::MailConfig::get_config(),
+        config::RevisionPolicy::get_config(),
+        config::AttachmentPolicy::get_config(),
     )
     .await;
     database.init().await;
 
+    // start background tasks
+    rb::digest::spawn(database.clone());
+
+    let revisions_database = database.clone();
+    tokio::spawn(async move {
+        // remove expired edit history
+        loop {
+            revisions_database.prune_revisions().await;
+            tokio::time::sleep(std::time::Duration::from_secs(60 * 60)).await;
+        }
+    });
+
+    let polls_database = database.clone();
+    tokio::spawn(async move {
+        // close finished polls
+        loop {
+            polls_database.close_polls().await;
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+        }
+    });
+
     // create app
     let app = Router::new()
         // api
@@ -101,6 +131,12 @@ pub async fn main() {
                 "{static_dir}/manifest.json"
             ))),
         )
+        .nest_service(
+            "/sw.js",
+            get_service(tower_http::services::ServeFile::new(format!(
+                "{static_dir}/js/sw.js"
+            ))),
+        )
         .fallback_service(get(routing::pages::not_found).with_state(database.clone()))
         .layer(axum::extract::DefaultBodyLimit::max(
             var("MAX_BODY_LIMIT")
@@ -119,5 +155,10 @@ pub async fn main() {
         .unwrap();
 
     info!("🌈 Starting server at: http://localhost:{}!", config.port);
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
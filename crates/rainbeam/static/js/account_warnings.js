@@ -25,4 +25,21 @@
                     .setAttribute("disabled", "fully");
             });
     });
+
+    self.define("acknowledge", async function (_, id) {
+        fetch(`/api/v0/auth/warnings/${id}/acknowledge`, {
+            method: "POST",
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                trigger("app::toast", [
+                    res.success ? "success" : "error",
+                    res.message,
+                ]);
+
+                if (res.success) {
+                    window.location.reload();
+                }
+            });
+    });
 })();
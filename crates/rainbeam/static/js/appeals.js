@@ -0,0 +1,64 @@
+(() => {
+    const self = reg_ns("appeals");
+
+    self.define("file", function (_, e) {
+        e.preventDefault();
+        fetch("/api/v1/appeals", {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                kind: e.target.kind.value,
+                target: e.target.target.value,
+                content: e.target.content.value,
+            }),
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                if (res.success === true) {
+                    window.location.href = `/appeals/${res.payload.id}`;
+                    return;
+                }
+
+                trigger("app::shout", ["caution", res.message]);
+            });
+    });
+
+    self.define("decide", async function (_, e, id) {
+        e.preventDefault();
+        const granted = e.submitter.value === "true";
+
+        if (
+            !(await trigger("app::confirm", [
+                granted
+                    ? "Are you sure you want to grant this appeal? The appealed action will be reversed."
+                    : "Are you sure you want to deny this appeal?",
+            ]))
+        ) {
+            return;
+        }
+
+        fetch(`/api/v1/appeals/${id}/decision`, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                granted,
+                reason: e.target.reason.value,
+            }),
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                trigger("app::toast", [
+                    res.success ? "success" : "error",
+                    res.message,
+                ]);
+
+                if (res.success) {
+                    window.location.reload();
+                }
+            });
+    });
+})();
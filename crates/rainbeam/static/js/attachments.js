@@ -0,0 +1,53 @@
+(() => {
+    const self = reg_ns("attachments", ["app"]);
+
+    /// Upload each of `files` to the given response or comment, asking for alt text first
+    self.define("upload", async function ({ app }, asset_type, asset, files) {
+        for (const file of Array.from(files || [])) {
+            const alt = await app.prompt(`Describe "${file.name}" (alt text)`);
+
+            const res = await (
+                await fetch(
+                    `/api/v1/attachments?asset=${asset}&asset_type=${asset_type}&alt=${encodeURIComponent(alt || "")}`,
+                    {
+                        method: "POST",
+                        headers: {
+                            "Content-Type": file.type,
+                        },
+                        body: file,
+                    },
+                )
+            ).json();
+
+            if (res.success === false) {
+                app.toast("error", res.message);
+                return;
+            }
+        }
+    });
+
+    self.define("delete", async function ({ app }, id) {
+        if (
+            !(await app.confirm(
+                "Are you sure you want to do this? The image will be deleted permanently.",
+            ))
+        ) {
+            return;
+        }
+
+        fetch(`/api/v1/attachments/${id}`, {
+            method: "DELETE",
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                app.toast(
+                    res.success ? "success" : "error",
+                    res.success ? "Image deleted!" : res.message,
+                );
+
+                if (res.success === true) {
+                    document.getElementById(`attachment:${id}`).remove();
+                }
+            });
+    });
+})();
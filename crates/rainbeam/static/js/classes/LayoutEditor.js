@@ -118,6 +118,48 @@ const COMPONENTS = [
             component: "actions",
         },
     ],
+    [
+        "Pinned responses",
+        {
+            component: "pins",
+        },
+    ],
+    [
+        "Links",
+        {
+            component: "links",
+        },
+    ],
+    [
+        "Badges & labels",
+        {
+            component: "badges",
+        },
+    ],
+    [
+        "Statistics",
+        {
+            component: "stats",
+        },
+    ],
+    [
+        "Drawing gallery",
+        {
+            component: "drawings",
+        },
+    ],
+    [
+        "Tag cloud",
+        {
+            component: "tags",
+        },
+    ],
+    [
+        "Recent questions",
+        {
+            component: "questions",
+        },
+    ],
     [
         "CSS stylesheet",
         {
@@ -250,6 +292,15 @@ class LayoutEditor {
         })
             .then((r) => r.json())
             .then((r) => {
+                if (r.errors.length > 0) {
+                    trigger("app::toast", [
+                        "error",
+                        `${r.errors[0].path}: ${r.errors[0].message}`,
+                    ]);
+
+                    return;
+                }
+
                 this.element.innerHTML = r.block;
                 this.tree = r.tree;
 
@@ -516,6 +567,19 @@ class LayoutEditor {
                 add_option("Class name", "class");
             } else if (this.current.component === "style") {
                 add_option("Style data", "data", [], "textarea");
+            } else if (
+                ["pins", "links", "badges", "stats"].includes(
+                    this.current.component,
+                )
+            ) {
+                add_option("Class name", "class");
+            } else if (
+                ["drawings", "tags", "questions"].includes(
+                    this.current.component,
+                )
+            ) {
+                add_option("Limit (1-24)", "limit");
+                add_option("Class name", "class");
             } else {
                 options.remove();
             }
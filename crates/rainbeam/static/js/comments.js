@@ -108,4 +108,65 @@
                 );
             });
     });
+
+    self.define("collapse", function (_, button) {
+        const row = button.closest("[data-depth]");
+        const depth = parseInt(row.getAttribute("data-depth"));
+        const collapsed = row.getAttribute("data-collapsed") !== "true";
+
+        row.setAttribute("data-collapsed", collapsed);
+
+        for (const span of button.children) {
+            span.classList.toggle("hidden");
+        }
+
+        // rows are flat, so everything deeper than this row until the next
+        // row at the same depth is a reply in this thread
+        let next = row.nextElementSibling;
+
+        while (next && parseInt(next.getAttribute("data-depth")) > depth) {
+            if (collapsed) {
+                next.style.display = "none";
+            } else if (next.getAttribute("data-collapsed") !== "true") {
+                next.style.display = "";
+            } else {
+                // keep threads that were collapsed on their own collapsed
+                next.style.display = "";
+                const nested_depth = parseInt(next.getAttribute("data-depth"));
+
+                while (
+                    next.nextElementSibling &&
+                    parseInt(
+                        next.nextElementSibling.getAttribute("data-depth"),
+                    ) > nested_depth
+                ) {
+                    next = next.nextElementSibling;
+                }
+            }
+
+            next = next.nextElementSibling;
+        }
+    });
+
+    self.define(
+        "load_thread",
+        function (_, button, response, parent, offset, depth) {
+            const row = button.closest("[data-depth]");
+            const partial = document.createElement("include-partial");
+
+            partial.setAttribute("data-depth", depth);
+            partial.setAttribute("outerhtml", "true");
+            partial.setAttribute("instant", "true");
+            partial.setAttribute(
+                "uses",
+                "app::clean_date_codes,app::link_filter,app::hooks::alt,app::hooks::partial_embeds",
+            );
+            partial.setAttribute(
+                "src",
+                `/_app/components/comment_tree.html?id=${response}&parent=${parent}&offset=${offset}&depth=${depth}`,
+            );
+
+            row.replaceWith(partial);
+        },
+    );
 })();
@@ -3,7 +3,7 @@
 
     self.define(
         "create",
-        function ({ $, app }, name, description, content, cost, type) {
+        function ({ $, app }, name, description, content, cost, type, tags) {
             return new Promise((resolve, reject) => {
                 fetch("/api/v0/auth/items", {
                     method: "POST",
@@ -16,6 +16,7 @@
                         content,
                         cost,
                         type,
+                        tags: tags || [],
                     }),
                 })
                     .then((res) => res.json())
@@ -168,7 +169,7 @@
 
     self.define(
         "edit",
-        async function ({ $, app }, id, name, description, cost) {
+        async function ({ $, app }, id, name, description, cost, tags) {
             fetch(`/api/v0/auth/item/${id}`, {
                 method: "POST",
                 headers: {
@@ -178,6 +179,7 @@
                     name,
                     description,
                     cost,
+                    tags,
                 }),
             })
                 .then((res) => res.json())
@@ -193,6 +195,12 @@
     self.define("edit_content", async function ({ $, app }, id, type) {
         const content = await $.content_input(type);
 
+        if (!content) {
+            return;
+        }
+
+        const changelog = await app.prompt_long("What changed in this version?");
+
         fetch(`/api/v0/auth/item/${id}/content`, {
             method: "POST",
             headers: {
@@ -200,14 +208,99 @@
             },
             body: JSON.stringify({
                 content,
+                changelog: changelog || "",
             }),
         })
             .then((res) => res.json())
             .then((res) => {
                 app.toast(
                     res.success ? "success" : "error",
-                    res.success ? "Item updated!" : res.message,
+                    res.success ? "Version submitted!" : res.message,
+                );
+            });
+    });
+
+    self.define("version_status", async function ({ $, app }, id, status) {
+        fetch(`/api/v0/auth/item/version/${id}/status`, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                status,
+            }),
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                app.toast(
+                    res.success ? "success" : "error",
+                    res.success ? "Version updated!" : res.message,
+                );
+            });
+    });
+
+    self.define("pin", async function ({ $, app }, id, version) {
+        fetch(`/api/v0/auth/item/${id}/pin`, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                version,
+            }),
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                app.toast(
+                    res.success ? "success" : "error",
+                    res.success ? "Version pinned!" : res.message,
+                );
+
+                if (res.success) {
+                    window.location.reload();
+                }
+            });
+    });
+
+    self.define("apply", async function ({ $, app }, id) {
+        if (
+            !(await app.confirm(
+                "Are you sure you want to apply this item to your profile?",
+            ))
+        ) {
+            return;
+        }
+
+        fetch(`/api/v0/auth/item/${id}/apply`, {
+            method: "POST",
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                app.toast(
+                    res.success ? "success" : "error",
+                    res.success ? "Item applied!" : res.message,
+                );
+
+                if (res.success) {
+                    window.location.reload();
+                }
+            });
+    });
+
+    self.define("revert", async function ({ $, app }, id) {
+        fetch(`/api/v0/auth/item/${id}/revert`, {
+            method: "POST",
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                app.toast(
+                    res.success ? "success" : "error",
+                    res.success ? "Item reverted!" : res.message,
                 );
+
+                if (res.success) {
+                    window.location.reload();
+                }
             });
     });
 
@@ -241,4 +334,78 @@
                 }
             });
     });
+
+    self.define("review", async function ({ $, app }, id, rating, content) {
+        fetch(`/api/v0/auth/item/${id}/reviews`, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                rating,
+                content,
+            }),
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                app.toast(
+                    res.success ? "success" : "error",
+                    res.success ? "Review posted!" : res.message,
+                );
+
+                if (res.success) {
+                    window.location.reload();
+                }
+            });
+    });
+
+    self.define("reply_review", async function ({ $, app }, id) {
+        const content = await app.prompt_long("Reply to this review:");
+
+        if (!content) {
+            return;
+        }
+
+        fetch(`/api/v0/auth/review/${id}/reply`, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                content,
+            }),
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                app.toast(
+                    res.success ? "success" : "error",
+                    res.success ? "Reply saved!" : res.message,
+                );
+            });
+    });
+
+    self.define("delete_review", async function ({ $, app }, id) {
+        if (
+            !(await trigger("app::confirm", [
+                "Are you sure you want to do this?",
+            ]))
+        ) {
+            return;
+        }
+
+        fetch(`/api/v0/auth/review/${id}`, {
+            method: "DELETE",
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                app.toast(
+                    res.success ? "success" : "error",
+                    res.success ? "Review deleted!" : res.message,
+                );
+
+                if (res.success) {
+                    document.getElementById(`review:${id}`).remove();
+                }
+            });
+    });
 })();
@@ -46,9 +46,152 @@
             });
     });
 
-    self.define("onopen", function ({ $ }, id) {
+    self.define("read", async function ({ $, app }, id, quiet) {
+        fetch(`/api/v0/auth/notifications/${id}/read`, {
+            method: "POST",
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                if (quiet && res.success) {
+                    return;
+                }
+
+                trigger("app::toast", [
+                    res.success ? "success" : "error",
+                    res.success ? "Notification marked as read!" : res.message,
+                ]);
+
+                if (res.success && !quiet) {
+                    window.location.reload();
+                }
+            });
+    });
+
+    self.define("unread", async function ({ $, app }, id) {
+        fetch(`/api/v0/auth/notifications/${id}/read`, {
+            method: "DELETE",
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                trigger("app::toast", [
+                    res.success ? "success" : "error",
+                    res.success ? "Notification marked as unread!" : res.message,
+                ]);
+
+                if (res.success) {
+                    window.location.reload();
+                }
+            });
+    });
+
+    self.define("read_all", async function (_) {
+        fetch("/api/v0/auth/notifications/read", {
+            method: "POST",
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                trigger("app::toast", [
+                    res.success ? "success" : "error",
+                    res.success ? "Notifications marked as read!" : res.message,
+                ]);
+
+                if (res.success) {
+                    window.location.reload();
+                }
+            });
+    });
+
+    self.define("push_subscription", async function (_) {
+        if (!("serviceWorker" in navigator) || !("PushManager" in window)) {
+            return null;
+        }
+
+        const registration = await navigator.serviceWorker.register("/sw.js");
+        return await registration.pushManager.getSubscription();
+    });
+
+    self.define("push_subscribe", async function ({ $ }) {
+        if (!("serviceWorker" in navigator) || !("PushManager" in window)) {
+            trigger("app::toast", [
+                "error",
+                "Push notifications are not supported by this browser.",
+            ]);
+
+            return;
+        }
+
+        // get server key
+        const key = await (await fetch("/api/v0/auth/push/key")).json();
+
+        if (!key.success) {
+            trigger("app::toast", ["error", key.message]);
+            return;
+        }
+
+        // subscribe
+        if ((await Notification.requestPermission()) !== "granted") {
+            trigger("app::toast", ["error", "Permission denied."]);
+            return;
+        }
+
+        const registration = await navigator.serviceWorker.register("/sw.js");
+        await navigator.serviceWorker.ready;
+
+        const subscription =
+            (await $.push_subscription()) ||
+            (await registration.pushManager.subscribe({
+                userVisibleOnly: true,
+                applicationServerKey: key.payload,
+            }));
+
+        const res = await (
+            await fetch("/api/v0/auth/push/subscriptions", {
+                method: "POST",
+                headers: {
+                    "Content-Type": "application/json",
+                },
+                body: JSON.stringify(subscription.toJSON()),
+            })
+        ).json();
+
+        trigger("app::toast", [
+            res.success ? "success" : "error",
+            res.message,
+        ]);
+    });
+
+    self.define("push_unsubscribe", async function ({ $ }) {
+        const subscription = await $.push_subscription();
+
+        if (!subscription) {
+            return;
+        }
+
+        const res = await (
+            await fetch("/api/v0/auth/push/subscriptions", {
+                method: "DELETE",
+                headers: {
+                    "Content-Type": "application/json",
+                },
+                body: JSON.stringify({
+                    endpoint: subscription.endpoint,
+                }),
+            })
+        ).json();
+
+        await subscription.unsubscribe();
+
+        trigger("app::toast", [
+            res.success ? "success" : "error",
+            res.message,
+        ]);
+    });
+
+    self.define("onopen", function ({ $ }, id, read) {
         if (window.localStorage.getItem("clear_notifs") === "true") {
             $.delete(id, true);
+        } else if (!read) {
+            $.read(id, true);
         }
     });
 })();
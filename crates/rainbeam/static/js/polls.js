@@ -0,0 +1,71 @@
+(() => {
+    const self = reg_ns("polls", ["app"]);
+
+    self.define("vote", function ({ app }, e, id) {
+        e.preventDefault();
+        const options = Array.from(e.target.querySelectorAll("input:checked")).map(
+            (input) => Number.parseInt(input.value),
+        );
+
+        if (options.length === 0) {
+            return;
+        }
+
+        fetch(`/api/v1/polls/${id}/vote`, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                options,
+            }),
+        })
+            .then((res) => res.json())
+            .then(async (res) => {
+                trigger("app::toast", [
+                    res.success ? "success" : "error",
+                    res.message,
+                ]);
+
+                if (res.success === false) {
+                    return;
+                }
+
+                // show results
+                const poll = document.getElementById(`poll:${id}`);
+
+                if (poll) {
+                    poll.outerHTML = await (
+                        await fetch(`/_app/components/poll.html?id=${id}`)
+                    ).text();
+
+                    app.clean_date_codes();
+                }
+            });
+    });
+
+    /// Read the poll fields of a form into a `PollCreate`
+    ///
+    /// Returns `null` if no options were given.
+    self.define("from_form", function (_, form) {
+        if (!form.poll_options) {
+            return null;
+        }
+
+        const options = form.poll_options.value
+            .split("\n")
+            .map((o) => o.trim())
+            .filter((o) => o !== "");
+
+        if (options.length === 0) {
+            return null;
+        }
+
+        return {
+            options,
+            multiple: form.poll_multiple.checked,
+            anonymous: form.poll_anonymous.checked,
+            duration: Number.parseInt(form.poll_duration.value),
+        };
+    });
+})();
@@ -12,6 +12,7 @@
             reply = "",
             unlisted = false,
             circle = "",
+            audience = "Public",
         ) {
             await app.debounce("responses::create");
             if (!tags) {
@@ -35,6 +36,7 @@
                         reply: reply || "",
                         unlisted: unlisted || false,
                         circle: circle || "",
+                        audience: audience || "Public",
                     }),
                 })
                     .then((res) => res.json())
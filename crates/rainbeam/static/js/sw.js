@@ -0,0 +1,41 @@
+// Service worker, shows push notifications
+self.addEventListener("push", (event) => {
+    if (!event.data) {
+        return;
+    }
+
+    const message = event.data.json();
+
+    event.waitUntil(
+        self.registration.showNotification(message.title, {
+            body: message.body,
+            icon: "/static/images/logo/logo_192x192.png",
+            data: {
+                url: message.url,
+            },
+        }),
+    );
+});
+
+self.addEventListener("notificationclick", (event) => {
+    event.notification.close();
+
+    const url = new URL(
+        event.notification.data.url || "/inbox/notifications",
+        self.location.origin,
+    ).href;
+
+    event.waitUntil(
+        self.clients
+            .matchAll({ type: "window", includeUncontrolled: true })
+            .then((clients) => {
+                for (const client of clients) {
+                    if (client.url === url && "focus" in client) {
+                        return client.focus();
+                    }
+                }
+
+                return self.clients.openWindow(url);
+            }),
+    );
+});
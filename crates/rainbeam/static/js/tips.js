@@ -0,0 +1,52 @@
+(() => {
+    const self = reg_ns("tips");
+
+    self.define("tip", async function ({ app }, type, id, target) {
+        const amount = Number.parseInt(
+            await app.prompt("How many coins would you like to tip?"),
+        );
+
+        if (!amount || amount < 1) {
+            return;
+        }
+
+        const message = await app.prompt_long(
+            "Add a message to your tip (optional)",
+        );
+
+        fetch("/api/v1/tips", {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                asset: id,
+                asset_type: type,
+                amount,
+                message: message || "",
+            }),
+        })
+            .then((res) => res.json())
+            .then((res) => {
+                trigger("app::toast", [
+                    res.success ? "success" : "error",
+                    res.success ? `Tipped ${amount} coins!` : res.message,
+                ]);
+
+                if (!res.success || !target) {
+                    return;
+                }
+
+                const count = target.querySelector(".notification");
+
+                if (count) {
+                    count.innerText = Number.parseInt(count.innerText) + amount;
+                } else {
+                    const new_count = document.createElement("span");
+                    new_count.className = "notification camo";
+                    new_count.innerText = amount;
+                    target.appendChild(new_count);
+                }
+            });
+    });
+})();
@@ -0,0 +1,157 @@
+//! Email digests of unread notifications and unanswered questions
+use std::time::Duration;
+
+use authbeam::model::{Notification, Profile};
+use reva_axum::Template;
+
+use crate::config::Config;
+use crate::database::Database;
+use crate::mail::Mail;
+use crate::model::{DatabaseError, Question};
+
+/// How often due digests are checked for
+const DIGEST_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Template)]
+#[template(path = "mail/digest.html")]
+struct DigestTemplate {
+    config: Config,
+    lang: langbeam::LangFile,
+    profile: Box<Profile>,
+    notifications: Vec<Notification>,
+    questions: Vec<Question>,
+    unsubscribe: String,
+}
+
+#[derive(Template)]
+#[template(path = "mail/confirm_email.html")]
+struct ConfirmEmailTemplate {
+    config: Config,
+    lang: langbeam::LangFile,
+    profile: Box<Profile>,
+    confirm: String,
+}
+
+/// Send the link which confirms the current email address of the given user
+/// (see [`Database::confirm_digest_email`])
+pub async fn send_confirmation(
+    database: &Database,
+    profile: &Profile,
+) -> Result<(), DatabaseError> {
+    if profile.metadata.email.is_empty() {
+        return Err(DatabaseError::ValueError);
+    }
+
+    let confirm = format!(
+        "{}/digest/confirm/{}/{}",
+        database.config.host,
+        profile.id,
+        database.digest_confirm_token(&profile.id, &profile.metadata.email)
+    );
+
+    let lang = database.lang("");
+    let subject = format!(
+        "{} - {}",
+        lang.get("general:text.confirm_email_subject"),
+        database.config.name
+    );
+
+    let body = ConfirmEmailTemplate {
+        config: database.config.clone(),
+        lang,
+        profile: Box::new(profile.clone()),
+        confirm,
+    }
+    .render()
+    .unwrap();
+
+    database
+        .send_mail(Mail {
+            to: profile.metadata.email.clone(),
+            subject,
+            body,
+            unsubscribe: None,
+        })
+        .await
+}
+
+/// Send every digest which is currently due
+pub async fn send_digests(database: &Database) {
+    let subscriptions = match database.get_due_digest_subscriptions().await {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    for subscription in subscriptions {
+        let profile = match database.auth.get_profile(&subscription.user).await {
+            Ok(ua) => ua,
+            Err(_) => {
+                // profile was deleted
+                let _ = database
+                    .delete_digest_subscription(&subscription.user)
+                    .await;
+
+                continue;
+            }
+        };
+
+        let timestamp = rainbeam_shared::unix_epoch_timestamp();
+        let (notifications, questions) = database.get_digest(&profile).await;
+
+        if database.is_digest_email_confirmed(&profile).await
+            && (!notifications.is_empty() || !questions.is_empty())
+        {
+            let unsubscribe = format!(
+                "{}/unsubscribe/{}/{}",
+                database.config.host,
+                profile.id,
+                database.digest_unsubscribe_token(&profile.id)
+            );
+
+            let lang = database.lang("");
+            let subject = format!(
+                "{} - {}",
+                lang.get("general:text.digest_subject"),
+                database.config.name
+            );
+
+            let body = DigestTemplate {
+                config: database.config.clone(),
+                lang,
+                profile: profile.clone(),
+                notifications,
+                questions,
+                unsubscribe: unsubscribe.clone(),
+            }
+            .render()
+            .unwrap();
+
+            if let Err(e) = database
+                .send_mail(Mail {
+                    to: profile.metadata.email.clone(),
+                    subject,
+                    body,
+                    unsubscribe: Some(unsubscribe),
+                })
+                .await
+            {
+                tracing::error!("failed to send digest to {}: {}", profile.id, e.to_string());
+                continue;
+            }
+        }
+
+        let _ = database
+            .update_digest_subscription_last_sent(&profile.id, timestamp)
+            .await;
+    }
+}
+
+/// Start the background task which sends digests
+pub fn spawn(database: Database) {
+    tokio::spawn(async move {
+        loop {
+            send_digests(&database).await;
+            tokio::time::sleep(DIGEST_CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -7,7 +7,9 @@ use reva_axum::Template;
 
 pub use rainbeam::database;
 pub use rainbeam::config;
+pub use rainbeam::mail;
 pub use rainbeam::model;
+pub mod digest;
 pub mod routing;
 
 /// Trait to convert errors into HTML
@@ -0,0 +1,78 @@
+use crate::database::Database;
+use crate::model::{AppealCreate, AppealDecide};
+use axum::http::{HeaderMap, HeaderValue};
+use authbeam::extract::{scope, Auth};
+use databeam::prelude::DefaultReturn;
+
+use axum::response::IntoResponse;
+use axum::{
+    extract::{Path, State},
+    routing::post,
+    Json, Router,
+};
+
+pub fn routes(database: Database) -> Router {
+    Router::new()
+        .route("/", post(create_request))
+        .route("/{id}/decision", post(decide_request)) // staff
+        // ...
+        .with_state(database)
+}
+
+// routes
+
+/// Appeal a moderation action
+///
+/// Suspended and IP banned users are allowed to do this.
+pub async fn create_request(
+    headers: HeaderMap,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+    Json(req): Json<AppealCreate>,
+) -> impl IntoResponse {
+    // get real ip
+    let real_ip = if let Some(ref real_ip_header) = database.config.real_ip_header {
+        headers
+            .get(real_ip_header.to_owned())
+            .unwrap_or(&HeaderValue::from_static(""))
+            .to_str()
+            .unwrap_or("")
+            .to_string()
+    } else {
+        String::new()
+    };
+
+    // create appeal
+    Json(
+        match database
+            .create_appeal(req, auth.map(|a| a.user), &real_ip)
+            .await
+        {
+            Ok(r) => DefaultReturn {
+                success: true,
+                message: r.id.clone(),
+                payload: Some(r),
+            },
+            Err(e) => e.into(),
+        },
+    )
+}
+
+/// Grant or deny an appeal
+pub async fn decide_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Moderator>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+    Json(req): Json<AppealDecide>,
+) -> impl IntoResponse {
+    Json(match database.decide_appeal(&id, req, auth_user).await {
+        Ok(r) => DefaultReturn {
+            success: true,
+            message: "Appeal decided".to_string(),
+            payload: Some(r),
+        },
+        Err(e) => e.into(),
+    })
+}
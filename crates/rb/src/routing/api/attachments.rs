@@ -0,0 +1,119 @@
+use crate::database::Database;
+use crate::model::{AttachmentCreate, DatabaseError};
+use authbeam::avif::Image;
+use authbeam::extract::{scope, Auth};
+use databeam::prelude::DefaultReturn;
+
+use axum::response::IntoResponse;
+use axum::{
+    extract::{Path, Query, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+
+pub fn routes(database: Database) -> Router {
+    Router::new()
+        .route("/", post(create_request))
+        .route("/usage", get(usage_request))
+        .route("/{id}", get(get_request))
+        .route("/{id}", delete(delete_request))
+        // ...
+        .with_state(database)
+}
+
+// routes
+
+/// [`Database::create_attachment`]
+pub async fn create_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
+    State(database): State<Database>,
+    Query(props): Query<AttachmentCreate>,
+    img: Image,
+) -> impl IntoResponse {
+    Json(
+        match database
+            .create_attachment(props, img.0.to_vec(), &auth_user)
+            .await
+        {
+            Ok(r) => DefaultReturn {
+                success: true,
+                message: r.id.clone(),
+                payload: Some(r),
+            },
+            Err(e) => e.into(),
+        },
+    )
+}
+
+/// Get the image of an attachment
+pub async fn get_request(
+    auth: Option<Auth>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    let attachment = match database.get_attachment(&id).await {
+        Ok(a) => a,
+        Err(e) => return e.into_response(),
+    };
+
+    match database
+        .can_view_attachment(&attachment, auth_user.as_deref())
+        .await
+    {
+        Ok(true) => (),
+        Ok(false) => return DatabaseError::NotAllowed.into_response(),
+        Err(e) => return e.into_response(),
+    }
+
+    match std::fs::read(database.attachment_path(&attachment.id)) {
+        Ok(bytes) => (
+            [
+                ("Content-Type", "image/avif"),
+                // attachments are never changed, only deleted
+                ("Cache-Control", "private, max-age=604800, immutable"),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Err(_) => DatabaseError::NotFound.into_response(),
+    }
+}
+
+/// [`Database::delete_attachment`]
+pub async fn delete_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    Json(match database.delete_attachment(&id, &auth_user).await {
+        Ok(_) => DefaultReturn {
+            success: true,
+            message: "Attachment deleted".to_string(),
+            payload: (),
+        },
+        Err(e) => e.into(),
+    })
+}
+
+/// [`Database::get_attachment_usage`]
+pub async fn usage_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    Json(match database.get_attachment_usage(&auth_user).await {
+        Ok(r) => DefaultReturn {
+            success: true,
+            message: String::new(),
+            payload: Some(r),
+        },
+        Err(e) => e.into(),
+    })
+}
@@ -1,8 +1,9 @@
 use crate::database::Database;
-use crate::model::{anonymous_profile, CommentCreate, DatabaseError, ResponseEdit};
+use crate::model::{anonymous_profile, AssetType, CommentCreate, DatabaseError, ResponseEdit};
 use axum::http::{HeaderMap, HeaderValue};
 use hcaptcha_no_wasm::Hcaptcha;
-use authbeam::model::{IpBlockCreate, NotificationCreate};
+use authbeam::extract::{scope, Auth};
+use authbeam::model::{IpBlockCreate, NotificationCreate, NotificationKind};
 use databeam::prelude::DefaultReturn;
 
 use axum::response::{IntoResponse, Redirect};
@@ -18,6 +19,7 @@ pub fn routes(database: Database) -> Router {
     Router::new()
         .route("/", post(create_request))
         .route("/{id}", get(get_request))
+        .route("/{id}/revisions", get(revisions_request))
         .route("/{id}", put(edit_request))
         .route("/{id}", delete(delete_request))
         .route("/{id}/report", post(report_request))
@@ -31,6 +33,7 @@ pub fn routes(database: Database) -> Router {
 /// [`Database::create_comment`]
 pub async fn create_request(
     jar: CookieJar,
+    auth: Option<Auth<scope::ManageAssets>>,
     headers: HeaderMap,
     State(database): State<Database>,
     Json(req): Json<CommentCreate>,
@@ -38,18 +41,11 @@ pub async fn create_request(
     // get user from token
     let mut was_not_anonymous = false;
 
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => {
-                was_not_anonymous = true;
-                ua
-            }
-            Err(_) => anonymous_profile(database.create_anonymous().0),
-        },
+    let auth_user = match auth {
+        Some(a) => {
+            was_not_anonymous = true;
+            a.user
+        }
         None => anonymous_profile(database.create_anonymous().0),
     };
 
@@ -157,28 +153,87 @@ pub async fn create_request(
 
 /// [`Database::get_comment`]
 pub async fn get_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    Json(match database.get_comment(id, true).await {
-        Ok(mut r) => DefaultReturn {
-            success: true,
-            message: String::new(),
-            payload: {
-                r.0.author.clean();
-                Some(r)
-            },
-        },
-        Err(e) => e.into(),
+    let auth_user = auth.map(|a| a.user);
+
+    let mut r = match database.get_comment(id, true, auth_user.as_deref()).await {
+        Ok(r) => r,
+        Err(e) => return Json(e.into()),
+    };
+
+    // the comment is hidden until the profile of its response's author is unlocked
+    if !database
+        .is_unlocked_comment(&r.0, auth_user.as_deref(), &jar)
+        .await
+    {
+        return Json(DatabaseError::NotAllowed.into());
+    }
+
+    r.0.author.clean();
+
+    Json(DefaultReturn {
+        success: true,
+        message: String::new(),
+        payload: Some(r),
     })
 }
 
+/// [`Database::get_asset_revisions`]
+pub async fn revisions_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    if let Ok(c) = database
+        .get_comment(id.clone(), false, auth_user.as_deref())
+        .await
+    {
+        if !database
+            .is_unlocked_comment(&c.0, auth_user.as_deref(), &jar)
+            .await
+        {
+            return Json(DatabaseError::NotAllowed.into());
+        }
+    }
+
+    Json(
+        match database
+            .get_asset_revisions(AssetType::Comment, &id, auth_user.as_deref())
+            .await
+        {
+            Ok(mut r) => {
+                // hide tokens, password, salt, and metadata
+                for revision in r.iter_mut() {
+                    revision.editor.clean();
+                }
+
+                DefaultReturn {
+                    success: true,
+                    message: String::new(),
+                    payload: Some(r),
+                }
+            }
+            Err(e) => e.into(),
+        },
+    )
+}
+
 /// Redirect to the full ID of a comment through its short ID
 pub async fn expand_request(
+    auth: Option<Auth>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    match database.get_comment(id, false).await {
+    let auth_user = auth.map(|a| a.user);
+
+    match database.get_comment(id, false, auth_user.as_deref()).await {
         Ok(c) => Redirect::to(&format!("/@{}/c/{}", c.0.author.username, c.0.id)),
         Err(_) => Redirect::to("/"),
     }
@@ -186,28 +241,13 @@ pub async fn expand_request(
 
 /// [`Database::update_comment_content`]
 pub async fn edit_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(req): Json<ResponseEdit>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(
         match database
@@ -226,27 +266,12 @@ pub async fn edit_request(
 
 /// [`Database::delete_comment`]
 pub async fn delete_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(match database.delete_comment(id, auth_user).await {
         Ok(r) => DefaultReturn {
@@ -260,6 +285,7 @@ pub async fn delete_request(
 
 /// Report a comment
 pub async fn report_request(
+    auth: Option<Auth>,
     headers: HeaderMap,
     Path(id): Path<String>,
     State(database): State<Database>,
@@ -278,7 +304,13 @@ pub async fn report_request(
     }
 
     // get comment
-    if let Err(_) = database.get_comment(id.clone(), false).await {
+    let auth_user = auth.map(|a| a.user);
+
+    if database
+        .get_comment(id.clone(), false, auth_user.as_deref())
+        .await
+        .is_err()
+    {
         return Json(DefaultReturn {
             success: false,
             message: DatabaseError::NotFound.to_string(),
@@ -316,6 +348,7 @@ pub async fn report_request(
                 content: format!("{}\n\n***\n\n[{real_ip}](/+i/{real_ip})", req.content),
                 address: format!("/comment/{id}"),
                 recipient: "*".to_string(), // all staff
+                kind: NotificationKind::System,
             },
             None,
         )
@@ -338,29 +371,17 @@ pub async fn report_request(
 
 /// IP block a comment's author
 pub async fn ipblock_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // get comment
-    let comment = match database.get_comment(id.clone(), false).await {
+    let comment = match database
+        .get_comment(id.clone(), false, Some(&auth_user))
+        .await
+    {
         Ok(q) => q.0,
         Err(e) => return Json(e.to_json()),
     };
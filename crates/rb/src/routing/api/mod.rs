@@ -1,8 +1,13 @@
+pub mod appeals;
+pub mod attachments;
 pub mod comments;
+pub mod polls;
 pub mod profiles;
 pub mod questions;
 pub mod reactions;
 pub mod responses;
+pub mod reviews;
+pub mod tips;
 pub mod util;
 
 use crate::database::Database;
@@ -25,4 +30,9 @@ pub fn routes(database: Database) -> Router {
         .nest("/comments", comments::routes(database.clone()))
         .nest("/reactions", reactions::routes(database.clone()))
         .nest("/profiles", profiles::routes(database.clone()))
+        .nest("/appeals", appeals::routes(database.clone()))
+        .nest("/tips", tips::routes(database.clone()))
+        .nest("/reviews", reviews::routes(database.clone()))
+        .nest("/polls", polls::routes(database.clone()))
+        .nest("/attachments", attachments::routes(database.clone()))
 }
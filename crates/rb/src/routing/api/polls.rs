@@ -0,0 +1,71 @@
+use crate::database::Database;
+use crate::model::PollVoteCreate;
+use authbeam::extract::{scope, Auth};
+use databeam::prelude::DefaultReturn;
+
+use axum::response::IntoResponse;
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+
+pub fn routes(database: Database) -> Router {
+    Router::new()
+        .route("/{id}", get(get_request))
+        .route("/{id}/vote", post(vote_request))
+        // ...
+        .with_state(database)
+}
+
+// routes
+
+/// [`Database::get_poll_results`]
+pub async fn get_request(
+    auth: Option<Auth>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    Json(
+        match database.get_poll_results(&id, auth_user.as_deref()).await {
+            Ok(mut r) => {
+                // hide tokens, password, salt, and metadata
+                for voters in r.voters.iter_mut() {
+                    for voter in voters.iter_mut() {
+                        voter.clean();
+                    }
+                }
+
+                DefaultReturn {
+                    success: true,
+                    message: String::new(),
+                    payload: Some(r),
+                }
+            }
+            Err(e) => e.into(),
+        },
+    )
+}
+
+/// [`Database::create_poll_vote`]
+pub async fn vote_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+    Json(req): Json<PollVoteCreate>,
+) -> impl IntoResponse {
+    Json(
+        match database.create_poll_vote(&id, req, &auth_user).await {
+            Ok(_) => DefaultReturn {
+                success: true,
+                message: "Vote cast".to_string(),
+                payload: (),
+            },
+            Err(e) => e.into(),
+        },
+    )
+}
@@ -1,19 +1,19 @@
 use crate::database::Database;
-use crate::model::{DataExportOptions, DatabaseError};
+use crate::model::{DataExportOptions, DatabaseError, DigestSubscriptionCreate};
 use crate::ToHtml;
 use axum::body::Body;
 use axum::extract::Query;
 use axum::http::{HeaderMap, HeaderValue, Response};
-use axum_extra::extract::CookieJar;
 use hcaptcha_no_wasm::Hcaptcha;
 
-use authbeam::model::{FinePermission, IpBlockCreate, NotificationCreate};
+use authbeam::extract::{scope, Auth};
+use authbeam::model::{FinePermission, IpBlockCreate, NotificationCreate, NotificationKind};
 use databeam::prelude::DefaultReturn;
 
 use axum::{
     extract::{Path, State},
     response::{IntoResponse, Redirect},
-    routing::{get, post},
+    routing::{delete, get, post},
     Json, Router,
 };
 
@@ -22,6 +22,8 @@ pub fn routes(database: Database) -> Router {
         .route("/{id}/report", post(report_request))
         .route("/{id}/export", get(export_request)) // staff
         .route("/{id}/ipblock", post(ipblock_request))
+        .route("/digest", post(digest_subscribe_request))
+        .route("/digest", delete(digest_unsubscribe_request))
         // ...
         .with_state(database)
 }
@@ -46,25 +48,13 @@ pub async fn expand_request(
 
 /// Redirect an IP to a full username
 pub async fn expand_ip_request(
-    jar: CookieJar,
+    auth: Option<Auth>,
     Path(ip): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    match jar.get("__Secure-Token") {
-        Some(c) => {
-            if let Err(_) = database
-                .auth
-                .get_profile_by_unhashed(c.value_trimmed())
-                .await
-            {
-                return Redirect::to("/");
-            }
-        }
-        None => {
-            return Redirect::to("/");
-        }
-    };
+    if auth.is_none() {
+        return Redirect::to("/");
+    }
 
     // return
     match database.auth.get_profile_by_ip(&ip).await {
@@ -128,6 +118,7 @@ pub async fn report_request(
                 content: format!("{}\n\n***\n\n[{real_ip}](/+i/{real_ip})", req.content),
                 address: format!("/@{input}"),
                 recipient: "*".to_string(), // all staff
+                kind: NotificationKind::System,
             },
             None,
         )
@@ -150,24 +141,13 @@ pub async fn report_request(
 
 /// Create a data export of the given user
 pub async fn export_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAccount>,
     Path(username): Path<String>,
     State(database): State<Database>,
     Query(props): Query<DataExportOptions>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(e) => return Json(e.to_json()),
-        },
-        None => return Json(DatabaseError::NotAllowed.to_json()),
-    };
-
     let group = match database.auth.get_group_by_id(auth_user.group).await {
         Ok(g) => g,
         Err(_) => {
@@ -214,27 +194,12 @@ pub async fn export_request(
 
 /// IP block a profile
 pub async fn ipblock_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // get profile
     let profile = match database.auth.get_profile(&id).await {
         Ok(p) => p,
@@ -264,3 +229,54 @@ pub async fn ipblock_request(
         payload: (),
     });
 }
+
+/// [`Database::create_digest_subscription`]
+pub async fn digest_subscribe_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    State(database): State<Database>,
+    Json(props): Json<DigestSubscriptionCreate>,
+) -> impl IntoResponse {
+    if let Err(e) = database.create_digest_subscription(&auth_user, props).await {
+        return Json(e.into());
+    }
+
+    // digests are only sent to confirmed addresses
+    if database.is_digest_email_confirmed(&auth_user).await {
+        return Json(DefaultReturn {
+            success: true,
+            message: "Subscribed to digest".to_string(),
+            payload: (),
+        });
+    }
+
+    Json(
+        match crate::digest::send_confirmation(&database, &auth_user).await {
+            Ok(_) => DefaultReturn {
+                success: true,
+                message: "Subscribed to digest, check your email to confirm your address"
+                    .to_string(),
+                payload: (),
+            },
+            Err(e) => e.into(),
+        },
+    )
+}
+
+/// [`Database::delete_digest_subscription`]
+pub async fn digest_unsubscribe_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    Json(match database.delete_digest_subscription(&auth_user.id).await {
+        Ok(_) => DefaultReturn {
+            success: true,
+            message: "Unsubscribed from digest".to_string(),
+            payload: (),
+        },
+        Err(e) => e.into(),
+    })
+}
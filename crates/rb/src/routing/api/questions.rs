@@ -1,7 +1,8 @@
 use crate::database::Database;
 use crate::model::{anonymous_profile, DatabaseError, QuestionCreate};
 use axum::http::{HeaderMap, HeaderValue};
-use authbeam::model::{IpBlockCreate, NotificationCreate};
+use authbeam::extract::{scope, Auth};
+use authbeam::model::{IpBlockCreate, NotificationCreate, NotificationKind};
 use carp::CarpGraph;
 use databeam::prelude::DefaultReturn;
 
@@ -36,6 +37,7 @@ pub fn routes(database: Database) -> Router {
 /// [`Database::create_question`]
 pub async fn create_request(
     jar: CookieJar,
+    auth: Option<Auth<scope::ManageAssets>>,
     headers: HeaderMap,
     State(database): State<Database>,
     Json(req): Json<QuestionCreate>,
@@ -43,18 +45,11 @@ pub async fn create_request(
     // get user from token
     let mut was_not_anonymous = false;
 
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => {
-                was_not_anonymous = true;
-                ua
-            }
-            Err(_) => anonymous_profile(database.create_anonymous().0),
-        },
+    let auth_user = match auth {
+        Some(a) => {
+            was_not_anonymous = true;
+            a.user
+        }
         None => anonymous_profile(database.create_anonymous().0),
     };
 
@@ -162,29 +157,38 @@ pub async fn create_request(
 
 /// [`Database::get_question`]
 pub async fn get_request(
+    auth: Option<Auth>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
     Json(match database.get_question(id).await {
-        Ok(mut r) => DefaultReturn {
-            success: true,
-            message: String::new(),
-            payload: {
-                r.ip = String::new();
+        Ok(mut r) => {
+            if !database.can_view_question(&r, auth_user.as_deref()).await {
+                return Json(DatabaseError::NotAllowed.into());
+            }
 
-                // hide anonymous author id
-                if r.author.id.starts_with("anonymous#") {
-                    r.author.id = "anonymous".to_string()
-                }
+            DefaultReturn {
+                success: true,
+                message: String::new(),
+                payload: {
+                    r.ip = String::new();
 
-                // hide tokens, password, salt, and metadata
-                r.author.clean();
-                r.recipient.clean();
+                    // hide anonymous author id
+                    if r.author.id.starts_with("anonymous#") {
+                        r.author.id = "anonymous".to_string()
+                    }
 
-                // return
-                Some(r)
-            },
-        },
+                    // hide tokens, password, salt, and metadata
+                    r.author.clean();
+                    r.recipient.clean();
+
+                    // return
+                    Some(r)
+                },
+            }
+        }
         Err(e) => e.into(),
     })
 }
@@ -202,27 +206,12 @@ pub async fn expand_request(
 
 /// [`Database::delete_question`]
 pub async fn delete_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(match database.delete_question(id, auth_user).await {
         Ok(r) => DefaultReturn {
@@ -236,27 +225,12 @@ pub async fn delete_request(
 
 /// [`Database::delete_questions_by_recipient`]
 pub async fn delete_inbox_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(
         match database.delete_questions_by_recipient(&id, auth_user).await {
@@ -272,26 +246,11 @@ pub async fn delete_inbox_request(
 
 /// [`Database::delete_questions_by_recipient`]
 pub async fn delete_my_inbox_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(
         match database
@@ -366,6 +325,7 @@ pub async fn report_request(
                 content: format!("{}\n\n***\n\n[{real_ip}](/+i/{real_ip})", req.content),
                 address: format!("/question/{id}"),
                 recipient: "*".to_string(), // all staff
+                kind: NotificationKind::System,
             },
             None,
         )
@@ -388,27 +348,12 @@ pub async fn report_request(
 
 /// IP block a question's author
 pub async fn ipblock_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageProfile>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // get question
     let question = match database.get_question(id.clone()).await {
         Ok(q) => q,
@@ -1,6 +1,7 @@
 use crate::database::Database;
 use crate::model::{AssetType, DatabaseError, ReactionCreate};
-use authbeam::model::{NotificationCreate, RelationshipStatus};
+use authbeam::extract::{scope, Auth};
+use authbeam::model::{NotificationCreate, NotificationKind, RelationshipStatus};
 use databeam::prelude::DefaultReturn;
 
 use axum::response::IntoResponse;
@@ -10,8 +11,6 @@ use axum::{
     Json, Router,
 };
 
-use axum_extra::extract::cookie::CookieJar;
-
 pub fn routes(database: Database) -> Router {
     Router::new()
         .route("/{id}", post(create_request))
@@ -25,24 +24,13 @@ pub fn routes(database: Database) -> Router {
 
 /// [`Database::create_reaction`]
 pub async fn create_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(props): Json<ReactionCreate>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Json(DatabaseError::NotAllowed.into()),
-        },
-        None => return Json(DatabaseError::NotAllowed.into()),
-    };
-
     // verify asset from type
     match props.r#type {
         AssetType::Question => {
@@ -57,22 +45,28 @@ pub async fn create_request(
                 }
             };
 
-            // create notification
-            if let Err(_) = database
+            // create notification (unless the author has muted the user)
+            if !database
                 .auth
-                .create_notification(
-                    NotificationCreate {
-                        title: format!(
-                            "[@{}](/+u/{}) has reacted to a question you created!",
-                            auth_user.username, auth_user.id
-                        ),
-                        content: String::new(),
-                        address: format!("/question/{id}"),
-                        recipient: asset.author.id,
-                    },
-                    None,
-                )
+                .is_muted(&asset.author.id, &auth_user.id)
                 .await
+                && database
+                    .auth
+                    .create_notification(
+                        NotificationCreate {
+                            title: format!(
+                                "[@{}](/+u/{}) has reacted to a question you created!",
+                                auth_user.username, auth_user.id
+                            ),
+                            content: String::new(),
+                            address: format!("/question/{id}"),
+                            recipient: asset.author.id,
+                            kind: NotificationKind::Reaction,
+                        },
+                        None,
+                    )
+                    .await
+                    .is_err()
             {
                 return Json(DefaultReturn {
                     success: false,
@@ -82,7 +76,7 @@ pub async fn create_request(
             }
         }
         AssetType::Response => {
-            let asset = match database.get_response(id.clone()).await {
+            let asset = match database.get_response(id.clone(), Some(&auth_user)).await {
                 Ok(r) => r.1,
                 Err(e) => {
                     return Json(DefaultReturn {
@@ -108,22 +102,28 @@ pub async fn create_request(
                 });
             }
 
-            // create notification
-            if let Err(_) = database
+            // create notification (unless the author has muted the user)
+            if !database
                 .auth
-                .create_notification(
-                    NotificationCreate {
-                        title: format!(
-                            "[@{}](/+u/{}) has reacted to a response you created!",
-                            auth_user.username, auth_user.id
-                        ),
-                        content: String::new(),
-                        address: format!("/response/{id}"),
-                        recipient: asset.author.id,
-                    },
-                    None,
-                )
+                .is_muted(&asset.author.id, &auth_user.id)
                 .await
+                && database
+                    .auth
+                    .create_notification(
+                        NotificationCreate {
+                            title: format!(
+                                "[@{}](/+u/{}) has reacted to a response you created!",
+                                auth_user.username, auth_user.id
+                            ),
+                            content: String::new(),
+                            address: format!("/response/{id}"),
+                            recipient: asset.author.id,
+                            kind: NotificationKind::Reaction,
+                        },
+                        None,
+                    )
+                    .await
+                    .is_err()
             {
                 return Json(DefaultReturn {
                     success: false,
@@ -133,7 +133,10 @@ pub async fn create_request(
             }
         }
         AssetType::Comment => {
-            let asset = match database.get_comment(id.clone(), false).await {
+            let asset = match database
+                .get_comment(id.clone(), false, Some(&auth_user))
+                .await
+            {
                 Ok(r) => r.0,
                 Err(e) => {
                     return Json(DefaultReturn {
@@ -159,22 +162,28 @@ pub async fn create_request(
                 });
             }
 
-            // create notification
-            if let Err(_) = database
+            // create notification (unless the author has muted the user)
+            if !database
                 .auth
-                .create_notification(
-                    NotificationCreate {
-                        title: format!(
-                            "[@{}](/+u/{}) has reacted to a comment you created!",
-                            auth_user.username, auth_user.id
-                        ),
-                        content: String::new(),
-                        address: format!("/comment/{id}"),
-                        recipient: asset.author.id,
-                    },
-                    None,
-                )
+                .is_muted(&asset.author.id, &auth_user.id)
                 .await
+                && database
+                    .auth
+                    .create_notification(
+                        NotificationCreate {
+                            title: format!(
+                                "[@{}](/+u/{}) has reacted to a comment you created!",
+                                auth_user.username, auth_user.id
+                            ),
+                            content: String::new(),
+                            address: format!("/comment/{id}"),
+                            recipient: asset.author.id,
+                            kind: NotificationKind::Reaction,
+                        },
+                        None,
+                    )
+                    .await
+                    .is_err()
             {
                 return Json(DefaultReturn {
                     success: false,
@@ -210,22 +219,25 @@ pub async fn create_request(
                 });
             }
 
-            // create notification
-            if let Err(_) = database
-                .auth
-                .create_notification(
-                    NotificationCreate {
-                        title: format!(
-                            "[@{}](/+u/{}) has reacted to an item you created!",
-                            auth_user.username, auth_user.id
-                        ),
-                        content: String::new(),
-                        address: format!("/market/item/{id}"),
-                        recipient: asset.creator,
-                    },
-                    None,
-                )
-                .await
+            // create notification (unless the creator has muted the user)
+            if !database.auth.is_muted(&asset.creator, &auth_user.id).await
+                && database
+                    .auth
+                    .create_notification(
+                        NotificationCreate {
+                            title: format!(
+                                "[@{}](/+u/{}) has reacted to an item you created!",
+                                auth_user.username, auth_user.id
+                            ),
+                            content: String::new(),
+                            address: format!("/market/item/{id}"),
+                            recipient: asset.creator,
+                            kind: NotificationKind::Reaction,
+                        },
+                        None,
+                    )
+                    .await
+                    .is_err()
             {
                 return Json(DefaultReturn {
                     success: false,
@@ -249,27 +261,12 @@ pub async fn create_request(
 
 /// [`Database::get_reaction`]
 pub async fn get_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Any>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     Json(match database.get_reaction(auth_user.id, id).await {
         Ok(r) => DefaultReturn {
             success: true,
@@ -282,27 +279,12 @@ pub async fn get_request(
 
 /// [`Database::delete_reaction`]
 pub async fn delete_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(match database.delete_reaction(id, auth_user).await {
         Ok(r) => DefaultReturn {
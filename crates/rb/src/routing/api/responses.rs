@@ -4,7 +4,8 @@ use crate::routing::pages::PaginatedQuery;
 use axum::extract::Query;
 use axum::http::{HeaderMap, HeaderValue};
 use hcaptcha_no_wasm::Hcaptcha;
-use authbeam::model::NotificationCreate;
+use authbeam::extract::{scope, Auth};
+use authbeam::model::{NotificationCreate, NotificationKind};
 use databeam::prelude::DefaultReturn;
 
 use axum::response::{IntoResponse, Redirect};
@@ -15,12 +16,18 @@ use axum::{
 };
 
 use axum_extra::extract::cookie::CookieJar;
-use rainbeam::model::{ResponseDeleteMultiple, ResponseEditTagsMultiple, ResponseEditWarning};
+
+use rainbeam::model::{
+    AssetType, CommentTreeNode, CommentTreeQuery, ResponseDeleteMultiple, ResponseEditTagsMultiple,
+    ResponseEditWarning,
+};
 
 pub fn routes(database: Database) -> Router {
     Router::new()
         .route("/", post(create_request))
         .route("/{id}", get(get_request))
+        .route("/{id}/revisions", get(revisions_request))
+        .route("/{id}/comments", get(comments_request))
         .route("/{id}", post(edit_request))
         .route("/{id}/tags", post(edit_tags_request))
         .route("/{id}/context", post(edit_context_request))
@@ -40,25 +47,13 @@ pub fn routes(database: Database) -> Router {
 
 /// [`Database::create_response`]
 pub async fn create_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     State(database): State<Database>,
     Json(req): Json<ResponseCreate>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua.id,
-            Err(_) => return Json(DatabaseError::NotAllowed.into()),
-        },
-        None => return Json(DatabaseError::NotAllowed.into()),
-    };
-
-    // ...
-    Json(match database.create_response(req, auth_user).await {
+    Json(match database.create_response(req, auth_user.id).await {
         Ok(r) => DefaultReturn {
             success: true,
             message: String::new(),
@@ -70,38 +65,140 @@ pub async fn create_request(
 
 /// [`Database::get_response`]
 pub async fn get_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    Json(match database.get_response(id).await {
-        Ok(mut r) => DefaultReturn {
-            success: true,
-            message: String::new(),
-            payload: {
-                // hide anonymous author id
-                if r.0.author.id.starts_with("anonymous#") {
-                    r.0.author.id = "anonymous".to_string()
-                }
+    let auth_user = auth.map(|a| a.user);
+
+    let mut r = match database.get_response(id, auth_user.as_deref()).await {
+        Ok(r) => r,
+        Err(e) => return Json(e.into()),
+    };
+
+    // the response is hidden until its author's profile is unlocked
+    if !database
+        .is_unlocked_response(&r.1, auth_user.as_deref(), &jar)
+        .await
+    {
+        return Json(DatabaseError::NotAllowed.into());
+    }
+
+    // hide anonymous author id
+    if r.0.author.id.starts_with("anonymous#") {
+        r.0.author.id = "anonymous".to_string()
+    }
 
+    // hide tokens, password, salt, and metadata
+    r.0.author.clean();
+    r.0.recipient.clean();
+    r.1.author.clean();
+
+    // return
+    Json(DefaultReturn {
+        success: true,
+        message: String::new(),
+        payload: Some(r),
+    })
+}
+
+/// [`Database::get_asset_revisions`]
+pub async fn revisions_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    if let Ok(r) = database.get_response_short(id.clone()).await {
+        if !database
+            .is_unlocked_response(&r, auth_user.as_deref(), &jar)
+            .await
+        {
+            return Json(DatabaseError::NotAllowed.into());
+        }
+    }
+
+    Json(
+        match database
+            .get_asset_revisions(AssetType::Response, &id, auth_user.as_deref())
+            .await
+        {
+            Ok(mut r) => {
                 // hide tokens, password, salt, and metadata
-                r.0.author.clean();
-                r.0.recipient.clean();
-                r.1.author.clean();
+                for revision in r.iter_mut() {
+                    revision.editor.clean();
+                }
 
-                // return
-                Some(r)
-            },
+                DefaultReturn {
+                    success: true,
+                    message: String::new(),
+                    payload: Some(r),
+                }
+            }
+            Err(e) => e.into(),
         },
-        Err(e) => e.into(),
-    })
+    )
+}
+
+/// [`Database::get_comment_tree`]
+pub async fn comments_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+    Query(props): Query<CommentTreeQuery>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    if let Ok(r) = database.get_response_short(id.clone()).await {
+        if !database
+            .is_unlocked_response(&r, auth_user.as_deref(), &jar)
+            .await
+        {
+            return Json(DatabaseError::NotAllowed.into());
+        }
+    }
+
+    Json(
+        match database
+            .get_comment_tree(id, props, auth_user.as_deref())
+            .await
+        {
+            Ok(mut r) => {
+                clean_comment_tree(&mut r.comments);
+
+                DefaultReturn {
+                    success: true,
+                    message: String::new(),
+                    payload: Some(r),
+                }
+            }
+            Err(e) => e.into(),
+        },
+    )
+}
+
+/// Hide the tokens, password, salt, and metadata of every author in a comment tree (and the IPs of every comment)
+fn clean_comment_tree(nodes: &mut [CommentTreeNode]) {
+    for node in nodes.iter_mut() {
+        node.comment.author.clean();
+        node.comment.ip = String::new();
+        clean_comment_tree(&mut node.replies);
+    }
 }
 
 /// Redirect to the full ID of a response through its short ID
 pub async fn expand_request(
+    auth: Option<Auth>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    match database.get_response(id).await {
+    let auth_user = auth.map(|a| a.user);
+
+    match database.get_response(id, auth_user.as_deref()).await {
         Ok(r) => Redirect::to(&format!("/@{}/r/{}", r.1.author.username, r.1.id)),
         Err(_) => Redirect::to("/"),
     }
@@ -109,28 +206,13 @@ pub async fn expand_request(
 
 /// [`Database::update_response_content`]
 pub async fn edit_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(req): Json<ResponseEdit>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(
         match database
@@ -149,28 +231,13 @@ pub async fn edit_request(
 
 /// [`Database::update_response_tags`]
 pub async fn edit_tags_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(req): Json<ResponseEditTags>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(
         match database.update_response_tags(id, req.tags, auth_user).await {
@@ -186,27 +253,12 @@ pub async fn edit_tags_request(
 
 /// [`Database::update_response_tags_multiple`]
 pub async fn edit_tags_multiple_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     State(database): State<Database>,
     Json(req): Json<ResponseEditTagsMultiple>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(
         match database
@@ -225,28 +277,13 @@ pub async fn edit_tags_multiple_request(
 
 /// [`Database::update_response_context`]
 pub async fn edit_context_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(req): Json<ResponseEditContext>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(
         match database
@@ -265,28 +302,13 @@ pub async fn edit_context_request(
 
 /// [`Database::update_response_context`]
 pub async fn edit_warning_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
     Json(req): Json<ResponseEditWarning>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // make sure the response exists
     let response = match database.get_response_short(id.clone()).await {
         Ok(q) => q,
@@ -314,27 +336,12 @@ pub async fn edit_warning_request(
 
 /// [`Database::delete_response`]
 pub async fn delete_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(match database.delete_response(id, auth_user, false).await {
         Ok(r) => DefaultReturn {
@@ -348,27 +355,12 @@ pub async fn delete_request(
 
 /// [`Database::delete_response_multiple`]
 pub async fn delete_multiple_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     State(database): State<Database>,
     Json(req): Json<ResponseDeleteMultiple>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(
         match database.delete_response_multiple(req.ids, auth_user).await {
@@ -384,27 +376,12 @@ pub async fn delete_multiple_request(
 
 /// [`Database::unsend_response`]
 pub async fn unsend_request(
-    jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAssets>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(match database.unsend_response(id, auth_user).await {
         Ok(r) => DefaultReturn {
@@ -418,6 +395,7 @@ pub async fn unsend_request(
 
 /// Report a response
 pub async fn report_request(
+    auth: Option<Auth>,
     headers: HeaderMap,
     Path(id): Path<String>,
     State(database): State<Database>,
@@ -436,7 +414,13 @@ pub async fn report_request(
     }
 
     // get response
-    if let Err(_) = database.get_response(id.clone()).await {
+    let auth_user = auth.map(|a| a.user);
+
+    if database
+        .get_response(id.clone(), auth_user.as_deref())
+        .await
+        .is_err()
+    {
         return Json(DefaultReturn {
             success: false,
             message: DatabaseError::NotFound.to_string(),
@@ -474,6 +458,7 @@ pub async fn report_request(
                 content: format!("{}\n\n***\n\n[{real_ip}](/+i/{real_ip})", req.content),
                 address: format!("/response/{id}"),
                 recipient: "*".to_string(), // all staff
+                kind: NotificationKind::System,
             },
             None,
         )
@@ -497,33 +482,23 @@ pub async fn report_request(
 /// Home timeline request ("/")
 pub async fn home_timeline_request(
     jar: CookieJar,
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::Any>,
     State(database): State<Database>,
     Query(props): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    // get user from token
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => {
-                return Json(DatabaseError::NotAllowed.into());
-            }
-        },
-        None => {
-            return Json(DatabaseError::NotAllowed.into());
-        }
-    };
-
     // ...
     Json(
         match database
             .get_responses_by_following_paginated(&auth_user.id, props.page)
             .await
         {
-            Ok(mut r) => {
+            Ok(r) => {
+                let mut r = database
+                    .filter_unlocked_responses(r, Some(&auth_user), &jar)
+                    .await;
+
                 for response in &mut r {
                     response.1.author.clean();
                     response.0.recipient.clean();
@@ -0,0 +1,102 @@
+use crate::database::Database;
+use crate::model::DatabaseError;
+use axum::http::{HeaderMap, HeaderValue};
+use hcaptcha_no_wasm::Hcaptcha;
+use authbeam::model::{NotificationCreate, NotificationKind};
+use databeam::prelude::DefaultReturn;
+
+use axum::response::IntoResponse;
+use axum::{
+    extract::{Path, State},
+    routing::post,
+    Json, Router,
+};
+
+pub fn routes(database: Database) -> Router {
+    Router::new()
+        .route("/{id}/report", post(report_request))
+        // ...
+        .with_state(database)
+}
+
+// routes
+
+/// Report a market item review
+pub async fn report_request(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+    Json(req): Json<super::CreateReport>,
+) -> impl IntoResponse {
+    // check hcaptcha
+    if let Err(e) = req
+        .valid_response(&database.config.captcha.secret, None)
+        .await
+    {
+        return Json(DefaultReturn {
+            success: false,
+            message: e.to_string(),
+            payload: (),
+        });
+    }
+
+    // get review
+    let review = match database.auth.get_review(&id).await {
+        Ok(r) => r,
+        Err(_) => {
+            return Json(DefaultReturn {
+                success: false,
+                message: DatabaseError::NotFound.to_string(),
+                payload: (),
+            })
+        }
+    };
+
+    // get real ip
+    let real_ip = if let Some(ref real_ip_header) = database.config.real_ip_header {
+        headers
+            .get(real_ip_header.to_owned())
+            .unwrap_or(&HeaderValue::from_static(""))
+            .to_str()
+            .unwrap_or("")
+            .to_string()
+    } else {
+        String::new()
+    };
+
+    // check ip
+    if database.auth.get_ipban_by_ip(&real_ip).await.is_ok() {
+        return Json(DefaultReturn {
+            success: false,
+            message: DatabaseError::Banned.to_string(),
+            payload: (),
+        });
+    }
+
+    // report
+    match database
+        .auth
+        .create_notification(
+            NotificationCreate {
+                title: format!("**REVIEW REPORT**: {id}"),
+                content: format!("{}\n\n***\n\n[{real_ip}](/+i/{real_ip})", req.content),
+                address: format!("/market/item/{}#/reviews", review.item),
+                recipient: "*".to_string(), // all staff
+                kind: NotificationKind::System,
+            },
+            None,
+        )
+        .await
+    {
+        Ok(_) => Json(DefaultReturn {
+            success: true,
+            message: "Review reported!".to_string(),
+            payload: (),
+        }),
+        Err(_) => Json(DefaultReturn {
+            success: false,
+            message: DatabaseError::NotFound.to_string(),
+            payload: (),
+        }),
+    }
+}
@@ -0,0 +1,34 @@
+use crate::database::Database;
+use crate::model::TipCreate;
+use authbeam::extract::{scope, Auth};
+use databeam::prelude::DefaultReturn;
+
+use axum::response::IntoResponse;
+use axum::{extract::State, routing::post, Json, Router};
+
+pub fn routes(database: Database) -> Router {
+    Router::new()
+        .route("/", post(create_request))
+        // ...
+        .with_state(database)
+}
+
+// routes
+
+/// [`Database::create_tip`]
+pub async fn create_request(
+    Auth {
+        user: auth_user, ..
+    }: Auth<scope::ManageAccount>,
+    State(database): State<Database>,
+    Json(req): Json<TipCreate>,
+) -> impl IntoResponse {
+    Json(match database.create_tip(req, auth_user).await {
+        Ok(r) => DefaultReturn {
+            success: true,
+            message: r.id.clone(),
+            payload: Some(r),
+        },
+        Err(e) => e.into(),
+    })
+}
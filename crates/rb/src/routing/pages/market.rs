@@ -1,4 +1,6 @@
-use authbeam::layout::LayoutComponent;
+use authbeam::css::sanitize_css;
+use authbeam::extract::Auth;
+use authbeam::layout::{FreeRendererTemplate, LayoutComponent, MARKET_ID_OPTION};
 use reva_axum::Template;
 use axum::response::IntoResponse;
 use axum::{
@@ -7,14 +9,15 @@ use axum::{
 };
 use axum_extra::extract::CookieJar;
 
-use authbeam::model::{FinePermission, Item, ItemStatus, ItemType, Profile};
+use authbeam::model::{FinePermission, Item, ItemRating, ItemReview, ItemStatus, ItemType, Profile};
+use authbeam::model::{ItemSearch, ItemSort, ItemVersion};
 
 use crate::config::Config;
 use crate::database::Database;
 use crate::model::DatabaseError;
 use crate::ToHtml;
 
-use super::MarketQuery;
+use super::{MarketQuery, PaginatedQuery};
 
 #[derive(Template)]
 #[template(path = "market/homepage.html")]
@@ -29,25 +32,25 @@ struct HomepageTemplate {
     status: ItemStatus,
     creator: String,
     customer: String,
-    items: Vec<(Item, Box<Profile>)>,
+    item_type: String,
+    tag: String,
+    sort: ItemSort,
+    min_cost: String,
+    max_cost: String,
+    items: Vec<(Item, Box<Profile>, ItemRating)>,
+    pending_versions: Vec<(ItemVersion, Item)>,
     is_helper: bool,
 }
 
 /// GET /market
 pub async fn homepage_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<MarketQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -80,27 +83,7 @@ pub async fn homepage_request(
     }
 
     // data
-    let items = if props.creator.is_empty() {
-        if let Some(r#type) = props.r#type {
-            match database
-                .auth
-                .get_items_by_type_paginated(r#type, props.page)
-                .await
-            {
-                Ok(i) => i,
-                Err(e) => return Html(e.to_string()),
-            }
-        } else {
-            match database
-                .auth
-                .get_items_by_status_searched_paginated(props.status.clone(), props.page, &props.q)
-                .await
-            {
-                Ok(i) => i,
-                Err(e) => return Html(e.to_string()),
-            }
-        }
-    } else if !props.customer.is_empty() {
+    let items = if !props.customer.is_empty() {
         match database
             .auth
             .get_transactions_by_customer_paginated(&props.customer, props.page)
@@ -110,48 +93,87 @@ pub async fn homepage_request(
                 let mut out = Vec::new();
 
                 for x in i {
-                    out.push((
-                        match x.0 .1 {
-                            Some(i) => i.clone(),
-                            None => return Html(DatabaseError::NotFound.to_html(database)),
-                        },
-                        x.2.clone(),
-                    ))
+                    let item = match x.0 .1 {
+                        Some(i) => i.clone(),
+                        // transactions without an item (like tips)
+                        None => continue,
+                    };
+
+                    let rating = database.auth.get_item_rating(&item.id).await;
+                    out.push((item, x.2.clone(), rating))
                 }
 
                 out
             }
             Err(e) => return Html(e.to_string()),
         }
-    } else {
+    } else if !props.creator.is_empty() {
         if (auth_user.id != props.creator) && !is_helper {
             // we cannot sort by somebody that isnt us if we arent helper
             return Html(DatabaseError::NotAllowed.to_html(database));
         }
 
-        if let Some(r#type) = props.r#type {
+        let items = if let Some(r#type) = props.item_type() {
             // creator and type
-            match database
+            database
                 .auth
                 .get_items_by_creator_type_paginated(&props.creator, r#type, props.page)
                 .await
-            {
-                Ok(i) => i,
-                Err(e) => return Html(e.to_string()),
-            }
         } else {
             // no type, just creator
-            match database
+            database
                 .auth
                 .get_items_by_creator_paginated(&props.creator, props.page)
                 .await
-            {
-                Ok(i) => i,
-                Err(e) => return Html(e.to_string()),
+        };
+
+        match items {
+            Ok(i) => {
+                let mut out = Vec::new();
+
+                for (item, creator) in i {
+                    let rating = database.auth.get_item_rating(&item.id).await;
+                    out.push((item, creator, rating));
+                }
+
+                out
             }
+            Err(e) => return Html(e.to_string()),
+        }
+    } else {
+        match database
+            .auth
+            .search_items(ItemSearch {
+                q: props.q.clone(),
+                tag: props.tag.clone(),
+                status: props.status.clone(),
+                r#type: props.item_type(),
+                sort: props.sort.clone(),
+                min_cost: props.min_cost.parse().ok(),
+                max_cost: props.max_cost.parse().ok(),
+                page: props.page,
+            })
+            .await
+        {
+            Ok(i) => i,
+            Err(e) => return Html(e.to_string()),
         }
     };
 
+    // new versions of approved items are reviewed alongside pending items
+    let pending_versions = if is_helper
+        && (props.status == ItemStatus::Pending)
+        && props.creator.is_empty()
+        && props.customer.is_empty()
+    {
+        match database.auth.get_pending_item_versions().await {
+            Ok(v) => v,
+            Err(e) => return Html(e.to_string()),
+        }
+    } else {
+        Vec::new()
+    };
+
     // ...
     Html(
         HomepageTemplate {
@@ -169,7 +191,13 @@ pub async fn homepage_request(
             status: props.status,
             creator: props.creator,
             customer: props.customer,
+            item_type: props.r#type,
+            tag: props.tag,
+            sort: props.sort,
+            min_cost: props.min_cost,
+            max_cost: props.max_cost,
             items,
+            pending_versions,
             is_helper,
         }
         .render()
@@ -188,16 +216,13 @@ struct CreateTemplate {
 }
 
 /// GET /market/new
-pub async fn create_request(jar: CookieJar, State(database): State<Database>) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+pub async fn create_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -238,23 +263,28 @@ struct ItemTemplate {
     is_owned: bool,
     is_helper: bool,
     reaction_count: usize,
+    content: String,
+    versions: Vec<ItemVersion>,
+    current_version: i32,
+    pinned: Option<ItemVersion>,
+    is_applied: bool,
+    tags: Vec<String>,
+    rating: ItemRating,
+    reviews: Vec<(ItemReview, Box<Profile>)>,
+    my_review: Option<ItemReview>,
+    page: i32,
 }
 
 /// GET /market/item/{id}
 pub async fn item_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(id): Path<String>,
     State(database): State<Database>,
+    Query(props): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -288,6 +318,52 @@ pub async fn item_request(
         return Html(DatabaseError::NotAllowed.to_string());
     }
 
+    let is_creator = auth_user.id == item.creator;
+
+    let versions: Vec<ItemVersion> = match database.auth.get_item_versions(&item.id).await {
+        // only the creator and helpers can see versions which aren't approved
+        Ok(v) => v
+            .into_iter()
+            .filter(|v| is_creator | is_helper | (v.status == ItemStatus::Approved))
+            .collect(),
+        Err(e) => return Html(e.to_string()),
+    };
+
+    let current_version = versions
+        .iter()
+        .filter(|v| v.status == ItemStatus::Approved)
+        .map(|v| v.number)
+        .max()
+        .unwrap_or(0);
+
+    let content = database.auth.get_item_content(&item, &auth_user.id).await;
+    let pinned = database
+        .auth
+        .get_item_version_pin(&item.id, &auth_user.id)
+        .await;
+
+    // if this is the layout or theme the user applied most recently
+    let is_applied = database
+        .auth
+        .get_item_install(&auth_user.id, &item.r#type)
+        .await
+        .is_some_and(|i| i.item == item.id);
+
+    let my_review = database
+        .auth
+        .get_review_by_author_item(&auth_user.id, &item.id)
+        .await
+        .ok();
+
+    let reviews = match database
+        .auth
+        .get_reviews_by_item_paginated(&item.id, props.page)
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => return Html(e.to_string()),
+    };
+
     // ...
     Html(
         ItemTemplate {
@@ -311,7 +387,17 @@ pub async fn item_request(
             },
             item,
             is_helper,
-            reaction_count: database.get_reaction_count_by_asset(id).await,
+            reaction_count: database.get_reaction_count_by_asset(id.clone()).await,
+            content,
+            versions,
+            current_version,
+            pinned,
+            is_applied,
+            tags: database.auth.get_item_tags(&id).await,
+            rating: database.auth.get_item_rating(&id).await,
+            reviews,
+            my_review,
+            page: props.page,
         }
         .render()
         .unwrap(),
@@ -330,18 +416,12 @@ struct ThemePlaygroundTemplate {
 /// GET /market/_app/theme_playground.html
 pub async fn theme_playground_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -364,8 +444,8 @@ pub async fn theme_playground_request(
             } else {
                 ""
             }),
+            css: sanitize_css(&database.auth.get_item_content(&item, &auth_user.id).await).0,
             profile: Some(auth_user),
-            css: item.content,
         }
         .render()
         .unwrap(),
@@ -384,18 +464,12 @@ struct LayoutPlaygroundTemplate {
 /// GET /market/_app/layout_playground.html
 pub async fn layout_playground_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(id): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -418,11 +492,85 @@ pub async fn layout_playground_request(
             } else {
                 ""
             }),
-            profile: Some(auth_user),
-            layout: match serde_json::from_str(&item.content) {
+            layout: match serde_json::from_str(
+                &database.auth.get_item_content(&item, &auth_user.id).await,
+            ) {
                 Ok(l) => l,
                 Err(_) => return Html(DatabaseError::ValueError.to_string()),
             },
+            profile: Some(auth_user),
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "partials/components/item_preview.html")]
+struct ItemPreviewTemplate {
+    config: Config,
+    lang: langbeam::LangFile,
+    profile: Option<Box<Profile>>,
+    rendered: String,
+    css: String,
+}
+
+/// GET /market/_app/item_preview.html/{id}
+///
+/// Preview a layout or theme item applied to your own profile.
+pub async fn item_preview_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path(id): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = match auth {
+        Some(a) => a.user,
+        None => return Html(DatabaseError::NotAllowed.to_html(database)),
+    };
+
+    // data
+    let item = match database.auth.get_item(&id).await {
+        Ok(i) => i,
+        Err(_) => return Html(DatabaseError::NotFound.to_html(database)),
+    };
+
+    let content = database.auth.get_item_content(&item, &auth_user.id).await;
+
+    let (layout, css) = match item.r#type {
+        ItemType::Layout => {
+            let mut layout: LayoutComponent = match serde_json::from_str(&content) {
+                Ok(l) => l,
+                Err(_) => return Html(DatabaseError::ValueError.to_html(database)),
+            };
+
+            layout
+                .options
+                .insert(MARKET_ID_OPTION.to_string(), item.id.clone());
+
+            (layout, String::new())
+        }
+        ItemType::UserTheme => (auth_user.layout.clone(), sanitize_css(&content).0),
+        _ => return Html(DatabaseError::ValueError.to_html(database)),
+    };
+
+    // ...
+    Html(
+        ItemPreviewTemplate {
+            config: database.config.clone(),
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            rendered: FreeRendererTemplate {
+                other: &auth_user,
+                component: &layout,
+            }
+            .render()
+            .unwrap(),
+            css,
+            profile: Some(auth_user),
         }
         .render()
         .unwrap(),
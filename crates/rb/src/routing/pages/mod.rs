@@ -1,11 +1,15 @@
 use rainbeam::{
     database::Database,
     model::{RelationshipStatus, Question, Reaction, FullResponse, DatabaseError},
+    model::{Appeal, AppealKind, AppealStatus},
 };
 use rainbeam_shared::config::Config;
 use authbeam::{
     simplify,
+    extract::Auth,
     model::{Profile, ProfileMetadata, Notification, FinePermission, IpBan, ItemType, ItemStatus},
+    model::{Suspension, Warning, WarningSeverity},
+    model::ItemSort,
 };
 use langbeam::LangFile;
 
@@ -93,20 +97,11 @@ struct TimelineTemplate {
 /// GET /
 pub async fn homepage_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     // timeline
     if let Some(ref ua) = auth_user {
@@ -175,18 +170,12 @@ struct PartialTimelineTemplate {
 /// GET /_app/timelines/timeline.html
 pub async fn partial_timeline_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -198,6 +187,11 @@ pub async fn partial_timeline_request(
         Err(e) => return Html(e.to_html(database)),
     };
 
+    // responses are hidden until their author's profile is unlocked
+    let responses = database
+        .filter_unlocked_responses(responses, Some(auth_user.as_ref()), &jar)
+        .await;
+
     let mut is_helper: bool = false;
     let is_powerful = {
         let group = match database.auth.get_group_by_id(auth_user.group).await {
@@ -276,18 +270,12 @@ struct PublicTimelineTemplate {
 /// GET /public
 pub async fn public_timeline_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_string()),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_string()),
     };
 
@@ -333,26 +321,28 @@ struct PartialPublicTimelineTemplate {
 /// GET /_app/timelines/public_timeline.html
 pub async fn partial_public_timeline_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
-    let responses = match database.get_responses_paginated(props.page).await {
+    let responses = match database
+        .get_responses_paginated(props.page, Some(auth_user.as_ref()))
+        .await
+    {
         Ok(responses) => responses,
         Err(e) => return Html(e.to_html(database)),
     };
 
+    // responses are hidden until their author's profile is unlocked
+    let responses = database
+        .filter_unlocked_responses(responses, Some(auth_user.as_ref()), &jar)
+        .await;
+
     let mut is_helper: bool = false;
     let is_powerful = {
         let group = match database.auth.get_group_by_id(auth_user.group).await {
@@ -432,25 +422,24 @@ struct PartialTopResponsesTemplate {
 /// GET /_app/timelines/discover/responses_top.html
 pub async fn partial_top_responses_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
-    let responses = match database.get_top_reacted_responses(604_800_000).await {
+    let responses = match database
+        .get_top_reacted_responses(604_800_000, auth_user.as_deref())
+        .await
+    {
         Ok(r) => r,
         Err(e) => return Html(e.to_html(database)),
     };
 
+    // responses are hidden until their author's profile is unlocked
+    let responses = database
+        .filter_unlocked_responses(responses, auth_user.as_deref(), &jar)
+        .await;
+
     let mut is_helper: bool = false;
     let is_powerful = if let Some(ref ua) = auth_user {
         let group = match database.auth.get_group_by_id(ua.group).await {
@@ -606,17 +595,11 @@ struct DiscoverTemplate {
 /// GET /public
 pub async fn discover_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_string()),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_string()),
     };
 
@@ -657,18 +640,12 @@ pub struct MarkdownTemplate {
 }
 
 /// GET /site/about
-pub async fn about_request(jar: CookieJar, State(database): State<Database>) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+pub async fn about_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
 
     Html(
         MarkdownTemplate {
@@ -692,18 +669,12 @@ pub async fn about_request(jar: CookieJar, State(database): State<Database>) ->
 }
 
 /// GET /site/terms-of-service
-pub async fn tos_request(jar: CookieJar, State(database): State<Database>) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+pub async fn tos_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
 
     Html(
         MarkdownTemplate {
@@ -726,19 +697,10 @@ pub async fn tos_request(jar: CookieJar, State(database): State<Database>) -> im
 /// GET /site/privacy
 pub async fn privacy_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     Html(
         MarkdownTemplate {
@@ -761,6 +723,70 @@ pub async fn privacy_request(
     )
 }
 
+/// GET, POST /unsubscribe/{id}/{token}
+pub async fn unsubscribe_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path((id, token)): Path<(String, String)>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    if let Err(e) = database.unsubscribe_digest(&id, &token).await {
+        return Html(e.to_html(database));
+    }
+
+    let lang = database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+        c.value_trimmed()
+    } else {
+        ""
+    });
+
+    Html(
+        MarkdownTemplate {
+            config: database.config.clone(),
+            title: lang.get("general:text.unsubscribed_title"),
+            text: lang.get("general:text.unsubscribed"),
+            lang,
+            profile: auth_user,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+/// GET /digest/confirm/{id}/{token}
+pub async fn confirm_digest_email_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path((id, token)): Path<(String, String)>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    if let Err(e) = database.confirm_digest_email(&id, &token).await {
+        return Html(e.to_html(database));
+    }
+
+    let lang = database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+        c.value_trimmed()
+    } else {
+        ""
+    });
+
+    Html(
+        MarkdownTemplate {
+            config: database.config.clone(),
+            title: lang.get("general:text.email_confirmed_title"),
+            text: lang.get("general:text.email_confirmed"),
+            lang,
+            profile: auth_user,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
 #[derive(Template)]
 #[template(path = "fun/carp.html")]
 struct CarpTemplate {
@@ -770,18 +796,12 @@ struct CarpTemplate {
 }
 
 /// GET /site/fun/carp
-pub async fn carp_request(jar: CookieJar, State(database): State<Database>) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+pub async fn carp_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
 
     Html(
         CarpTemplate {
@@ -807,18 +827,12 @@ struct LoginTemplate {
 }
 
 /// GET /login
-pub async fn login_request(jar: CookieJar, State(database): State<Database>) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+pub async fn login_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
 
     Html(
         LoginTemplate {
@@ -846,6 +860,7 @@ struct SignUpTemplate {
 /// GET /sign_up
 pub async fn sign_up_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
     if database.config.registration_enabled == false {
@@ -853,17 +868,7 @@ pub async fn sign_up_request(
     }
 
     // ...
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     Html(
         SignUpTemplate {
@@ -916,8 +921,25 @@ pub struct MarketQuery {
     creator: String,
     #[serde(default)]
     customer: String,
+    /// An [`ItemType`] (or empty for any type)
+    #[serde(default)]
+    r#type: String,
     #[serde(default)]
-    r#type: Option<ItemType>,
+    tag: String,
+    #[serde(default)]
+    sort: ItemSort,
+    /// Kept as a string so an empty field in the filter form isn't an error
+    #[serde(default)]
+    min_cost: String,
+    #[serde(default)]
+    max_cost: String,
+}
+
+impl MarketQuery {
+    /// The [`ItemType`] items are filtered by
+    pub fn item_type(&self) -> Option<ItemType> {
+        serde_json::from_value(serde_json::Value::String(self.r#type.clone())).ok()
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -932,14 +954,20 @@ pub struct ProfileQuery {
     pub page: i32,
     pub tag: Option<String>,
     pub q: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct InboxQuery {
     #[serde(default)]
-    pub password: String,
+    pub filtered: bool,
 }
 
 #[derive(Deserialize)]
-pub struct PasswordQuery {
+pub struct AppealsQuery {
+    #[serde(default)]
+    pub kind: String,
     #[serde(default)]
-    pub password: String,
+    pub target: String,
 }
 
 /// Escape profile colors
@@ -979,6 +1007,9 @@ pub fn clean_metadata_raw(metadata: &ProfileMetadata) -> ProfileMetadata {
     // remove stupid characters
     let mut metadata = metadata.to_owned();
 
+    // never send the view password to viewers, even hashed
+    metadata.kv.remove("rainbeam:view_password");
+
     for field in metadata.kv.clone() {
         metadata.kv.insert(
             field.0.to_string(),
@@ -1049,20 +1080,11 @@ struct QuestionTemplate {
 /// GET /@{}/q/{id}
 pub async fn question_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path((_, id)): Path<(String, String)>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let unread = if let Some(ref ua) = auth_user {
         database.get_inbox_count_by_recipient(&ua.id).await
@@ -1084,11 +1106,26 @@ pub async fn question_request(
         Err(e) => return Html(e.to_html(database)),
     };
 
-    let responses = match database.get_responses_by_question(id.to_owned()).await {
+    if !database
+        .can_view_question(&question, auth_user.as_deref())
+        .await
+    {
+        return Html(DatabaseError::NotFound.to_html(database));
+    }
+
+    let responses = match database
+        .get_responses_by_question(id.to_owned(), auth_user.as_deref())
+        .await
+    {
         Ok(responses) => responses,
         Err(_) => return Html(DatabaseError::Other.to_html(database)),
     };
 
+    // responses are hidden until their author's profile is unlocked
+    let responses = database
+        .filter_unlocked_responses(responses, auth_user.as_deref(), &jar)
+        .await;
+
     let mut is_helper: bool = false;
     let is_powerful = if let Some(ref ua) = auth_user {
         let group = match database.auth.get_group_by_id(ua.group).await {
@@ -1149,6 +1186,8 @@ struct InboxTemplate {
     anonymous_username: Option<String>,
     anonymous_avatar: Option<String>,
     is_helper: bool,
+    filtered: bool,
+    filtered_count: usize,
 }
 
 #[derive(Template)]
@@ -1180,24 +1219,21 @@ pub async fn partial_reactions_request(
 }
 
 /// GET /inbox
-pub async fn inbox_request(jar: CookieJar, State(database): State<Database>) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+pub async fn inbox_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+    Query(query): Query<InboxQuery>,
+) -> impl IntoResponse {
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
+    let settings = auth_user.metadata.settings();
+
     // mark all as read
-    if !auth_user
-        .metadata
-        .is_true("rainbeam:do_not_clear_inbox_count_on_view")
-    {
+    if !settings.do_not_clear_inbox_count_on_view {
         simplify!(
             database
                 .auth
@@ -1208,10 +1244,14 @@ pub async fn inbox_request(jar: CookieJar, State(database): State<Database>) ->
     }
 
     // ...
-    let unread = match database.get_questions_by_recipient(&auth_user.id).await {
-        Ok(unread) => unread,
-        Err(_) => return Html(DatabaseError::Other.to_html(database)),
-    };
+    let (filtered, unread): (Vec<Question>, Vec<Question>) =
+        match database.get_questions_by_recipient(&auth_user.id).await {
+            Ok(unread) => unread.into_iter().partition(|q| q.is_filtered()),
+            Err(_) => return Html(DatabaseError::Other.to_html(database)),
+        };
+
+    let filtered_count = filtered.len();
+    let unread = if query.filtered { filtered } else { unread };
 
     let notifs = database
         .auth
@@ -1237,24 +1277,20 @@ pub async fn inbox_request(jar: CookieJar, State(database): State<Database>) ->
             }),
             unread,
             notifs,
-            anonymous_username: Some(
-                auth_user
-                    .metadata
-                    .kv
-                    .get("sparkler:anonymous_username")
-                    .unwrap_or(&"anonymous".to_string())
-                    .to_string(),
-            ),
-            anonymous_avatar: Some(
-                auth_user
-                    .metadata
-                    .kv
-                    .get("sparkler:anonymous_avatar")
-                    .unwrap_or(&"/static/images/default-avatar.svg".to_string())
-                    .to_string(),
-            ),
+            anonymous_username: Some(if settings.anonymous_username.is_empty() {
+                "anonymous".to_string()
+            } else {
+                settings.anonymous_username
+            }),
+            anonymous_avatar: Some(if settings.anonymous_avatar.is_empty() {
+                "/static/images/default-avatar.svg".to_string()
+            } else {
+                settings.anonymous_avatar.0
+            }),
             profile: Some(auth_user),
             is_helper,
+            filtered: query.filtered,
+            filtered_count,
         }
         .render()
         .unwrap(),
@@ -1278,18 +1314,12 @@ struct GlobalTimelineTemplate {
 /// GET /inbox/global/following
 pub async fn global_timeline_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(query): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -1386,18 +1416,12 @@ struct PublicGlobalTimelineTemplate {
 /// GET /inbox/global
 pub async fn public_global_timeline_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(query): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -1417,11 +1441,18 @@ pub async fn public_global_timeline_request(
         group.permissions.check_helper()
     };
 
-    let questions = match database.get_global_questions_paginated(query.page).await {
+    let questions = match database
+        .get_global_questions_paginated(query.page, Some(&auth_user))
+        .await
+    {
         Ok(r) => r,
         Err(e) => return Html(e.to_html(database)),
     };
 
+    let questions = database
+        .filter_visible_questions(questions, Some(&auth_user))
+        .await;
+
     // build relationships list
     let mut relationships: HashMap<String, RelationshipStatus> = HashMap::new();
 
@@ -1498,18 +1529,12 @@ struct NotificationsTemplate {
 /// GET /inbox/notifications
 pub async fn notifications_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<NotificationsQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -1570,6 +1595,75 @@ pub async fn notifications_request(
     )
 }
 
+#[derive(Template)]
+#[template(path = "warnings.html")]
+struct WarningsTemplate {
+    config: Config,
+    lang: langbeam::LangFile,
+    profile: Option<Box<Profile>>,
+    unread: usize,
+    notifs: usize,
+    warnings: Vec<Warning>,
+    suspension: Option<Suspension>,
+}
+
+/// GET /warnings
+pub async fn warnings_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = match auth {
+        Some(a) => a.user,
+        None => return Html(DatabaseError::NotAllowed.to_html(database)),
+    };
+
+    let unread = database.get_inbox_count_by_recipient(&auth_user.id).await;
+
+    let notifs = database
+        .auth
+        .get_notification_count_by_recipient(&auth_user.id)
+        .await;
+
+    let warnings = match database
+        .auth
+        .get_warnings_by_recipient(&auth_user.id, auth_user.clone())
+        .await
+    {
+        Ok(r) => r,
+        Err(_) => return Html(DatabaseError::Other.to_html(database)),
+    };
+
+    let suspension = match database.auth.get_suspension(&auth_user.id).await {
+        Ok(s) => {
+            if s.is_active() {
+                Some(s)
+            } else {
+                None
+            }
+        }
+        Err(_) => None,
+    };
+
+    Html(
+        WarningsTemplate {
+            config: database.config.clone(),
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            profile: Some(auth_user),
+            unread,
+            notifs,
+            warnings,
+            suspension,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
 #[derive(Template)]
 #[template(path = "reports.html")]
 struct ReportsTemplate {
@@ -1583,17 +1677,11 @@ struct ReportsTemplate {
 /// GET /inbox/reports
 pub async fn reports_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -1633,6 +1721,194 @@ pub async fn reports_request(
     )
 }
 
+#[derive(Template)]
+#[template(path = "appeals.html")]
+struct AppealsTemplate {
+    config: Config,
+    lang: langbeam::LangFile,
+    profile: Option<Box<Profile>>,
+    unread: usize,
+    notifs: usize,
+    appeals: Vec<Appeal>,
+    kind: String,
+    target: String,
+}
+
+/// GET /appeals
+pub async fn appeals_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+    Query(props): Query<AppealsQuery>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    let (unread, notifs, appeals) = match auth_user {
+        Some(ref ua) => (
+            database.get_inbox_count_by_recipient(&ua.id).await,
+            database.auth.get_notification_count_by_recipient(&ua.id).await,
+            match database.get_appeals_by_author(&ua.id).await {
+                Ok(r) => r,
+                Err(_) => return Html(DatabaseError::Other.to_html(database)),
+            },
+        ),
+        None => (0, 0, Vec::new()),
+    };
+
+    Html(
+        AppealsTemplate {
+            config: database.config.clone(),
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            profile: auth_user,
+            unread,
+            notifs,
+            appeals,
+            kind: props.kind,
+            target: props.target,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "appeal.html")]
+struct AppealTemplate {
+    config: Config,
+    lang: langbeam::LangFile,
+    profile: Option<Box<Profile>>,
+    appeal: Appeal,
+    context: String,
+    is_staff: bool,
+}
+
+/// GET /appeals/{id}
+pub async fn appeal_request(
+    jar: CookieJar,
+    Path(id): Path<String>,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    let appeal = match database.get_appeal(&id).await {
+        Ok(a) => a,
+        Err(e) => return Html(e.to_html(database)),
+    };
+
+    // check permission
+    let is_staff = if let Some(ref ua) = auth_user {
+        match database.auth.get_group_by_id(ua.group).await {
+            Ok(g) => g.permissions.check(FinePermission::VIEW_REPORTS),
+            Err(_) => false,
+        }
+    } else {
+        false
+    };
+
+    // appeals made while logged out can be viewed by anybody with the link
+    if (appeal.author != "anonymous")
+        && !is_staff
+        && auth_user.as_ref().map(|ua| &ua.id) != Some(&appeal.author)
+    {
+        return Html(DatabaseError::NotAllowed.to_html(database));
+    }
+
+    // get the action being appealed
+    let context = match appeal.kind {
+        AppealKind::Warning => database
+            .auth
+            .get_warning(&appeal.target)
+            .await
+            .map(|w| w.content)
+            .unwrap_or_default(),
+        AppealKind::Suspension => database
+            .auth
+            .get_suspension(&appeal.target)
+            .await
+            .map(|s| s.reason)
+            .unwrap_or_default(),
+        AppealKind::IpBan => database
+            .auth
+            .get_ipban(&appeal.target)
+            .await
+            .map(|b| b.reason)
+            .unwrap_or_default(),
+        AppealKind::Removal => database
+            .get_removal(&appeal.target)
+            .await
+            .ok()
+            .and_then(|r| r.rows.first().and_then(|r| r.row.get("content").cloned()))
+            .unwrap_or_default(),
+    };
+
+    Html(
+        AppealTemplate {
+            config: database.config.clone(),
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            profile: auth_user,
+            appeal,
+            context,
+            is_staff,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "appeals_queue.html")]
+struct AppealsQueueTemplate {
+    config: Config,
+    lang: langbeam::LangFile,
+    profile: Option<Box<Profile>>,
+    unread: usize,
+    appeals: Vec<Appeal>,
+}
+
+/// GET /inbox/appeals
+pub async fn appeals_queue_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = match auth {
+        Some(a) => a.user,
+        None => return Html(DatabaseError::NotAllowed.to_html(database)),
+    };
+
+    let appeals = match database.get_pending_appeals(&auth_user).await {
+        Ok(r) => r,
+        Err(e) => return Html(e.to_html(database)),
+    };
+
+    let unread = database.get_inbox_count_by_recipient(&auth_user.id).await;
+
+    Html(
+        AppealsQueueTemplate {
+            config: database.config.clone(),
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            profile: Some(auth_user),
+            unread,
+            appeals,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
 #[derive(Template)]
 #[template(path = "audit.html")]
 struct AuditTemplate {
@@ -1647,18 +1923,12 @@ struct AuditTemplate {
 /// GET /inbox/audit
 pub async fn audit_log_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -1714,16 +1984,13 @@ struct IpbansTemplate {
 }
 
 /// GET /inbox/audit/ipbans
-pub async fn ipbans_request(jar: CookieJar, State(database): State<Database>) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+pub async fn ipbans_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -1772,18 +2039,12 @@ struct ReportTemplate {
 }
 
 /// GET /intents/report
-pub async fn report_request(jar: CookieJar, State(database): State<Database>) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+pub async fn report_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
 
     Html(
         ReportTemplate {
@@ -1809,6 +2070,14 @@ pub async fn routes(database: Database) -> Router {
         .route("/site/about", get(about_request))
         .route("/site/terms-of-service", get(tos_request))
         .route("/site/privacy", get(privacy_request))
+        .route(
+            "/unsubscribe/{id}/{token}",
+            get(unsubscribe_request).post(unsubscribe_request),
+        )
+        .route(
+            "/digest/confirm/{id}/{token}",
+            get(confirm_digest_email_request),
+        )
         .route("/intents/report", get(report_request))
         .route("/site/fun/carp", get(carp_request))
         // inbox
@@ -1816,7 +2085,11 @@ pub async fn routes(database: Database) -> Router {
         .route("/inbox/global", get(public_global_timeline_request))
         .route("/inbox/global/following", get(global_timeline_request))
         .route("/inbox/notifications", get(notifications_request))
+        .route("/warnings", get(warnings_request))
+        .route("/appeals", get(appeals_request))
+        .route("/appeals/{id}", get(appeal_request))
         .route("/inbox/reports", get(reports_request)) // staff
+        .route("/inbox/appeals", get(appeals_queue_request)) // staff
         .route("/inbox/audit", get(audit_log_request)) // staff
         .route("/inbox/audit/ipbans", get(ipbans_request)) // staff
         // assets
@@ -1826,6 +2099,14 @@ pub async fn routes(database: Database) -> Router {
             get(models::response::response_request),
         )
         .route("/@{username}/c/{id}", get(models::comment::comment_request))
+        .route(
+            "/@{username}/r/{id}/history",
+            get(models::revision::response_revisions_request),
+        )
+        .route(
+            "/@{username}/c/{id}/history",
+            get(models::revision::comment_revisions_request),
+        )
         // profiles
         .route("/@{username}/_app/warning", get(profile::warning_request))
         .route("/@{username}/mod", get(profile::mod_request)) // staff
@@ -1837,6 +2118,10 @@ pub async fn routes(database: Database) -> Router {
         ) // staff
         .route("/@{username}/following", get(profile::following_request))
         .route("/@{username}/followers", get(profile::followers_request))
+        .route(
+            "/@{username}/followers/requests",
+            get(profile::follow_requests_request),
+        )
         .route("/@{username}/friends", get(profile::friends_request))
         .route(
             "/@{username}/friends/requests",
@@ -1856,6 +2141,26 @@ pub async fn routes(database: Database) -> Router {
             "/@{username}/_app/feed.html",
             get(profile::partial_profile_request),
         )
+        .route(
+            "/@{username}/_app/pins.html",
+            get(profile::partial_pins_request),
+        )
+        .route(
+            "/@{username}/_app/badges.html",
+            get(profile::partial_badges_request),
+        )
+        .route(
+            "/@{username}/_app/drawings.html",
+            get(profile::partial_drawings_request),
+        )
+        .route(
+            "/@{username}/_app/tags.html",
+            get(profile::partial_tags_request),
+        )
+        .route(
+            "/@{username}/_app/questions.html",
+            get(profile::partial_questions_request),
+        )
         .route(
             "/@{username}/layout",
             get(profile::profile_layout_editor_request),
@@ -1886,6 +2191,10 @@ pub async fn routes(database: Database) -> Router {
             "/market/_app/layout_playground.html/{id}",
             get(market::layout_playground_request),
         )
+        .route(
+            "/market/_app/item_preview.html/{id}",
+            get(market::item_preview_request),
+        )
         // auth
         .route("/login", get(login_request))
         .route("/sign_up", get(sign_up_request))
@@ -1907,6 +2216,10 @@ pub async fn routes(database: Database) -> Router {
             "/_app/components/response_comments.html",
             get(models::comment::partial_response_comments_request),
         )
+        .route(
+            "/_app/components/comment_tree.html",
+            get(models::comment::partial_comment_tree_request),
+        )
         .route(
             "/_app/components/response.html",
             get(models::response::partial_response_request),
@@ -1915,6 +2228,10 @@ pub async fn routes(database: Database) -> Router {
             "/_app/components/short_reactions.html",
             get(partial_reactions_request),
         )
+        .route(
+            "/_app/components/poll.html",
+            get(models::poll::partial_poll_request),
+        )
         .route(
             "/_app/timelines/timeline.html",
             get(partial_timeline_request),
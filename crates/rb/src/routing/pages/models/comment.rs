@@ -1,8 +1,12 @@
 use rainbeam::{
     database::Database,
-    model::{ResponseComment, Question, QuestionResponse, Reaction, DatabaseError},
+    model::{
+        ResponseComment, Question, QuestionResponse, Reaction, DatabaseError, CommentSort,
+        CommentTreeNode, CommentTreeQuery,
+    },
 };
 use rainbeam_shared::config::Config;
+use authbeam::extract::Auth;
 use authbeam::model::Profile;
 
 use axum::{
@@ -15,6 +19,59 @@ use reva_axum::Template;
 use crate::{ToHtml, routing::pages::PaginatedQuery};
 use serde::Deserialize;
 
+/// A single row of a flattened [`CommentTreeNode`] tree
+///
+/// Rows are rendered in order with their `depth` as indentation, so collapsing a
+/// comment only has to hide the rows after it which are deeper than it.
+pub struct CommentThreadRow {
+    /// The comment in this row (`None` for a button to load more replies to `parent`)
+    pub comment: Option<(ResponseComment, usize, usize)>,
+    /// How deep the row is in the thread
+    pub depth: usize,
+    /// The ID of the comment the row is replying to
+    pub parent: String,
+    /// The offset to load more replies to `parent` from
+    pub next: usize,
+}
+
+/// Flatten a comment tree into rows (see [`CommentThreadRow`])
+///
+/// # Arguments
+/// * `nodes`
+/// * `next` - the offset to load more of `nodes` from
+/// * `parent` - the ID of the comment `nodes` are replying to
+/// * `depth` - how deep `nodes` are in the thread
+/// * `out`
+pub fn flatten_comment_tree(
+    nodes: Vec<CommentTreeNode>,
+    next: Option<usize>,
+    parent: &str,
+    depth: usize,
+    out: &mut Vec<CommentThreadRow>,
+) {
+    for node in nodes {
+        let id = node.comment.id.clone();
+
+        out.push(CommentThreadRow {
+            comment: Some((node.comment, node.reply_count, node.reaction_count)),
+            depth,
+            parent: parent.to_string(),
+            next: 0,
+        });
+
+        flatten_comment_tree(node.replies, node.next, &id, depth + 1, out);
+    }
+
+    if let Some(next) = next {
+        out.push(CommentThreadRow {
+            comment: None,
+            depth,
+            parent: parent.to_string(),
+            next,
+        });
+    }
+}
+
 #[derive(Template)]
 #[template(path = "views/comment.html")]
 struct CommentTemplate {
@@ -24,7 +81,8 @@ struct CommentTemplate {
     unread: usize,
     notifs: usize,
     comment: (ResponseComment, usize, usize),
-    replies: Vec<(ResponseComment, usize, usize)>,
+    rows: Vec<CommentThreadRow>,
+    has_more: bool,
     reactions: Vec<Reaction>,
     page: i32,
     question: Question,
@@ -40,21 +98,12 @@ struct CommentTemplate {
 /// GET /@{username}/c/{id}
 pub async fn comment_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path((_, id)): Path<(String, String)>,
     State(database): State<Database>,
     Query(props): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let unread = if let Some(ref ua) = auth_user {
         database.get_inbox_count_by_recipient(&ua.id).await
@@ -71,24 +120,52 @@ pub async fn comment_request(
         0
     };
 
-    let comment = match database.get_comment(id.clone(), true).await {
+    let comment = match database
+        .get_comment(id.clone(), true, auth_user.as_deref())
+        .await
+    {
         Ok(r) => r,
         Err(e) => return Html(e.to_html(database)),
     };
 
-    let response = match database.get_response(comment.0.response.clone()).await {
+    let response = match database
+        .get_response(comment.0.response.clone(), auth_user.as_deref())
+        .await
+    {
         Ok(r) => r,
         Err(e) => return Html(e.to_html(database)),
     };
 
-    let replies = match database
-        .get_replies_by_comment_paginated(comment.0.id.clone(), props.page.clone())
+    // the response is hidden until its author's profile is unlocked
+    if !database
+        .is_unlocked_response(&response.1, auth_user.as_deref(), &jar)
         .await
     {
-        Ok(r) => r,
-        Err(_) => Vec::new(),
+        return Html(DatabaseError::NotAllowed.to_html(database));
+    }
+
+    let tree = match database
+        .get_comment_tree(
+            response.1.id.clone(),
+            CommentTreeQuery {
+                parent: comment.0.id.clone(),
+                offset: props.page.max(0) as usize * 12,
+                depth: 3,
+                limit: 12,
+                sort: CommentSort::Newest,
+            },
+            auth_user.as_deref(),
+        )
+        .await
+    {
+        Ok(t) => t,
+        Err(e) => return Html(e.to_html(database)),
     };
 
+    // pages are used for the top of the thread, so we only need the rows below it
+    let mut rows = Vec::new();
+    flatten_comment_tree(tree.comments, None, &comment.0.id, 0, &mut rows);
+
     let mut is_helper: bool = false;
     let is_powerful = if let Some(ref ua) = auth_user {
         let group = match database.auth.get_group_by_id(ua.group).await {
@@ -119,7 +196,8 @@ pub async fn comment_request(
             unread,
             notifs,
             comment,
-            replies,
+            rows,
+            has_more: tree.next.is_some(),
             reactions,
             page: props.page,
             question: response.0,
@@ -158,39 +236,41 @@ pub struct PartialCommentsProps {
 /// GET /_app/components/comments.html
 pub async fn partial_comments_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<PartialCommentsProps>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
-    let comment = match database.get_comment(props.id.clone(), true).await {
+    let comment = match database
+        .get_comment(props.id.clone(), true, auth_user.as_deref())
+        .await
+    {
         Ok(r) => r,
         Err(e) => return Html(e.to_html(database)),
     };
 
-    let comments = match database
-        .get_replies_by_comment_paginated(comment.0.id.clone(), props.page.clone())
+    let response = match database
+        .get_response(comment.0.response.clone(), auth_user.as_deref())
         .await
     {
-        Ok(r) => r,
-        Err(_) => Vec::new(),
-    };
-
-    let response = match database.get_response(comment.0.response.clone()).await {
         Ok(r) => r.1,
         Err(e) => return Html(e.to_html(database)),
     };
 
+    // the response is hidden until its author's profile is unlocked
+    if !database
+        .is_unlocked_response(&response, auth_user.as_deref(), &jar)
+        .await
+    {
+        return Html(DatabaseError::NotAllowed.to_html(database));
+    }
+
+    let comments = database
+        .get_replies_by_comment_paginated(comment.0.id.clone(), props.page, auth_user.as_deref())
+        .await
+        .unwrap_or_default();
+
     let mut is_helper: bool = false;
     let is_powerful = if let Some(ref ua) = auth_user {
         let group = match database.auth.get_group_by_id(ua.group).await {
@@ -240,33 +320,32 @@ struct ResponseCommentsPartialTemplate {
 /// GET /_app/components/response_comments.html
 pub async fn partial_response_comments_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<PartialCommentsProps>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
-    let response = match database.get_response(props.id.clone()).await {
+    let response = match database
+        .get_response(props.id.clone(), auth_user.as_deref())
+        .await
+    {
         Ok(r) => r.1,
         Err(e) => return Html(e.to_html(database)),
     };
 
-    let comments = match database
-        .get_comments_by_response_paginated(response.id.clone(), props.page.clone())
+    // the response is hidden until its author's profile is unlocked
+    if !database
+        .is_unlocked_response(&response, auth_user.as_deref(), &jar)
         .await
     {
-        Ok(r) => r,
-        Err(_) => Vec::new(),
-    };
+        return Html(DatabaseError::NotAllowed.to_html(database));
+    }
+
+    let comments = database
+        .get_comments_by_response_paginated(response.id.clone(), props.page, auth_user.as_deref())
+        .await
+        .unwrap_or_default();
 
     let mut is_helper: bool = false;
     let is_powerful = if let Some(ref ua) = auth_user {
@@ -300,3 +379,113 @@ pub async fn partial_response_comments_request(
         .unwrap(),
     )
 }
+
+#[derive(Template)]
+#[template(path = "partials/views/comment_tree.html")]
+struct CommentTreePartialTemplate {
+    config: Config,
+    lang: langbeam::LangFile,
+    profile: Option<Box<Profile>>,
+    response: QuestionResponse,
+    rows: Vec<CommentThreadRow>,
+    open_replies_in_tab: bool,
+    is_powerful: bool,
+    is_helper: bool,
+}
+
+#[derive(Deserialize)]
+pub struct PartialCommentTreeProps {
+    pub id: String,
+    #[serde(default)]
+    pub parent: String,
+    #[serde(default)]
+    pub offset: usize,
+    /// How deep the replies to `parent` are in the thread they're being added to
+    #[serde(default)]
+    pub depth: usize,
+}
+
+/// GET /_app/components/comment_tree.html
+pub async fn partial_comment_tree_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+    Query(props): Query<PartialCommentTreeProps>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    let response = match database
+        .get_response(props.id.clone(), auth_user.as_deref())
+        .await
+    {
+        Ok(r) => r.1,
+        Err(e) => return Html(e.to_html(database)),
+    };
+
+    // the response is hidden until its author's profile is unlocked
+    if !database
+        .is_unlocked_response(&response, auth_user.as_deref(), &jar)
+        .await
+    {
+        return Html(DatabaseError::NotAllowed.to_html(database));
+    }
+
+    let tree = match database
+        .get_comment_tree(
+            props.id,
+            CommentTreeQuery {
+                parent: props.parent.clone(),
+                offset: props.offset,
+                depth: 3,
+                limit: 12,
+                sort: CommentSort::Newest,
+            },
+            auth_user.as_deref(),
+        )
+        .await
+    {
+        Ok(t) => t,
+        Err(e) => return Html(e.to_html(database)),
+    };
+
+    let mut rows = Vec::new();
+    flatten_comment_tree(
+        tree.comments,
+        tree.next,
+        &props.parent,
+        props.depth,
+        &mut rows,
+    );
+
+    let mut is_helper: bool = false;
+    let is_powerful = if let Some(ref ua) = auth_user {
+        let group = match database.auth.get_group_by_id(ua.group).await {
+            Ok(g) => g,
+            Err(_) => return Html(DatabaseError::Other.to_html(database)),
+        };
+
+        is_helper = group.permissions.check_helper();
+        group.permissions.check_manager()
+    } else {
+        false
+    };
+
+    Html(
+        CommentTreePartialTemplate {
+            config: database.config.clone(),
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            profile: auth_user,
+            response,
+            rows,
+            open_replies_in_tab: true,
+            is_powerful,
+            is_helper,
+        }
+        .render()
+        .unwrap(),
+    )
+}
@@ -1,2 +1,4 @@
 pub mod comment;
+pub mod poll;
 pub mod response;
+pub mod revision;
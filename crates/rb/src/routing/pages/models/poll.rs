@@ -0,0 +1,79 @@
+use rainbeam::{database::Database, model::PollResults};
+use authbeam::extract::Auth;
+
+use axum::{
+    extract::{Query, State},
+    response::{Html, IntoResponse},
+};
+use axum_extra::extract::CookieJar;
+use reva_axum::Template;
+
+use crate::ToHtml;
+use serde::Deserialize;
+
+#[derive(Template)]
+#[template(path = "partials/components/poll.html")]
+struct PollPartialTemplate {
+    lang: langbeam::LangFile,
+    results: PollResults,
+    /// Each option as `(option, votes, percent, voted for by viewer)`
+    options: Vec<(String, usize, usize, bool)>,
+    closed: bool,
+    can_vote: bool,
+}
+
+#[derive(Deserialize)]
+pub struct PartialPollProps {
+    pub id: String,
+}
+
+/// GET /_app/components/poll.html
+pub async fn partial_poll_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    State(database): State<Database>,
+    Query(props): Query<PartialPollProps>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    let results = match database
+        .get_poll_results(&props.id, auth_user.as_deref())
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => return Html(e.to_html(database)),
+    };
+
+    let closed = results.poll.is_closed();
+    let options = results
+        .poll
+        .options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let count = results.counts.get(i).copied().unwrap_or(0);
+            (
+                option.to_owned(),
+                count,
+                (count * 100).checked_div(results.total).unwrap_or(0),
+                results.voted.contains(&i),
+            )
+        })
+        .collect();
+
+    Html(
+        PollPartialTemplate {
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            can_vote: auth_user.is_some() && results.voted.is_empty() && !closed,
+            results,
+            options,
+            closed,
+        }
+        .render()
+        .unwrap(),
+    )
+}
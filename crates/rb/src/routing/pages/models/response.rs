@@ -6,6 +6,7 @@ use rainbeam::{
     },
 };
 use rainbeam_shared::config::Config;
+use authbeam::extract::Auth;
 use authbeam::model::Profile;
 
 use axum::{
@@ -43,21 +44,12 @@ struct ResponseTemplate {
 /// GET /@{}/r/{id}
 pub async fn response_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path((_, id)): Path<(String, String)>,
     State(database): State<Database>,
     Query(query): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let unread = if let Some(ref ua) = auth_user {
         database.get_inbox_count_by_recipient(&ua.id).await
@@ -74,13 +66,24 @@ pub async fn response_request(
         0
     };
 
-    let response = match database.get_response(id.clone()).await {
+    let response = match database
+        .get_response(id.clone(), auth_user.as_deref())
+        .await
+    {
         Ok(r) => r,
         Err(e) => return Html(e.to_html(database)),
     };
 
+    // the response is hidden until its author's profile is unlocked
+    if !database
+        .is_unlocked_response(&response.1, auth_user.as_deref(), &jar)
+        .await
+    {
+        return Html(DatabaseError::NotAllowed.to_html(database));
+    }
+
     let comments = match database
-        .get_comments_by_response_paginated(id.clone(), query.page)
+        .get_comments_by_response_paginated(id.clone(), query.page, auth_user.as_deref())
         .await
     {
         Ok(r) => r,
@@ -178,26 +181,28 @@ pub struct PartialResponseProps {
 /// GET /_app/components/response.html
 pub async fn partial_response_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<PartialResponseProps>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
-    let response = match database.get_response(props.id.clone()).await {
+    let response = match database
+        .get_response(props.id.clone(), auth_user.as_deref())
+        .await
+    {
         Ok(r) => r,
         Err(e) => return Html(e.to_html(database)),
     };
 
+    // the response is hidden until its author's profile is unlocked
+    if !database
+        .is_unlocked_response(&response.1, auth_user.as_deref(), &jar)
+        .await
+    {
+        return Html(DatabaseError::NotAllowed.to_html(database));
+    }
+
     let mut is_helper: bool = false;
     let is_powerful = if let Some(ref ua) = auth_user {
         let group = match database.auth.get_group_by_id(ua.group).await {
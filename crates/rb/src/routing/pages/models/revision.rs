@@ -0,0 +1,113 @@
+use rainbeam::{
+    database::Database,
+    model::{AssetType, Revision, RevisionKind},
+};
+use rainbeam_shared::config::Config;
+use authbeam::extract::Auth;
+use authbeam::model::Profile;
+
+use axum::{
+    extract::{Path, State},
+    response::{Html, IntoResponse},
+};
+use axum_extra::extract::CookieJar;
+use reva_axum::Template;
+
+use crate::ToHtml;
+
+#[derive(Template)]
+#[template(path = "views/revisions.html")]
+struct RevisionsTemplate {
+    config: Config,
+    lang: langbeam::LangFile,
+    profile: Option<Box<Profile>>,
+    /// The link to the asset, empty if it was deleted
+    link: String,
+    /// The current content of the asset, `None` if it was deleted
+    current: Option<String>,
+    revisions: Vec<Revision>,
+}
+
+/// Render the edit history of a response or comment
+async fn revisions_page(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    asset_type: AssetType,
+    id: String,
+    database: Database,
+) -> Html<String> {
+    let auth_user = auth.map(|a| a.user);
+
+    let revisions = match database
+        .get_asset_revisions(asset_type.clone(), &id, auth_user.as_deref())
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => return Html(e.to_html(database)),
+    };
+
+    let current = match asset_type {
+        AssetType::Response => database
+            .get_response(id.clone(), auth_user.as_deref())
+            .await
+            .ok()
+            .map(|r| {
+                (
+                    format!("/@{}/r/{}", r.1.author.username, r.1.id),
+                    r.1.content,
+                )
+            }),
+        _ => database
+            .get_comment(id.clone(), false, auth_user.as_deref())
+            .await
+            .ok()
+            .map(|c| {
+                (
+                    format!("/@{}/c/{}", c.0.author.username, c.0.id),
+                    c.0.content,
+                )
+            }),
+    };
+
+    let (link, current) = match current {
+        Some((link, content)) => (link, Some(content)),
+        None => (String::new(), None),
+    };
+
+    Html(
+        RevisionsTemplate {
+            config: database.config.clone(),
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            profile: auth_user,
+            link,
+            current,
+            revisions,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+/// GET /@{}/r/{id}/history
+pub async fn response_revisions_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path((_, id)): Path<(String, String)>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    revisions_page(jar, auth, AssetType::Response, id, database).await
+}
+
+/// GET /@{}/c/{id}/history
+pub async fn comment_revisions_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path((_, id)): Path<(String, String)>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    revisions_page(jar, auth, AssetType::Comment, id, database).await
+}
@@ -1,13 +1,17 @@
 use std::collections::HashMap;
 
-use authbeam::layout::LayoutComponent;
+use authbeam::extract::Auth;
+use authbeam::layout::{LayoutComponent, LAYOUT_MAX_LIMIT, MARKET_ID_OPTION};
 use reva_axum::Template;
 use axum::extract::{Path, Query};
 use axum::response::IntoResponse;
 use axum::{extract::State, response::Html};
 use axum_extra::extract::CookieJar;
 
-use authbeam::model::{FinePermission, ItemType, Profile, UserFollow, Warning};
+use authbeam::model::{
+    FinePermission, FollowRequest, ItemType, Profile, Suspension, UserFollow, UserLabel, Warning,
+    WarningSeverity,
+};
 use serde::Deserialize;
 
 use crate::config::Config;
@@ -15,7 +19,41 @@ use crate::database::Database;
 use crate::model::{DatabaseError, FullResponse, Question, RelationshipStatus};
 use crate::ToHtml;
 
-use super::{clean_metadata, MarkdownTemplate, PaginatedQuery, PasswordQuery, ProfileQuery};
+use super::{clean_metadata, MarkdownTemplate, PaginatedQuery, ProfileQuery};
+
+/// Get the responses pinned by the given profile which the viewer can see.
+///
+/// Returns `None` if the profile hasn't pinned anything.
+async fn pinned_responses(
+    database: &Database,
+    other: &Profile,
+    viewer: Option<&Profile>,
+) -> Option<Vec<FullResponse>> {
+    let pinned = other.metadata.settings().pinned;
+
+    if pinned.is_empty() {
+        return None;
+    }
+
+    let mut out = Vec::new();
+
+    for id in pinned {
+        match database.get_response(id, viewer).await {
+            Ok(response) => {
+                if response.1.author.id != other.id {
+                    // don't allow us to pin responses from other users
+                    continue;
+                }
+
+                // push
+                out.push(response)
+            }
+            Err(_) => continue,
+        }
+    }
+
+    Some(out)
+}
 
 #[derive(Template)]
 #[template(path = "profile/profile.html")]
@@ -44,7 +82,6 @@ struct ProfileTemplate {
     disallow_anonymous: bool,
     require_account: bool,
     hide_social: bool,
-    view_password: String,
     unlocked: bool,
     is_powerful: bool, // at least "manager"
     is_helper: bool,   // at least "helper"
@@ -54,21 +91,12 @@ struct ProfileTemplate {
 /// GET /@{username}
 pub async fn profile_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
     Query(query): Query<ProfileQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let unread = if let Some(ref ua) = auth_user {
         database.get_inbox_count_by_recipient(&ua.id).await
@@ -90,7 +118,9 @@ pub async fn profile_request(
         Err(_) => return Html(DatabaseError::NotFound.to_html(database)),
     };
 
-    if other.metadata.is_true("rainbeam:authenticated_only") & auth_user.is_none() {
+    let settings = other.metadata.settings();
+
+    if settings.authenticated_only & auth_user.is_none() {
         // this profile only allows authenticated users to view their profile
         return Html(DatabaseError::NotAllowed.to_html(database));
     }
@@ -147,30 +177,14 @@ pub async fn profile_request(
         false
     };
 
-    // ...
-    let pinned = if let Some(pinned) = other.metadata.kv.get("sparkler:pinned") {
-        if pinned.is_empty() {
-            None
-        } else {
-            let mut out = Vec::new();
-
-            for id in pinned.split(",") {
-                match database.get_response(id.to_string()).await {
-                    Ok(response) => {
-                        if response.1.author.id != other.id {
-                            // don't allow us to pin responses from other users
-                            continue;
-                        }
-
-                        // push
-                        out.push(response)
-                    }
-                    Err(_) => continue,
-                }
-            }
+    // the feed (and pins) are hidden until the profile is unlocked
+    let unlocked = database
+        .auth
+        .is_unlocked_viewer(&other, auth_user.as_deref(), &jar)
+        .await;
 
-            Some(out)
-        }
+    let pinned = if unlocked {
+        pinned_responses(&database, &other, auth_user.as_deref()).await
     } else {
         None
     };
@@ -245,39 +259,11 @@ pub async fn profile_request(
             query: query.q.unwrap_or(String::new()),
             // ...
             relationship,
-            lock_profile: other
-                .metadata
-                .kv
-                .get("sparkler:lock_profile")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            disallow_anonymous: other
-                .metadata
-                .kv
-                .get("sparkler:disallow_anonymous")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            require_account: other
-                .metadata
-                .kv
-                .get("sparkler:require_account")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            hide_social: (other
-                .metadata
-                .kv
-                .get("sparkler:private_social")
-                .unwrap_or(&"false".to_string())
-                == "true")
-                && !is_self,
-            view_password: query.password.clone(),
-            unlocked: if other.metadata.exists("rainbeam:view_password") {
-                (other.metadata.soft_get("rainbeam:view_password") == query.password)
-                    | is_self
-                    | is_helper
-            } else {
-                true
-            },
+            lock_profile: settings.lock_profile,
+            disallow_anonymous: settings.disallow_anonymous,
+            require_account: settings.require_account,
+            hide_social: settings.private_social && !is_self,
+            unlocked,
             is_powerful,
             is_helper,
             is_self,
@@ -304,27 +290,34 @@ struct PartialProfileTemplate {
 /// GET /@{username}/_app/feed.html
 pub async fn partial_profile_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
     Query(query): Query<ProfileQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let other = match database.auth.get_profile_by_username(&username).await {
         Ok(ua) => ua,
         Err(_) => return Html(DatabaseError::NotFound.to_html(database)),
     };
 
+    if !database
+        .auth
+        .is_approved_viewer(&other, auth_user.as_deref())
+        .await
+    {
+        return Html(DatabaseError::NotAllowed.to_html(database));
+    }
+
+    if !database
+        .auth
+        .is_unlocked_viewer(&other, auth_user.as_deref(), &jar)
+        .await
+    {
+        return Html(DatabaseError::NotAllowed.to_html(database));
+    }
+
     let responses = if let Some(ref tag) = query.tag {
         // tagged
         match database
@@ -332,6 +325,7 @@ pub async fn partial_profile_request(
                 other.id.to_owned(),
                 tag.to_owned(),
                 query.page,
+                auth_user.as_deref(),
             )
             .await
         {
@@ -346,6 +340,7 @@ pub async fn partial_profile_request(
                     other.id.to_owned(),
                     search.to_owned(),
                     query.page,
+                    auth_user.as_deref(),
                 )
                 .await
             {
@@ -355,7 +350,11 @@ pub async fn partial_profile_request(
         } else {
             // normal
             match database
-                .get_responses_by_author_paginated(other.id.to_owned(), query.page)
+                .get_responses_by_author_paginated(
+                    other.id.to_owned(),
+                    query.page,
+                    auth_user.as_deref(),
+                )
                 .await
             {
                 Ok(responses) => responses,
@@ -468,6 +467,339 @@ pub async fn partial_profile_request(
     )
 }
 
+#[derive(Deserialize)]
+pub struct LayoutComponentQuery {
+    #[serde(default)]
+    limit: String,
+}
+
+impl LayoutComponentQuery {
+    /// The number of entries to show, clamped to [`LAYOUT_MAX_LIMIT`].
+    fn limit(&self, default: usize) -> usize {
+        self.limit
+            .parse::<usize>()
+            .unwrap_or(default)
+            .clamp(1, LAYOUT_MAX_LIMIT)
+    }
+}
+
+/// Get the profile a layout component partial is being rendered for, along with
+/// if the viewer is at least a manager and at least a helper.
+///
+/// Fails if the viewer isn't allowed to see the profile, or hasn't unlocked it.
+async fn layout_component_profile(
+    database: &Database,
+    username: &str,
+    viewer: Option<&Profile>,
+    jar: &CookieJar,
+) -> Result<(Box<Profile>, bool, bool), DatabaseError> {
+    let other = match database.auth.get_profile_by_username(username).await {
+        Ok(ua) => ua,
+        Err(_) => return Err(DatabaseError::NotFound),
+    };
+
+    if !database.auth.is_approved_viewer(&other, viewer).await
+        || !database.auth.is_unlocked_viewer(&other, viewer, jar).await
+    {
+        return Err(DatabaseError::NotAllowed);
+    }
+
+    let mut is_helper: bool = false;
+    let is_powerful = if let Some(ua) = viewer {
+        let group = match database.auth.get_group_by_id(ua.group).await {
+            Ok(g) => g,
+            Err(_) => return Err(DatabaseError::Other),
+        };
+
+        is_helper = group.permissions.check_helper();
+        group.permissions.check_manager()
+    } else {
+        false
+    };
+
+    if let Some(ua) = viewer {
+        if !is_helper && (ua.id != other.id) {
+            let relationship = database.auth.get_user_relationship(&other.id, &ua.id).await;
+
+            if relationship.0 == RelationshipStatus::Blocked {
+                return Err(DatabaseError::NotFound);
+            }
+        }
+    }
+
+    Ok((other, is_powerful, is_helper))
+}
+
+#[derive(Template)]
+#[template(path = "partials/profile/pins.html")]
+struct PartialPinsTemplate {
+    config: Config,
+    lang: langbeam::LangFile,
+    profile: Option<Box<Profile>>,
+    other: Box<Profile>,
+    responses: Vec<FullResponse>,
+    // ...
+    is_powerful: bool, // at least "manager"
+    is_helper: bool,   // at least "helper"
+}
+
+/// GET /@{username}/_app/pins.html
+pub async fn partial_pins_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path(username): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    let (other, is_powerful, is_helper) =
+        match layout_component_profile(&database, &username, auth_user.as_deref(), &jar).await {
+            Ok(x) => x,
+            Err(e) => return Html(e.to_html(database)),
+        };
+
+    let responses = pinned_responses(&database, &other, auth_user.as_deref())
+        .await
+        .unwrap_or_default();
+
+    Html(
+        PartialPinsTemplate {
+            config: database.config.clone(),
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            profile: auth_user,
+            other,
+            responses,
+            is_powerful,
+            is_helper,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "partials/profile/badges.html")]
+struct PartialBadgesTemplate {
+    lang: langbeam::LangFile,
+    other: Box<Profile>,
+    labels: Vec<UserLabel>,
+}
+
+/// GET /@{username}/_app/badges.html
+pub async fn partial_badges_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path(username): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    let (other, _, _) =
+        match layout_component_profile(&database, &username, auth_user.as_deref(), &jar).await {
+            Ok(x) => x,
+            Err(e) => return Html(e.to_html(database)),
+        };
+
+    // reserved labels (like the quarantine label) are for staff only
+    let mut labels = Vec::new();
+
+    for id in &other.labels {
+        if *id < 0 {
+            continue;
+        }
+
+        if let Ok(label) = database.auth.get_label(*id).await {
+            labels.push(label);
+        }
+    }
+
+    Html(
+        PartialBadgesTemplate {
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            other,
+            labels,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "partials/profile/drawings.html")]
+struct PartialDrawingsTemplate {
+    lang: langbeam::LangFile,
+    responses: Vec<FullResponse>,
+}
+
+/// GET /@{username}/_app/drawings.html
+pub async fn partial_drawings_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path(username): Path<String>,
+    State(database): State<Database>,
+    Query(query): Query<LayoutComponentQuery>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    let (other, _, _) =
+        match layout_component_profile(&database, &username, auth_user.as_deref(), &jar).await {
+            Ok(x) => x,
+            Err(e) => return Html(e.to_html(database)),
+        };
+
+    let responses = match database
+        .get_responses_by_author(&other.id, auth_user.as_deref())
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => return Html(e.to_html(database)),
+    };
+
+    let responses: Vec<FullResponse> = responses
+        .into_iter()
+        .filter(|r| !r.0.render_media().is_empty())
+        .take(query.limit(6))
+        .collect();
+
+    Html(
+        PartialDrawingsTemplate {
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            responses,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "partials/profile/tags.html")]
+struct PartialTagsTemplate {
+    lang: langbeam::LangFile,
+    other: Box<Profile>,
+    tags: Vec<(String, usize)>,
+}
+
+/// GET /@{username}/_app/tags.html
+pub async fn partial_tags_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path(username): Path<String>,
+    State(database): State<Database>,
+    Query(query): Query<LayoutComponentQuery>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    let (other, _, _) =
+        match layout_component_profile(&database, &username, auth_user.as_deref(), &jar).await {
+            Ok(x) => x,
+            Err(e) => return Html(e.to_html(database)),
+        };
+
+    let responses = match database
+        .get_responses_by_author(&other.id, auth_user.as_deref())
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => return Html(e.to_html(database)),
+    };
+
+    // count tags
+    let mut counts: HashMap<String, usize> = HashMap::new();
+
+    for response in responses {
+        for tag in response.1.tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+
+    let mut tags: Vec<(String, usize)> = counts.into_iter().collect();
+    tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    tags.truncate(query.limit(16));
+
+    Html(
+        PartialTagsTemplate {
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            other,
+            tags,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "partials/profile/questions.html")]
+struct PartialQuestionsTemplate {
+    config: Config,
+    lang: langbeam::LangFile,
+    profile: Option<Box<Profile>>,
+    questions: Vec<(Question, usize, usize)>,
+    is_helper: bool,
+}
+
+/// GET /@{username}/_app/questions.html
+pub async fn partial_questions_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path(username): Path<String>,
+    State(database): State<Database>,
+    Query(query): Query<LayoutComponentQuery>,
+) -> impl IntoResponse {
+    let auth_user = auth.map(|a| a.user);
+
+    let (other, _, is_helper) =
+        match layout_component_profile(&database, &username, auth_user.as_deref(), &jar).await {
+            Ok(x) => x,
+            Err(e) => return Html(e.to_html(database)),
+        };
+
+    let questions = match database
+        .get_global_questions_by_author_paginated(other.id.to_owned(), 0)
+        .await
+    {
+        Ok(q) => q,
+        Err(e) => return Html(e.to_html(database)),
+    };
+
+    let mut questions = database
+        .filter_visible_questions(questions, auth_user.as_deref())
+        .await;
+
+    questions.truncate(query.limit(5));
+
+    Html(
+        PartialQuestionsTemplate {
+            config: database.config.clone(),
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            profile: auth_user,
+            questions,
+            is_helper,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
 #[derive(Template)]
 #[template(path = "profile/layout_editor.html")]
 struct ProfileLayoutEditorTemplate {
@@ -488,21 +820,12 @@ pub struct ProfileLayoutEditorQuery {
 /// GET /@{username}/layout
 pub async fn profile_layout_editor_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
     Query(props): Query<ProfileLayoutEditorQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let other = match database.auth.get_profile(&username).await {
         Ok(ua) => ua,
@@ -555,9 +878,11 @@ pub async fn profile_layout_editor_request(
                     return Html(DatabaseError::ValueError.to_string());
                 }
 
+                let customer = auth_user.unwrap().id;
+
                 if !database
                     .auth
-                    .get_transaction_by_customer_item(&auth_user.unwrap().id, &item.id)
+                    .get_transaction_by_customer_item(&customer, &item.id)
                     .await
                     .is_ok()
                 {
@@ -565,7 +890,10 @@ pub async fn profile_layout_editor_request(
                     return Html(DatabaseError::NotAllowed.to_string());
                 }
 
-                let mut layout: LayoutComponent = match serde_json::from_str(&item.content) {
+                // use the version this user has pinned
+                let content = database.auth.get_item_content(&item, &customer).await;
+
+                let mut layout: LayoutComponent = match serde_json::from_str(&content) {
                     Ok(l) => l,
                     Err(_) => return Html(DatabaseError::ValueError.to_string()),
                 };
@@ -573,7 +901,7 @@ pub async fn profile_layout_editor_request(
                 // mark that this layout came from this item
                 layout
                     .options
-                    .insert("#rainbeam:market_id".to_string(), props.id.clone());
+                    .insert(MARKET_ID_OPTION.to_string(), props.id.clone());
 
                 // return
                 layout
@@ -603,79 +931,39 @@ struct ProfileEmbedTemplate {
 /// GET /@{username}/embed
 pub async fn profile_embed_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let other = match database.auth.get_profile_by_username(&username).await {
         Ok(ua) => ua,
         Err(_) => return Html(DatabaseError::NotFound.to_html(database)),
     };
 
-    if other.metadata.is_true("rainbeam:authenticated_only") & auth_user.is_none() {
+    let settings = other.metadata.settings();
+
+    if settings.authenticated_only & auth_user.is_none() {
         // this profile only allows authenticated users to view their profile
         return Html(DatabaseError::NotAllowed.to_html(database));
     }
 
-    let pinned = if let Some(pinned) = other.metadata.kv.get("sparkler:pinned") {
-        if pinned.is_empty() {
-            None
-        } else {
-            let mut out = Vec::new();
-
-            for id in pinned.split(",") {
-                match database.get_response(id.to_string()).await {
-                    Ok(response) => {
-                        if response.1.author.id != other.id {
-                            // don't allow us to pin responses from other users
-                            continue;
-                        }
-
-                        // push
-                        out.push(response)
-                    }
-                    Err(_) => continue,
-                }
-            }
-
-            Some(out)
-        }
+    // pins are part of the feed, which is hidden until the profile is unlocked
+    let pinned = if database
+        .auth
+        .is_unlocked_viewer(&other, auth_user.as_deref(), &jar)
+        .await
+    {
+        pinned_responses(&database, &other, auth_user.as_deref()).await
     } else {
         None
     };
 
     // permissions
-    let lock_profile = other
-        .metadata
-        .kv
-        .get("sparkler:lock_profile")
-        .unwrap_or(&"false".to_string())
-        == "true";
-
-    let disallow_anonymous = other
-        .metadata
-        .kv
-        .get("sparkler:disallow_anonymous")
-        .unwrap_or(&"false".to_string())
-        == "true";
-
-    let require_account = other
-        .metadata
-        .kv
-        .get("sparkler:require_account")
-        .unwrap_or(&"false".to_string())
-        == "true";
+    let lock_profile = settings.lock_profile;
+    let disallow_anonymous = settings.disallow_anonymous;
+    let require_account = settings.require_account;
 
     let mut is_helper: bool = false;
     let is_powerful = if let Some(ref ua) = auth_user {
@@ -751,21 +1039,12 @@ struct FollowersTemplate {
 /// GET /@{username}/followers
 pub async fn followers_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
     Query(query): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let unread = if let Some(ref ua) = auth_user {
         database.get_inbox_count_by_recipient(&ua.id).await
@@ -787,7 +1066,9 @@ pub async fn followers_request(
         Err(e) => return Html(e.to_string()),
     };
 
-    if other.metadata.is_true("rainbeam:authenticated_only") & auth_user.is_none() {
+    let settings = other.metadata.settings();
+
+    if settings.authenticated_only & auth_user.is_none() {
         // this profile only allows authenticated users to view their profile
         return Html(DatabaseError::NotAllowed.to_html(database));
     }
@@ -809,15 +1090,7 @@ pub async fn followers_request(
         false
     };
 
-    if !is_self
-        && (other
-            .metadata
-            .kv
-            .get("sparkler:private_social")
-            .unwrap_or(&"false".to_string())
-            == "true")
-        && !is_helper
-    {
+    if !is_self && settings.private_social && !is_helper {
         // hide social if not self and private_social is true
         return Html(DatabaseError::NotAllowed.to_html(database));
     }
@@ -896,21 +1169,12 @@ struct FollowingTemplate {
 /// GET /@{username}/following
 pub async fn following_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
     Query(query): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let unread = if let Some(ref ua) = auth_user {
         database.get_inbox_count_by_recipient(&ua.id).await
@@ -932,7 +1196,9 @@ pub async fn following_request(
         Err(e) => return Html(e.to_string()),
     };
 
-    if other.metadata.is_true("rainbeam:authenticated_only") & auth_user.is_none() {
+    let settings = other.metadata.settings();
+
+    if settings.authenticated_only & auth_user.is_none() {
         // this profile only allows authenticated users to view their profile
         return Html(DatabaseError::NotAllowed.to_html(database));
     }
@@ -954,15 +1220,7 @@ pub async fn following_request(
         false
     };
 
-    if !is_self
-        && (other
-            .metadata
-            .kv
-            .get("sparkler:private_social")
-            .unwrap_or(&"false".to_string())
-            == "true")
-        && !is_helper
-    {
+    if !is_self && settings.private_social && !is_helper {
         // hide social if not self and private_social is true
         return Html(DatabaseError::NotAllowed.to_html(database));
     }
@@ -1041,21 +1299,12 @@ struct FriendsTemplate {
 /// GET /@{username}/friends
 pub async fn friends_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
     Query(query): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let unread = if let Some(ref ua) = auth_user {
         database.get_inbox_count_by_recipient(&ua.id).await
@@ -1077,7 +1326,9 @@ pub async fn friends_request(
         Err(e) => return Html(e.to_string()),
     };
 
-    if other.metadata.is_true("rainbeam:authenticated_only") & auth_user.is_none() {
+    let settings = other.metadata.settings();
+
+    if settings.authenticated_only & auth_user.is_none() {
         // this profile only allows authenticated users to view their profile
         return Html(DatabaseError::NotAllowed.to_html(database));
     }
@@ -1099,15 +1350,7 @@ pub async fn friends_request(
         false
     };
 
-    if !is_self
-        && (other
-            .metadata
-            .kv
-            .get("sparkler:private_social")
-            .unwrap_or(&"false".to_string())
-            == "true")
-        && !is_helper
-    {
+    if !is_self && settings.private_social && !is_helper {
         // hide social if not self and private_social is true
         return Html(DatabaseError::NotAllowed.to_html(database));
     }
@@ -1190,19 +1433,13 @@ struct FriendRequestsTemplate {
 /// GET /@{username}/friends/requests
 pub async fn friend_requests_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
     Query(query): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -1267,6 +1504,92 @@ pub async fn friend_requests_request(
     )
 }
 
+#[derive(Template)]
+#[template(path = "profile/social/follow_requests.html")]
+struct FollowRequestsTemplate {
+    config: Config,
+    lang: langbeam::LangFile,
+    profile: Option<Box<Profile>>,
+    unread: usize,
+    notifs: usize,
+    other: Box<Profile>,
+    requests: Vec<(FollowRequest, Box<Profile>)>,
+    followers_count: usize,
+    following_count: usize,
+    friends_count: usize,
+    // ...
+    is_self: bool,
+    is_helper: bool,
+}
+
+/// GET /@{username}/followers/requests
+pub async fn follow_requests_request(
+    jar: CookieJar,
+    auth: Option<Auth>,
+    Path(username): Path<String>,
+    State(database): State<Database>,
+) -> impl IntoResponse {
+    let auth_user = match auth {
+        Some(a) => a.user,
+        None => return Html(DatabaseError::NotAllowed.to_html(database)),
+    };
+
+    let unread = database.get_inbox_count_by_recipient(&auth_user.id).await;
+
+    let notifs = database
+        .auth
+        .get_notification_count_by_recipient(&auth_user.id)
+        .await;
+
+    let is_helper = {
+        let group = match database.auth.get_group_by_id(auth_user.group).await {
+            Ok(g) => g,
+            Err(_) => return Html(DatabaseError::Other.to_html(database)),
+        };
+
+        group.permissions.check_helper()
+    };
+
+    let other = match database.auth.get_profile_by_username(&username).await {
+        Ok(ua) => ua,
+        Err(e) => return Html(e.to_string()),
+    };
+
+    let is_self = auth_user.id == other.id;
+
+    if !is_self && !is_helper {
+        return Html(DatabaseError::NotAllowed.to_html(database));
+    }
+
+    Html(
+        FollowRequestsTemplate {
+            config: database.config.clone(),
+            lang: database.lang(if let Some(c) = jar.get("net.rainbeam.langs.choice") {
+                c.value_trimmed()
+            } else {
+                ""
+            }),
+            profile: Some(auth_user),
+            unread,
+            notifs,
+            other: other.clone(),
+            requests: database
+                .auth
+                .get_follow_requests(&other.id)
+                .await
+                .unwrap_or(Vec::new()),
+            followers_count: database.auth.get_followers_count(&other.id).await,
+            following_count: database.auth.get_following_count(&other.id).await,
+            friends_count: database.auth.get_friendship_count_by_user(&other.id).await,
+            // ...
+            is_self,
+            is_helper,
+        }
+        .render()
+        .unwrap(),
+    )
+}
+
 #[derive(Template)]
 #[template(path = "profile/social/blocks.html")]
 struct BlocksTemplate {
@@ -1289,19 +1612,13 @@ struct BlocksTemplate {
 /// GET /@{username}/friends/blocks
 pub async fn blocks_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
     Query(query): Query<PaginatedQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -1405,21 +1722,12 @@ struct ProfileQuestionsTemplate {
 /// GET /@{username}/questions
 pub async fn questions_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
     Query(query): Query<ProfileQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let unread = if let Some(ref ua) = auth_user {
         database.get_inbox_count_by_recipient(&ua.id).await
@@ -1441,7 +1749,9 @@ pub async fn questions_request(
         Err(e) => return Html(e.to_string()),
     };
 
-    if other.metadata.is_true("rainbeam:authenticated_only") & auth_user.is_none() {
+    let settings = other.metadata.settings();
+
+    if settings.authenticated_only & auth_user.is_none() {
         // this profile only allows authenticated users to view their profile
         return Html(DatabaseError::NotAllowed.to_html(database));
     }
@@ -1486,6 +1796,10 @@ pub async fn questions_request(
         }
     };
 
+    let questions = database
+        .filter_visible_questions(questions, auth_user.as_deref())
+        .await;
+
     let mut is_helper: bool = false;
     let is_powerful = if let Some(ref ua) = auth_user {
         let group = match database.auth.get_group_by_id(ua.group).await {
@@ -1554,38 +1868,14 @@ pub async fn questions_request(
             query: query.q.unwrap_or(String::new()),
             // ...
             relationship,
-            lock_profile: other
-                .metadata
-                .kv
-                .get("sparkler:lock_profile")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            disallow_anonymous: other
-                .metadata
-                .kv
-                .get("sparkler:disallow_anonymous")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            require_account: other
-                .metadata
-                .kv
-                .get("sparkler:require_account")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            hide_social: (other
-                .metadata
-                .kv
-                .get("sparkler:private_social")
-                .unwrap_or(&"false".to_string())
-                == "true")
-                && !is_self,
-            unlocked: if other.metadata.exists("rainbeam:view_password") {
-                (other.metadata.soft_get("rainbeam:view_password") == query.password)
-                    | is_self
-                    | is_helper
-            } else {
-                true
-            },
+            lock_profile: settings.lock_profile,
+            disallow_anonymous: settings.disallow_anonymous,
+            require_account: settings.require_account,
+            hide_social: settings.private_social && !is_self,
+            unlocked: database
+                .auth
+                .is_unlocked_viewer(&other, auth_user.as_deref(), &jar)
+                .await,
             is_powerful,
             is_helper,
             is_self,
@@ -1605,6 +1895,8 @@ struct ModTemplate {
     notifs: usize,
     other: Box<Profile>,
     warnings: Vec<Warning>,
+    suspension: Option<Suspension>,
+    strikes: usize,
     response_count: usize,
     questions_count: usize,
     followers_count: usize,
@@ -1631,19 +1923,12 @@ struct ModTemplate {
 /// GET /@{username}/mod
 pub async fn mod_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
-    Query(query): Query<PasswordQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -1659,6 +1944,8 @@ pub async fn mod_request(
         Err(_) => return Html(DatabaseError::NotFound.to_html(database)),
     };
 
+    let settings = other.metadata.settings();
+
     let is_following = match database.auth.get_follow(&auth_user.id, &other.id).await {
         Ok(_) => true,
         Err(_) => false,
@@ -1701,6 +1988,26 @@ pub async fn mod_request(
         Err(_) => return Html(DatabaseError::Other.to_html(database)),
     };
 
+    let suspension = match database.auth.get_suspension(&other.id).await {
+        Ok(s) => {
+            if s.is_active() {
+                Some(s)
+            } else {
+                None
+            }
+        }
+        Err(_) => None,
+    };
+
+    let strikes = database
+        .auth
+        .get_strike_count(
+            &other.id,
+            rainbeam_shared::unix_epoch_timestamp()
+                .saturating_sub(database.auth.config.strike_policy.window),
+        )
+        .await;
+
     let is_self = auth_user.id == other.id;
     let relationship = RelationshipStatus::Friends; // moderators should always be your friend! (bypass private profile)
 
@@ -1717,6 +2024,8 @@ pub async fn mod_request(
             notifs,
             other: other.clone(),
             warnings,
+            suspension,
+            strikes,
             response_count: database.get_response_count_by_author(&other.id).await,
             questions_count: database
                 .get_global_questions_count_by_author(&other.id)
@@ -1732,38 +2041,14 @@ pub async fn mod_request(
             tokens_src: other.tokens.clone(),
             // ...
             relationship,
-            lock_profile: other
-                .metadata
-                .kv
-                .get("sparkler:lock_profile")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            disallow_anonymous: other
-                .metadata
-                .kv
-                .get("sparkler:disallow_anonymous")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            require_account: other
-                .metadata
-                .kv
-                .get("sparkler:require_account")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            hide_social: (other
-                .metadata
-                .kv
-                .get("sparkler:private_social")
-                .unwrap_or(&"false".to_string())
-                == "true")
-                && !is_self,
-            unlocked: if other.metadata.exists("rainbeam:view_password") {
-                (other.metadata.soft_get("rainbeam:view_password") == query.password)
-                    | is_self
-                    | is_helper
-            } else {
-                true
-            },
+            lock_profile: settings.lock_profile,
+            disallow_anonymous: settings.disallow_anonymous,
+            require_account: settings.require_account,
+            hide_social: settings.private_social && !is_self,
+            unlocked: database
+                .auth
+                .is_unlocked_viewer(&other, Some(auth_user.as_ref()), &jar)
+                .await,
             is_powerful,
             is_helper,
             is_self,
@@ -1806,19 +2091,12 @@ struct ProfileQuestionsInboxTemplate {
 /// GET /@{username}/questions/inbox
 pub async fn inbox_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
-    Query(query): Query<PasswordQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -1834,6 +2112,8 @@ pub async fn inbox_request(
         Err(e) => return Html(e.to_string()),
     };
 
+    let settings = other.metadata.settings();
+
     let is_following = match database.auth.get_follow(&auth_user.id, &other.id).await {
         Ok(_) => true,
         Err(_) => false,
@@ -1906,38 +2186,14 @@ pub async fn inbox_request(
             metadata: clean_metadata(&other.metadata),
             // ...
             relationship,
-            lock_profile: other
-                .metadata
-                .kv
-                .get("sparkler:lock_profile")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            disallow_anonymous: other
-                .metadata
-                .kv
-                .get("sparkler:disallow_anonymous")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            require_account: other
-                .metadata
-                .kv
-                .get("sparkler:require_account")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            hide_social: (other
-                .metadata
-                .kv
-                .get("sparkler:private_social")
-                .unwrap_or(&"false".to_string())
-                == "true")
-                && !is_self,
-            unlocked: if other.metadata.exists("rainbeam:view_password") {
-                (other.metadata.soft_get("rainbeam:view_password") == query.password)
-                    | is_self
-                    | is_helper
-            } else {
-                true
-            },
+            lock_profile: settings.lock_profile,
+            disallow_anonymous: settings.disallow_anonymous,
+            require_account: settings.require_account,
+            hide_social: settings.private_social && !is_self,
+            unlocked: database
+                .auth
+                .is_unlocked_viewer(&other, Some(auth_user.as_ref()), &jar)
+                .await,
             is_powerful,
             is_helper,
             is_self,
@@ -1981,19 +2237,13 @@ struct ProfileQuestionsOutboxTemplate {
 /// GET /@{username}/questions/outbox
 pub async fn outbox_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
     Query(query): Query<ProfileQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -2009,6 +2259,8 @@ pub async fn outbox_request(
         Err(e) => return Html(e.to_string()),
     };
 
+    let settings = other.metadata.settings();
+
     let is_following = match database.auth.get_follow(&auth_user.id, &other.id).await {
         Ok(_) => true,
         Err(_) => false,
@@ -2085,38 +2337,14 @@ pub async fn outbox_request(
             page: query.page,
             // ...
             relationship,
-            lock_profile: other
-                .metadata
-                .kv
-                .get("sparkler:lock_profile")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            disallow_anonymous: other
-                .metadata
-                .kv
-                .get("sparkler:disallow_anonymous")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            require_account: other
-                .metadata
-                .kv
-                .get("sparkler:require_account")
-                .unwrap_or(&"false".to_string())
-                == "true",
-            hide_social: (other
-                .metadata
-                .kv
-                .get("sparkler:private_social")
-                .unwrap_or(&"false".to_string())
-                == "true")
-                && !is_self,
-            unlocked: if other.metadata.exists("rainbeam:view_password") {
-                (other.metadata.soft_get("rainbeam:view_password") == query.password)
-                    | is_self
-                    | is_helper
-            } else {
-                true
-            },
+            lock_profile: settings.lock_profile,
+            disallow_anonymous: settings.disallow_anonymous,
+            require_account: settings.require_account,
+            hide_social: settings.private_social && !is_self,
+            unlocked: database
+                .auth
+                .is_unlocked_viewer(&other, Some(auth_user.as_ref()), &jar)
+                .await,
             is_powerful,
             is_helper,
             is_self,
@@ -2140,18 +2368,12 @@ struct FriendRequestTemplate {
 /// GET /@{username}/relationship/friend_accept
 pub async fn friend_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -2206,20 +2428,11 @@ struct CardTemplate {
 /// GET /@{username}/_app/card.html
 pub async fn render_card_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     Html(
         CardTemplate {
@@ -2251,20 +2464,11 @@ struct WarningTemplate {
 /// GET /@{username}/_app/warning
 pub async fn warning_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     Path(username): Path<String>,
     State(database): State<Database>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let other = match database.auth.get_profile_by_username(&username).await {
         Ok(ua) => ua,
@@ -6,6 +6,7 @@ use axum::response::IntoResponse;
 use axum::{extract::State, response::Html};
 use axum_extra::extract::CookieJar;
 
+use authbeam::extract::Auth;
 use authbeam::model::{Profile, RelationshipStatus};
 
 use super::{SearchHomeQuery, SearchQuery};
@@ -29,20 +30,11 @@ struct HomepageTemplate {
 /// GET /search
 pub async fn search_homepage_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(query): Query<SearchHomeQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let unread = if let Some(ref ua) = auth_user {
         database.get_inbox_count_by_recipient(&ua.id).await
@@ -99,20 +91,11 @@ struct ResponsesTemplate {
 /// GET /search/responses
 pub async fn search_responses_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(query): Query<SearchQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let unread = if let Some(ref ua) = auth_user {
         database.get_inbox_count_by_recipient(&ua.id).await
@@ -132,7 +115,7 @@ pub async fn search_responses_request(
     // search results
     let results = if query.tag.is_empty() {
         match database
-            .get_responses_searched_paginated(query.page, query.q.clone())
+            .get_responses_searched_paginated(query.page, query.q.clone(), auth_user.as_deref())
             .await
         {
             Ok(responses) => responses,
@@ -140,7 +123,7 @@ pub async fn search_responses_request(
         }
     } else {
         match database
-            .get_responses_tagged_paginated(query.tag.clone(), query.page)
+            .get_responses_tagged_paginated(query.tag.clone(), query.page, auth_user.as_deref())
             .await
         {
             Ok(responses) => responses,
@@ -148,6 +131,11 @@ pub async fn search_responses_request(
         }
     };
 
+    // responses are hidden until their author's profile is unlocked
+    let results = database
+        .filter_unlocked_responses(results, auth_user.as_deref(), &jar)
+        .await;
+
     // permissions
     let mut is_helper: bool = false;
     let is_powerful = if let Some(ref ua) = auth_user {
@@ -243,20 +231,11 @@ struct QuestionsTemplate {
 /// GET /search/questions
 pub async fn search_questions_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(query): Query<SearchQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let unread = if let Some(ref ua) = auth_user {
         database.get_inbox_count_by_recipient(&ua.id).await
@@ -275,13 +254,17 @@ pub async fn search_questions_request(
 
     // search results
     let results = match database
-        .get_global_questions_searched_paginated(query.page, query.q.clone())
+        .get_global_questions_searched_paginated(query.page, query.q.clone(), auth_user.as_deref())
         .await
     {
         Ok(responses) => responses,
         Err(e) => return Html(e.to_html(database)),
     };
 
+    let results = database
+        .filter_visible_questions(results, auth_user.as_deref())
+        .await;
+
     // build relationships list
     let mut relationships: HashMap<String, RelationshipStatus> = HashMap::new();
 
@@ -372,20 +355,11 @@ struct UsersTemplate {
 /// GET /search/users
 pub async fn search_users_request(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(query): Query<SearchQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => Some(ua),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let auth_user = auth.map(|a| a.user);
 
     let unread = if let Some(ref ua) = auth_user {
         database.get_inbox_count_by_recipient(&ua.id).await
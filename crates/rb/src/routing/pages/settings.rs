@@ -6,11 +6,12 @@ use axum::{
 };
 use axum_extra::extract::CookieJar;
 
-use authbeam::model::{IpBlock, Item, Profile, Transaction};
+use authbeam::extract::Auth;
+use authbeam::model::{IpBlock, Item, Profile, Transaction, UserMute};
 
 use crate::config::Config;
 use crate::database::Database;
-use crate::model::{DatabaseError, RelationshipStatus};
+use crate::model::{DatabaseError, DigestFrequency, DigestSubscription, RelationshipStatus};
 use crate::ToHtml;
 
 use super::{clean_metadata_short, NotificationsQuery};
@@ -25,7 +26,9 @@ struct AccountSettingsTemplate {
     notifs: usize,
     metadata: String,
     relationships: Vec<(Box<Profile>, RelationshipStatus)>,
+    mutes: Vec<(Box<Profile>, UserMute)>,
     ipblocks: Vec<IpBlock>,
+    digest: Option<DigestSubscription>,
     user: Box<Profile>,
     viewing_other_profile: bool,
 }
@@ -33,18 +36,12 @@ struct AccountSettingsTemplate {
 /// GET /settings
 pub async fn account_settings(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<NotificationsQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -90,11 +87,19 @@ pub async fn account_settings(
         Err(_) => Vec::new(),
     };
 
+    let mutes = database
+        .auth
+        .get_user_mutes(&user.id)
+        .await
+        .unwrap_or_default();
+
     let ipblocks = match database.auth.get_ipblocks(&user.id).await {
         Ok(r) => r,
         Err(_) => Vec::new(),
     };
 
+    let digest = database.get_digest_subscription(&user.id).await.ok();
+
     Html(
         AccountSettingsTemplate {
             config: database.config.clone(),
@@ -108,7 +113,9 @@ pub async fn account_settings(
             unread,
             notifs,
             relationships,
+            mutes,
             ipblocks,
+            digest,
             user,
             viewing_other_profile,
         }
@@ -133,18 +140,12 @@ struct ProfileSettingsTemplate {
 /// GET /settings/profile
 pub async fn profile_settings(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<NotificationsQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -217,18 +218,12 @@ struct ThemeSettingsTemplate {
 /// GET /settings/theme
 pub async fn theme_settings(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<NotificationsQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -301,18 +296,12 @@ struct PrivacySettingsTemplate {
 /// GET /settings/privacy
 pub async fn privacy_settings(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<NotificationsQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -388,18 +377,12 @@ struct SessionsSettingsTemplate {
 /// GET /settings/sessions
 pub async fn sessions_settings(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<NotificationsQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
@@ -482,18 +465,12 @@ struct CoinsSettingsTemplate {
 /// GET /settings/coins
 pub async fn coins_settings(
     jar: CookieJar,
+    auth: Option<Auth>,
     State(database): State<Database>,
     Query(props): Query<NotificationsQuery>,
 ) -> impl IntoResponse {
-    let auth_user = match jar.get("__Secure-Token") {
-        Some(c) => match database
-            .auth
-            .get_profile_by_unhashed(c.value_trimmed())
-            .await
-        {
-            Ok(ua) => ua,
-            Err(_) => return Html(DatabaseError::NotAllowed.to_html(database)),
-        },
+    let auth_user = match auth {
+        Some(a) => a.user,
         None => return Html(DatabaseError::NotAllowed.to_html(database)),
     };
 
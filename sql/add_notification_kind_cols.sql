@@ -0,0 +1,8 @@
+ALTER TABLE "xnotifications"
+ADD COLUMN "kind" TEXT DEFAULT '"System"';
+
+ALTER TABLE "xnotifications"
+ADD COLUMN "read" TEXT DEFAULT 'false';
+
+ALTER TABLE "xnotifications"
+ADD COLUMN "others" TEXT DEFAULT '0';
@@ -0,0 +1,12 @@
+ALTER TABLE "xwarnings"
+ADD COLUMN "severity" TEXT DEFAULT '"Minor"';
+
+ALTER TABLE "xwarnings"
+ADD COLUMN "rule" TEXT DEFAULT '';
+
+ALTER TABLE "xwarnings"
+ADD COLUMN "strikes" TEXT DEFAULT '1';
+
+-- existing warnings are considered acknowledged
+ALTER TABLE "xwarnings"
+ADD COLUMN "acknowledged" TEXT DEFAULT '1';